@@ -0,0 +1,138 @@
+// Benchmarks for collision checks and the runner's per-step world update
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+
+use inf_runner::camera::{Camera, CameraConfig};
+use inf_runner::clock::SimClock;
+use inf_runner::p_rect;
+use inf_runner::physics::Body;
+use inf_runner::physics::Coin;
+use inf_runner::physics::Obstacle;
+use inf_runner::physics::PhysRect;
+use inf_runner::physics::Player;
+use inf_runner::physics::Power;
+use inf_runner::profiler::Profiler;
+use inf_runner::rect;
+use inf_runner::world::{get_ground_coord, StepContext, World, PLAYER_X};
+use inf_runner::ObstacleType;
+use inf_runner::PowerType;
+use inf_runner::POWER_DEFS;
+use inf_runner::TILE_SIZE;
+
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::rect::Rect;
+use sdl2::render::Texture;
+use sdl2::surface::Surface;
+
+const NUM_ENTITIES: i32 = 100;
+const CAM_W: i32 = 1280;
+const CAM_H: i32 = 720;
+
+struct Run<'a> {
+    clock: SimClock,
+    world: World<'a>,
+    player: Player<'a>,
+}
+
+// A fresh world as the runner starts one, with NUM_ENTITIES obstacles, coins,
+// and powers spread out on the ground in front of the player. Every obstacle
+// is already knocked loose so it runs through the full physics path.
+fn build_run<'a>(texture: &'a Texture<'a>) -> Run<'a> {
+    let clock = SimClock::new();
+    let mut world = World::new(
+        1666,
+        &clock,
+        Camera::new(CameraConfig::new(CAM_H)),
+        texture,
+        vec![texture; POWER_DEFS.len()],
+    );
+
+    for i in 0..NUM_ENTITIES {
+        let x = PLAYER_X + i * TILE_SIZE as i32 / 2;
+        let ground = get_ground_coord(&world.terrain, x);
+        let hitbox = p_rect!(x, ground.y() - TILE_SIZE as i32, TILE_SIZE, TILE_SIZE);
+        match i % 3 {
+            0 => {
+                let mut obstacle = Obstacle::new(hitbox, 50.0, ObstacleType::Statue);
+                obstacle.collided = true;
+                world.obstacles.push(obstacle);
+            }
+            1 => world.coins.push(Coin::new(hitbox, texture, 1000)),
+            _ => world.powers.push(Power::new(hitbox, texture, PowerType::Shield)),
+        }
+    }
+
+    let start_y = get_ground_coord(&world.terrain, PLAYER_X).y() - TILE_SIZE as i32;
+    Run {
+        clock,
+        world,
+        player: Player::new(
+            p_rect!(PLAYER_X, start_y, TILE_SIZE, TILE_SIZE),
+            rect!(PLAYER_X, start_y, TILE_SIZE, TILE_SIZE),
+            3.0,
+            texture,
+        ),
+    }
+}
+
+fn bench_has_intersection(c: &mut Criterion) {
+    let a = p_rect!(0, 0, TILE_SIZE, TILE_SIZE);
+    let overlapping = p_rect!(TILE_SIZE / 2, TILE_SIZE / 2, TILE_SIZE, TILE_SIZE);
+    let mut rotated = overlapping;
+    rotated.rotate(std::f64::consts::PI / 6.0);
+    let apart = p_rect!(4 * TILE_SIZE, 0, TILE_SIZE, TILE_SIZE);
+
+    c.bench_function("has_intersection overlapping", |b| {
+        b.iter(|| black_box(a).has_intersection(black_box(overlapping)))
+    });
+    c.bench_function("has_intersection rotated", |b| {
+        b.iter(|| black_box(a).has_intersection(black_box(rotated)))
+    });
+    c.bench_function("has_intersection apart", |b| {
+        b.iter(|| black_box(a).has_intersection(black_box(apart)))
+    });
+}
+
+fn bench_world_step(c: &mut Criterion) {
+    // Software renderer, so entities can hold a texture without opening a window
+    let surface = Surface::new(TILE_SIZE, TILE_SIZE, PixelFormatEnum::RGBA8888).unwrap();
+    let canvas = surface.into_canvas().unwrap();
+    let texture_creator = canvas.texture_creator();
+    let texture = texture_creator
+        .create_texture_target(None, TILE_SIZE, TILE_SIZE)
+        .unwrap();
+
+    let mut profiler = Profiler::new();
+    c.bench_function("world step, 100 entities", |b| {
+        b.iter_batched(
+            || build_run(&texture),
+            |mut run| {
+                // The runner's step: sense what's underfoot, then step the world
+                run.clock.queue_steps(1);
+                run.clock.tick();
+                run.player.remember_pos();
+                let under = run.world.sense(&mut run.player);
+                let report = run.world.step(
+                    &mut run.player,
+                    &under,
+                    &StepContext {
+                        clock: &run.clock,
+                        game_over: false,
+                        score: 0,
+                        density: 1,
+                        lanterns: false,
+                        view_w: CAM_W,
+                        shake: 0,
+                    },
+                    &mut profiler,
+                );
+                black_box(report);
+                run
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, bench_has_intersection, bench_world_step);
+criterion_main!(benches);
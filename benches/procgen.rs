@@ -0,0 +1,62 @@
+// Benchmarks for the procedural generation hot paths
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use inf_runner::proceduralgen;
+use inf_runner::proceduralgen::ProceduralGen;
+use inf_runner::rng::GameRng;
+
+use rand::Rng;
+
+const CAM_W: i32 = 1280;
+const CAM_H: i32 = 720;
+const BG_CURVES_SIZE: usize = CAM_W as usize / 10;
+
+// Same gradient table the runner builds at the start of every run
+fn gradient_table(rng: &mut GameRng) -> Box<[[(i32, i32); 256]; 256]> {
+    let mut random = Box::new([[(0, 0); 256]; 256]);
+    for row in random.iter_mut() {
+        for cell in row.iter_mut() {
+            *cell = (rng.gen_range(0..256), rng.gen_range(0..256));
+        }
+    }
+    random
+}
+
+fn bench_gen_terrain(c: &mut Criterion) {
+    let mut procgen = ProceduralGen::new(&GameRng::new(1666));
+    let random = gradient_table(procgen.terrain_rng());
+
+    c.bench_function("gen_terrain", |b| {
+        b.iter(|| {
+            procgen.gen_terrain(
+                black_box(&random),
+                black_box((0.0, CAM_H as f64 * 2.0 / 3.0)),
+                CAM_W,
+                CAM_H,
+                Vec::with_capacity(CAM_W as usize),
+                false,
+                false,
+                false,
+            )
+        })
+    });
+}
+
+// One full screen of both background hill layers, as generated at run start
+fn bench_perlin_hills(c: &mut Criterion) {
+    c.bench_function("gen_perlin_hill_point x BG_CURVES_SIZE", |b| {
+        b.iter(|| {
+            let mid: Vec<i16> = (0..BG_CURVES_SIZE)
+                .map(|i| proceduralgen::gen_perlin_hill_point(black_box(i), 550.0, 3.0, 0.5, 600.0))
+                .collect();
+            let back: Vec<i16> = (0..BG_CURVES_SIZE)
+                .map(|i| proceduralgen::gen_perlin_hill_point(black_box(i), 550.0, 4.0, 1.0, 820.0))
+                .collect();
+            (mid, back)
+        })
+    });
+}
+
+criterion_group!(benches, bench_gen_terrain, bench_perlin_hills);
+criterion_main!(benches);
@@ -0,0 +1,223 @@
+// What the player looks like from step to step. A small state machine picks a
+// visual state from the active power and tracks how long it's been in it, so
+// effects can ease in when a power kicks in instead of popping on. The runner
+// draws the effects behind the sprite, then the sprite with the state's
+// texture and lean.
+//
+// - SpeedBoost leans the player forward a frame at a time and trails speed
+//   lines behind them
+// - BouncyShoes glows green, flaring up every time the player bounces
+// - LowerGravity kicks up dust that drifts down slowly, more on landing
+
+use crate::physics::{Body, Entity, Player};
+use crate::rect;
+use crate::PowerType;
+use crate::TILE_SIZE;
+
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+
+use std::f64::consts::PI;
+
+const SIZE: i32 = TILE_SIZE as i32;
+
+// Ring that grows out of the player when a power kicks in, in steps
+const ACTIVATE_STEPS: u32 = 20;
+// Full forward lean under SpeedBoost, reached over LEAN_FRAMES frames
+const LEAN_ANGLE: f64 = PI / 12.0;
+const LEAN_FRAMES: u32 = 4;
+const STEPS_PER_LEAN_FRAME: u32 = 3;
+const SPEED_LINES: i32 = 5;
+// Glow around bouncy shoes at rest, and how fast a bounce's flare fades
+const GLOW_IDLE: f64 = 0.3;
+const GLOW_DECAY: f64 = 0.9;
+// Steps between dust puffs while floating, and puffs thrown up on landing
+const DUST_EVERY: u32 = 6;
+const LANDING_DUST: usize = 6;
+const DUST_STEPS: u32 = 40;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum PlayerVisual {
+    Plain,
+    Shielded,
+    Speeding,
+    Bouncing,
+    Floating,
+}
+
+impl PlayerVisual {
+    pub fn from_power(power: Option<PowerType>) -> PlayerVisual {
+        match power {
+            Some(PowerType::Shield) => PlayerVisual::Shielded,
+            Some(PowerType::SpeedBoost) => PlayerVisual::Speeding,
+            Some(PowerType::BouncyShoes) => PlayerVisual::Bouncing,
+            Some(PowerType::LowerGravity) => PlayerVisual::Floating,
+            Some(PowerType::ScoreMultiplier | PowerType::Lantern) | None => PlayerVisual::Plain,
+        }
+    }
+
+    // Power the state shows, for its glow color
+    fn power(&self) -> Option<PowerType> {
+        match self {
+            PlayerVisual::Plain => None,
+            PlayerVisual::Shielded => Some(PowerType::Shield),
+            PlayerVisual::Speeding => Some(PowerType::SpeedBoost),
+            PlayerVisual::Bouncing => Some(PowerType::BouncyShoes),
+            PlayerVisual::Floating => Some(PowerType::LowerGravity),
+        }
+    }
+}
+
+struct Puff {
+    x: f64,
+    y: f64,
+    drift: f64, // Sideways speed on top of the world scrolling past
+    age: u32,
+}
+
+pub struct PlayerAnimator {
+    visual: PlayerVisual,
+    steps: u32, // Steps spent in the current state
+    lean_frame: u32,
+    glow: f64, // 0 to 1
+    was_grounded: bool,
+    puffs: Vec<Puff>,
+}
+
+impl Default for PlayerAnimator {
+    fn default() -> Self {
+        PlayerAnimator::new()
+    }
+}
+
+impl PlayerAnimator {
+    pub fn new() -> PlayerAnimator {
+        PlayerAnimator {
+            visual: PlayerVisual::Plain,
+            steps: 0,
+            lean_frame: 0,
+            glow: GLOW_IDLE,
+            was_grounded: true,
+            puffs: Vec::new(),
+        }
+    }
+
+    pub fn visual(&self) -> PlayerVisual {
+        self.visual
+    }
+
+    // Extra rotation on top of the player's own, in radians
+    pub fn lean(&self) -> f64 {
+        LEAN_ANGLE * self.lean_frame as f64 / LEAN_FRAMES as f64
+    }
+
+    // Call once per simulation step, after the player has moved
+    pub fn update(&mut self, player: &Player) {
+        let visual = PlayerVisual::from_power(player.power_up());
+        if visual != self.visual {
+            self.visual = visual;
+            self.steps = 0;
+        } else {
+            self.steps += 1;
+        }
+
+        // Lean in a frame at a time while speeding, and back out after
+        if self.steps.is_multiple_of(STEPS_PER_LEAN_FRAME) {
+            if visual == PlayerVisual::Speeding {
+                self.lean_frame = (self.lean_frame + 1).min(LEAN_FRAMES);
+            } else {
+                self.lean_frame = self.lean_frame.saturating_sub(1);
+            }
+        }
+
+        let grounded = player.is_grounded();
+        let landed = grounded && !self.was_grounded;
+        self.was_grounded = grounded;
+
+        if visual == PlayerVisual::Bouncing && landed {
+            self.glow = 1.0;
+        } else {
+            self.glow = (self.glow * GLOW_DECAY).max(GLOW_IDLE);
+        }
+
+        // Dust stays where it was kicked up, so it scrolls with the world
+        let feet = (player.x() as f64 + SIZE as f64 / 2.0, (player.y() + SIZE) as f64);
+        for puff in self.puffs.iter_mut() {
+            puff.x += puff.drift - player.vel_x();
+            puff.y += 0.3;
+            puff.age += 1;
+        }
+        self.puffs.retain(|p| p.age < DUST_STEPS);
+        if visual == PlayerVisual::Floating {
+            if landed {
+                for i in 0..LANDING_DUST {
+                    let drift = (i as f64 / (LANDING_DUST - 1) as f64 - 0.5) * 3.0;
+                    self.puffs.push(Puff {
+                        x: feet.0,
+                        y: feet.1,
+                        drift,
+                        age: 0,
+                    });
+                }
+            } else if !grounded && self.steps.is_multiple_of(DUST_EVERY) {
+                self.puffs.push(Puff {
+                    x: feet.0,
+                    y: feet.1,
+                    drift: 0.0,
+                    age: 0,
+                });
+            }
+        }
+    }
+
+    // Effects that go behind the player sprite at (x, y)
+    pub fn draw_behind(&self, canvas: &mut WindowCanvas, x: i32, y: i32) -> Result<(), String> {
+        for puff in self.puffs.iter() {
+            let t = puff.age as f64 / DUST_STEPS as f64;
+            let size = (8.0 + 10.0 * t) as u32;
+            canvas.set_draw_color(Color::RGBA(230, 225, 210, (160.0 * (1.0 - t)) as u8));
+            canvas.fill_rect(rect!(
+                puff.x as i32 - size as i32 / 2,
+                puff.y as i32 - size as i32 / 2,
+                size,
+                size
+            ))?;
+        }
+
+        match self.visual {
+            PlayerVisual::Speeding => {
+                // Streaks stream off the back, fading in as the lean does
+                let alpha = (200.0 * self.lean_frame as f64 / LEAN_FRAMES as f64) as u8;
+                canvas.set_draw_color(Color::RGBA(255, 255, 255, alpha));
+                for i in 0..SPEED_LINES {
+                    let len = 30 + (i * 17) % 25;
+                    let offset = (self.steps as i32 * 12 + i * 37) % 60;
+                    canvas.fill_rect(rect!(x - len - offset, y + 15 + i * 16, len, 3))?;
+                }
+            }
+            PlayerVisual::Bouncing => {
+                let glow = PowerType::BouncyShoes.def().glow;
+                for ring in 1..=3 {
+                    let grow = ring * 4 + (self.glow * 8.0) as i32;
+                    let alpha = ((110 - ring * 30) as f64 * self.glow) as u8;
+                    canvas.set_draw_color(Color::RGBA(glow.r, glow.g, glow.b, alpha));
+                    canvas.fill_rect(rect!(x - grow, y - grow, SIZE + 2 * grow, SIZE + 2 * grow))?;
+                }
+            }
+            _ => {}
+        }
+
+        // Ring growing out of the player as a power kicks in
+        if let Some(power) = self.visual.power() {
+            if self.steps < ACTIVATE_STEPS {
+                let t = self.steps as f64 / ACTIVATE_STEPS as f64;
+                let grow = (t * SIZE as f64 / 2.0) as i32;
+                let glow = power.def().glow;
+                canvas.set_draw_color(Color::RGBA(glow.r, glow.g, glow.b, (255.0 * (1.0 - t)) as u8));
+                canvas.draw_rect(rect!(x - grow, y - grow, SIZE + 2 * grow, SIZE + 2 * grow))?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,240 @@
+// Export/import of a profile's saves, its config included, as one portable
+// file.
+//
+// Layout of an archive:
+//
+//     INF_RUNNER_SAVE 1
+//     profile <name>
+//     checksum <fnv-1a 64 of everything below, in hex>
+//     <relative path>
+//     <length in bytes>
+//     <raw bytes>
+//     ... one path/length/bytes group per file
+//
+// Every path starts with `profile/` and is relative to the profile's save
+// folder. The game folder's config.txt is the fallback for every profile, so
+// it's never archived.
+
+use crate::profile::{self, Profile, SAVE_DIR};
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+const MAGIC: &str = "INF_RUNNER_SAVE";
+const VERSION: u32 = 1;
+pub const EXTENSION: &str = "irsave";
+const PROFILE_PREFIX: &str = "profile/";
+// Archives to import are dropped here, apart from the exports in saves/ so
+// importing never picks up one of your own
+pub const IMPORT_DIR: &str = "import";
+
+pub struct SaveArchive {
+    profile: String,
+    files: Vec<(String, Vec<u8>)>,
+}
+
+impl SaveArchive {
+    // Gathers every save file of `profile`, its config.txt included
+    pub fn collect(profile: &Profile) -> Result<SaveArchive, String> {
+        let mut files = Vec::new();
+        let dir = profile.path("");
+        let mut pending = vec![dir.clone()];
+        while let Some(current) = pending.pop() {
+            for entry in fs::read_dir(&current).map_err(|e| e.to_string())? {
+                let path = entry.map_err(|e| e.to_string())?.path();
+                if path.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+                let relative = path.strip_prefix(&dir).map_err(|e| e.to_string())?;
+                let name = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                files.push((
+                    format!("{}{}", PROFILE_PREFIX, name),
+                    fs::read(&path).map_err(|e| e.to_string())?,
+                ));
+            }
+        }
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(SaveArchive {
+            profile: profile.name().to_string(),
+            files,
+        })
+    }
+
+    // Reads an archive, rejecting it if it is malformed or the checksum
+    // doesn't match its contents
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<SaveArchive, String> {
+        let bytes = fs::read(path).map_err(|e| e.to_string())?;
+        let mut pos = 0;
+
+        let header = next_line(&bytes, &mut pos)?;
+        if header != format!("{} {}", MAGIC, VERSION) {
+            return Err("Not a save archive, or from a newer version".to_string());
+        }
+        let profile = next_line(&bytes, &mut pos)?
+            .strip_prefix("profile ")
+            .ok_or("Missing profile name")?
+            .to_string();
+        if !profile::is_valid_name(&profile) {
+            return Err(format!("Invalid profile name '{}'", profile));
+        }
+        let checksum = next_line(&bytes, &mut pos)?
+            .strip_prefix("checksum ")
+            .and_then(|c| u64::from_str_radix(c, 16).ok())
+            .ok_or("Missing checksum")?;
+        if fnv1a(&bytes[pos..]) != checksum {
+            return Err("Checksum mismatch, the archive is corrupted".to_string());
+        }
+
+        let mut files = Vec::new();
+        while pos < bytes.len() {
+            let name = next_line(&bytes, &mut pos)?;
+            if !is_safe_path(&name) || !name.starts_with(PROFILE_PREFIX) {
+                return Err(format!("Refusing to write outside the profile: {}", name));
+            }
+            let len: usize = next_line(&bytes, &mut pos)?
+                .parse()
+                .map_err(|_| format!("Bad length for {}", name))?;
+            let end = match pos.checked_add(len) {
+                Some(end) if end <= bytes.len() => end,
+                _ => return Err(format!("Archive ends in the middle of {}", name)),
+            };
+            files.push((name, bytes[pos..end].to_vec()));
+            pos = end;
+        }
+
+        Ok(SaveArchive { profile, files })
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let mut body = Vec::new();
+        for (name, bytes) in self.files.iter() {
+            body.extend_from_slice(format!("{}\n{}\n", name, bytes.len()).as_bytes());
+            body.extend_from_slice(bytes);
+        }
+
+        let mut out = format!(
+            "{} {}\nprofile {}\nchecksum {:016x}\n",
+            MAGIC,
+            VERSION,
+            self.profile,
+            fnv1a(&body)
+        )
+        .into_bytes();
+        out.extend_from_slice(&body);
+        fs::write(path, out).map_err(|e| e.to_string())
+    }
+
+    // Replaces the profile's save folder with the archive's files and returns
+    // the restored profile. The files are written to a folder of their own
+    // first and swapped in once they're all there, so nothing the archive
+    // doesn't have is left behind and a failed restore leaves the old saves.
+    pub fn restore(&self) -> Result<Profile, String> {
+        let profile = Profile::create(&self.profile)?;
+        let dir = PathBuf::from(SAVE_DIR).join(profile.name());
+        // Not a valid profile name, so it never shows up as a profile
+        let staging = PathBuf::from(SAVE_DIR).join(format!("{}.restoring", profile.name()));
+        if staging.exists() {
+            fs::remove_dir_all(&staging).map_err(|e| e.to_string())?;
+        }
+        if let Err(e) = self.write_files(&staging) {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+        fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+        fs::rename(&staging, &dir).map_err(|e| e.to_string())?;
+        Ok(profile)
+    }
+
+    // Writes every file in the archive under `dir`
+    fn write_files(&self, dir: &Path) -> Result<(), String> {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        for (name, bytes) in self.files.iter() {
+            let path = match name.strip_prefix(PROFILE_PREFIX) {
+                Some(file) => dir.join(file),
+                None => return Err(format!("Refusing to write outside the profile: {}", name)),
+            };
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            fs::write(&path, bytes).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    pub fn profile_name(&self) -> &str {
+        &self.profile
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+}
+
+// Where the given profile gets exported to
+pub fn export_path(profile: &Profile) -> PathBuf {
+    PathBuf::from(SAVE_DIR).join(format!("{}.{}", profile.name(), EXTENSION))
+}
+
+// The most recently modified archive in the import folder, where an archive
+// copied over from another machine should be dropped
+pub fn find_import() -> Option<PathBuf> {
+    fs::read_dir(IMPORT_DIR)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == EXTENSION))
+        .max_by_key(|p| fs::metadata(p).and_then(|m| m.modified()).ok())
+}
+
+fn next_line(bytes: &[u8], pos: &mut usize) -> Result<String, String> {
+    let rest = &bytes[*pos..];
+    let end = rest
+        .iter()
+        .position(|&b| b == b'\n')
+        .ok_or("Unexpected end of archive")?;
+    *pos += end + 1;
+    String::from_utf8(rest[..end].to_vec()).map_err(|e| e.to_string())
+}
+
+// Only plain relative paths, so an archive can't reach outside the game folder
+fn is_safe_path(name: &str) -> bool {
+    !name.is_empty() && Path::new(name).components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restore_removes_files_not_in_the_archive() {
+        let profile = Profile::create("archive_test").unwrap();
+        fs::write(profile.path("kept.txt"), "kept").unwrap();
+        let archive = SaveArchive::collect(&profile).unwrap();
+
+        fs::write(profile.path("stale.txt"), "stale").unwrap();
+        fs::write(profile.path("kept.txt"), "changed").unwrap();
+        let restored = archive.restore();
+        let kept = fs::read_to_string(profile.path("kept.txt"));
+        let stale_left = profile.path("stale.txt").exists();
+        fs::remove_dir_all(PathBuf::from(SAVE_DIR).join(profile.name())).unwrap();
+
+        assert_eq!(restored.unwrap().name(), "archive_test");
+        assert_eq!(kept.unwrap(), "kept");
+        assert!(!stale_left);
+    }
+}
@@ -0,0 +1,411 @@
+// Images decoded ahead of time, so scenes don't hit the disk when they start.
+// Decoding happens on a worker thread into plain RGBA pixels, since SDL
+// surfaces can't leave the thread that made them. Turning pixels into a
+// texture is cheap and stays on the main thread. Anything that isn't in the
+// cache is still loaded from disk as before.
+//
+// A few assets are also built into the binary, and missing images fall back
+// to those or to a placeholder, so the game runs without the assets directory.
+//
+// The font file is read once and kept, every screen opens its sizes from
+// that copy.
+//
+// Each scene keeps the textures it's made in a `TextureCache`, so asking for
+// the same image twice hands back the texture that's already there.
+//
+// Asset paths in code are written as `assets/...` and resolved against the
+// asset root, so the game works no matter which directory it's launched from.
+// See `init_root` for where the root comes from.
+
+use crate::config::Config;
+
+use sdl2::image::{ImageRWops, LoadSurface};
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::render::{Texture, TextureCreator};
+use sdl2::rwops::RWops;
+use sdl2::surface::Surface;
+use sdl2::ttf::{Font, Sdl2TtfContext};
+use sdl2::video::WindowContext;
+
+use log::{info, warn};
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::OnceLock;
+use std::thread;
+
+// Every image the game draws, in roughly the order scenes need them
+pub const PRELOAD: [&str; 27] = [
+    "assets/bg.png",
+    "assets/sky.png",
+    "assets/sunset_gradient.png",
+    "assets/obstacles/statue.png",
+    "assets/obstacles/balloon.png",
+    "assets/obstacles/box.png",
+    "assets/obstacles/coin.png",
+    "assets/obstacles/powerup.png",
+    "assets/powers/speed.png",
+    "assets/powers/multiplier.png",
+    "assets/powers/bouncy.png",
+    "assets/powers/floaty.png",
+    "assets/powers/shield.png",
+    "assets/powers/lantern.png",
+    "assets/player/player.png",
+    "assets/player/shielded_player.png",
+    "assets/player/winged_player.png",
+    "assets/player/bouncy_player.png",
+    "assets/player/speed_player.png",
+    "assets/headshots/caleb_hs.jpg",
+    "assets/headshots/dane_hs.jpg",
+    "assets/headshots/andrew_hs.png",
+    "assets/headshots/benjamin_hs.jpg",
+    "assets/headshots/dominic_hs.jpg",
+    "assets/headshots/mateen_hs.jpg",
+    "assets/headshots/elliot_hs.jpg",
+    "assets/headshots/michael_hs.jpg",
+];
+
+// Environment variable that overrides the asset root
+pub const ROOT_ENV: &str = "INF_RUNNER_ASSETS";
+
+static ROOT: OnceLock<PathBuf> = OnceLock::new();
+// The font file's bytes, from the first screen that opened it
+static FONT_DATA: OnceLock<Vec<u8>> = OnceLock::new();
+
+/*  Picks the directory `assets/...` paths are resolved against, first match
+ *  wins:
+ *
+ *  - `cli`, from the `--assets <dir>` flag
+ *  - The INF_RUNNER_ASSETS environment variable
+ *  - The `asset_root` key of the config file
+ *  - An `assets` directory next to the executable or in one of its parent
+ *    directories, which finds the repo's assets under `cargo run`
+ *  - `assets` in the working directory
+ *
+ *  Only the first call has any effect.
+ */
+pub fn init_root(cli: Option<&str>, config: &Config) {
+    let root = cli
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os(ROOT_ENV).map(PathBuf::from))
+        .or_else(|| config.get("asset_root").map(PathBuf::from))
+        .or_else(find_root_near_exe)
+        .unwrap_or_else(|| PathBuf::from("assets"));
+    info!("Asset root: {}", root.display());
+    let _ = ROOT.set(root);
+}
+
+fn find_root_near_exe() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    exe.ancestors()
+        .skip(1)
+        .map(|dir| dir.join("assets"))
+        .find(|dir| dir.is_dir())
+}
+
+pub fn root() -> &'static Path {
+    ROOT.get().map(PathBuf::as_path).unwrap_or_else(|| Path::new("assets"))
+}
+
+// Where an `assets/...` path actually is
+pub fn resolve(path: &str) -> PathBuf {
+    match path.strip_prefix("assets/") {
+        Some(rest) => root().join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+pub const FONT: &str = "assets/DroidSansMono.ttf";
+pub const PLAYER: &str = "assets/player/player.png";
+
+const EMBEDDED_FONT: &[u8] = include_bytes!("../assets/DroidSansMono.ttf");
+const EMBEDDED_IMAGES: [(&str, &[u8]); 3] = [
+    (PLAYER, include_bytes!("../assets/player/player.png")),
+    (
+        "assets/obstacles/coin.png",
+        include_bytes!("../assets/obstacles/coin.png"),
+    ),
+    (
+        "assets/obstacles/statue.png",
+        include_bytes!("../assets/obstacles/statue.png"),
+    ),
+];
+// How textures are sampled when they're drawn at another size than their own
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub enum TextureFilter {
+    Nearest, // Blocky, every pixel stays sharp
+    #[default]
+    Linear, // Smooth, neighbouring pixels blend
+}
+
+impl TextureFilter {
+    pub const ALL: [TextureFilter; 2] = [TextureFilter::Nearest, TextureFilter::Linear];
+
+    // Key in the config file, also what SDL calls it
+    pub fn key(&self) -> &'static str {
+        match self {
+            TextureFilter::Nearest => "nearest",
+            TextureFilter::Linear => "linear",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<TextureFilter> {
+        TextureFilter::ALL.iter().copied().find(|f| f.key() == key)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TextureFilter::Nearest => "Nearest",
+            TextureFilter::Linear => "Linear",
+        }
+    }
+
+    pub fn next(&self) -> TextureFilter {
+        let i = TextureFilter::ALL.iter().position(|f| f == self).unwrap_or(0);
+        TextureFilter::ALL[(i + 1) % TextureFilter::ALL.len()]
+    }
+
+    // Textures created from now on use this filter. SDL's own default
+    // differs between platforms, so it's always set before creating one.
+    pub fn apply(&self) {
+        sdl2::hint::set("SDL_RENDER_SCALE_QUALITY", self.key());
+    }
+}
+
+// Size and color of the stand-in for images that aren't anywhere
+const PLACEHOLDER_SIZE: u32 = 100;
+const PLACEHOLDER_COLOR: Color = Color::RGB(255, 0, 255);
+
+/*  Loads the image at `path`, works on any thread
+ *
+ *  - Falls back to the copy built into the binary if the file can't be read,
+ *    player skins all fall back to the plain player
+ *  - Anything else that's missing becomes a magenta square
+ */
+pub fn load_surface(path: &str) -> Result<Surface<'static>, String> {
+    let e = match Surface::from_file(resolve(path)) {
+        Ok(surface) => return Ok(surface),
+        Err(e) => e,
+    };
+    let embedded_path = if path.starts_with("assets/player/") {
+        PLAYER
+    } else {
+        path
+    };
+    match EMBEDDED_IMAGES.iter().find(|(p, _)| *p == embedded_path) {
+        Some((_, bytes)) => {
+            warn!("Couldn't load {} ({}), using the built in copy", path, e);
+            RWops::from_bytes(bytes)?.load()
+        }
+        None => {
+            warn!("Couldn't load {} ({}), using a placeholder", path, e);
+            let mut surface = Surface::new(PLACEHOLDER_SIZE, PLACEHOLDER_SIZE, PixelFormatEnum::RGBA32)?;
+            surface.fill_rect(None, PLACEHOLDER_COLOR)?;
+            Ok(surface)
+        }
+    }
+}
+
+// The game's font at `size`, from the built in copy if the file is missing
+pub fn load_font(ttf: &Sdl2TtfContext, size: u16) -> Result<Font<'_, 'static>, String> {
+    let data = FONT_DATA.get_or_init(|| match std::fs::read(resolve(FONT)) {
+        Ok(data) => data,
+        Err(e) => {
+            warn!("Couldn't load {} ({}), using the built in copy", FONT, e);
+            EMBEDDED_FONT.to_vec()
+        }
+    });
+    match ttf.load_font_from_rwops(RWops::from_bytes(data)?, size) {
+        Ok(font) => Ok(font),
+        Err(e) => {
+            warn!("Couldn't open {} ({}), using the built in copy", FONT, e);
+            ttf.load_font_from_rwops(RWops::from_bytes(EMBEDDED_FONT)?, size)
+        }
+    }
+}
+
+// An image as RGBA32 pixels, safe to hand between threads
+pub struct DecodedImage {
+    width: u32,
+    height: u32,
+    pitch: u32,
+    pixels: Vec<u8>,
+}
+
+impl DecodedImage {
+    // Reads and decodes an image file, works on any thread
+    pub fn load(path: &str) -> Result<DecodedImage, String> {
+        let surface = load_surface(path)?.convert_format(PixelFormatEnum::RGBA32)?;
+        let pixels = surface.with_lock(|p| p.to_vec());
+        Ok(DecodedImage {
+            width: surface.width(),
+            height: surface.height(),
+            pitch: surface.pitch(),
+            pixels,
+        })
+    }
+
+    pub fn to_texture<'a>(&self, texture_creator: &'a TextureCreator<WindowContext>) -> Result<Texture<'a>, String> {
+        let mut pixels = self.pixels.clone();
+        let surface = Surface::from_data(
+            &mut pixels,
+            self.width,
+            self.height,
+            self.pitch,
+            PixelFormatEnum::RGBA32,
+        )?;
+        texture_creator
+            .create_texture_from_surface(&surface)
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Default)]
+pub struct AssetCache {
+    images: HashMap<String, DecodedImage>,
+    filter: TextureFilter, // For every texture made from here
+}
+
+impl AssetCache {
+    pub fn new() -> AssetCache {
+        AssetCache::default()
+    }
+
+    pub fn insert(&mut self, path: &str, image: DecodedImage) {
+        self.images.insert(path.to_string(), image);
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.images.contains_key(path)
+    }
+
+    pub fn set_filter(&mut self, filter: TextureFilter) {
+        self.filter = filter;
+        filter.apply();
+    }
+
+    pub fn filter(&self) -> TextureFilter {
+        self.filter
+    }
+
+    // Texture of the image at `path`, from the cache if it was preloaded
+    pub fn texture<'a>(
+        &self,
+        texture_creator: &'a TextureCreator<WindowContext>,
+        path: &str,
+    ) -> Result<Texture<'a>, String> {
+        self.filter.apply();
+        match self.images.get(path) {
+            Some(image) => image.to_texture(texture_creator),
+            None => {
+                let surface = load_surface(path)?;
+                texture_creator
+                    .create_texture_from_surface(&surface)
+                    .map_err(|e| e.to_string())
+            }
+        }
+    }
+
+    // Render target `width` by `height`. Always linear, it's drawn back down
+    // to the window when supersampling and nearest would throw away the
+    // extra pixels.
+    pub fn render_target<'a>(
+        &self,
+        texture_creator: &'a TextureCreator<WindowContext>,
+        width: u32,
+        height: u32,
+    ) -> Result<Texture<'a>, String> {
+        TextureFilter::Linear.apply();
+        let target = texture_creator.create_texture_target(None, width, height);
+        self.filter.apply();
+        target.map_err(|e| e.to_string())
+    }
+}
+
+// Textures one scene has made, keyed by path. They share the lifetime of the
+// scene's texture creator, so the cache is made once per scene.
+pub struct TextureCache<'a> {
+    texture_creator: &'a TextureCreator<WindowContext>,
+    textures: HashMap<String, Rc<Texture<'a>>>,
+}
+
+impl<'a> TextureCache<'a> {
+    pub fn new(texture_creator: &'a TextureCreator<WindowContext>) -> TextureCache<'a> {
+        TextureCache {
+            texture_creator,
+            textures: HashMap::new(),
+        }
+    }
+
+    // Texture of the image at `path`, only made the first time it's asked
+    // for. Anything that changes a texture's color or alpha mod needs its
+    // own copy from `AssetCache::texture` instead, it would change them all.
+    pub fn get(&mut self, assets: &AssetCache, path: &str) -> Result<Rc<Texture<'a>>, String> {
+        if let Some(texture) = self.textures.get(path) {
+            return Ok(Rc::clone(texture));
+        }
+        let texture = Rc::new(assets.texture(self.texture_creator, path)?);
+        self.textures.insert(path.to_string(), Rc::clone(&texture));
+        Ok(texture)
+    }
+}
+
+// Decodes a list of images on a worker thread, handing each one back as it's
+// done
+pub struct Preloader {
+    rx: Receiver<(String, Result<DecodedImage, String>)>,
+    total: usize,
+    done: usize,
+}
+
+impl Preloader {
+    pub fn start(paths: &[&str]) -> Preloader {
+        let paths: Vec<String> = paths.iter().map(|p| p.to_string()).collect();
+        let total = paths.len();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for path in paths {
+                let image = DecodedImage::load(&path);
+                if tx.send((path, image)).is_err() {
+                    // Nobody's waiting anymore
+                    break;
+                }
+            }
+        });
+        Preloader { rx, total, done: 0 }
+    }
+
+    // Images finished since the last call, without waiting
+    pub fn poll(&mut self) -> Vec<(String, Result<DecodedImage, String>)> {
+        let mut finished = Vec::new();
+        loop {
+            match self.rx.try_recv() {
+                Ok(item) => finished.push(item),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    // The worker died, whatever it didn't get to loads from
+                    // disk later
+                    self.total = self.done + finished.len();
+                    break;
+                }
+            }
+        }
+        self.done += finished.len();
+        finished
+    }
+
+    // 0 to 1
+    pub fn progress(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.done as f64 / self.total as f64
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.done >= self.total
+    }
+}
@@ -0,0 +1,574 @@
+// Sound, synthesized when it's played and mixed in an SDL audio callback.
+// The game doesn't ship any sound files, so everything is a short sequence
+// of notes, or filtered noise for ambience. The mixer has four channels:
+// looping music, a looping ambient bed under it, stingers layered over the
+// music for game events, and one-off effects. The music ducks under whatever
+// stinger is playing. Each biome has its own music and ambience, and moving
+// into a new one crossfades both over to it. Effects from something in the world can be
+// placed relative to the player: panned towards its side of the screen and
+// quieter the further off it is. Without an audio device every call here
+// does nothing.
+
+use crate::biome::Biome;
+use crate::SDLCore;
+
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
+
+use log::warn;
+
+use std::f64::consts::PI;
+
+const SAMPLE_RATE: i32 = 44100;
+// Fade at both ends of every note so they don't click, in seconds
+const FADE: f64 = 0.01;
+
+// Loudness of each channel at full volume, low enough that all of them
+// together don't clip
+const MUSIC_LEVEL: f64 = 0.12;
+const AMBIENCE_LEVEL: f64 = 0.06;
+const STINGER_LEVEL: f64 = 0.3;
+const EFFECT_LEVEL: f64 = 0.25;
+
+// Music level while a stinger plays, and how long it takes to get there and
+// back, in seconds
+const DUCK_GAIN: f32 = 0.3;
+const DUCK_SECS: f32 = 0.08;
+
+// Seconds music and ambience take to crossfade into a new biome's
+const CROSSFADE_SECS: f32 = 2.0;
+// Seconds an ambient loop lasts before it repeats, and how much of its end
+// is blended into its start so the repeat can't be heard
+const AMBIENCE_SECS: f64 = 8.0;
+const AMBIENCE_OVERLAP_SECS: f64 = 0.5;
+
+// Pixels to the side of the player a world sound is panned all it will be
+// at, and how far it can be before it's as quiet as it gets
+const PAN_RANGE: f64 = 900.0;
+const MAX_PAN: f64 = 0.8;
+const HEARING_RANGE: f64 = 1400.0;
+const MIN_GAIN: f64 = 0.3;
+
+// One note of a sound. A frequency of 0 is a rest.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Note {
+    pub freq: f64,
+    pub ms: u32,
+}
+
+// Rising C major arpeggio for a new personal best
+pub const FANFARE: [Note; 5] = [
+    Note { freq: 523.25, ms: 110 },
+    Note { freq: 659.25, ms: 110 },
+    Note { freq: 783.99, ms: 110 },
+    Note { freq: 0.0, ms: 40 },
+    Note { freq: 1046.5, ms: 420 },
+];
+
+// Music under a run in the hills, an A minor arpeggio loop
+pub const RUN_THEME: [Note; 16] = [
+    Note { freq: 220.0, ms: 180 },
+    Note { freq: 261.63, ms: 180 },
+    Note { freq: 329.63, ms: 180 },
+    Note { freq: 261.63, ms: 180 },
+    Note { freq: 174.61, ms: 180 },
+    Note { freq: 220.0, ms: 180 },
+    Note { freq: 261.63, ms: 180 },
+    Note { freq: 220.0, ms: 180 },
+    Note { freq: 196.0, ms: 180 },
+    Note { freq: 246.94, ms: 180 },
+    Note { freq: 293.66, ms: 180 },
+    Note { freq: 246.94, ms: 180 },
+    Note { freq: 164.81, ms: 180 },
+    Note { freq: 207.65, ms: 180 },
+    Note { freq: 246.94, ms: 180 },
+    Note { freq: 207.65, ms: 180 },
+];
+
+// Slower D minor loop with long notes for the dunes
+pub const DUNES_THEME: [Note; 8] = [
+    Note { freq: 293.66, ms: 360 },
+    Note { freq: 349.23, ms: 180 },
+    Note { freq: 440.0, ms: 360 },
+    Note { freq: 415.3, ms: 180 },
+    Note { freq: 349.23, ms: 360 },
+    Note { freq: 311.13, ms: 180 },
+    Note { freq: 293.66, ms: 360 },
+    Note { freq: 0.0, ms: 180 },
+];
+
+// Open fifths climbing and falling back for the mountains
+pub const MOUNTAINS_THEME: [Note; 8] = [
+    Note { freq: 196.0, ms: 240 },
+    Note { freq: 293.66, ms: 240 },
+    Note { freq: 392.0, ms: 240 },
+    Note { freq: 587.33, ms: 480 },
+    Note { freq: 523.25, ms: 240 },
+    Note { freq: 392.0, ms: 240 },
+    Note { freq: 349.23, ms: 240 },
+    Note { freq: 293.66, ms: 480 },
+];
+
+// Quick syncopated bass line for the city
+pub const CITY_THEME: [Note; 12] = [
+    Note { freq: 110.0, ms: 150 },
+    Note { freq: 0.0, ms: 75 },
+    Note { freq: 110.0, ms: 75 },
+    Note { freq: 130.81, ms: 150 },
+    Note { freq: 146.83, ms: 150 },
+    Note { freq: 0.0, ms: 150 },
+    Note { freq: 98.0, ms: 150 },
+    Note { freq: 0.0, ms: 75 },
+    Note { freq: 98.0, ms: 75 },
+    Note { freq: 116.54, ms: 150 },
+    Note { freq: 130.81, ms: 150 },
+    Note { freq: 0.0, ms: 150 },
+];
+
+// Sparse low notes with a lot of space for the cave
+pub const CAVE_THEME: [Note; 6] = [
+    Note { freq: 130.81, ms: 400 },
+    Note { freq: 0.0, ms: 400 },
+    Note { freq: 155.56, ms: 400 },
+    Note { freq: 0.0, ms: 400 },
+    Note { freq: 123.47, ms: 600 },
+    Note { freq: 0.0, ms: 600 },
+];
+
+// Beds of sound looped under a biome's music
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Ambience {
+    Wind,  // Noise that swells and dies away
+    Waves, // Noise breaking in slow surges
+    Hum,   // Mains hum and its overtones
+    Drips, // Odd plinks in the quiet
+}
+
+// Quick falling sweep for a fast-fall
+pub const WHOOSH: [Note; 5] = [
+    Note { freq: 880.0, ms: 30 },
+    Note { freq: 659.25, ms: 30 },
+    Note { freq: 493.88, ms: 30 },
+    Note { freq: 369.99, ms: 30 },
+    Note { freq: 277.18, ms: 50 },
+];
+
+// Jump sounds, one for each cosmetics::JumpSound
+pub const JUMP: [Note; 2] = [Note { freq: 392.0, ms: 40 }, Note { freq: 523.25, ms: 60 }];
+pub const JUMP_HIGH: [Note; 2] = [Note { freq: 783.99, ms: 35 }, Note { freq: 1046.5, ms: 50 }];
+pub const JUMP_LOW: [Note; 2] = [Note { freq: 196.0, ms: 50 }, Note { freq: 261.63, ms: 70 }];
+pub const JUMP_CHIRP: [Note; 4] = [
+    Note { freq: 659.25, ms: 20 },
+    Note { freq: 880.0, ms: 20 },
+    Note { freq: 1174.66, ms: 20 },
+    Note { freq: 1567.98, ms: 40 },
+];
+
+// Dull thud when the player hits a tunnel ceiling
+pub const BONK: [Note; 2] = [Note { freq: 130.81, ms: 40 }, Note { freq: 98.0, ms: 70 }];
+
+// Clock tick while a power is running out
+pub const TICK: [Note; 1] = [Note { freq: 1760.0, ms: 25 }];
+
+// Ping for a coin picked up
+pub const COIN: [Note; 2] = [Note { freq: 1318.51, ms: 30 }, Note { freq: 1975.53, ms: 70 }];
+
+// Low rumble of debris hitting the ground
+pub const THUD: [Note; 3] = [
+    Note { freq: 82.41, ms: 50 },
+    Note { freq: 65.41, ms: 60 },
+    Note { freq: 55.0, ms: 90 },
+];
+
+const MILESTONE_NOTES: [Note; 3] = [
+    Note { freq: 783.99, ms: 90 },
+    Note { freq: 987.77, ms: 90 },
+    Note { freq: 1174.66, ms: 260 },
+];
+const SHIELD_BREAK_NOTES: [Note; 3] = [
+    Note { freq: 659.25, ms: 80 },
+    Note { freq: 523.25, ms: 80 },
+    Note { freq: 440.0, ms: 220 },
+];
+const GAME_OVER_NOTES: [Note; 4] = [
+    Note { freq: 523.25, ms: 260 },
+    Note { freq: 493.88, ms: 260 },
+    Note { freq: 466.16, ms: 260 },
+    Note { freq: 440.0, ms: 700 },
+];
+
+// Short musical cues for game events, played over the music
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Stinger {
+    Milestone,
+    ShieldBreak,
+    GameOver,
+}
+
+impl Stinger {
+    // A stinger only cuts off the one playing if it's at least as important
+    fn priority(&self) -> u8 {
+        match self {
+            Stinger::Milestone => 0,
+            Stinger::ShieldBreak => 1,
+            Stinger::GameOver => 2,
+        }
+    }
+
+    fn notes(&self) -> &'static [Note] {
+        match self {
+            Stinger::Milestone => &MILESTONE_NOTES,
+            Stinger::ShieldBreak => &SHIELD_BREAK_NOTES,
+            Stinger::GameOver => &GAME_OVER_NOTES,
+        }
+    }
+}
+
+// Synthesized samples being played on a channel
+struct Voice {
+    samples: Vec<f32>,
+    cursor: usize,
+    looping: bool,
+    gain: (f32, f32), // Left and right
+}
+
+impl Voice {
+    fn new(samples: Vec<f32>, looping: bool) -> Voice {
+        Voice {
+            samples,
+            cursor: 0,
+            looping,
+            gain: (1.0, 1.0),
+        }
+    }
+
+    // Plays `offset` pixels to the right of the player, or left if negative
+    fn placed(mut self, offset: i32) -> Voice {
+        let pan = (offset as f64 / PAN_RANGE).clamp(-1.0, 1.0) * MAX_PAN;
+        let gain = 1.0 - (1.0 - MIN_GAIN) * (offset.abs() as f64 / HEARING_RANGE).min(1.0);
+        self.gain = (
+            ((1.0 - pan).min(1.0) * gain) as f32,
+            ((1.0 + pan).min(1.0) * gain) as f32,
+        );
+        self
+    }
+
+    fn next(&mut self) -> f32 {
+        if self.looping && self.cursor >= self.samples.len() {
+            self.cursor = 0;
+        }
+        let sample = self.samples.get(self.cursor).copied().unwrap_or(0.0);
+        self.cursor += 1;
+        sample
+    }
+
+    fn finished(&self) -> bool {
+        !self.looping && self.cursor >= self.samples.len()
+    }
+}
+
+// A looping channel that crossfades from what it was playing to whatever
+// it's given next
+struct Track {
+    playing: Option<Voice>,
+    leaving: Option<Voice>, // Fading out under the one playing
+    fade: f32,              // How far the crossfade has got, 1 once it's done
+    fade_step: f32,         // Change in fade per sample
+}
+
+impl Track {
+    fn new(fade_step: f32) -> Track {
+        Track {
+            playing: None,
+            leaving: None,
+            fade: 1.0,
+            fade_step,
+        }
+    }
+
+    // Starts `voice`, fading out what was playing if anything was
+    fn switch(&mut self, voice: Option<Voice>) {
+        self.leaving = self.playing.take();
+        self.playing = voice;
+        self.fade = if self.leaving.is_some() { 0.0 } else { 1.0 };
+    }
+
+    fn cut(&mut self) {
+        self.playing = None;
+        self.leaving = None;
+        self.fade = 1.0;
+    }
+
+    fn next(&mut self) -> f32 {
+        self.fade = (self.fade + self.fade_step).min(1.0);
+        if self.fade >= 1.0 {
+            self.leaving = None;
+        }
+        let fade = self.fade;
+        let incoming = self.playing.as_mut().map_or(0.0, |v| v.next() * fade);
+        let outgoing = self.leaving.as_mut().map_or(0.0, |v| v.next() * (1.0 - fade));
+        incoming + outgoing
+    }
+}
+
+struct Mixer {
+    music: Track,
+    ambience: Track,
+    stinger: Option<(Stinger, Voice)>,
+    effects: Vec<Voice>,
+    duck: f32,       // Current music gain, 1 when nothing is ducking it
+    duck_step: f32,  // Change in gain per sample
+    channels: usize, // What the device gave us, effects are only panned in stereo
+}
+
+impl AudioCallback for Mixer {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        for frame in out.chunks_mut(self.channels) {
+            if matches!(&self.stinger, Some((_, voice)) if voice.finished()) {
+                self.stinger = None;
+            }
+            let target = if self.stinger.is_some() { DUCK_GAIN } else { 1.0 };
+            if self.duck < target {
+                self.duck = (self.duck + self.duck_step).min(target);
+            } else {
+                self.duck = (self.duck - self.duck_step).max(target);
+            }
+
+            let mut mix = self.music.next() * self.duck + self.ambience.next();
+            if let Some((_, voice)) = self.stinger.as_mut() {
+                mix += voice.next();
+            }
+            let (mut left, mut right) = (mix, mix);
+            for effect in self.effects.iter_mut() {
+                let sample = effect.next();
+                left += sample * effect.gain.0;
+                right += sample * effect.gain.1;
+            }
+            match frame {
+                [mono] => *mono = ((left + right) / 2.0).clamp(-1.0, 1.0),
+                [l, r, rest @ ..] => {
+                    *l = left.clamp(-1.0, 1.0);
+                    *r = right.clamp(-1.0, 1.0);
+                    // Surround channels stay quiet
+                    rest.iter_mut().for_each(|s| *s = 0.0);
+                }
+                [] => {}
+            }
+        }
+        self.effects.retain(|e| !e.finished());
+    }
+}
+
+pub struct Audio {
+    device: Option<AudioDevice<Mixer>>,
+    rate: i32,
+    volume: f64, // 0 to 1
+}
+
+impl Audio {
+    // Opens the default output device at `volume` percent
+    pub fn open(core: &SDLCore, volume: i32) -> Audio {
+        let desired = AudioSpecDesired {
+            freq: Some(SAMPLE_RATE),
+            channels: Some(2),
+            samples: None,
+        };
+        let device = core.audio().and_then(|audio| {
+            audio.open_playback(None, &desired, |spec| Mixer {
+                music: Track::new(1.0 / (CROSSFADE_SECS * spec.freq as f32)),
+                ambience: Track::new(1.0 / (CROSSFADE_SECS * spec.freq as f32)),
+                stinger: None,
+                effects: Vec::new(),
+                duck: 1.0,
+                duck_step: 1.0 / (DUCK_SECS * spec.freq as f32),
+                channels: (spec.channels as usize).max(1),
+            })
+        });
+        match device {
+            Ok(device) => {
+                device.resume();
+                let rate = device.spec().freq;
+                Audio {
+                    device: Some(device),
+                    rate,
+                    volume: (volume as f64 / 100.0).clamp(0.0, 1.0),
+                }
+            }
+            Err(e) => {
+                warn!("No audio: {}", e);
+                Audio::disabled()
+            }
+        }
+    }
+
+    pub fn disabled() -> Audio {
+        Audio {
+            device: None,
+            rate: SAMPLE_RATE,
+            volume: 0.0,
+        }
+    }
+
+    // Plays a one-off effect over everything else
+    pub fn play(&mut self, notes: &[Note]) {
+        let samples = synth(notes, self.rate, EFFECT_LEVEL * self.volume);
+        if let Some(device) = self.device.as_mut() {
+            device.lock().effects.push(Voice::new(samples, false));
+        }
+    }
+
+    // Plays a one-off effect from something `offset` pixels to the right of
+    // the player, or to the left if it's negative
+    pub fn play_at(&mut self, notes: &[Note], offset: i32) {
+        let samples = synth(notes, self.rate, EFFECT_LEVEL * self.volume);
+        if let Some(device) = self.device.as_mut() {
+            device.lock().effects.push(Voice::new(samples, false).placed(offset));
+        }
+    }
+
+    // Loops `notes` as the music, crossfading from what was playing
+    pub fn play_music(&mut self, notes: &[Note]) {
+        let samples = synth(notes, self.rate, MUSIC_LEVEL * self.volume);
+        if let Some(device) = self.device.as_mut() {
+            device.lock().music.switch(Some(Voice::new(samples, true)));
+        }
+    }
+
+    pub fn stop_music(&mut self) {
+        if let Some(device) = self.device.as_mut() {
+            device.lock().music.cut();
+        }
+    }
+
+    // Loops `ambience` under the music, crossfading from what was playing
+    pub fn play_ambience(&mut self, ambience: Ambience) {
+        if self.device.is_none() {
+            return;
+        }
+        let samples = synth_ambience(ambience, self.rate, AMBIENCE_LEVEL * self.volume);
+        if let Some(device) = self.device.as_mut() {
+            device.lock().ambience.switch(Some(Voice::new(samples, true)));
+        }
+    }
+
+    // Moves the music and ambience over to `biome`'s, call it whenever the
+    // biome changes
+    pub fn set_biome(&mut self, biome: Biome) {
+        let def = biome.def();
+        self.play_music(def.music);
+        self.play_ambience(def.ambience);
+    }
+
+    // Plays a stinger over the music unless a more important one is playing
+    pub fn stinger(&mut self, stinger: Stinger) {
+        let device = match self.device.as_mut() {
+            Some(device) => device,
+            None => return,
+        };
+        let samples = synth(stinger.notes(), self.rate, STINGER_LEVEL * self.volume);
+        let mut mixer = device.lock();
+        if let Some((playing, _)) = &mixer.stinger {
+            if playing.priority() > stinger.priority() {
+                return;
+            }
+        }
+        mixer.stinger = Some((stinger, Voice::new(samples, false)));
+    }
+
+    // Silences every channel
+    pub fn stop(&mut self) {
+        if let Some(device) = self.device.as_mut() {
+            let mut mixer = device.lock();
+            mixer.music.cut();
+            mixer.ambience.cut();
+            mixer.stinger = None;
+            mixer.effects.clear();
+        }
+    }
+}
+
+// Sine waves with a fade in and out on every note
+fn synth(notes: &[Note], rate: i32, level: f64) -> Vec<f32> {
+    let mut samples = Vec::new();
+    if level <= 0.0 {
+        return samples;
+    }
+    for note in notes {
+        let len = (rate as u64 * note.ms as u64 / 1000) as usize;
+        let fade = ((FADE * rate as f64) as usize).max(1);
+        for i in 0..len {
+            let envelope = (i.min(len - i) as f64 / fade as f64).min(1.0);
+            let t = i as f64 / rate as f64;
+            samples.push(((2.0 * PI * note.freq * t).sin() * level * envelope) as f32);
+        }
+    }
+    samples
+}
+
+// Noise, hum or plinks, made to loop seamlessly every AMBIENCE_SECS
+fn synth_ambience(ambience: Ambience, rate: i32, level: f64) -> Vec<f32> {
+    if level <= 0.0 {
+        return Vec::new();
+    }
+    let len = (AMBIENCE_SECS * rate as f64) as usize;
+    let overlap = (AMBIENCE_OVERLAP_SECS * rate as f64) as usize;
+    // Swells that repeat a whole number of times over the loop
+    let swell = |t: f64, times: f64| (2.0 * PI * t * times / AMBIENCE_SECS).sin();
+
+    // White noise from a fixed seed, it doesn't need to be different each
+    // time and it's kept out of the game's random streams
+    let mut state: u32 = 0x2545_f491;
+    let mut noise = move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as f64 / u32::MAX as f64 * 2.0 - 1.0
+    };
+
+    let mut low = 0.0;
+    let mut samples: Vec<f64> = (0..len + overlap)
+        .map(|i| {
+            let t = i as f64 / rate as f64;
+            match ambience {
+                Ambience::Wind => {
+                    low += 0.02 * (noise() - low);
+                    low * (0.55 + 0.25 * swell(t, 2.0) + 0.2 * swell(t, 3.0))
+                }
+                Ambience::Waves => {
+                    low += 0.08 * (noise() - low);
+                    low * (0.5 + 0.5 * swell(t, 2.0)).powi(3)
+                }
+                Ambience::Hum => {
+                    let hum = (2.0 * PI * 60.0 * t).sin() * 0.6
+                        + (2.0 * PI * 120.0 * t).sin() * 0.3
+                        + (2.0 * PI * 180.0 * t).sin() * 0.1;
+                    hum * (1.0 + 0.1 * swell(t, 2.0))
+                }
+                Ambience::Drips => [
+                    (0.7, 1800.0),
+                    (2.3, 2200.0),
+                    (3.1, 1600.0),
+                    (5.6, 2000.0),
+                    (6.9, 2400.0),
+                ]
+                .iter()
+                .filter(|&&(at, _)| t >= at && t < at + 0.2)
+                .map(|&(at, freq)| (2.0 * PI * freq * (t - at)).sin() * (-25.0 * (t - at)).exp())
+                .sum(),
+            }
+        })
+        .collect();
+
+    // Blend the end into the start, so the loop runs straight on from its
+    // last sample back into its first
+    for i in 0..overlap {
+        let t = i as f64 / overlap as f64;
+        samples[i] = samples[i] * t + samples[len + i] * (1.0 - t);
+    }
+    samples.truncate(len);
+
+    let peak = samples.iter().fold(0.0f64, |peak, s| peak.max(s.abs()));
+    let scale = if peak > 0.0 { level / peak } else { 0.0 };
+    samples.iter().map(|s| (s * scale) as f32).collect()
+}
@@ -0,0 +1,167 @@
+// A simple computer player, used for the attract demo, the in-run assist and
+// `--bot` soak tests. Each step it casts rays ahead of the player at foot and
+// body height and jumps when one of them hits an obstacle that would end the
+// run, or when a hazard strip is right ahead, unless something overhead is in
+// the way. It never flips, so it always
+// lands upright.
+//
+// The auto-jump assist uses the same rays but only ever makes the smallest
+// jump that clears an obstacle, as late as it still can, so the player is
+// left to do everything else.
+
+use crate::physics::{Body, Entity, Obstacle, Player};
+use crate::ObstacleType;
+use crate::TILE_SIZE;
+
+use sdl2::rect::Point;
+use std::time::Duration;
+
+// Steps of travel ahead the rays reach
+const REACTION_STEPS: f64 = 18.0;
+// Rays always reach at least this far, so it still reacts when crawling
+const MIN_RAY_LENGTH: i32 = 2 * TILE_SIZE as i32;
+// Obstacles closer than this get the highest jump
+const CLOSE_DISTANCE: i32 = TILE_SIZE as i32;
+// Obstacles floating this far above the ground are overhead, jumping into
+// them is worse than running under
+const OVERHEAD_CLEARANCE: i32 = 2 * TILE_SIZE as i32;
+// Steps of travel before an obstacle auto-jump takes off at, and the closest
+// it waits for
+const HOP_LEAD_STEPS: f64 = 12.0;
+const MIN_HOP_LEAD: i32 = CLOSE_DISTANCE + TILE_SIZE as i32 / 2;
+
+pub struct Autopilot;
+
+impl Autopilot {
+    pub fn new() -> Autopilot {
+        Autopilot
+    }
+
+    /*  How long to hold jump this step, None to keep skating
+     *
+     *  - Takes in `hazards` which are the screen x spans of hazard strips
+     *  - Takes in `ground_y` which gives the screen y of the ground at a
+     *    screen x, used to tell floating obstacles from ones on the ground
+     */
+    pub fn decide<F>(
+        &self,
+        player: &Player,
+        obstacles: &[Obstacle],
+        hazards: &[(i32, i32)],
+        ground_y: F,
+    ) -> Option<Duration>
+    where
+        F: Fn(i32) -> i32,
+    {
+        if player.is_jumping() {
+            return None;
+        }
+        let (nearest, under_ceiling) = self.threat(player, obstacles, hazards, ground_y)?;
+        Some(if under_ceiling {
+            Duration::from_millis(50)
+        } else if nearest <= CLOSE_DISTANCE {
+            Duration::from_millis(300)
+        } else {
+            Duration::from_millis(150)
+        })
+    }
+
+    // How long to hold jump this step for the auto-jump assist, which waits
+    // until the last moment a small jump clears the obstacle
+    pub fn auto_jump<F>(
+        &self,
+        player: &Player,
+        obstacles: &[Obstacle],
+        hazards: &[(i32, i32)],
+        ground_y: F,
+    ) -> Option<Duration>
+    where
+        F: Fn(i32) -> i32,
+    {
+        if player.is_jumping() {
+            return None;
+        }
+        let (nearest, under_ceiling) = self.threat(player, obstacles, hazards, ground_y)?;
+        let lead = ((player.vel_x() * HOP_LEAD_STEPS) as i32).max(MIN_HOP_LEAD);
+        if under_ceiling {
+            Some(Duration::from_millis(50))
+        } else if nearest <= CLOSE_DISTANCE {
+            // Too late for anything smaller
+            Some(Duration::from_millis(300))
+        } else if nearest <= lead {
+            Some(Duration::from_millis(150))
+        } else {
+            None
+        }
+    }
+
+    /*  Distance to the nearest obstacle the rays hit, and whether something
+     *  overhead starts before it's cleared. None if nothing's in the way.
+     */
+    fn threat<F>(
+        &self,
+        player: &Player,
+        obstacles: &[Obstacle],
+        hazards: &[(i32, i32)],
+        ground_y: F,
+    ) -> Option<(i32, bool)>
+    where
+        F: Fn(i32) -> i32,
+    {
+        let front = player.hitbox().as_rect().right();
+        let reach = ((player.vel_x() * REACTION_STEPS) as i32).max(MIN_RAY_LENGTH);
+        let jump_reach = reach + 2 * TILE_SIZE as i32;
+        let rays = [
+            player.hitbox().as_rect().bottom() - 4, // Feet
+            player.center().y(),                    // Body
+        ];
+
+        // Springs are safe to hit, they just bounce the player
+        let dangerous = obstacles
+            .iter()
+            .filter(|o| !matches!(o.obstacle_type(), ObstacleType::Balloon) && !o.collided());
+
+        let mut nearest: Option<i32> = None;
+        let mut overhead: Option<i32> = None;
+        for o in dangerous {
+            let rect = o.hitbox().as_rect();
+            let distance = rect.left() - front;
+            if distance < 0 || distance > jump_reach {
+                continue;
+            }
+            if ground_y(rect.center().x()) - rect.bottom() >= OVERHEAD_CLEARANCE {
+                overhead = Some(overhead.map_or(distance, |d| d.min(distance)));
+                continue;
+            }
+            let hit = rays
+                .iter()
+                .filter_map(|&y| rect.intersect_line(Point::new(front, y), Point::new(front + reach, y)))
+                .map(|(hit, _)| hit.x() - front)
+                .min();
+            if let Some(d) = hit {
+                nearest = Some(nearest.map_or(d, |n| n.min(d)));
+            }
+        }
+
+        // Hazard strips are jumped from as close as is safe, which gets the
+        // highest jump to carry over the whole strip
+        for &(start, _) in hazards {
+            let distance = start - front;
+            if (0..=CLOSE_DISTANCE).contains(&distance) {
+                nearest = Some(nearest.map_or(distance, |n| n.min(distance)));
+            }
+        }
+
+        let nearest = nearest?;
+        // Keep a low profile under a ceiling that starts before the obstacle
+        // is cleared
+        let under_ceiling = overhead.is_some_and(|d| d <= nearest + TILE_SIZE as i32);
+        Some((nearest, under_ceiling))
+    }
+}
+
+impl Default for Autopilot {
+    fn default() -> Self {
+        Autopilot::new()
+    }
+}
@@ -0,0 +1,229 @@
+// Biomes, which mostly change the look of the background: each one has a
+// palette and the hill layers drawn from it, and dark ones only light up
+// around the player. Each has its own music and ambience, and its own
+// decoration in front of the terrain, too. A run's biomes are picked from
+// its visual random stream, so a seed always looks the same, and a gate
+// leads from one to the next.
+
+use crate::audio;
+use crate::audio::{Ambience, Note};
+use crate::decor::DecorKind;
+use crate::parallax::HillLayer;
+use crate::rng::GameRng;
+
+use sdl2::pixels::Color;
+
+use rand::Rng;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Biome {
+    Hills,
+    Dunes,
+    Mountains,
+    City,
+    Cave,
+}
+
+pub struct BiomeDef {
+    pub biome: Biome,
+    pub key: &'static str,
+    pub label: &'static str,
+    pub palette: &'static [Color],
+    pub layers: &'static [HillLayer], // Back to front
+    pub light_radius: Option<u32>,    // Pixels lit around the player, None for lit biomes
+    pub music: &'static [Note],
+    pub ambience: Ambience,
+    pub decor: &'static [DecorKind], // In front of the terrain, see decor
+}
+
+pub const BIOME_DEFS: [BiomeDef; 5] = [
+    BiomeDef {
+        biome: Biome::Hills,
+        key: "hills",
+        label: "Hills",
+        palette: &[Color::RGB(128, 51, 6), Color::RGB(96, 161, 152)],
+        layers: &[
+            HillLayer {
+                amp: (2.0, 7.0),
+                modifier: 1.0,
+                mul: 820.0,
+                scroll_every: 5,
+                flat_columns: 1,
+                palette: 0,
+            },
+            HillLayer {
+                amp: (1.0, 5.0),
+                modifier: 0.5,
+                mul: 600.0,
+                scroll_every: 3,
+                flat_columns: 1,
+                palette: 1,
+            },
+        ],
+        light_radius: None,
+        music: &audio::RUN_THEME,
+        ambience: Ambience::Wind,
+        decor: &[DecorKind::Grass, DecorKind::Fence],
+    },
+    BiomeDef {
+        biome: Biome::Dunes,
+        key: "dunes",
+        label: "Dunes",
+        palette: &[
+            Color::RGB(176, 120, 72),
+            Color::RGB(206, 152, 90),
+            Color::RGB(232, 190, 122),
+        ],
+        layers: &[
+            HillLayer {
+                amp: (1.5, 3.0),
+                modifier: 0.6,
+                mul: 900.0,
+                scroll_every: 7,
+                flat_columns: 1,
+                palette: 0,
+            },
+            HillLayer {
+                amp: (1.0, 2.5),
+                modifier: 0.5,
+                mul: 760.0,
+                scroll_every: 5,
+                flat_columns: 1,
+                palette: 1,
+            },
+            HillLayer {
+                amp: (0.5, 2.0),
+                modifier: 0.4,
+                mul: 620.0,
+                scroll_every: 3,
+                flat_columns: 1,
+                palette: 2,
+            },
+        ],
+        light_radius: None,
+        music: &audio::DUNES_THEME,
+        ambience: Ambience::Waves,
+        decor: &[DecorKind::DryGrass],
+    },
+    BiomeDef {
+        biome: Biome::Mountains,
+        key: "mountains",
+        label: "Mountains",
+        palette: &[
+            Color::RGB(120, 130, 160),
+            Color::RGB(78, 88, 112),
+            Color::RGB(44, 74, 56),
+        ],
+        layers: &[
+            HillLayer {
+                amp: (5.0, 9.0),
+                modifier: 1.4,
+                mul: 1000.0,
+                scroll_every: 8,
+                flat_columns: 1,
+                palette: 0,
+            },
+            HillLayer {
+                amp: (3.0, 7.0),
+                modifier: 1.0,
+                mul: 820.0,
+                scroll_every: 5,
+                flat_columns: 1,
+                palette: 1,
+            },
+            HillLayer {
+                amp: (1.0, 4.0),
+                modifier: 0.5,
+                mul: 600.0,
+                scroll_every: 3,
+                flat_columns: 1,
+                palette: 2,
+            },
+        ],
+        light_radius: None,
+        music: &audio::MOUNTAINS_THEME,
+        ambience: Ambience::Wind,
+        decor: &[DecorKind::Grass],
+    },
+    BiomeDef {
+        biome: Biome::City,
+        key: "city",
+        label: "City",
+        palette: &[Color::RGB(70, 62, 92), Color::RGB(40, 36, 58)],
+        layers: &[
+            HillLayer {
+                amp: (4.0, 8.0),
+                modifier: 1.2,
+                mul: 900.0,
+                scroll_every: 6,
+                flat_columns: 6,
+                palette: 0,
+            },
+            HillLayer {
+                amp: (3.0, 6.0),
+                modifier: 0.9,
+                mul: 700.0,
+                scroll_every: 3,
+                flat_columns: 4,
+                palette: 1,
+            },
+        ],
+        light_radius: None,
+        music: &audio::CITY_THEME,
+        ambience: Ambience::Hum,
+        decor: &[DecorKind::Lamp, DecorKind::Fence],
+    },
+    BiomeDef {
+        biome: Biome::Cave,
+        key: "cave",
+        label: "Cave",
+        palette: &[Color::RGB(46, 40, 44), Color::RGB(30, 26, 30)],
+        layers: &[
+            HillLayer {
+                amp: (3.0, 6.0),
+                modifier: 1.6,
+                mul: 780.0,
+                scroll_every: 5,
+                flat_columns: 1,
+                palette: 0,
+            },
+            HillLayer {
+                amp: (2.0, 5.0),
+                modifier: 1.2,
+                mul: 620.0,
+                scroll_every: 3,
+                flat_columns: 1,
+                palette: 1,
+            },
+        ],
+        light_radius: Some(260),
+        music: &audio::CAVE_THEME,
+        ambience: Ambience::Drips,
+        decor: &[DecorKind::Stalagmite],
+    },
+];
+
+impl Biome {
+    // Position of the biome in BIOME_DEFS
+    pub fn index(&self) -> usize {
+        BIOME_DEFS.iter().position(|d| d.biome == *self).unwrap()
+    }
+
+    pub fn def(&self) -> &'static BiomeDef {
+        &BIOME_DEFS[self.index()]
+    }
+
+    pub fn from_key(key: &str) -> Option<Biome> {
+        BIOME_DEFS.iter().find(|d| d.key == key).map(|d| d.biome)
+    }
+
+    pub fn pick(rng: &mut GameRng) -> Biome {
+        BIOME_DEFS[rng.gen_range(0..BIOME_DEFS.len())].biome
+    }
+
+    // Any biome but this one, for the next along a run
+    pub fn pick_next(&self, rng: &mut GameRng) -> Biome {
+        let i = rng.gen_range(0..BIOME_DEFS.len() - 1);
+        BIOME_DEFS[if i >= self.index() { i + 1 } else { i }].biome
+    }
+}
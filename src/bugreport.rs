@@ -0,0 +1,187 @@
+// Bug report bundles. F9 in a run saves a zip to reports/ with everything
+// needed to chase down a physics or procgen problem: a screenshot of the
+// frame, the last game events, the seed and where the player was, the inputs
+// so far and the config file. Playtesters attach the zip to their issue.
+//
+// Zips are written by hand, stored without compression, to avoid pulling in a
+// crate for something this small.
+
+use crate::config::Config;
+use crate::config::CONFIG_PATH;
+
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::render::WindowCanvas;
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const REPORT_DIR: &str = "reports";
+
+pub struct BugReport {
+    files: Vec<(String, Vec<u8>)>,
+}
+
+impl BugReport {
+    // Starts a report with a copy of the current profile's config file, when
+    // there is one
+    pub fn new() -> BugReport {
+        let mut report = BugReport { files: Vec::new() };
+        if let Ok(bytes) = fs::read(Config::current().path()) {
+            report.add(CONFIG_PATH, bytes);
+        }
+        report
+    }
+
+    pub fn add(&mut self, name: &str, bytes: Vec<u8>) {
+        self.files.push((name.to_string(), bytes));
+    }
+
+    // Adds a screenshot of what's been drawn to `canvas` so far this frame,
+    // so call it before presenting
+    pub fn add_screenshot(&mut self, canvas: &WindowCanvas) -> Result<(), String> {
+        // Pixels are read at the window's real resolution, not the logical one
+        let (scale_x, scale_y) = canvas.scale();
+        let viewport = canvas.viewport();
+        let width = (viewport.width() as f32 * scale_x).round() as u32;
+        let height = (viewport.height() as f32 * scale_y).round() as u32;
+        let pixels = canvas.read_pixels(None, PixelFormatEnum::RGB24)?;
+        if pixels.len() != (width * height * 3) as usize {
+            return Err(format!(
+                "Read {} bytes for a {}x{} screenshot",
+                pixels.len(),
+                width,
+                height
+            ));
+        }
+        self.add("screenshot.bmp", bmp(&pixels, width, height));
+        Ok(())
+    }
+
+    // Writes the zip to reports/ and returns where it went
+    pub fn write(&self) -> Result<PathBuf, String> {
+        fs::create_dir_all(REPORT_DIR).map_err(|e| e.to_string())?;
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+        let path = PathBuf::from(REPORT_DIR).join(format!("report-{}.zip", stamp));
+        fs::write(&path, self.zip()).map_err(|e| e.to_string())?;
+        Ok(path)
+    }
+
+    fn zip(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+        for (name, bytes) in self.files.iter() {
+            let offset = out.len() as u32;
+            let crc = crc32(bytes);
+            let size = bytes.len() as u32;
+
+            // Local file header
+            put32(&mut out, 0x0403_4b50);
+            put16(&mut out, 20); // Version needed, 2.0
+            put16(&mut out, 0); // Flags
+            put16(&mut out, 0); // Stored
+            put16(&mut out, 0); // Time
+            put16(&mut out, 0x21); // Date, 1980-01-01
+            put32(&mut out, crc);
+            put32(&mut out, size);
+            put32(&mut out, size);
+            put16(&mut out, name.len() as u16);
+            put16(&mut out, 0); // Extra field length
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(bytes);
+
+            // Central directory entry
+            put32(&mut central, 0x0201_4b50);
+            put16(&mut central, 20); // Version made by
+            put16(&mut central, 20);
+            put16(&mut central, 0);
+            put16(&mut central, 0);
+            put16(&mut central, 0);
+            put16(&mut central, 0x21);
+            put32(&mut central, crc);
+            put32(&mut central, size);
+            put32(&mut central, size);
+            put16(&mut central, name.len() as u16);
+            put16(&mut central, 0); // Extra field length
+            put16(&mut central, 0); // Comment length
+            put16(&mut central, 0); // Disk number
+            put16(&mut central, 0); // Internal attributes
+            put32(&mut central, 0); // External attributes
+            put32(&mut central, offset);
+            central.extend_from_slice(name.as_bytes());
+        }
+
+        let central_offset = out.len() as u32;
+        out.extend_from_slice(&central);
+        // End of central directory
+        put32(&mut out, 0x0605_4b50);
+        put16(&mut out, 0);
+        put16(&mut out, 0);
+        put16(&mut out, self.files.len() as u16);
+        put16(&mut out, self.files.len() as u16);
+        put32(&mut out, central.len() as u32);
+        put32(&mut out, central_offset);
+        put16(&mut out, 0); // Comment length
+        out
+    }
+}
+
+impl Default for BugReport {
+    fn default() -> Self {
+        BugReport::new()
+    }
+}
+
+fn put16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+// 24 bit BMP, which stores rows bottom up in BGR padded to 4 bytes
+fn bmp(pixels: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let row = (width * 3) as usize;
+    let padded = row.next_multiple_of(4);
+    let data_size = (padded * height as usize) as u32;
+    let mut out = Vec::with_capacity(54 + data_size as usize);
+    out.extend_from_slice(b"BM");
+    put32(&mut out, 54 + data_size);
+    put32(&mut out, 0);
+    put32(&mut out, 54); // Offset of the pixels
+    put32(&mut out, 40); // Info header size
+    put32(&mut out, width);
+    put32(&mut out, height);
+    put16(&mut out, 1); // Planes
+    put16(&mut out, 24); // Bits per pixel
+    put32(&mut out, 0); // No compression
+    put32(&mut out, data_size);
+    put32(&mut out, 2835); // 72 DPI
+    put32(&mut out, 2835);
+    put32(&mut out, 0);
+    put32(&mut out, 0);
+    for y in (0..height as usize).rev() {
+        let start = y * row;
+        for pixel in pixels[start..start + row].chunks(3) {
+            out.extend_from_slice(&[pixel[2], pixel[1], pixel[0]]);
+        }
+        out.resize(out.len() + padded - row, 0);
+    }
+    out
+}
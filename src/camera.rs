@@ -0,0 +1,152 @@
+// Vertical camera framing. Keeps the ground under the player between an upper
+// and lower bound, and eases the view towards what's coming: the ground is
+// pulled up the screen before big drops and pushed down before climbs, so the
+// next crest or dip is already in view when the player gets there.
+
+use crate::config::Config;
+use crate::TILE_SIZE;
+
+#[derive(Copy, Clone)]
+pub struct CameraConfig {
+    // Screen y range the player's ground is always held in
+    pub upper_bound: i32,
+    pub lower_bound: i32,
+    // Pixels ahead of the player the terrain is looked at
+    pub lookahead: i32,
+    // How far the framing leans into an upcoming slope, per pixel of height
+    // change ahead
+    pub bias: f64,
+    // Fraction of the remaining distance to the ideal framing covered per step
+    pub smoothing: f64,
+}
+
+impl CameraConfig {
+    // Defaults for a screen of the given height
+    pub fn new(screen_h: i32) -> CameraConfig {
+        let margin = 2 * TILE_SIZE as i32;
+        CameraConfig {
+            upper_bound: margin,
+            lower_bound: screen_h - margin,
+            lookahead: 480,
+            bias: 0.5,
+            smoothing: 0.05,
+        }
+    }
+
+    // Reads the `camera_*` keys of the config file
+    pub fn from_config(config: &Config, screen_h: i32) -> CameraConfig {
+        let defaults = CameraConfig::new(screen_h);
+        // The ground is a tile tall, the upper bound leaves room for it
+        // above the bottom of the screen
+        let upper_bound = config
+            .get_or("camera_upper", defaults.upper_bound)
+            .min(screen_h - TILE_SIZE as i32)
+            .max(0);
+        CameraConfig {
+            upper_bound,
+            lower_bound: config
+                .get_or("camera_lower", defaults.lower_bound)
+                .max(upper_bound + TILE_SIZE as i32)
+                .min(screen_h),
+            lookahead: config.get_or("camera_lookahead", defaults.lookahead).max(0),
+            bias: config.get_or("camera_bias", defaults.bias).clamp(0.0, 2.0),
+            smoothing: config.get_or("camera_smoothing", defaults.smoothing).clamp(0.0, 1.0),
+        }
+    }
+}
+
+pub struct Camera {
+    config: CameraConfig,
+}
+
+impl Camera {
+    pub fn new(config: CameraConfig) -> Camera {
+        Camera { config }
+    }
+
+    pub fn config(&self) -> &CameraConfig {
+        &self.config
+    }
+
+    /*  Vertical adjustment to apply to everything on screen this step
+     *
+     *  - Takes in `ground_y` which is the screen y of the ground under the player
+     *  - Takes in `ahead` which is the screen y of the ground at points up to
+     *    the lookahead distance in front of the player
+     */
+    pub fn adjust(&self, ground_y: i32, ahead: &[i32]) -> i32 {
+        let c = &self.config;
+
+        // Out of bounds is corrected right away, same as the fixed bounds did
+        if ground_y < c.upper_bound {
+            return c.upper_bound - ground_y;
+        } else if ground_y + TILE_SIZE as i32 > c.lower_bound {
+            return c.lower_bound - ground_y;
+        }
+        if ahead.is_empty() {
+            return 0;
+        }
+
+        // Positive when the terrain drops ahead, negative before a climb
+        let avg = ahead.iter().map(|&y| y as f64).sum::<f64>() / ahead.len() as f64;
+        let slope = avg - ground_y as f64;
+
+        let mid = (c.upper_bound + c.lower_bound) as f64 / 2.0;
+        let mut target = (mid - slope * c.bias).clamp(c.upper_bound as f64, (c.lower_bound - TILE_SIZE as i32) as f64);
+
+        // Keep the crest ahead below the upper bound and the lowest point above
+        // the lower one, the player's own ground wins if both can't fit
+        let crest = *ahead.iter().min().unwrap_or(&ground_y) - ground_y;
+        let dip = *ahead.iter().max().unwrap_or(&ground_y) - ground_y;
+        target = target.min((c.lower_bound - dip) as f64);
+        target = target.max((c.upper_bound - crest) as f64);
+        target = target.clamp(c.upper_bound as f64, (c.lower_bound - TILE_SIZE as i32) as f64);
+
+        ((target - ground_y as f64) * c.smoothing).round() as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A config with just the given keys, from a file that doesn't exist
+    fn config_with(entries: &[(&str, &str)]) -> Config {
+        let mut config = Config::load("no_such_dir/camera_test_config.txt");
+        for (key, value) in entries {
+            config.set(key, value);
+        }
+        config
+    }
+
+    #[test]
+    fn defaults_without_camera_keys() {
+        let camera = CameraConfig::from_config(&config_with(&[]), 720);
+        let defaults = CameraConfig::new(720);
+        assert_eq!(camera.upper_bound, defaults.upper_bound);
+        assert_eq!(camera.lower_bound, defaults.lower_bound);
+    }
+
+    #[test]
+    fn upper_bound_too_low_on_screen_leaves_room_for_the_ground() {
+        let camera = CameraConfig::from_config(&config_with(&[("camera_upper", "700")]), 720);
+        assert_eq!(camera.upper_bound, 720 - TILE_SIZE as i32);
+        assert_eq!(camera.lower_bound, 720);
+    }
+
+    #[test]
+    fn lower_bound_above_upper_bound_is_pushed_below_it() {
+        let config = config_with(&[("camera_upper", "300"), ("camera_lower", "100")]);
+        let camera = CameraConfig::from_config(&config, 720);
+        assert_eq!(camera.upper_bound, 300);
+        assert_eq!(camera.lower_bound, 300 + TILE_SIZE as i32);
+    }
+
+    #[test]
+    fn bounds_stay_on_screen() {
+        let config = config_with(&[("camera_upper", "-50"), ("camera_lower", "5000")]);
+        let camera = CameraConfig::from_config(&config, 720);
+        assert_eq!(camera.upper_bound, 0);
+        assert_eq!(camera.lower_bound, 720);
+    }
+}
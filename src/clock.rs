@@ -0,0 +1,150 @@
+// Simulation time. Real time only reaches the simulation through the clock,
+// which turns it into whole steps and takes none in while it's stopped, so
+// every timer counted against it stops with the game: while paused, while a
+// replay is paused, or before a run has started. Timers are deadlines on the
+// clock rather than counters, so nothing has to remember to count them down.
+//
+// A hit-stop freezes the clock for a few frames to give an impact weight. The
+// time that passes meanwhile is dropped, so it changes when steps play out
+// but never what happens in them.
+//
+// Frames rarely land right on a step, so the fraction of a step taken in but
+// not yet simulated is there for drawing things that far between the last two
+// steps. Motion stays smooth when frames and steps don't line up, and nothing
+// is ever drawn somewhere the simulation hasn't been.
+
+use crate::framerate::SIM_STEP;
+
+use std::time::Instant;
+
+// Longest stretch of real time the simulation catches up on in one go, so a
+// stall doesn't turn into a burst of steps
+pub const MAX_CATCH_UP: f64 = 0.25;
+
+pub struct SimClock {
+    step: u32,                  // Steps simulated so far
+    accumulator: f64,           // Real time not yet simulated, in seconds
+    last_feed: Option<Instant>, // None while stopped
+    frozen_frames: u32,         // Frames left of a hit-stop
+}
+
+impl Default for SimClock {
+    fn default() -> Self {
+        SimClock::new()
+    }
+}
+
+impl SimClock {
+    // A stopped clock at step 0
+    pub fn new() -> SimClock {
+        SimClock {
+            step: 0,
+            accumulator: 0.0,
+            last_feed: None,
+            frozen_frames: 0,
+        }
+    }
+
+    pub fn step(&self) -> u32 {
+        self.step
+    }
+
+    pub fn secs(&self) -> f64 {
+        self.step as f64 * SIM_STEP
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.last_feed.is_some()
+    }
+
+    // Stops taking in real time. Time already taken in but not yet stepped
+    // is kept for when the clock starts again.
+    pub fn stop(&mut self) {
+        self.last_feed = None;
+    }
+
+    // Takes in the real time since the last call, `speed` times over. The
+    // first call after the clock was stopped only starts it again.
+    pub fn feed(&mut self, now: Instant, speed: f64) {
+        if self.frozen_frames > 0 {
+            self.frozen_frames -= 1;
+            self.accumulator = 0.0;
+        } else if let Some(last) = self.last_feed {
+            self.accumulator += (now - last).as_secs_f64().min(MAX_CATCH_UP) * speed;
+        }
+        self.last_feed = Some(now);
+    }
+
+    // Fraction of a step taken in but not yet simulated, from 0 to 1
+    pub fn alpha(&self) -> f64 {
+        if self.frozen_frames > 0 {
+            return 0.0;
+        }
+        (self.accumulator / SIM_STEP).clamp(0.0, 1.0)
+    }
+
+    // Drops time taken in but not yet stepped
+    pub fn discard(&mut self) {
+        self.accumulator = 0.0;
+    }
+
+    // Has exactly `steps` more steps ready, and half of one more so
+    // rounding can't lose one, for skipping ahead
+    pub fn queue_steps(&mut self, steps: u32) {
+        self.accumulator = (steps as f64 + 0.5) * SIM_STEP;
+    }
+
+    // Freezes the clock for the next `frames` frames, from the step after
+    // this one
+    pub fn hit_stop(&mut self, frames: u32) {
+        self.frozen_frames = self.frozen_frames.max(frames);
+    }
+
+    // Takes a step's worth of time and advances the clock, if there's enough
+    pub fn tick(&mut self) -> bool {
+        if self.frozen_frames > 0 || self.accumulator < SIM_STEP {
+            return false;
+        }
+        self.accumulator -= SIM_STEP;
+        self.step += 1;
+        true
+    }
+
+    // A timer running out `steps` from now
+    pub fn timer(&self, steps: u32) -> Timer {
+        Timer {
+            start: self.step,
+            end: self.step + steps,
+        }
+    }
+}
+
+// Deadline on a SimClock
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Timer {
+    start: u32,
+    end: u32,
+}
+
+impl Timer {
+    // One that's already run out
+    pub fn done() -> Timer {
+        Timer { start: 0, end: 0 }
+    }
+
+    pub fn steps_left(&self, clock: &SimClock) -> u32 {
+        self.end.saturating_sub(clock.step())
+    }
+
+    pub fn is_done(&self, clock: &SimClock) -> bool {
+        self.steps_left(clock) == 0
+    }
+
+    // From 1 when started down to 0 when done
+    pub fn fraction_left(&self, clock: &SimClock) -> f64 {
+        match self.end - self.start {
+            0 => 0.0,
+            length => self.steps_left(clock) as f64 / length as f64,
+        }
+    }
+}
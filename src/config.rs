@@ -0,0 +1,96 @@
+// Plain text `key = value` config file. Settings are kept per profile, in a
+// config.txt in the profile's folder, and the one in the game folder is only
+// used without a profile.
+// Lines starting with '#' are comments. Unknown keys are kept and written back
+// out untouched so older builds don't wipe settings added by newer ones.
+
+use crate::profile::Profile;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const CONFIG_PATH: &str = "config.txt";
+
+pub struct Config {
+    path: PathBuf,
+    entries: Vec<(String, String)>,
+}
+
+impl Config {
+    // Reads the config file at `path`. A missing or unreadable file just
+    // results in an empty config, so every key falls back to its default.
+    pub fn load<P: AsRef<Path>>(path: P) -> Config {
+        let path = path.as_ref().to_path_buf();
+        let entries = match fs::read_to_string(&path) {
+            Ok(text) => parse(&text),
+            Err(_) => Vec::new(),
+        };
+        Config { path, entries }
+    }
+
+    // Settings of the given profile, or the game folder's without one. A
+    // profile that hasn't saved any settings yet starts from the game
+    // folder's, and saves them to its own file from then on.
+    pub fn for_profile(profile: Option<&Profile>) -> Config {
+        let global = Config::load(CONFIG_PATH);
+        match profile {
+            Some(profile) => {
+                let path = profile.path(CONFIG_PATH);
+                if path.is_file() {
+                    Config::load(path)
+                } else {
+                    Config { path, ..global }
+                }
+            }
+            None => global,
+        }
+    }
+
+    // Settings of the profile that's selected now
+    pub fn current() -> Config {
+        Config::for_profile(Profile::current().as_ref())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    // Parses the value of `key`, falling back to `default` when it's missing or
+    // malformed
+    pub fn get_or<T: std::str::FromStr>(&self, key: &str, default: T) -> T {
+        self.get(key).and_then(|v| v.parse().ok()).unwrap_or(default)
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) {
+        match self.entries.iter_mut().find(|(k, _)| k == key) {
+            Some(entry) => entry.1 = value.to_string(),
+            None => self.entries.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let mut text = String::new();
+        for (k, v) in self.entries.iter() {
+            text.push_str(&format!("{} = {}\n", k, v));
+        }
+        fs::write(&self.path, text).map_err(|e| e.to_string())
+    }
+}
+
+fn parse(text: &str) -> Vec<(String, String)> {
+    let mut entries = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some((k, v)) = line.split_once('=') {
+            entries.push((k.trim().to_string(), v.trim().to_string()));
+        }
+    }
+    entries
+}
@@ -0,0 +1,235 @@
+// Cosmetics: a trail behind the player and the sound a jump makes, picked on
+// the pre-run screen and kept in the profile's progress. They only change how
+// a run looks and sounds, so they're free and input logs don't record them.
+
+use crate::audio;
+use crate::audio::Note;
+use crate::rect;
+
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+
+use std::collections::VecDeque;
+
+// Points a trail keeps, one per step
+const TRAIL_LEN: usize = 24;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TrailStyle {
+    Off,
+    Sparks,
+    Rainbow,
+    Smoke,
+}
+
+pub struct TrailDef {
+    pub style: TrailStyle,
+    pub key: &'static str, // Name in progress files
+    pub label: &'static str,
+    pub colors: &'static [Color], // Cycled along the trail, none draws nothing
+    pub size: u32,                // Pixels across at the player's end
+}
+
+pub const TRAIL_DEFS: [TrailDef; 4] = [
+    TrailDef {
+        style: TrailStyle::Off,
+        key: "off",
+        label: "Off",
+        colors: &[],
+        size: 0,
+    },
+    TrailDef {
+        style: TrailStyle::Sparks,
+        key: "sparks",
+        label: "Sparks",
+        colors: &[Color::RGB(255, 210, 60), Color::RGB(255, 150, 30)],
+        size: 8,
+    },
+    TrailDef {
+        style: TrailStyle::Rainbow,
+        key: "rainbow",
+        label: "Rainbow",
+        colors: &[
+            Color::RGB(255, 60, 60),
+            Color::RGB(255, 170, 40),
+            Color::RGB(250, 240, 60),
+            Color::RGB(70, 220, 90),
+            Color::RGB(60, 140, 255),
+            Color::RGB(170, 80, 255),
+        ],
+        size: 12,
+    },
+    TrailDef {
+        style: TrailStyle::Smoke,
+        key: "smoke",
+        label: "Smoke",
+        colors: &[Color::RGB(150, 150, 150), Color::RGB(110, 110, 110)],
+        size: 18,
+    },
+];
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum JumpSound {
+    Classic,
+    High,
+    Low,
+    Chirp,
+}
+
+pub struct JumpSoundDef {
+    pub sound: JumpSound,
+    pub key: &'static str, // Name in progress files
+    pub label: &'static str,
+    pub notes: &'static [Note],
+}
+
+pub const JUMP_SOUND_DEFS: [JumpSoundDef; 4] = [
+    JumpSoundDef {
+        sound: JumpSound::Classic,
+        key: "classic",
+        label: "Classic",
+        notes: &audio::JUMP,
+    },
+    JumpSoundDef {
+        sound: JumpSound::High,
+        key: "high",
+        label: "High",
+        notes: &audio::JUMP_HIGH,
+    },
+    JumpSoundDef {
+        sound: JumpSound::Low,
+        key: "low",
+        label: "Low",
+        notes: &audio::JUMP_LOW,
+    },
+    JumpSoundDef {
+        sound: JumpSound::Chirp,
+        key: "chirp",
+        label: "Chirp",
+        notes: &audio::JUMP_CHIRP,
+    },
+];
+
+impl TrailStyle {
+    // Position of the style in TRAIL_DEFS
+    pub fn index(&self) -> usize {
+        TRAIL_DEFS.iter().position(|d| d.style == *self).unwrap()
+    }
+
+    pub fn def(&self) -> &'static TrailDef {
+        &TRAIL_DEFS[self.index()]
+    }
+
+    pub fn from_key(key: &str) -> Option<TrailStyle> {
+        TRAIL_DEFS.iter().find(|d| d.key == key).map(|d| d.style)
+    }
+
+    // The one after it in TRAIL_DEFS, wrapping around
+    pub fn next(&self) -> TrailStyle {
+        TRAIL_DEFS[(self.index() + 1) % TRAIL_DEFS.len()].style
+    }
+}
+
+impl JumpSound {
+    // Position of the sound in JUMP_SOUND_DEFS
+    pub fn index(&self) -> usize {
+        JUMP_SOUND_DEFS.iter().position(|d| d.sound == *self).unwrap()
+    }
+
+    pub fn def(&self) -> &'static JumpSoundDef {
+        &JUMP_SOUND_DEFS[self.index()]
+    }
+
+    pub fn from_key(key: &str) -> Option<JumpSound> {
+        JUMP_SOUND_DEFS.iter().find(|d| d.key == key).map(|d| d.sound)
+    }
+
+    // The one after it in JUMP_SOUND_DEFS, wrapping around
+    pub fn next(&self) -> JumpSound {
+        JUMP_SOUND_DEFS[(self.index() + 1) % JUMP_SOUND_DEFS.len()].sound
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Cosmetics {
+    pub trail: TrailStyle,
+    pub jump_sound: JumpSound,
+}
+
+impl Default for Cosmetics {
+    fn default() -> Self {
+        Cosmetics {
+            trail: TrailStyle::Off,
+            jump_sound: JumpSound::Classic,
+        }
+    }
+}
+
+// Where the player has been over the last few steps, in screen space, moved
+// along with the world so it streams out behind them
+pub struct Trail {
+    style: TrailStyle,
+    points: VecDeque<(f64, f64)>, // Oldest first
+}
+
+impl Trail {
+    pub fn new(style: TrailStyle) -> Trail {
+        Trail {
+            style,
+            points: VecDeque::with_capacity(TRAIL_LEN + 1),
+        }
+    }
+
+    // A full trail lying along a gentle arc from `from` to `to`, for showing
+    // the style off outside a run
+    pub fn preview(style: TrailStyle, from: (i32, i32), to: (i32, i32)) -> Trail {
+        let mut trail = Trail::new(style);
+        for i in 0..TRAIL_LEN {
+            let t = i as f64 / (TRAIL_LEN - 1) as f64;
+            let arc = (t * std::f64::consts::PI).sin() * 20.0;
+            trail.points.push_back((
+                from.0 as f64 + (to.0 - from.0) as f64 * t,
+                from.1 as f64 + (to.1 - from.1) as f64 * t - arc,
+            ));
+        }
+        trail
+    }
+
+    // Moves the trail with the world, `travel` pixels left and `camera_adj_y`
+    // down, then adds the player's center `at` to it
+    pub fn step(&mut self, at: (i32, i32), travel: f64, camera_adj_y: i32) {
+        if self.style == TrailStyle::Off {
+            return;
+        }
+        for point in self.points.iter_mut() {
+            point.0 -= travel;
+            point.1 += camera_adj_y as f64;
+        }
+        self.points.push_back((at.0 as f64, at.1 as f64));
+        if self.points.len() > TRAIL_LEN {
+            self.points.pop_front();
+        }
+    }
+
+    pub fn draw(&self, canvas: &mut WindowCanvas) -> Result<(), String> {
+        let def = self.style.def();
+        if def.colors.is_empty() {
+            return Ok(());
+        }
+        for (i, &(x, y)) in self.points.iter().enumerate() {
+            // Smaller and fainter towards the old end
+            let t = (i + 1) as f64 / self.points.len() as f64;
+            let color = def.colors[i % def.colors.len()];
+            let size = (def.size as f64 * (0.3 + 0.7 * t)).max(1.0) as u32;
+            canvas.set_draw_color(Color::RGBA(color.r, color.g, color.b, (200.0 * t) as u8));
+            canvas.fill_rect(rect!(
+                x as i32 - size as i32 / 2,
+                y as i32 - size as i32 / 2,
+                size,
+                size
+            ))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+// Crash reporter. A panic hook writes the panic message, a backtrace and what
+// the current run looked like (seed, distance, entity counts, the last few
+// game events) to crashes/, then tells the player where the report went in a
+// message box, since a panic otherwise just closes the window.
+//
+// The runner keeps the context up to date through `begin_run`, `update` and
+// `event`. These only take a lock briefly, the hook uses try_lock so a panic
+// while one of them holds the lock can't deadlock it.
+
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use sdl2::messagebox::{show_simple_message_box, MessageBoxFlag};
+
+const CRASH_DIR: &str = "crashes";
+// Game events kept for the report, oldest are dropped first. Bug reports
+// include them too.
+const MAX_EVENTS: usize = 200;
+
+struct RunContext {
+    seed: Option<u64>,
+    started: Option<Instant>,
+    distance: i32,
+    obstacles: usize,
+    coins: usize,
+    powers: usize,
+    events: VecDeque<String>,
+}
+
+static CONTEXT: Mutex<RunContext> = Mutex::new(RunContext {
+    seed: None,
+    started: None,
+    distance: 0,
+    obstacles: 0,
+    coins: 0,
+    powers: 0,
+    events: VecDeque::new(),
+});
+
+// Replaces the default panic hook. The default one still runs first, so the
+// panic shows up on stderr as usual.
+pub fn install() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        let report = build_report(info);
+        let message = match write_report(&report) {
+            Ok(path) => format!(
+                "Urban Odyssey crashed. A crash report was saved to\n{}\n\nPlease include it when reporting the problem.",
+                path.display()
+            ),
+            Err(e) => format!("Urban Odyssey crashed, and the crash report could not be saved: {}", e),
+        };
+        log::error!("{}", report);
+        log::logger().flush();
+        // Nothing more can be done if this fails too
+        let _ = show_simple_message_box(MessageBoxFlag::ERROR, "Urban Odyssey crashed", &message, None);
+    }));
+}
+
+// Starts tracking a new run
+pub fn begin_run(seed: u64) {
+    let mut context = lock();
+    context.seed = Some(seed);
+    context.started = Some(Instant::now());
+    context.distance = 0;
+    context.events.clear();
+    push_event(&mut context, format!("Run started with seed {}", seed));
+}
+
+// Stops tracking the current run
+pub fn end_run() {
+    let mut context = lock();
+    push_event(&mut context, "Run ended".to_string());
+    context.seed = None;
+}
+
+// Latest distance and entity counts of the run
+pub fn update(distance: i32, obstacles: usize, coins: usize, powers: usize) {
+    let mut context = lock();
+    context.distance = distance;
+    context.obstacles = obstacles;
+    context.coins = coins;
+    context.powers = powers;
+}
+
+// Records something that happened in the run, like a pickup or a hit
+pub fn event(text: &str) {
+    let mut context = lock();
+    push_event(&mut context, text.to_string());
+}
+
+// What the current run looks like, as written into a crash report
+pub fn context() -> String {
+    let mut text = String::new();
+    write_context(&mut text, &lock());
+    text
+}
+
+fn lock() -> std::sync::MutexGuard<'static, RunContext> {
+    // A poisoned lock only means a panic happened mid-update, the data is
+    // still fine for a report
+    CONTEXT.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn push_event(context: &mut RunContext, text: String) {
+    let time = context.started.map(|s| s.elapsed().as_secs_f64()).unwrap_or(0.0);
+    if context.events.len() >= MAX_EVENTS {
+        context.events.pop_front();
+    }
+    context.events.push_back(format!("[{:8.2}s] {}", time, text));
+}
+
+fn build_report(info: &PanicHookInfo) -> String {
+    let mut report = String::new();
+    report.push_str(&format!("Urban Odyssey {} crash report\n\n", env!("CARGO_PKG_VERSION")));
+    report.push_str(&format!("{}\n\n", info));
+    report.push_str(&format!("Backtrace:\n{}\n\n", Backtrace::force_capture()));
+
+    match CONTEXT.try_lock() {
+        Ok(context) => write_context(&mut report, &context),
+        Err(std::sync::TryLockError::Poisoned(e)) => write_context(&mut report, &e.into_inner()),
+        Err(std::sync::TryLockError::WouldBlock) => report.push_str("Run context unavailable (locked)\n"),
+    }
+    report
+}
+
+fn write_context(report: &mut String, context: &RunContext) {
+    match context.seed {
+        Some(seed) => {
+            report.push_str(&format!("Seed: {}\n", seed));
+            report.push_str(&format!("Distance: {}\n", context.distance));
+            report.push_str(&format!(
+                "Entities: {} obstacles, {} coins, {} powers\n",
+                context.obstacles, context.coins, context.powers
+            ));
+        }
+        None => report.push_str("Not in a run\n"),
+    }
+    report.push_str(&format!("\nLast {} events:\n", context.events.len()));
+    for event in context.events.iter() {
+        report.push_str(event);
+        report.push('\n');
+    }
+}
+
+fn write_report(report: &str) -> Result<PathBuf, String> {
+    fs::create_dir_all(CRASH_DIR).map_err(|e| e.to_string())?;
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let path = PathBuf::from(CRASH_DIR).join(format!("crash-{}.txt", stamp));
+    fs::write(&path, report).map_err(|e| e.to_string())?;
+    Ok(path)
+}
@@ -1,17 +1,19 @@
-use crate::rect;
+use inf_runner::assets;
+use inf_runner::rect;
 use inf_runner::Game;
 use inf_runner::GameState;
 use inf_runner::GameStatus;
 use inf_runner::SDLCore;
 
 use sdl2::event::Event;
-use sdl2::image::LoadTexture;
 use sdl2::keyboard::Keycode;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 use sdl2::render::Texture;
 use sdl2::render::TextureQuery;
 
+use std::rc::Rc;
+
 const CAM_W: u32 = 1280;
 const CAM_H: u32 = 720;
 const MOVE_PER_FRAME: u32 = 2;
@@ -21,11 +23,11 @@ pub struct Credits;
 struct Headshot<'a> {
     pos: Rect,
     src: Rect,
-    texture: Texture<'a>,
+    texture: Rc<Texture<'a>>,
 }
 
 impl<'a> Headshot<'a> {
-    fn new(pos: Rect, texture: Texture<'a>) -> Headshot {
+    fn new(pos: Rect, texture: Rc<Texture<'a>>) -> Headshot<'a> {
         let src = rect!(0, 0, 400, 400);
         Headshot { pos, src, texture }
     }
@@ -38,7 +40,7 @@ impl<'a> Headshot<'a> {
         self.src
     }
 
-    fn texture(&self) -> &Texture {
+    fn texture(&self) -> &Texture<'a> {
         &self.texture
     }
 }
@@ -55,10 +57,11 @@ impl Game for Credits {
 
         let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
 
-        let mut font = ttf_context.load_font("./assets/DroidSansMono.ttf", 128)?;
+        let mut font = assets::load_font(&ttf_context, 128)?;
         font.set_style(sdl2::ttf::FontStyle::BOLD);
 
         let texture_creator = core.wincan.texture_creator();
+        let mut textures = assets::TextureCache::new(&texture_creator);
 
         let surface = font
             .render("Caleb Kessler")
@@ -70,7 +73,7 @@ impl Game for Credits {
 
         let caleb_hs = Headshot::new(
             rect!((CAM_W / 2 - 400 / 2), 0, 400, 400),
-            texture_creator.load_texture("assets/headshots/caleb_hs.jpg")?,
+            textures.get(&core.assets, "assets/headshots/caleb_hs.jpg")?,
         );
 
         let surface = font
@@ -83,7 +86,7 @@ impl Game for Credits {
 
         let dane_hs = Headshot::new(
             rect!((CAM_W / 2 - 400 / 2), 0, 400, 400),
-            texture_creator.load_texture("assets/headshots/dane_hs.jpg")?,
+            textures.get(&core.assets, "assets/headshots/dane_hs.jpg")?,
         );
 
         let surface = font
@@ -96,7 +99,7 @@ impl Game for Credits {
 
         let andrew_hs = Headshot::new(
             rect!((CAM_W / 2 - 400 / 2), 0, 400, 400),
-            texture_creator.load_texture("assets/headshots/andrew_hs.png")?,
+            textures.get(&core.assets, "assets/headshots/andrew_hs.png")?,
         );
 
         let surface = font
@@ -109,7 +112,7 @@ impl Game for Credits {
 
         let benjamin_hs = Headshot::new(
             rect!((CAM_W / 2 - 400 / 2), 0, 400, 400),
-            texture_creator.load_texture("assets/headshots/benjamin_hs.jpg")?,
+            textures.get(&core.assets, "assets/headshots/benjamin_hs.jpg")?,
         );
 
         let surface = font
@@ -122,7 +125,7 @@ impl Game for Credits {
 
         let dominic_hs = Headshot::new(
             rect!((CAM_W / 2 - 400 / 2), 0, 400, 400),
-            texture_creator.load_texture("assets/headshots/dominic_hs.jpg")?,
+            textures.get(&core.assets, "assets/headshots/dominic_hs.jpg")?,
         );
 
         let surface = font
@@ -135,7 +138,7 @@ impl Game for Credits {
 
         let mateen_hs = Headshot::new(
             rect!((CAM_W / 2 - 400 / 2), 0, 400, 400),
-            texture_creator.load_texture("assets/headshots/mateen_hs.jpg")?,
+            textures.get(&core.assets, "assets/headshots/mateen_hs.jpg")?,
         );
 
         let surface = font
@@ -148,7 +151,7 @@ impl Game for Credits {
 
         let elliot_hs = Headshot::new(
             rect!((CAM_W / 2 - 400 / 2), 0, 400, 400),
-            texture_creator.load_texture("assets/headshots/elliot_hs.jpg")?,
+            textures.get(&core.assets, "assets/headshots/elliot_hs.jpg")?,
         );
 
         let surface = font
@@ -161,7 +164,7 @@ impl Game for Credits {
 
         let michael_hs = Headshot::new(
             rect!((CAM_W / 2 - 400 / 2), 0, 400, 400),
-            texture_creator.load_texture("assets/headshots/michael_hs.jpg")?,
+            textures.get(&core.assets, "assets/headshots/michael_hs.jpg")?,
         );
 
         let team = [
@@ -194,8 +197,11 @@ impl Game for Credits {
         'gameloop: loop {
             for event in core.event_pump.poll_iter() {
                 match event {
-                    Event::Quit { .. }
-                    | Event::KeyDown {
+                    Event::Quit { .. } => {
+                        next_status = GameStatus::Quit;
+                        break 'gameloop;
+                    }
+                    Event::KeyDown {
                         keycode: Some(Keycode::Escape | Keycode::Q),
                         ..
                     } => break 'gameloop,
@@ -231,10 +237,7 @@ impl Game for Credits {
             }
         }
 
-        Ok(GameState {
-            status: Some(next_status),
-            score: 0,
-        })
+        Ok(GameState::to(next_status))
     }
 }
 
@@ -0,0 +1,109 @@
+// Marks on the ground, for depth: blob shadows under anything off the ground,
+// and decals left where something happened. A shadow sits on the terrain right
+// below what casts it and shrinks and fades the higher that is, so it's clear
+// where a jump will come down. Decals are scorches where a run crashed and
+// skid marks after hard landings; they stay where they were made, scrolling
+// with the terrain, and fade out.
+
+use crate::rect;
+
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+
+// Height above the ground a shadow has shrunk and faded all it will by
+const SHADOW_FADE_HEIGHT: f64 = 400.0;
+const SHADOW_MIN_SCALE: f64 = 0.35;
+const SHADOW_ALPHA: f64 = 110.0;
+// Steps a decal lasts, the last FADE_STEPS of them fading out
+pub const DECAL_STEPS: u32 = 600;
+const FADE_STEPS: u32 = 120;
+
+// Soft shadow centered on `center_x`, `width` wide at full size, on ground at
+// `ground_y` under something `height` pixels above it
+pub fn draw_shadow(
+    canvas: &mut WindowCanvas,
+    center_x: i32,
+    width: u32,
+    ground_y: i32,
+    height: i32,
+) -> Result<(), String> {
+    let t = (height.max(0) as f64 / SHADOW_FADE_HEIGHT).min(1.0);
+    let scale = 1.0 - (1.0 - SHADOW_MIN_SCALE) * t;
+    let alpha = SHADOW_ALPHA * scale;
+    let w = width as f64 * scale;
+    // Two overlapping bands, darker in the middle, read as a blob
+    canvas.set_draw_color(Color::RGBA(0, 0, 0, (alpha / 2.0) as u8));
+    canvas.fill_rect(rect!(center_x - (w / 2.0) as i32, ground_y - 3, w, 6))?;
+    canvas.fill_rect(rect!(center_x - (w * 0.35) as i32, ground_y - 5, w * 0.7, 10))?;
+    Ok(())
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum DecalKind {
+    Scorch, // Where a run crashed
+    Skid,   // After a hard landing
+}
+
+struct Decal {
+    x: f64,
+    kind: DecalKind,
+    age: u32, // Steps
+}
+
+#[derive(Default)]
+pub struct Decals {
+    decals: Vec<Decal>,
+}
+
+impl Decals {
+    pub fn new() -> Decals {
+        Decals { decals: Vec::new() }
+    }
+
+    // Leaves a decal centered on `x` in screen space
+    pub fn add(&mut self, x: i32, kind: DecalKind) {
+        self.decals.push(Decal {
+            x: x as f64,
+            kind,
+            age: 0,
+        });
+    }
+
+    // Scrolls every decal `travel` pixels left with the terrain and ages it a
+    // step, dropping the ones that are offscreen or gone
+    pub fn step(&mut self, travel: f64) {
+        for decal in self.decals.iter_mut() {
+            decal.x -= travel;
+            decal.age += 1;
+        }
+        self.decals.retain(|d| d.age < DECAL_STEPS && d.x > -200.0);
+    }
+
+    // Draws every decal on the ground, `ground_y` gives its height at an x
+    pub fn draw<F>(&self, canvas: &mut WindowCanvas, ground_y: F) -> Result<(), String>
+    where
+        F: Fn(i32) -> i32,
+    {
+        for decal in self.decals.iter() {
+            let fade = ((DECAL_STEPS - decal.age) as f64 / FADE_STEPS as f64).min(1.0);
+            let x = decal.x as i32;
+            let y = ground_y(x);
+            match decal.kind {
+                DecalKind::Scorch => {
+                    canvas.set_draw_color(Color::RGBA(30, 20, 15, (150.0 * fade) as u8));
+                    canvas.fill_rect(rect!(x - 45, y - 4, 90, 8))?;
+                    canvas.set_draw_color(Color::RGBA(10, 5, 5, (170.0 * fade) as u8));
+                    canvas.fill_rect(rect!(x - 25, y - 6, 50, 12))?;
+                }
+                DecalKind::Skid => {
+                    // Two streaks trailing back from where the wheels came down
+                    canvas.set_draw_color(Color::RGBA(25, 25, 25, (140.0 * fade) as u8));
+                    canvas.fill_rect(rect!(x - 70, y - 3, 60, 3))?;
+                    canvas.fill_rect(rect!(x - 50, y + 1, 55, 3))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
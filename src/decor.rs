@@ -0,0 +1,143 @@
+// Decoration in front of the terrain: grass tufts, fence posts, lamp posts
+// and the like, depending on the biome. It's only there to be looked at,
+// nothing collides with it. Each terrain segment has its own scattered along
+// it when it's generated, from the visual random stream so gameplay never
+// sees it. It scrolls a little faster than the terrain, as if it were nearer
+// the camera, and sits just low enough to hide the player's feet as they
+// pass behind it.
+
+use crate::rect;
+use crate::rng::GameRng;
+
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+
+use rand::Rng;
+
+// How much faster than the terrain decoration scrolls
+pub const SCROLL: f64 = 1.15;
+// How far below the ground decoration stands
+pub const DROP: i32 = 18;
+// Pixels between one decoration and the next, picked from this range
+const SPACING: (i32, i32) = (70, 280);
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum DecorKind {
+    Grass,
+    DryGrass,
+    Fence,
+    Lamp,
+    Stalagmite,
+}
+
+pub struct DecorDef {
+    pub kind: DecorKind,
+    pub size: (u32, u32),
+    pub color: Color,
+}
+
+pub const DECOR_DEFS: [DecorDef; 5] = [
+    DecorDef {
+        kind: DecorKind::Grass,
+        size: (36, 34),
+        color: Color::RGB(34, 110, 40),
+    },
+    DecorDef {
+        kind: DecorKind::DryGrass,
+        size: (30, 28),
+        color: Color::RGB(170, 140, 70),
+    },
+    DecorDef {
+        kind: DecorKind::Fence,
+        size: (12, 70),
+        color: Color::RGB(110, 76, 44),
+    },
+    DecorDef {
+        kind: DecorKind::Lamp,
+        size: (10, 190),
+        color: Color::RGB(50, 52, 60),
+    },
+    DecorDef {
+        kind: DecorKind::Stalagmite,
+        size: (32, 60),
+        color: Color::RGB(70, 62, 58),
+    },
+];
+
+impl DecorKind {
+    // Position of the kind in DECOR_DEFS
+    pub fn index(&self) -> usize {
+        DECOR_DEFS.iter().position(|d| d.kind == *self).unwrap()
+    }
+
+    pub fn def(&self) -> &'static DecorDef {
+        &DECOR_DEFS[self.index()]
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Decor {
+    pub kind: DecorKind,
+    pub at: i32, // From the segment's x
+}
+
+// Decoration for a segment `width` pixels long, picked from `kinds`
+pub fn scatter(kinds: &[DecorKind], width: i32, rng: &mut GameRng) -> Vec<Decor> {
+    let mut decor = Vec::new();
+    if kinds.is_empty() {
+        return decor;
+    }
+    let mut at = rng.gen_range(0..SPACING.1);
+    while at < width {
+        decor.push(Decor {
+            kind: kinds[rng.gen_range(0..kinds.len())],
+            at,
+        });
+        at += rng.gen_range(SPACING.0..=SPACING.1);
+    }
+    decor
+}
+
+// Where decoration over the terrain's screen `x` is drawn, scrolled faster
+// than the terrain around the player's `anchor` x
+pub fn screen_x(x: i32, anchor: i32) -> i32 {
+    anchor + ((x - anchor) as f64 * SCROLL) as i32
+}
+
+// Draws `kind` standing at `x` on ground at `ground_y`
+pub fn draw(canvas: &mut WindowCanvas, kind: DecorKind, x: i32, ground_y: i32) -> Result<(), String> {
+    let def = kind.def();
+    let (w, h) = (def.size.0 as i32, def.size.1 as i32);
+    let base = ground_y + DROP;
+    canvas.set_draw_color(def.color);
+    match kind {
+        // A few blades of different heights
+        DecorKind::Grass | DecorKind::DryGrass => {
+            for (i, tall) in [0.7, 1.0, 0.55, 0.85].iter().enumerate() {
+                let blade = (h as f64 * tall) as i32;
+                canvas.fill_rect(rect!(x + i as i32 * w / 4, base - blade, 5, blade))?;
+            }
+        }
+        // A post with the stubs of its rails
+        DecorKind::Fence => {
+            canvas.fill_rect(rect!(x, base - h, w, h))?;
+            canvas.fill_rect(rect!(x - 14, base - h + 14, w + 28, 6))?;
+            canvas.fill_rect(rect!(x - 14, base - h + 38, w + 28, 6))?;
+        }
+        // A pole with its light hanging off the top
+        DecorKind::Lamp => {
+            canvas.fill_rect(rect!(x, base - h, w, h))?;
+            canvas.fill_rect(rect!(x, base - h, 34, 6))?;
+            canvas.set_draw_color(Color::RGB(255, 230, 150));
+            canvas.fill_rect(rect!(x + 24, base - h + 6, 14, 8))?;
+        }
+        // A stack of narrowing blocks
+        DecorKind::Stalagmite => {
+            for row in 0..4 {
+                canvas.fill_rect(rect!(x + row * w / 8, base - (row + 1) * h / 4, w - row * w / 4, h / 4))?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,120 @@
+// Rare random events that take over a run for a while, announced with a
+// banner when they start. Every so often the scheduler rolls whether one
+// starts, from the spawn stream so replays see the same ones, and only one
+// runs at a time. Which one it is is weighted by difficulty, so the nastier
+// ones come up more later in a run.
+
+use crate::rng::GameRng;
+
+use rand::Rng;
+
+// Steps into a run before the first roll, and between rolls after it
+const FIRST_CHECK_STEPS: u32 = 1800;
+const CHECK_STEPS: u32 = 600;
+// Chance that a roll starts an event
+const EVENT_CHANCE: f64 = 0.12;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum RandomEvent {
+    CoinRain,
+    Earthquake,
+}
+
+pub struct EventDef {
+    pub event: RandomEvent,
+    pub banner: &'static str,
+    pub steps: u32,         // How long it lasts
+    pub weight: (f64, f64), // Odds of being picked, at no difficulty and at full
+}
+
+pub const EVENT_DEFS: [EventDef; 2] = [
+    EventDef {
+        event: RandomEvent::CoinRain,
+        banner: "Coin Rain!",
+        steps: 600,
+        weight: (1.0, 1.0),
+    },
+    EventDef {
+        event: RandomEvent::Earthquake,
+        banner: "Earthquake!",
+        steps: 480,
+        weight: (0.3, 1.5),
+    },
+];
+
+impl RandomEvent {
+    // Position of the event in EVENT_DEFS
+    pub fn index(&self) -> usize {
+        EVENT_DEFS.iter().position(|d| d.event == *self).unwrap()
+    }
+
+    pub fn def(&self) -> &'static EventDef {
+        &EVENT_DEFS[self.index()]
+    }
+}
+
+impl EventDef {
+    // Odds of being picked at `difficulty`, from 0 to 1
+    pub fn weight_at(&self, difficulty: f64) -> f64 {
+        self.weight.0 + (self.weight.1 - self.weight.0) * difficulty.clamp(0.0, 1.0)
+    }
+}
+
+pub struct EventScheduler {
+    until_check: u32,
+    active: Option<(RandomEvent, u32)>, // With its steps left
+}
+
+impl Default for EventScheduler {
+    fn default() -> Self {
+        EventScheduler::new()
+    }
+}
+
+impl EventScheduler {
+    pub fn new() -> EventScheduler {
+        EventScheduler {
+            until_check: FIRST_CHECK_STEPS,
+            active: None,
+        }
+    }
+
+    // Moves a step along at `difficulty`, giving the event that starts this
+    // step if one does
+    pub fn step(&mut self, rng: &mut GameRng, difficulty: f64) -> Option<RandomEvent> {
+        if let Some((event, steps)) = self.active {
+            self.active = if steps > 1 { Some((event, steps - 1)) } else { None };
+            return None;
+        }
+        self.until_check = self.until_check.saturating_sub(1);
+        if self.until_check > 0 {
+            return None;
+        }
+        self.until_check = CHECK_STEPS;
+        if !rng.gen_bool(EVENT_CHANCE) {
+            return None;
+        }
+        let total: f64 = EVENT_DEFS.iter().map(|d| d.weight_at(difficulty)).sum();
+        let mut pick = rng.gen::<f64>() * total;
+        let mut event = EVENT_DEFS[0].event;
+        for def in EVENT_DEFS.iter() {
+            event = def.event;
+            pick -= def.weight_at(difficulty);
+            if pick < 0.0 {
+                break;
+            }
+        }
+        self.active = Some((event, event.def().steps));
+        Some(event)
+    }
+
+    // Event running now, if any
+    pub fn active(&self) -> Option<RandomEvent> {
+        self.active.map(|(event, _)| event)
+    }
+
+    // Steps left of the running event, 0 with none running
+    pub fn steps_left(&self) -> u32 {
+        self.active.map_or(0, |(_, steps)| steps)
+    }
+}
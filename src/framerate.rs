@@ -0,0 +1,69 @@
+// Target render frame rate. Only drawing follows this, the simulation always
+// steps at SIM_FPS no matter how often frames are presented. Menus and runs
+// sleep out the rest of each frame.
+
+use crate::config::Config;
+
+// Rate the physics and game logic are stepped at
+pub const SIM_FPS: f64 = 60.0;
+pub const SIM_STEP: f64 = 1.0 / SIM_FPS;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum FrameCap {
+    Fps30,
+    Fps60,
+    Fps120,
+    // No limit at all, for benchmarking
+    Uncapped,
+}
+
+impl FrameCap {
+    pub const ALL: [FrameCap; 4] = [FrameCap::Fps30, FrameCap::Fps60, FrameCap::Fps120, FrameCap::Uncapped];
+
+    // Reads the `fps` key of the config file: 30, 60, 120, or 0 for uncapped
+    pub fn from_config(config: &Config) -> FrameCap {
+        match config.get_or("fps", 60) {
+            30 => FrameCap::Fps30,
+            120 => FrameCap::Fps120,
+            0 => FrameCap::Uncapped,
+            _ => FrameCap::Fps60,
+        }
+    }
+
+    pub fn save(&self, config: &mut Config) -> Result<(), String> {
+        config.set("fps", &self.fps().unwrap_or(0).to_string());
+        config.save()
+    }
+
+    pub fn fps(&self) -> Option<u32> {
+        match self {
+            FrameCap::Fps30 => Some(30),
+            FrameCap::Fps60 => Some(60),
+            FrameCap::Fps120 => Some(120),
+            FrameCap::Uncapped => None,
+        }
+    }
+
+    // Seconds each frame should take, None when uncapped
+    pub fn frame_time(&self) -> Option<f64> {
+        self.fps().map(|fps| 1.0 / fps as f64)
+    }
+
+    // Vsync would hold faster caps to the display's refresh rate, so it's
+    // only used at or below 60
+    pub fn vsync(&self) -> bool {
+        matches!(self, FrameCap::Fps30 | FrameCap::Fps60)
+    }
+
+    pub fn next(&self) -> FrameCap {
+        let i = FrameCap::ALL.iter().position(|c| c == self).unwrap_or(0);
+        FrameCap::ALL[(i + 1) % FrameCap::ALL.len()]
+    }
+
+    pub fn label(&self) -> String {
+        match self.fps() {
+            Some(fps) => fps.to_string(),
+            None => "Uncapped".to_string(),
+        }
+    }
+}
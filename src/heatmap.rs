@@ -0,0 +1,75 @@
+// Recent spawn density, for checking that spawn tuning paces a run the way
+// it's meant to. Spawns are counted into buckets of a second of simulation
+// each, alongside where the difficulty curve was at the time, and the debug
+// overlay (F3) draws the last minute of them as a strip over the ground.
+
+use std::collections::VecDeque;
+
+// Simulation steps per bucket, and buckets kept
+pub const BUCKET_STEPS: u32 = 60;
+pub const BUCKETS: usize = 60;
+
+#[derive(Copy, Clone, Default, Debug)]
+pub struct Bucket {
+    pub obstacles: u32,
+    pub pickups: u32,        // Coins and powers
+    pub spawn_gap: i32,      // Minimum steps between spawns at the end of the bucket
+    pub pattern_chance: f64, // Chance a spawn was a pattern, likewise
+}
+
+impl Bucket {
+    pub fn spawns(&self) -> u32 {
+        self.obstacles + self.pickups
+    }
+}
+
+pub struct SpawnHeatmap {
+    buckets: VecDeque<Bucket>, // Oldest first, the last one is filling
+    steps: u32,                // Steps into the filling bucket
+}
+
+impl Default for SpawnHeatmap {
+    fn default() -> Self {
+        SpawnHeatmap::new()
+    }
+}
+
+impl SpawnHeatmap {
+    pub fn new() -> SpawnHeatmap {
+        let mut buckets = VecDeque::with_capacity(BUCKETS);
+        buckets.push_back(Bucket::default());
+        SpawnHeatmap { buckets, steps: 0 }
+    }
+
+    // Counts what spawned this step, call once per simulation step
+    pub fn record(&mut self, obstacles: usize, pickups: usize, spawn_gap: i32, pattern_chance: f64) {
+        let bucket = self.buckets.back_mut().unwrap();
+        bucket.obstacles += obstacles as u32;
+        bucket.pickups += pickups as u32;
+        bucket.spawn_gap = spawn_gap;
+        bucket.pattern_chance = pattern_chance;
+
+        self.steps += 1;
+        if self.steps == BUCKET_STEPS {
+            self.steps = 0;
+            let next = Bucket {
+                obstacles: 0,
+                pickups: 0,
+                ..*bucket
+            };
+            if self.buckets.len() == BUCKETS {
+                self.buckets.pop_front();
+            }
+            self.buckets.push_back(next);
+        }
+    }
+
+    pub fn buckets(&self) -> impl Iterator<Item = &Bucket> {
+        self.buckets.iter()
+    }
+
+    // Most spawns in any one bucket, to scale the colors by
+    pub fn peak(&self) -> u32 {
+        self.buckets.iter().map(|b| b.spawns()).max().unwrap_or(0)
+    }
+}
@@ -0,0 +1,115 @@
+// The profile's ten best ranked runs, kept in highscores.txt in its folder.
+// Unlike the best score in progress, every run that makes the top ten is
+// kept, with when it was played and its seed so it can be played again.
+//
+// One run per line, best first:
+//
+//     <score> <unix time> <seed>
+
+use crate::profile::Profile;
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const HIGH_SCORES_FILE: &str = "highscores.txt";
+pub const MAX_ENTRIES: usize = 10;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct HighScore {
+    pub score: i32,
+    pub time: u64, // Seconds since the Unix epoch
+    pub seed: u64,
+}
+
+impl HighScore {
+    // When it was played, as YYYY-MM-DD in UTC
+    pub fn date(&self) -> String {
+        let (year, month, day) = civil_date(self.time / 86400);
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+pub struct HighScores {
+    path: Option<PathBuf>, // None without a profile, nothing is saved then
+    entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    // High scores of the given profile, or an empty table if there's no
+    // profile. Lines that don't parse are skipped.
+    pub fn load(profile: Option<&Profile>) -> HighScores {
+        let path = profile.map(|p| p.path(HIGH_SCORES_FILE));
+        let mut entries: Vec<HighScore> = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .map(|text| text.lines().filter_map(parse_line).collect())
+            .unwrap_or_default();
+        entries.sort_by_key(|e| std::cmp::Reverse(e.score));
+        entries.truncate(MAX_ENTRIES);
+        HighScores { path, entries }
+    }
+
+    // Best first
+    pub fn entries(&self) -> &[HighScore] {
+        &self.entries
+    }
+
+    // Adds a run that just ended, returns its place from 0 if it made the
+    // table. Ties go below the runs that got there first.
+    pub fn add(&mut self, score: i32, seed: u64) -> Option<usize> {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let place = self
+            .entries
+            .iter()
+            .position(|e| e.score < score)
+            .unwrap_or(self.entries.len());
+        if place >= MAX_ENTRIES {
+            return None;
+        }
+        self.entries.insert(place, HighScore { score, time, seed });
+        self.entries.truncate(MAX_ENTRIES);
+        Some(place)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = match self.path.as_ref() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let text: String = self
+            .entries
+            .iter()
+            .map(|e| format!("{} {} {}\n", e.score, e.time, e.seed))
+            .collect();
+        fs::write(path, text).map_err(|e| e.to_string())
+    }
+}
+
+fn parse_line(line: &str) -> Option<HighScore> {
+    let mut fields = line.split_whitespace();
+    let score = fields.next()?.parse().ok()?;
+    let time = fields.next()?.parse().ok()?;
+    let seed = fields.next()?.parse().ok()?;
+    Some(HighScore { score, time, seed })
+}
+
+// Year, month and day of the given day since the Unix epoch, in the
+// proleptic Gregorian calendar
+fn civil_date(days: u64) -> (i64, u32, u32) {
+    // Counted in 400 year eras starting on 0000-03-01, so leap days fall at
+    // the end of each year
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
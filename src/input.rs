@@ -0,0 +1,268 @@
+// Per-frame snapshot of the keyboard, built from SDL events. Gameplay asks
+// whether an action was pressed or released this frame, or is held, instead
+// of reacting to raw KeyDown events. OS key repeat is ignored, so holding a
+// key only counts as one press, and a press and release that land in the
+// same frame are both still seen.
+
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::EventPump;
+
+use std::time::Duration;
+
+// Steps an action stays lit on the input display after it was pressed, so a
+// tap that's released right away can still be seen
+const DISPLAY_FLASH_STEPS: u32 = 12;
+
+// Everything the runner reacts to from the keyboard
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Action {
+    Jump,
+    FastFall,
+    Pause,
+    Restart,
+    Menu,
+    Quit,
+    Assist,
+    Profiler,
+    InputDisplay,
+    BugReport,
+    // Replay playback controls
+    Slower,
+    Faster,
+    SeekBack,
+    SeekForward,
+}
+
+impl Action {
+    pub const ALL: [Action; 14] = [
+        Action::Jump,
+        Action::FastFall,
+        Action::Pause,
+        Action::Restart,
+        Action::Menu,
+        Action::Quit,
+        Action::Assist,
+        Action::Profiler,
+        Action::InputDisplay,
+        Action::BugReport,
+        Action::Slower,
+        Action::Faster,
+        Action::SeekBack,
+        Action::SeekForward,
+    ];
+
+    pub fn from_key(key: Keycode) -> Option<Action> {
+        match key {
+            Keycode::W | Keycode::Up | Keycode::Space => Some(Action::Jump),
+            Keycode::S | Keycode::Down => Some(Action::FastFall),
+            Keycode::Escape => Some(Action::Pause),
+            Keycode::R => Some(Action::Restart),
+            Keycode::M => Some(Action::Menu),
+            Keycode::Q => Some(Action::Quit),
+            Keycode::F2 => Some(Action::Assist),
+            Keycode::F3 => Some(Action::Profiler),
+            Keycode::F4 => Some(Action::InputDisplay),
+            Keycode::F9 => Some(Action::BugReport),
+            Keycode::Minus | Keycode::KpMinus => Some(Action::Slower),
+            Keycode::Equals | Keycode::Plus | Keycode::KpPlus => Some(Action::Faster),
+            Keycode::Left => Some(Action::SeekBack),
+            Keycode::Right => Some(Action::SeekForward),
+            _ => None,
+        }
+    }
+
+    // The action with left and right swapped, for a mirrored view
+    pub fn mirrored(self) -> Action {
+        match self {
+            Action::SeekBack => Action::SeekForward,
+            Action::SeekForward => Action::SeekBack,
+            action => action,
+        }
+    }
+
+    fn index(self) -> usize {
+        Action::ALL.iter().position(|&a| a == self).unwrap()
+    }
+}
+
+#[derive(Copy, Clone, Default)]
+struct ActionState {
+    keys_down: u8, // Bound keys currently down, several keys share an action
+    pressed: bool,
+    released: bool,
+    down_at: u32,                // SDL timestamp (ms) of the press
+    last_hold: Option<Duration>, // How long it was down, set on release
+}
+
+pub struct InputState {
+    actions: [ActionState; Action::ALL.len()],
+    quit_requested: bool, // Window closed
+    any_pressed: bool,    // Any key or mouse button went down this frame
+    mirrored: bool,       // Left and right keys swapped
+}
+
+impl InputState {
+    pub fn new() -> InputState {
+        InputState {
+            actions: [ActionState::default(); Action::ALL.len()],
+            quit_requested: false,
+            any_pressed: false,
+            mirrored: false,
+        }
+    }
+
+    // Swaps left and right, so directions match a mirrored view
+    pub fn set_mirrored(&mut self, mirrored: bool) {
+        self.mirrored = mirrored;
+    }
+
+    fn action(&self, key: Keycode) -> Option<Action> {
+        let action = Action::from_key(key)?;
+        Some(if self.mirrored { action.mirrored() } else { action })
+    }
+
+    // Starts a new frame from everything queued since the last one
+    pub fn poll(&mut self, event_pump: &mut EventPump) {
+        self.begin_frame();
+        for event in event_pump.poll_iter() {
+            self.handle(&event);
+        }
+    }
+
+    // Clears this frame's presses and releases, held keys stay held
+    pub fn begin_frame(&mut self) {
+        for action in self.actions.iter_mut() {
+            action.pressed = false;
+            action.released = false;
+            action.last_hold = None;
+        }
+        self.quit_requested = false;
+        self.any_pressed = false;
+    }
+
+    pub fn handle(&mut self, event: &Event) {
+        match event {
+            Event::Quit { .. } => self.quit_requested = true,
+            Event::MouseButtonDown { .. } => self.any_pressed = true,
+            Event::KeyDown { repeat: true, .. } => {}
+            Event::KeyDown { keycode, timestamp, .. } => {
+                self.any_pressed = true;
+                if let Some(action) = keycode.and_then(|k| self.action(k)) {
+                    let state = &mut self.actions[action.index()];
+                    if state.keys_down == 0 {
+                        state.pressed = true;
+                        state.down_at = *timestamp;
+                    }
+                    state.keys_down += 1;
+                }
+            }
+            Event::KeyUp { keycode, timestamp, .. } => {
+                if let Some(action) = keycode.and_then(|k| self.action(k)) {
+                    let state = &mut self.actions[action.index()];
+                    // Keys that went down before this snapshot existed, like
+                    // the one that started the run, don't count
+                    if state.keys_down > 0 {
+                        state.keys_down -= 1;
+                        if state.keys_down == 0 {
+                            state.released = true;
+                            let held_ms = timestamp.saturating_sub(state.down_at);
+                            state.last_hold = Some(Duration::from_millis(held_ms as u64));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Went down this frame
+    pub fn pressed(&self, action: Action) -> bool {
+        self.actions[action.index()].pressed
+    }
+
+    // Came back up this frame
+    pub fn released(&self, action: Action) -> bool {
+        self.actions[action.index()].released
+    }
+
+    // How long the action was held, on the frame it was released. Measured
+    // with the events' own timestamps, so it doesn't depend on frame timing.
+    pub fn hold_time(&self, action: Action) -> Option<Duration> {
+        self.actions[action.index()].last_hold
+    }
+
+    // Currently down
+    pub fn held(&self, action: Action) -> bool {
+        self.actions[action.index()].keys_down > 0
+    }
+
+    pub fn quit_requested(&self) -> bool {
+        self.quit_requested
+    }
+
+    pub fn any_pressed(&self) -> bool {
+        self.any_pressed
+    }
+}
+
+impl Default for InputState {
+    fn default() -> Self {
+        InputState::new()
+    }
+}
+
+// On-screen display of the gameplay actions, for streams, tutorials and
+// going over replays. It's fed the presses and releases of each simulation
+// step whether they came from the keyboard or a replay, so it shows the same
+// for a run and its replay.
+pub struct InputDisplay {
+    held: [bool; 2],
+    flash: [u32; 2], // Steps left lit after a press
+}
+
+impl Default for InputDisplay {
+    fn default() -> Self {
+        InputDisplay::new()
+    }
+}
+
+impl InputDisplay {
+    // Actions shown, in order
+    pub const ACTIONS: [Action; 2] = [Action::Jump, Action::FastFall];
+
+    pub fn new() -> InputDisplay {
+        InputDisplay {
+            held: [false; 2],
+            flash: [0; 2],
+        }
+    }
+
+    pub fn press(&mut self, action: Action) {
+        if let Some(i) = InputDisplay::slot(action) {
+            self.held[i] = true;
+            self.flash[i] = DISPLAY_FLASH_STEPS;
+        }
+    }
+
+    pub fn release(&mut self, action: Action) {
+        if let Some(i) = InputDisplay::slot(action) {
+            self.held[i] = false;
+        }
+    }
+
+    // Call once per simulation step, after feeding it that step's input
+    pub fn step(&mut self) {
+        for flash in self.flash.iter_mut() {
+            *flash = flash.saturating_sub(1);
+        }
+    }
+
+    pub fn is_lit(&self, action: Action) -> bool {
+        InputDisplay::slot(action).is_some_and(|i| self.held[i] || self.flash[i] > 0)
+    }
+
+    fn slot(action: Action) -> Option<usize> {
+        InputDisplay::ACTIONS.iter().position(|&a| a == action)
+    }
+}
@@ -0,0 +1,50 @@
+// Draw order for a run's world. Everything in the world pass is queued with
+// the layer it belongs on, and the queue draws back to front by layer once
+// it's all in. Things on the same layer keep the order they were queued in.
+// Terrain itself is drawn before the queue and the darkness of dark biomes
+// after it.
+
+use crate::SDLCore;
+
+// Back to front
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Layer {
+    Scenery,    // Gates, flags and banners
+    Ground,     // Hazards, mud, telegraphs, marks and shadows on the ground
+    Rails,      // Gap platforms and their rails
+    Obstacles,  // Obstacles and falling debris
+    Pickups,    // Coins and powers
+    Player,     // The player, their trail and the raced ghost
+    Companions, // The drone
+    Foreground, // In front of everything, can hide the player
+}
+
+pub type DrawFn<'a> = Box<dyn FnOnce(&mut SDLCore) -> Result<(), String> + 'a>;
+
+#[derive(Default)]
+pub struct DrawQueue<'a> {
+    items: Vec<(Layer, DrawFn<'a>)>,
+}
+
+impl<'a> DrawQueue<'a> {
+    pub fn new() -> DrawQueue<'a> {
+        DrawQueue::default()
+    }
+
+    pub fn push<F>(&mut self, layer: Layer, draw: F)
+    where
+        F: FnOnce(&mut SDLCore) -> Result<(), String> + 'a,
+    {
+        self.items.push((layer, Box::new(draw)));
+    }
+
+    // Draws everything back to front
+    pub fn draw(mut self, core: &mut SDLCore) -> Result<(), String> {
+        // Stable, so each layer keeps its queued order
+        self.items.sort_by_key(|(layer, _)| *layer);
+        for (_, draw) in self.items {
+            draw(core)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+// Screen layout for windows that aren't 16:9. The logical view keeps its
+// height and grows or shrinks in width to match the window, so wider windows
+// show more of the world instead of being letterboxed. HUD elements are placed
+// against an edge or corner of whatever the view is, instead of at absolute
+// pixels that assume 1280 wide.
+
+use sdl2::rect::Rect;
+
+// Narrowest and widest the view gets, past these SDL letterboxes
+pub const MIN_ASPECT: f64 = 16.0 / 10.0;
+pub const MAX_ASPECT: f64 = 32.0 / 9.0;
+
+// Width of the view for a window of the given size, at a fixed view height
+pub fn view_width(window_w: u32, window_h: u32, height: u32) -> u32 {
+    let aspect = if window_h == 0 {
+        16.0 / 9.0
+    } else {
+        window_w as f64 / window_h as f64
+    };
+    (height as f64 * aspect.clamp(MIN_ASPECT, MAX_ASPECT)).round() as u32
+}
+
+// Widest view there can be at the given height, for sizing render targets
+pub fn max_view_width(height: u32) -> u32 {
+    (height as f64 * MAX_ASPECT).ceil() as u32
+}
+
+// Point of the view an element is placed against
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    // Horizontal and vertical position, 0 start, 1 middle, 2 end
+    fn sides(&self) -> (i32, i32) {
+        match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::Top => (1, 0),
+            Anchor::TopRight => (2, 0),
+            Anchor::Left => (0, 1),
+            Anchor::Center => (1, 1),
+            Anchor::Right => (2, 1),
+            Anchor::BottomLeft => (0, 2),
+            Anchor::Bottom => (1, 2),
+            Anchor::BottomRight => (2, 2),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Layout {
+    width: i32,
+    height: i32,
+}
+
+impl Layout {
+    pub fn new(width: i32, height: i32) -> Layout {
+        Layout { width, height }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /*  Rect of size `w` x `h` placed against `anchor`
+     *
+     *  - The offset points inward from the edges the anchor touches, so
+     *    (10, 20) at TopRight is 10 left of the right edge and 20 down. On
+     *    a centered axis it just shifts the element right or down.
+     */
+    pub fn place(&self, anchor: Anchor, offset: (i32, i32), w: u32, h: u32) -> Rect {
+        let (sx, sy) = anchor.sides();
+        let x = align(sx, self.width, w as i32, offset.0);
+        let y = align(sy, self.height, h as i32, offset.1);
+        Rect::new(x, y, w, h)
+    }
+}
+
+fn align(side: i32, space: i32, size: i32, offset: i32) -> i32 {
+    match side {
+        0 => offset,
+        1 => (space - size) / 2 + offset,
+        _ => space - size - offset,
+    }
+}
@@ -1,34 +1,110 @@
 // File for important content used across entire project
 
+#![allow(dead_code)]
+#![allow(unused_variables)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
 extern crate float_cmp;
 extern crate sdl2;
 
+pub mod animation;
+pub mod archive;
+pub mod assets;
+pub mod audio;
+pub mod autopilot;
+pub mod biome;
+pub mod bugreport;
+pub mod camera;
+pub mod clock;
+pub mod config;
+pub mod cosmetics;
+pub mod crash;
+pub mod decals;
+pub mod decor;
+pub mod events;
+pub mod framerate;
+pub mod heatmap;
+pub mod highscores;
+pub mod input;
+pub mod layers;
+pub mod layout;
+pub mod lighting;
+pub mod loadout;
+pub mod logger;
+pub mod menu;
+pub mod modifiers;
+pub mod motes;
+pub mod parallax;
+pub mod physics;
+pub mod power;
+pub mod proceduralgen;
+pub mod profile;
+pub mod profiler;
+pub mod progress;
+pub mod quality;
+pub mod race;
+pub mod replay;
+pub mod rng;
+pub mod score;
+pub mod settings;
+pub mod showcase;
+pub mod spawner;
+pub mod speech;
+pub mod stall;
+pub mod terrainfile;
+pub mod terrainpool;
+pub mod tutorial;
+pub mod ui;
+pub mod utils;
+pub mod workers;
+pub mod world;
+
+use sdl2::image::LoadSurface;
+use sdl2::pixels::{Color, PixelFormatEnum};
 use sdl2::rect::Rect;
+use sdl2::render::{Texture, TextureCreator};
+use sdl2::surface::Surface;
+
+pub const TILE_SIZE: u32 = 100;
 
 pub struct SDLCore {
-    #[allow(dead_code)]
     sdl_cxt: sdl2::Sdl,
     pub wincan: sdl2::render::WindowCanvas,
     pub event_pump: sdl2::EventPump,
     pub cam: Rect,
+    pub assets: assets::AssetCache, // Filled by the loading screen
+    base_size: (u32, u32),          // Logical size the window was built with
 }
 
+#[derive(Copy, Clone, PartialEq)]
 pub enum GameStatus {
     Main,
     Game,
     Credits,
     BezierSim,
+    Quit,
 }
 
 // Contains all types of terrain
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum TerrainType {
     Grass,
     Asphalt,
     Sand,
     Water,
+    Mud, // Only in patches, see TerrainSegment::terrain_at
+}
+
+// Ground that hurts to touch, in short strips on terrain segments
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Hazard {
+    Lava,
+    Spikes,
 }
 
 // Contains all types of objects generated on terrain
+#[derive(Copy, Clone, PartialEq)]
 pub enum StaticObject {
     Coin,    // Collectable
     Power,   // Collectable
@@ -38,56 +114,528 @@ pub enum StaticObject {
 }
 
 // Contains all types of power ups
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum PowerType {
     SpeedBoost,
     ScoreMultiplier,
     BouncyShoes,
     LowerGravity,
     Shield,
+    Lantern, // Only found in dark biomes, widens the light around the player
+}
+
+// Everything about a power besides what it does, in the same order as
+// PowerType's variants
+pub struct PowerDef {
+    pub power: PowerType,
+    pub icon: &'static str, // Shown on the ground pickup and in the HUD
+    pub glow: Color,        // Pulses behind the pickup so it doesn't read as a coin
+}
+
+pub const POWER_DEFS: [PowerDef; 6] = [
+    PowerDef {
+        power: PowerType::SpeedBoost,
+        icon: "assets/powers/speed.png",
+        glow: Color::RGB(255, 140, 0),
+    },
+    PowerDef {
+        power: PowerType::ScoreMultiplier,
+        icon: "assets/powers/multiplier.png",
+        glow: Color::RGB(255, 215, 0),
+    },
+    PowerDef {
+        power: PowerType::BouncyShoes,
+        icon: "assets/powers/bouncy.png",
+        glow: Color::RGB(60, 220, 90),
+    },
+    PowerDef {
+        power: PowerType::LowerGravity,
+        icon: "assets/powers/floaty.png",
+        glow: Color::RGB(235, 235, 255),
+    },
+    PowerDef {
+        power: PowerType::Shield,
+        icon: "assets/powers/shield.png",
+        glow: Color::RGB(70, 160, 255),
+    },
+    PowerDef {
+        power: PowerType::Lantern,
+        icon: "assets/powers/lantern.png",
+        glow: Color::RGB(255, 196, 70),
+    },
+];
+
+impl PowerType {
+    // Position of the power in POWER_DEFS
+    pub fn index(&self) -> usize {
+        POWER_DEFS.iter().position(|d| d.power == *self).unwrap()
+    }
+
+    pub fn def(&self) -> &'static PowerDef {
+        &POWER_DEFS[self.index()]
+    }
 }
 
 // Contains all types of obstacles
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum ObstacleType {
     Statue,
+    GoldenStatue, // Rare statue, any contact is fatal but vaulting it pays
     Balloon,
     Chest,
 }
 
-#[allow(dead_code)]
-pub struct GameState {
-    pub status: Option<GameStatus>,
-    pub score: i32,
+// How each obstacle type scales with the difficulty curve, in the same order
+// as ObstacleType's variants. Ranges go from difficulty 0 to 1.
+pub struct ObstacleDef {
+    pub obstacle: ObstacleType,
+    pub speed: (f64, f64),        // Pixels per step it moves towards the player on its own
+    pub max_live: (usize, usize), // Most on screen or waiting to spawn at once
+}
+
+pub const OBSTACLE_DEFS: [ObstacleDef; 4] = [
+    ObstacleDef {
+        obstacle: ObstacleType::Statue,
+        speed: (0.0, 0.0),
+        max_live: (2, 4),
+    },
+    ObstacleDef {
+        obstacle: ObstacleType::GoldenStatue,
+        speed: (0.0, 0.0),
+        max_live: (1, 1),
+    },
+    ObstacleDef {
+        obstacle: ObstacleType::Balloon,
+        speed: (0.0, 0.0),
+        max_live: (1, 3),
+    },
+    ObstacleDef {
+        obstacle: ObstacleType::Chest,
+        speed: (0.0, 0.0),
+        max_live: (1, 3),
+    },
+];
+
+impl ObstacleType {
+    // Position of the obstacle in OBSTACLE_DEFS
+    pub fn index(&self) -> usize {
+        OBSTACLE_DEFS.iter().position(|d| d.obstacle == *self).unwrap()
+    }
+
+    pub fn def(&self) -> &'static ObstacleDef {
+        &OBSTACLE_DEFS[self.index()]
+    }
+}
+
+impl ObstacleDef {
+    // Speed at `difficulty`, from 0 to 1
+    pub fn speed_at(&self, difficulty: f64) -> f64 {
+        self.speed.0 + (self.speed.1 - self.speed.0) * difficulty.clamp(0.0, 1.0)
+    }
+
+    // Cap on how many there can be at once at `difficulty`, from 0 to 1
+    pub fn max_live_at(&self, difficulty: f64) -> usize {
+        let (early, late) = (self.max_live.0 as f64, self.max_live.1 as f64);
+        (early + (late - early) * difficulty.clamp(0.0, 1.0)).round() as usize
+    }
+}
+
+// What ended a run
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum DeathCause {
+    BadLanding,
+    Crash(ObstacleType),      // A single severe hit
+    OutOfLives(ObstacleType), // The hit that took the last life
+    Hazard(Hazard),           // Touching it took the last life
+    Fell,                     // Into a gap
+    Debris,                   // Falling debris took the last life
+    RiskZone(ObstacleType),   // Any hit inside a risk zone
+}
+
+// Settings a run starts with, picked by whichever scene starts it
+#[derive(Clone, Default)]
+pub struct GameConfig {
+    pub seed: Option<u64>,                // Fixed seed, None draws a fresh one
+    pub race: Option<race::ShareCode>,    // Friend's run to race, uses its seed
+    pub demo: bool,                       // Attract demo played by the autopilot
+    pub replay: Option<replay::InputLog>, // Recorded run to play back
+    pub replay_from: u32,                 // Step to skip ahead to in the replay
+    pub ghost: Option<replay::InputLog>,  // Recorded run to race the ghost of
+    pub loadout: loadout::Loadout,        // Modifiers bought for the run
+    pub practice: bool,                   // Shows what's about to spawn, unranked
+}
+
+// Kinds of run Play can start. The last one is remembered per profile and
+// preselected on the menu.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum RunMode {
+    Solo,
+    Race,  // Against the last share code raced
+    Ghost, // Against the ghost of the newest replay
+}
+
+impl RunMode {
+    pub const ALL: [RunMode; 3] = [RunMode::Solo, RunMode::Race, RunMode::Ghost];
+
+    // Mode a run with this config is played in. Replays and demos aren't
+    // really played, they count as solo.
+    pub fn of(config: &GameConfig) -> RunMode {
+        if config.race.is_some() {
+            RunMode::Race
+        } else if config.ghost.is_some() {
+            RunMode::Ghost
+        } else {
+            RunMode::Solo
+        }
+    }
+
+    // Name in save files
+    pub fn key(&self) -> &'static str {
+        match self {
+            RunMode::Solo => "solo",
+            RunMode::Race => "race",
+            RunMode::Ghost => "ghost",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<RunMode> {
+        RunMode::ALL.iter().copied().find(|m| m.key() == key)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RunMode::Solo => "Solo",
+            RunMode::Race => "Race last share code",
+            RunMode::Ghost => "Race replay ghost",
+        }
+    }
+
+    pub fn next(&self) -> RunMode {
+        let i = RunMode::ALL.iter().position(|m| m == self).unwrap();
+        RunMode::ALL[(i + 1) % RunMode::ALL.len()]
+    }
+}
+
+// Points each coin collected is worth once a run ends, on top of what it
+// scored when it was picked up
+pub const COIN_BONUS: i32 = 50;
+
+// Points for getting past an obstacle without touching it. A quarter of a
+// plain coin, so weaving through obstacles pays without outscoring coins.
+pub const DODGE_BONUS: i32 = 250;
+
+// How a run went, or is going for the pause screen
+#[derive(Clone, Default)]
+pub struct RunStats {
+    pub seed: u64,
+    pub score: i32,            // With the coin bonus
+    pub ledger: score::Ledger, // The score by where it came from
+    pub coins: i32,
+    pub distance: i32,
+    pub steps: u32, // Simulation steps it's lasted
+    pub death_cause: Option<DeathCause>,
+    pub ranked: bool,       // False for assisted runs, which don't count for anything
+    pub auto_jump: bool,    // Played with the auto-jump assist, ranked but flagged
+    pub golden_vaults: i32, // Golden statues jumped clean over
+    pub previous_best: i32, // Profile's best total before this run
+    pub new_best: bool,
+    pub best_verified: bool,  // Whether the best after this run has a signed input log
+    pub best_auto_jump: bool, // Whether the best after this run was reached with auto-jump
+    pub inputs: replay::InputLog,
+    pub score_scale: f64,   // From the loadout, the coin bonus is scaled by it too
+    pub config: GameConfig, // What the run started with, less its seed, for a quick restart
+}
+
+impl RunStats {
+    // What the coins added as the run ended
+    pub fn coin_bonus(&self) -> i32 {
+        self.ledger.get(score::ScoreSource::CoinBonus)
+    }
+
+    // Final score, with the coin bonus
+    pub fn total(&self) -> i32 {
+        self.score
+    }
+}
+
+// What a scene hands back when it ends: the scene to go to next, along with
+// anything that scene needs to start
+#[derive(Clone)]
+pub enum GameState {
+    ToMenu,
+    ToGame(GameConfig),
+    ToResults(RunStats),
+    ToCredits,
+    ToBezierSim,
+    Quit,
+}
+
+impl GameState {
+    // Plain transition to a scene that doesn't need anything to start
+    pub fn to(status: GameStatus) -> GameState {
+        match status {
+            GameStatus::Main => GameState::ToMenu,
+            GameStatus::Game => GameState::ToGame(GameConfig::default()),
+            GameStatus::Credits => GameState::ToCredits,
+            GameStatus::BezierSim => GameState::ToBezierSim,
+            GameStatus::Quit => GameState::Quit,
+        }
+    }
 }
 
 impl SDLCore {
     pub fn init(title: &str, vsync: bool, width: u32, height: u32) -> Result<SDLCore, String> {
+        SDLCoreBuilder::new(title, width, height).vsync(vsync).build()
+    }
+
+    // Widens or narrows the logical view to the window's aspect ratio, keeping
+    // its height, and updates `cam` to match. Cheap when nothing changed, so
+    // scenes that support other aspect ratios call it every frame.
+    pub fn fit_view(&mut self) -> Result<(), String> {
+        let (window_w, window_h) = self.wincan.window().size();
+        let width = layout::view_width(window_w, window_h, self.base_size.1);
+        self.set_view_width(width)
+    }
+
+    // Back to the fixed logical size, for scenes drawn at absolute positions
+    pub fn reset_view(&mut self) -> Result<(), String> {
+        self.set_view_width(self.base_size.0)
+    }
+
+    // Switches between a window and a borderless window covering the desktop
+    pub fn set_fullscreen(&mut self, fullscreen: bool) -> Result<(), String> {
+        let mode = if fullscreen {
+            sdl2::video::FullscreenType::Desktop
+        } else {
+            sdl2::video::FullscreenType::Off
+        };
+        self.wincan.window_mut().set_fullscreen(mode)
+    }
+
+    /*  Picks how the logical view is scaled up to the window
+     *
+     *  - Pixel perfect: whole number scales only, letterboxed to fit
+     *  - Otherwise: stretched to fill the window
+     *
+     *  How sprites look scaled is up to the texture filter.
+     */
+    pub fn set_pixel_perfect(&mut self, pixel_perfect: bool) -> Result<(), String> {
+        let enable = if pixel_perfect {
+            sdl2::sys::SDL_bool::SDL_TRUE
+        } else {
+            sdl2::sys::SDL_bool::SDL_FALSE
+        };
+        // Not wrapped by the sdl2 crate
+        let result = unsafe { sdl2::sys::SDL_RenderSetIntegerScale(self.wincan.raw(), enable) };
+        if result != 0 {
+            return Err(sdl2::get_error());
+        }
+        Ok(())
+    }
+
+    // Filter for textures created after the switch, which scenes do when
+    // they start
+    pub fn set_texture_filter(&mut self, filter: assets::TextureFilter) {
+        self.assets.set_filter(filter);
+    }
+
+    // Sends drawing to `target`, which has to be a render target texture, or
+    // back to the window with None. Unlike Canvas::with_texture_canvas the
+    // target stays set across any amount of drawing code.
+    pub fn set_render_target(&mut self, target: Option<&Texture>) -> Result<(), String> {
+        let raw = target.map_or(std::ptr::null_mut(), |t| t.raw());
+        // Not wrapped by the sdl2 crate
+        let result = unsafe { sdl2::sys::SDL_SetRenderTarget(self.wincan.raw(), raw) };
+        if result != 0 {
+            return Err(sdl2::get_error());
+        }
+        Ok(())
+    }
+
+    // What's been drawn so far as a texture, for screens that draw over a
+    // still of the last frame and need to draw more than once
+    pub fn snapshot<'a>(
+        &self,
+        texture_creator: &'a TextureCreator<sdl2::video::WindowContext>,
+    ) -> Result<Texture<'a>, String> {
+        // Pixels are read at the window's real resolution, not the logical one
+        let (scale_x, scale_y) = self.wincan.scale();
+        let viewport = self.wincan.viewport();
+        let width = (viewport.width() as f32 * scale_x).round() as u32;
+        let height = (viewport.height() as f32 * scale_y).round() as u32;
+        let pixels = self.wincan.read_pixels(None, PixelFormatEnum::RGBA32)?;
+        if pixels.len() != (width * height * 4) as usize {
+            return Err(format!(
+                "Read {} bytes for a {}x{} snapshot",
+                pixels.len(),
+                width,
+                height
+            ));
+        }
+        let mut texture = texture_creator
+            .create_texture_static(PixelFormatEnum::RGBA32, width, height)
+            .map_err(|e| e.to_string())?;
+        texture
+            .update(None, &pixels, width as usize * 4)
+            .map_err(|e| e.to_string())?;
+        Ok(texture)
+    }
+
+    pub fn audio(&self) -> Result<sdl2::AudioSubsystem, String> {
+        self.sdl_cxt.audio()
+    }
+
+    // HUD layout for the current view
+    pub fn layout(&self) -> layout::Layout {
+        layout::Layout::new(self.cam.width() as i32, self.cam.height() as i32)
+    }
+
+    fn set_view_width(&mut self, width: u32) -> Result<(), String> {
+        if width != self.cam.width() {
+            self.wincan
+                .set_logical_size(width, self.base_size.1)
+                .map_err(|e| e.to_string())?;
+            self.cam.set_width(width);
+        }
+        Ok(())
+    }
+}
+
+// Builder for SDLCore, for everything about the window beyond its title and
+// size. Rendering always happens at the logical `width` x `height`, SDL scales
+// it to whatever size the window actually is.
+pub struct SDLCoreBuilder {
+    title: String,
+    width: u32,
+    height: u32,
+    vsync: bool,
+    icon: Option<String>,
+    resizable: bool,
+    min_size: Option<(u32, u32)>,
+    high_dpi: bool,
+    fullscreen: bool,
+    pixel_perfect: bool,
+    texture_filter: assets::TextureFilter,
+}
+
+impl SDLCoreBuilder {
+    pub fn new(title: &str, width: u32, height: u32) -> SDLCoreBuilder {
+        SDLCoreBuilder {
+            title: title.to_string(),
+            width,
+            height,
+            vsync: true,
+            icon: None,
+            resizable: false,
+            min_size: None,
+            high_dpi: false,
+            fullscreen: false,
+            pixel_perfect: false,
+            texture_filter: assets::TextureFilter::default(),
+        }
+    }
+
+    pub fn vsync(mut self, vsync: bool) -> SDLCoreBuilder {
+        self.vsync = vsync;
+        self
+    }
+
+    // Image file shown as the window/taskbar icon
+    pub fn icon(mut self, path: &str) -> SDLCoreBuilder {
+        self.icon = Some(path.to_string());
+        self
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> SDLCoreBuilder {
+        self.resizable = resizable;
+        self
+    }
+
+    // Smallest size the window can be resized to
+    pub fn min_size(mut self, width: u32, height: u32) -> SDLCoreBuilder {
+        self.min_size = Some((width, height));
+        self
+    }
+
+    // Ask for a full resolution drawable on high-DPI displays
+    pub fn high_dpi(mut self, high_dpi: bool) -> SDLCoreBuilder {
+        self.high_dpi = high_dpi;
+        self
+    }
+
+    // Start as a borderless window covering the desktop
+    pub fn fullscreen(mut self, fullscreen: bool) -> SDLCoreBuilder {
+        self.fullscreen = fullscreen;
+        self
+    }
+
+    // Start with integer scaling, see SDLCore::set_pixel_perfect
+    pub fn pixel_perfect(mut self, pixel_perfect: bool) -> SDLCoreBuilder {
+        self.pixel_perfect = pixel_perfect;
+        self
+    }
+
+    // Start with this texture filter, see SDLCore::set_texture_filter
+    pub fn texture_filter(mut self, filter: assets::TextureFilter) -> SDLCoreBuilder {
+        self.texture_filter = filter;
+        self
+    }
+
+    pub fn build(self) -> Result<SDLCore, String> {
         let sdl_cxt = sdl2::init()?;
         let video_subsys = sdl_cxt.video()?;
 
-        let window = video_subsys
-            .window(title, width, height)
-            .build()
-            .map_err(|e| e.to_string())?;
+        let mut window_builder = video_subsys.window(&self.title, self.width, self.height);
+        window_builder.position_centered();
+        if self.resizable {
+            window_builder.resizable();
+        }
+        if self.high_dpi {
+            window_builder.allow_highdpi();
+        }
+        if self.fullscreen {
+            window_builder.fullscreen_desktop();
+        }
+        let mut window = window_builder.build().map_err(|e| e.to_string())?;
+
+        if let Some(path) = &self.icon {
+            // A missing icon shouldn't stop the game from starting
+            match assets::load_surface(path) {
+                Ok(icon) => window.set_icon(icon),
+                Err(e) => log::warn!("Couldn't load window icon {}: {}", path, e),
+            }
+        }
+        if let Some((w, h)) = self.min_size {
+            window.set_minimum_size(w, h).map_err(|e| e.to_string())?;
+        }
 
         let wincan = window.into_canvas().accelerated();
 
         // Check if we should lock to vsync
-        let wincan = if vsync { wincan.present_vsync() } else { wincan };
+        let wincan = if self.vsync { wincan.present_vsync() } else { wincan };
+
+        let mut wincan = wincan.build().map_err(|e| e.to_string())?;
 
-        let wincan = wincan.build().map_err(|e| e.to_string())?;
+        // Keep drawing in game coordinates no matter the window size or DPI
+        wincan
+            .set_logical_size(self.width, self.height)
+            .map_err(|e| e.to_string())?;
 
         let event_pump = sdl_cxt.event_pump()?;
 
-        let cam = Rect::new(0, 0, width, height);
+        let cam = Rect::new(0, 0, self.width, self.height);
 
-        Ok(SDLCore {
+        let mut core = SDLCore {
             sdl_cxt,
             wincan,
             event_pump,
             cam,
-        })
+            assets: assets::AssetCache::new(),
+            base_size: (self.width, self.height),
+        };
+        core.set_pixel_perfect(self.pixel_perfect)?;
+        core.set_texture_filter(self.texture_filter);
+        Ok(core)
     }
 }
 
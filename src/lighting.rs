@@ -0,0 +1,98 @@
+// Darkness for dark biomes. The screen is covered in near black except for a
+// circle of light around the player, which fades out towards its edge. The
+// circle is a texture made once and stretched to whatever the radius is, and
+// the rest of the screen around it is filled in flat. Things that glow are
+// drawn again on top afterwards.
+
+use crate::rect;
+
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Texture, TextureCreator, WindowCanvas};
+use sdl2::video::WindowContext;
+
+// Pixels across the light texture, it's scaled to the radius when drawn
+const LIGHT_SIZE: u32 = 256;
+// How dark it is outside the light
+pub const DARKNESS: Color = Color::RGBA(6, 4, 10, 236);
+// Fraction of the radius that's fully lit before the fade starts
+const LIT_CORE: f64 = 0.45;
+
+// Square of darkness with a soft hole punched out of the middle
+pub fn light_texture(texture_creator: &TextureCreator<WindowContext>) -> Result<Texture<'_>, String> {
+    let mut texture = texture_creator
+        .create_texture_streaming(PixelFormatEnum::RGBA32, LIGHT_SIZE, LIGHT_SIZE)
+        .map_err(|e| e.to_string())?;
+    texture.with_lock(None, |pixels, pitch| {
+        let half = LIGHT_SIZE as f64 / 2.0;
+        for y in 0..LIGHT_SIZE as usize {
+            for x in 0..LIGHT_SIZE as usize {
+                let (dx, dy) = (x as f64 + 0.5 - half, y as f64 + 0.5 - half);
+                let t = (((dx * dx + dy * dy).sqrt() / half - LIT_CORE) / (1.0 - LIT_CORE)).clamp(0.0, 1.0);
+                // Smoothstep, so the edge of the light doesn't band
+                let dark = t * t * (3.0 - 2.0 * t);
+                let i = y * pitch + x * 4;
+                pixels[i..i + 4].copy_from_slice(&[
+                    DARKNESS.r,
+                    DARKNESS.g,
+                    DARKNESS.b,
+                    (DARKNESS.a as f64 * dark) as u8,
+                ]);
+            }
+        }
+    })?;
+    texture.set_blend_mode(BlendMode::Blend);
+    Ok(texture)
+}
+
+// Darkens everything but `radius` pixels around `center` on a screen
+// `width` by `height`
+pub fn draw_darkness(
+    canvas: &mut WindowCanvas,
+    light: &Texture,
+    center: (i32, i32),
+    radius: u32,
+    width: u32,
+    height: u32,
+) -> Result<(), String> {
+    let r = radius as i32;
+    let (left, top) = (center.0 - r, center.1 - r);
+    let (right, bottom) = (center.0 + r, center.1 + r);
+    canvas.copy(light, None, rect!(left, top, 2 * radius, 2 * radius))?;
+
+    // Everything around the light, in bands above, below and to either side
+    canvas.set_draw_color(DARKNESS);
+    let (w, h) = (width as i32, height as i32);
+    if top > 0 {
+        canvas.fill_rect(rect!(0, 0, w, top))?;
+    }
+    if bottom < h {
+        canvas.fill_rect(rect!(0, bottom, w, h - bottom))?;
+    }
+    let (band_top, band_bottom) = (top.max(0), bottom.min(h));
+    if band_bottom > band_top {
+        if left > 0 {
+            canvas.fill_rect(rect!(0, band_top, left, band_bottom - band_top))?;
+        }
+        if right < w {
+            canvas.fill_rect(rect!(right, band_top, w - right, band_bottom - band_top))?;
+        }
+    }
+    Ok(())
+}
+
+// Soft square halo of `color` around a `size` pixel box at `pos`, for things
+// that glow through the dark
+pub fn draw_glow(canvas: &mut WindowCanvas, pos: (i32, i32), size: u32, color: Color) -> Result<(), String> {
+    for ring in 1..=3 {
+        let grow = ring * 6;
+        canvas.set_draw_color(Color::RGBA(color.r, color.g, color.b, (100 - ring * 25) as u8));
+        canvas.fill_rect(rect!(
+            pos.0 - grow,
+            pos.1 - grow,
+            size as i32 + 2 * grow,
+            size as i32 + 2 * grow
+        ))?;
+    }
+    Ok(())
+}
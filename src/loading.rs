@@ -0,0 +1,111 @@
+// First scene after the window opens. Decodes every image on a worker thread
+// into the asset cache while drawing a progress bar and a gameplay tip, then
+// goes on to the title screen.
+
+use inf_runner::rect;
+
+use inf_runner::assets;
+use inf_runner::assets::Preloader;
+use inf_runner::Game;
+use inf_runner::GameState;
+use inf_runner::SDLCore;
+
+use sdl2::event::Event;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::TextureQuery;
+
+use log::{info, warn};
+
+use std::time::{Duration, Instant};
+
+const CAM_W: u32 = 1280;
+const CAM_H: u32 = 720;
+
+const BAR_W: u32 = 800;
+const BAR_H: u32 = 24;
+
+const TIPS: [&str; 5] = [
+    "Hold jump longer to jump higher",
+    "Land on your wheels, not your head",
+    "Golden statues pay out if you jump clean over them",
+    "Risk zones score double, but shields don't work there",
+    "Coins are worth extra once the run ends",
+];
+// How long each tip stays up
+const TIP_SECS: u64 = 3;
+
+pub struct Loading;
+
+impl Game for Loading {
+    fn init() -> Result<Self, String> {
+        Ok(Loading {})
+    }
+
+    fn run(&mut self, core: &mut SDLCore) -> Result<GameState, String> {
+        let texture_creator = core.wincan.texture_creator();
+        let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
+        let font = assets::load_font(&ttf_context, 64)?;
+
+        let start = Instant::now();
+        let mut preloader = Preloader::start(&assets::PRELOAD);
+        loop {
+            for event in core.event_pump.poll_iter() {
+                if let Event::Quit { .. } = event {
+                    return Ok(GameState::Quit);
+                }
+            }
+
+            for (path, image) in preloader.poll() {
+                match image {
+                    Ok(image) => core.assets.insert(&path, image),
+                    // Loading it later will fail with a proper error if it's
+                    // really missing
+                    Err(e) => warn!("Couldn't preload {}: {}", path, e),
+                }
+            }
+            if preloader.finished() {
+                break;
+            }
+
+            core.wincan.set_draw_color(Color::RGBA(3, 120, 206, 255));
+            core.wincan.clear();
+
+            // Progress bar
+            let bar_x = (CAM_W - BAR_W) as i32 / 2;
+            let bar_y = CAM_H as i32 / 2;
+            core.wincan.set_draw_color(Color::RGBA(255, 255, 255, 255));
+            core.wincan
+                .draw_rect(rect!(bar_x - 2, bar_y - 2, BAR_W + 4, BAR_H + 4))?;
+            let filled = (BAR_W as f64 * preloader.progress()) as u32;
+            if filled > 0 {
+                core.wincan.fill_rect(rect!(bar_x, bar_y, filled, BAR_H))?;
+            }
+
+            let tip = TIPS[(start.elapsed().as_secs() / TIP_SECS) as usize % TIPS.len()];
+            let tex_tip = texture_creator
+                .create_texture_from_surface(
+                    &font
+                        .render(&format!("Tip: {}", tip))
+                        .blended(Color::RGBA(255, 255, 255, 255))
+                        .map_err(|e| e.to_string())?,
+                )
+                .map_err(|e| e.to_string())?;
+            let TextureQuery { width, height, .. } = tex_tip.query();
+            let h = 30;
+            let w = (width as f64 * h as f64 / height as f64) as u32;
+            core.wincan
+                .copy(&tex_tip, None, rect!((CAM_W as i32 - w as i32) / 2, bar_y + 60, w, h))?;
+
+            core.wincan.present();
+            std::thread::sleep(Duration::from_millis(16));
+        }
+
+        info!(
+            "Preloaded {} images in {} ms",
+            assets::PRELOAD.iter().filter(|p| core.assets.contains(p)).count(),
+            start.elapsed().as_millis()
+        );
+        Ok(GameState::ToMenu)
+    }
+}
@@ -0,0 +1,235 @@
+// Modifiers bought with banked coins on the pre-run screen, paid for again
+// every run. Each one scales the score, so bests stay comparable: a modifier
+// that makes a run easier scores less and one that makes it harder scores
+// more. A run's loadout is kept in its input log, so replays and score checks
+// simulate it the same.
+//
+// Mutators are picked on the same screen but are free and don't scale the
+// score. They change the game too much for that, so instead each combination
+// of them has its own board of bests.
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Modifier {
+    StartShield,
+    SpeedUp,
+    DoubleSpawns,
+}
+
+pub struct ModifierDef {
+    pub modifier: Modifier,
+    pub key: &'static str, // Name in input logs
+    pub label: &'static str,
+    pub cost: u32, // Banked coins
+    pub score_scale: f64,
+}
+
+pub const MODIFIER_DEFS: [ModifierDef; 3] = [
+    ModifierDef {
+        modifier: Modifier::StartShield,
+        key: "shield",
+        label: "Start with a shield",
+        cost: 40,
+        score_scale: 0.9,
+    },
+    ModifierDef {
+        modifier: Modifier::SpeedUp,
+        key: "speed",
+        label: "+10% speed",
+        cost: 25,
+        score_scale: 0.95,
+    },
+    ModifierDef {
+        modifier: Modifier::DoubleSpawns,
+        key: "spawns",
+        label: "Double spawns",
+        cost: 60,
+        score_scale: 2.0,
+    },
+];
+
+// Top speed and push SpeedUp adds
+pub const SPEED_UP: f64 = 0.1;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Mutator {
+    Mirror, // World runs right to left, directional controls swap
+    Turbo,  // Simulation runs faster than real time
+}
+
+pub struct MutatorDef {
+    pub mutator: Mutator,
+    pub key: &'static str, // Name in input logs and board names
+    pub label: &'static str,
+}
+
+pub const MUTATOR_DEFS: [MutatorDef; 2] = [
+    MutatorDef {
+        mutator: Mutator::Mirror,
+        key: "mirror",
+        label: "Mirror",
+    },
+    MutatorDef {
+        mutator: Mutator::Turbo,
+        key: "turbo",
+        label: "Turbo",
+    },
+];
+
+// Simulation steps per real-time step under Turbo
+pub const TURBO_SPEED: f64 = 1.5;
+
+// Boards of bests, one for each combination of mutators
+pub const BOARDS: [&str; 4] = ["standard", "mirror", "turbo", "mirror+turbo"];
+
+impl Mutator {
+    pub fn index(&self) -> usize {
+        MUTATOR_DEFS.iter().position(|d| d.mutator == *self).unwrap()
+    }
+
+    pub fn def(&self) -> &'static MutatorDef {
+        &MUTATOR_DEFS[self.index()]
+    }
+}
+
+impl Modifier {
+    // Position of the modifier in MODIFIER_DEFS
+    pub fn index(&self) -> usize {
+        MODIFIER_DEFS.iter().position(|d| d.modifier == *self).unwrap()
+    }
+
+    pub fn def(&self) -> &'static ModifierDef {
+        &MODIFIER_DEFS[self.index()]
+    }
+}
+
+#[derive(Copy, Clone, Default, PartialEq, Debug)]
+pub struct Loadout {
+    picked: [bool; 3],   // Indexed by Modifier::index
+    mutators: [bool; 2], // Indexed by Mutator::index
+}
+
+impl Loadout {
+    pub fn has(&self, modifier: Modifier) -> bool {
+        self.picked[modifier.index()]
+    }
+
+    pub fn set(&mut self, modifier: Modifier, picked: bool) {
+        self.picked[modifier.index()] = picked;
+    }
+
+    pub fn has_mutator(&self, mutator: Mutator) -> bool {
+        self.mutators[mutator.index()]
+    }
+
+    pub fn set_mutator(&mut self, mutator: Mutator, picked: bool) {
+        self.mutators[mutator.index()] = picked;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.picked.iter().chain(self.mutators.iter()).any(|&p| p)
+    }
+
+    pub fn modifiers(&self) -> impl Iterator<Item = Modifier> + '_ {
+        MODIFIER_DEFS.iter().map(|d| d.modifier).filter(move |m| self.has(*m))
+    }
+
+    // Banked coins it takes to start a run with this loadout
+    pub fn cost(&self) -> u32 {
+        self.modifiers().map(|m| m.def().cost).sum()
+    }
+
+    pub fn score_scale(&self) -> f64 {
+        self.modifiers().map(|m| m.def().score_scale).product()
+    }
+
+    pub fn speed_scale(&self) -> f64 {
+        if self.has(Modifier::SpeedUp) {
+            1.0 + SPEED_UP
+        } else {
+            1.0
+        }
+    }
+
+    // Simulation steps per real-time step
+    pub fn sim_speed(&self) -> f64 {
+        if self.has_mutator(Mutator::Turbo) {
+            TURBO_SPEED
+        } else {
+            1.0
+        }
+    }
+
+    pub fn is_mirrored(&self) -> bool {
+        self.has_mutator(Mutator::Mirror)
+    }
+
+    // Board the run's score goes on, one of BOARDS
+    pub fn board(&self) -> &'static str {
+        let i = MUTATOR_DEFS
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| self.has_mutator(d.mutator))
+            .map(|(i, _)| 1 << i)
+            .sum::<usize>();
+        BOARDS[i]
+    }
+
+    // Board name as shown to the player
+    pub fn board_label(&self) -> String {
+        let labels: Vec<_> = MUTATOR_DEFS
+            .iter()
+            .filter(|d| self.has_mutator(d.mutator))
+            .map(|d| d.label)
+            .collect();
+        if labels.is_empty() {
+            "Standard".to_string()
+        } else {
+            labels.join(" + ")
+        }
+    }
+
+    // Spawn gaps are divided by this
+    pub fn spawn_density(&self) -> i32 {
+        if self.has(Modifier::DoubleSpawns) {
+            2
+        } else {
+            1
+        }
+    }
+
+    // Modifier then mutator keys joined by '+', empty without any
+    pub fn encode(&self) -> String {
+        self.modifiers()
+            .map(|m| m.def().key)
+            .chain(
+                MUTATOR_DEFS
+                    .iter()
+                    .filter(|d| self.has_mutator(d.mutator))
+                    .map(|d| d.key),
+            )
+            .collect::<Vec<_>>()
+            .join("+")
+    }
+
+    pub fn decode(text: &str) -> Result<Loadout, String> {
+        let mut loadout = Loadout::default();
+        for key in text.split('+').filter(|k| !k.is_empty()) {
+            if let Some(def) = MODIFIER_DEFS.iter().find(|d| d.key == key) {
+                loadout.set(def.modifier, true);
+            } else if let Some(def) = MUTATOR_DEFS.iter().find(|d| d.key == key) {
+                loadout.set_mutator(def.mutator, true);
+            } else {
+                return Err(format!("Unknown modifier '{}'", key));
+            }
+        }
+        Ok(loadout)
+    }
+
+    // Score multiplier as shown to the player, like "x1.9"
+    pub fn score_label(&self) -> String {
+        format!("x{:.2}", self.score_scale())
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    }
+}
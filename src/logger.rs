@@ -0,0 +1,170 @@
+// Logger behind the `log` facade. Writes every enabled record to stderr and to
+// a size-rotated file under logs/.
+//
+// Levels are set with a filter spec such as `warn,physics=debug,procgen=off`:
+// a bare level is the default, `module=level` overrides it for one module.
+// The spec comes from the INF_RUNNER_LOG env var if set, otherwise from the
+// `log` key of the config file.
+
+use crate::config::Config;
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+pub const LOG_ENV_VAR: &str = "INF_RUNNER_LOG";
+const LOG_DIR: &str = "logs";
+const LOG_NAME: &str = "inf_runner.log";
+const DEFAULT_SPEC: &str = "info";
+
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+const MAX_OLD_FILES: u32 = 3;
+
+struct LogFile {
+    file: File,
+    written: u64,
+}
+
+pub struct Logger {
+    default: LevelFilter,
+    modules: Vec<(String, LevelFilter)>,
+    file: Mutex<Option<LogFile>>,
+}
+
+// Installs the logger as the global `log` backend
+pub fn init(config: &Config) -> Result<(), String> {
+    let spec = match std::env::var(LOG_ENV_VAR) {
+        Ok(spec) => spec,
+        Err(_) => config.get("log").unwrap_or(DEFAULT_SPEC).to_string(),
+    };
+
+    let logger = Logger::from_spec(&spec);
+    let max_level = logger
+        .modules
+        .iter()
+        .map(|(_, level)| *level)
+        .fold(logger.default, |a, b| a.max(b));
+
+    log::set_boxed_logger(Box::new(logger)).map_err(|e| e.to_string())?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+impl Logger {
+    pub fn from_spec(spec: &str) -> Logger {
+        let mut default = LevelFilter::Info;
+        let mut modules = Vec::new();
+
+        for part in spec.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+            match part.split_once('=') {
+                Some((module, level)) => {
+                    if let Ok(level) = level.trim().parse() {
+                        modules.push((module.trim().to_string(), level));
+                    }
+                }
+                None => {
+                    if let Ok(level) = part.parse() {
+                        default = level;
+                    }
+                }
+            }
+        }
+
+        Logger {
+            default,
+            modules,
+            file: Mutex::new(open_log_file()),
+        }
+    }
+
+    // Level a target is filtered at. Targets are module paths like
+    // `inf_runner::physics`, matched on their first module after the crate.
+    fn level_for(&self, target: &str) -> LevelFilter {
+        let module = target.split("::").nth(1).unwrap_or(target);
+        self.modules
+            .iter()
+            .find(|(m, _)| m == module)
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default)
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let secs = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0);
+        let line = format!(
+            "[{:.3} {:<5} {}] {}\n",
+            secs,
+            record.level(),
+            record.target(),
+            record.args()
+        );
+
+        eprint!("{}", line);
+
+        if let Ok(mut file) = self.file.lock() {
+            let rotate = match file.as_mut() {
+                Some(f) => {
+                    if f.file.write_all(line.as_bytes()).is_ok() {
+                        f.written += line.len() as u64;
+                    }
+                    f.written > MAX_FILE_BYTES
+                }
+                None => false,
+            };
+            if rotate {
+                *file = None;
+                *file = open_log_file();
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            if let Some(f) = file.as_mut() {
+                let _ = f.file.flush();
+            }
+        }
+    }
+}
+
+// Opens a fresh log file, shifting inf_runner.log to inf_runner.log.1 and so
+// on, dropping the oldest. Logging to file is skipped if this fails.
+fn open_log_file() -> Option<LogFile> {
+    fs::create_dir_all(LOG_DIR).ok()?;
+
+    let path = |i: u32| -> PathBuf {
+        if i == 0 {
+            PathBuf::from(LOG_DIR).join(LOG_NAME)
+        } else {
+            PathBuf::from(LOG_DIR).join(format!("{}.{}", LOG_NAME, i))
+        }
+    };
+    let _ = fs::remove_file(path(MAX_OLD_FILES));
+    for i in (0..MAX_OLD_FILES).rev() {
+        let _ = fs::rename(path(i), path(i + 1));
+    }
+
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(path(0))
+        .ok()?;
+    Some(LogFile { file, written: 0 })
+}
@@ -8,129 +8,234 @@
 #![allow(unused_imports)]
 
 mod credits;
-mod physics;
-mod proceduralgen;
+mod loading;
+mod results;
 mod runner;
+mod terraindump;
 mod testbezier;
 mod title;
-mod utils;
 
+use inf_runner::config::Config;
+use inf_runner::crash;
+use inf_runner::framerate::FrameCap;
+use inf_runner::logger;
+use inf_runner::quality::Quality;
+use inf_runner::settings::Settings;
 use inf_runner::Game;
+use inf_runner::GameConfig;
 use inf_runner::GameState;
 use inf_runner::GameStatus;
+use inf_runner::SDLCoreBuilder;
+
+use log::{error, info};
 
 const TITLE: &str = "Urban Odyssey";
 const CAM_W: u32 = 1280;
 const CAM_H: u32 = 720;
+const ICON: &str = inf_runner::assets::PLAYER;
 
 // A container for all the segments of our game
 pub struct UrbanOdyssey {
     core: inf_runner::SDLCore,
+    loading: loading::Loading,
     title: title::Title,
     runner: runner::Runner,
     credits: credits::Credits,
-    proceduralgen: proceduralgen::ProceduralGen,
+    results: results::Results,
+    proceduralgen: inf_runner::proceduralgen::ProceduralGen,
     testbezier: testbezier::TestBezier,
     /* physics?
      * procedural generation? */
 }
 
 fn main() {
-    println!("\nRunning {}:", TITLE);
-    print!("\tInitting...");
+    let config = Config::current();
+    if let Err(e) = logger::init(&config) {
+        eprintln!("Failed to start logger: {}", e);
+    }
+
+    crash::install();
+
+    // Dev subcommands run without opening the game window
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("dump-terrain") {
+        if let Err(e) = terraindump::run(&args[2..], CAM_W, CAM_H) {
+            error!("dump-terrain failed: {}", e);
+            eprintln!("{}", e);
+        }
+        log::logger().flush();
+        return;
+    }
+
+    info!("Running {}", TITLE);
+
+    let asset_root = args
+        .iter()
+        .position(|arg| arg == "--assets")
+        .and_then(|i| args.get(i + 1));
+    inf_runner::assets::init_root(asset_root.map(String::as_str), &config);
 
     // Init all segments, wrap into one UrbanOdyssey
-    let game = init();
+    let game = init(&config);
     match game {
-        Err(e) => println!("\n\t\tFailed to init: {}", e),
+        Err(e) => error!("Failed to init: {}", e),
         Ok(mut contents) => {
-            println!("DONE");
-
-            let mut game_manager = GameState {
-                status: Some(GameStatus::Main),
-                score: 0,
+            info!("Init done");
+
+            // Preload everything, then on to the title screen
+            let mut game_manager = match contents.loading.run(&mut (contents.core)) {
+                Err(e) => {
+                    error!("Preloading failed, assets load as scenes need them: {}", e);
+                    GameState::ToMenu
+                }
+                Ok(loading_state) => {
+                    info!("Loading Sequence exited cleanly");
+                    loading_state
+                }
             };
 
+            // `--bot` skips the title and lets the autopilot play run after run
+            if args.iter().any(|arg| arg == "--bot") && !matches!(game_manager, GameState::Quit) {
+                info!("Bot mode, the autopilot plays every run");
+                contents.runner.set_bot(true);
+                game_manager = GameState::ToGame(GameConfig::default());
+            }
+
             loop {
-                match game_manager.status {
-                    Some(GameStatus::Main) => {
-                        println!("\nRunning Title Sequence:");
-                        print!("\tRunning...");
+                game_manager = match game_manager {
+                    GameState::ToMenu => {
+                        info!("Running Title Sequence");
 
                         // TITLE SCREEN RUN
                         match contents.title.run(&mut (contents.core)) {
-                            Err(e) => println!("\n\t\tEncountered error while running: {}", e),
-                            Ok(title_status) => {
-                                game_manager = title_status;
-                                println!("DONE\nExiting cleanly");
+                            Err(e) => {
+                                error!("Encountered error while running: {}", e);
+                                GameState::ToMenu
                             }
-                        };
+                            Ok(title_state) => {
+                                info!("Title Sequence exited cleanly");
+                                title_state
+                            }
+                        }
                     }
-                    Some(GameStatus::Game) => {
-                        println!("\nRunning Game Sequence:");
-                        print!("\tRunning...");
+                    GameState::ToGame(config) => {
+                        info!("Running Game Sequence");
 
                         //GAME PLAY RUN
+                        contents.runner.set_config(config.clone());
                         match contents.runner.run(&mut (contents.core)) {
-                            Err(e) => println!("\n\t\tEncountered error while running: {}", e),
-                            Ok(game_status) => {
-                                game_manager = game_status;
-                                println!("DONE\nExiting cleanly");
+                            Err(e) => {
+                                error!("Encountered error while running: {}", e);
+                                GameState::ToGame(config)
                             }
-                        };
+                            Ok(game_state) => {
+                                info!("Game Sequence exited cleanly");
+                                game_state
+                            }
+                        }
+                    }
+                    GameState::ToResults(stats) => {
+                        info!("Running Results Sequence");
+                        info!("Run results: seed {}, score {}", stats.seed, stats.score);
+
+                        // The title keeps showing them after the results screen
+                        contents.title.set_last_run(stats.clone());
+                        contents.results.set_stats(stats);
+                        match contents.results.run(&mut (contents.core)) {
+                            Err(e) => {
+                                error!("Encountered error while running: {}", e);
+                                GameState::ToMenu
+                            }
+                            Ok(results_state) => {
+                                info!("Results Sequence exited cleanly");
+                                results_state
+                            }
+                        }
                     }
-                    Some(GameStatus::Credits) => {
-                        println!("\nRunning Credits Sequence:");
-                        print!("\tRunning...");
+                    GameState::ToCredits => {
+                        info!("Running Credits Sequence");
 
                         // CREDITS RUN
                         match contents.credits.run(&mut (contents.core)) {
-                            Err(e) => println!("\n\t\tEncountered error while running: {}", e),
-                            Ok(credits_status) => {
-                                game_manager = credits_status;
-                                println!("DONE\nExiting cleanly");
+                            Err(e) => {
+                                error!("Encountered error while running: {}", e);
+                                GameState::ToCredits
+                            }
+                            Ok(credits_state) => {
+                                info!("Credits Sequence exited cleanly");
+                                credits_state
                             }
-                        };
+                        }
                     }
-                    Some(GameStatus::BezierSim) => {
-                        println!("\nTesting Bezier Simulation:");
-                        println!("\tRunning...");
+                    GameState::ToBezierSim => {
+                        info!("Testing Bezier Simulation");
 
                         match contents.testbezier.run(&mut (contents.core)) {
                             Err(e) => {
-                                println!("\n\t\tEncountered error while running: {}", e)
+                                error!("Encountered error while running: {}", e);
+                                GameState::ToBezierSim
                             }
-                            Ok(game_status) => {
-                                game_manager = game_status;
-                                println!("DONE\nExiting cleanly");
+                            Ok(game_state) => {
+                                info!("Bezier Simulation exited cleanly");
+                                game_state
                             }
-                        };
+                        }
                     }
-                    None => {
+                    GameState::Quit => {
                         break;
                     }
                 };
             }
+
+            shutdown(contents);
         }
     };
+
+    log::logger().flush();
 }
 
-fn init() -> Result<UrbanOdyssey, String> {
-    let core = inf_runner::SDLCore::init(TITLE, true, CAM_W, CAM_H)?;
+// Orderly exit once a scene asks to quit. Runs save progress as they end, so
+// what's left is closing everything down: dropping the game closes audio
+// devices, textures and the window and shuts SDL down, then the log is
+// flushed before exiting cleanly.
+fn shutdown(game: UrbanOdyssey) -> ! {
+    info!("Shutting down");
+    drop(game);
+    log::logger().flush();
+    std::process::exit(0);
+}
 
+fn init(config: &Config) -> Result<UrbanOdyssey, String> {
+    let quality = Quality::from_config(config);
+    let settings = Settings::from_config(config);
+    let core = SDLCoreBuilder::new(TITLE, CAM_W, CAM_H)
+        .vsync(FrameCap::from_config(config).vsync())
+        .icon(ICON)
+        .resizable(true)
+        .min_size(CAM_W / 2, CAM_H / 2)
+        .high_dpi(quality.high_dpi)
+        .fullscreen(settings.fullscreen)
+        .pixel_perfect(settings.pixel_perfect)
+        .texture_filter(settings.texture_filter)
+        .build()?;
+
+    let loading = loading::Loading::init()?;
     let title = title::Title::init()?;
     let runner = runner::Runner::init()?;
     let credits = credits::Credits::init()?;
+    let results = results::Results::init()?;
     // physics?
-    let proceduralgen = proceduralgen::ProceduralGen::init()?;
+    let proceduralgen = inf_runner::proceduralgen::ProceduralGen::init()?;
     // procedural generation?
     let testbezier = testbezier::TestBezier::init()?;
 
     Ok(UrbanOdyssey {
         core,
+        loading,
         title,
         runner,
         credits,
+        results,
         proceduralgen,
         testbezier,
     })
@@ -0,0 +1,136 @@
+// Menus that are a list of options to pick from, built on the ui widgets so
+// they look and move like the settings menu. A screen feeds one its frame's
+// events and draws it, and gets back the option that was picked, if any, to
+// act on. Each option keeps a shortcut key that picks it straight away.
+
+use crate::input::{Action, InputState};
+use crate::ui::{MenuInput, MenuState, Ui};
+
+use sdl2::event::Event;
+use sdl2::render::{TextureCreator, WindowCanvas};
+use sdl2::ttf::Font;
+use sdl2::video::WindowContext;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum PauseOption {
+    Resume,
+    Restart,
+    MainMenu,
+    Quit,
+}
+
+pub struct PauseOptionDef {
+    pub option: PauseOption,
+    pub label: &'static str,
+    pub shortcut: Action,
+}
+
+// In the order they're listed
+pub const PAUSE_OPTION_DEFS: [PauseOptionDef; 4] = [
+    PauseOptionDef {
+        option: PauseOption::Resume,
+        label: "Resume (Esc)",
+        shortcut: Action::Pause,
+    },
+    PauseOptionDef {
+        option: PauseOption::Restart,
+        label: "Restart (R)",
+        shortcut: Action::Restart,
+    },
+    PauseOptionDef {
+        option: PauseOption::MainMenu,
+        label: "Main menu (M)",
+        shortcut: Action::Menu,
+    },
+    PauseOptionDef {
+        option: PauseOption::Quit,
+        label: "Quit (Q)",
+        shortcut: Action::Quit,
+    },
+];
+
+impl PauseOption {
+    // Position of the option in PAUSE_OPTION_DEFS
+    pub fn index(&self) -> usize {
+        PAUSE_OPTION_DEFS.iter().position(|d| d.option == *self).unwrap()
+    }
+
+    pub fn def(&self) -> &'static PauseOptionDef {
+        &PAUSE_OPTION_DEFS[self.index()]
+    }
+}
+
+// The menu over a paused run
+pub struct PauseMenu {
+    state: MenuState,
+    input: MenuInput,
+    dirty: bool, // Needs drawing again
+}
+
+impl Default for PauseMenu {
+    fn default() -> Self {
+        PauseMenu::new()
+    }
+}
+
+impl PauseMenu {
+    // Focus starts on Resume
+    pub fn new() -> PauseMenu {
+        PauseMenu {
+            state: MenuState::new(),
+            input: MenuInput::default(),
+            dirty: true,
+        }
+    }
+
+    // Takes one of the frame's events, returns true if it was a menu key
+    pub fn handle(&mut self, event: &Event) -> bool {
+        let used = self.input.handle(event);
+        self.dirty |= used;
+        used
+    }
+
+    // Option whose shortcut was pressed this frame, if any
+    pub fn shortcut(&self, input: &InputState) -> Option<PauseOption> {
+        PAUSE_OPTION_DEFS
+            .iter()
+            .find(|d| input.pressed(d.shortcut))
+            .map(|d| d.option)
+    }
+
+    // Whether anything's changed since the menu was last drawn
+    pub fn needs_redraw(&self) -> bool {
+        self.dirty
+    }
+
+    // Draws it again next frame, for when what's under it was redrawn
+    pub fn invalidate(&mut self) {
+        self.dirty = true;
+    }
+
+    /*  Draws the options a row apart from `pos` down
+     *
+     *  - Returns the option picked this frame, if any
+     *  - And the label that gained focus, for the announcer
+     */
+    pub fn draw(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        texture_creator: &TextureCreator<WindowContext>,
+        font: &Font<'_, 'static>,
+        scale: f64,
+        pos: (i32, i32),
+    ) -> Result<(Option<PauseOption>, Option<String>), String> {
+        let mut ui = Ui::begin(&mut self.state, std::mem::take(&mut self.input), font, scale);
+        let row = ui.row_height();
+        let mut picked = None;
+        for (i, def) in PAUSE_OPTION_DEFS.iter().enumerate() {
+            if ui.button(canvas, texture_creator, def.label, (pos.0, pos.1 + i as i32 * row))? {
+                picked = Some(def.option);
+            }
+        }
+        let focused = ui.end();
+        self.dirty = false;
+        Ok((picked, focused))
+    }
+}
@@ -0,0 +1,51 @@
+// How strongly powers act depending on where the player is. Physics asks for a
+// power's strength here instead of checking terrain or zones itself, so a new
+// interaction is one more match arm rather than a special case in a force.
+
+use crate::PowerType;
+use crate::TerrainType;
+
+// The parts of the world under the player that powers care about
+#[derive(Copy, Clone)]
+pub struct Surroundings {
+    pub terrain: TerrainType,
+    pub updraft: bool,
+}
+
+impl Default for Surroundings {
+    fn default() -> Self {
+        Surroundings {
+            terrain: TerrainType::Grass,
+            updraft: false,
+        }
+    }
+}
+
+// An active power and how strong it is right now, 1.0 being its normal effect
+#[derive(Copy, Clone)]
+pub struct PowerEffect {
+    pub power: PowerType,
+    pub strength: f64,
+}
+
+impl PowerEffect {
+    pub fn new(power: PowerType, surroundings: &Surroundings) -> PowerEffect {
+        PowerEffect {
+            power,
+            strength: power_strength(power, surroundings),
+        }
+    }
+}
+
+// Multiplier on the effect of `power` in the given surroundings
+pub fn power_strength(power: PowerType, surroundings: &Surroundings) -> f64 {
+    match (power, surroundings.terrain) {
+        // Sand gives the shoes something to push off
+        (PowerType::BouncyShoes, TerrainType::Sand) => 1.5,
+        // Hard to skate fast through water
+        (PowerType::SpeedBoost, TerrainType::Water) => 0.5,
+        // Wings catch the rising air
+        (PowerType::LowerGravity, _) if surroundings.updraft => 1.75,
+        _ => 1.0,
+    }
+}
@@ -0,0 +1,97 @@
+// Score motes: little sparks that fly from wherever points were earned in the
+// world to the HUD score counter, so the score visibly comes from somewhere.
+// Everything is in screen space. A mote bursts outward, then is pulled onto
+// the target harder and harder so it always lands after FLIGHT_SECS.
+
+use crate::rect;
+use crate::workers::Workers;
+
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+
+use std::f64::consts::PI;
+
+pub const FLIGHT_SECS: f64 = 0.5;
+// Pixels per second the burst scatters motes at
+const SCATTER_SPEED: f64 = 260.0;
+const SIZE: u32 = 8;
+
+struct Mote {
+    start: (f64, f64),
+    vel: (f64, f64),
+    age: f64,
+    color: Color,
+}
+
+impl Mote {
+    fn pos(&self, target: (f64, f64)) -> (f64, f64) {
+        let t = (self.age / FLIGHT_SECS).min(1.0);
+        // Scatter slows down over the flight while the pull ramps up
+        let drift = self.age * (1.0 - t / 2.0);
+        let free = (self.start.0 + self.vel.0 * drift, self.start.1 + self.vel.1 * drift);
+        let pull = t * t;
+        (free.0 + (target.0 - free.0) * pull, free.1 + (target.1 - free.1) * pull)
+    }
+}
+
+pub struct Motes {
+    motes: Vec<Mote>,
+    target: (f64, f64),
+}
+
+impl Motes {
+    pub fn new(target: (i32, i32)) -> Motes {
+        Motes {
+            motes: Vec::new(),
+            target: (target.0 as f64, target.1 as f64),
+        }
+    }
+
+    // Moves where motes are headed, for when the HUD moves
+    pub fn set_target(&mut self, target: (i32, i32)) {
+        self.target = (target.0 as f64, target.1 as f64);
+    }
+
+    // Bursts `count` motes out of `from`, evenly spread around it
+    pub fn emit(&mut self, from: (i32, i32), count: usize, color: Color) {
+        for i in 0..count {
+            let angle = 2.0 * PI * i as f64 / count as f64;
+            self.motes.push(Mote {
+                start: (from.0 as f64, from.1 as f64),
+                vel: (angle.cos() * SCATTER_SPEED, angle.sin() * SCATTER_SPEED),
+                age: 0.0,
+                color,
+            });
+        }
+    }
+
+    // Ages every mote by `dt` seconds on the worker threads. Returns how
+    // many reached the target.
+    pub fn update(&mut self, dt: f64, workers: &Workers) -> usize {
+        let before = self.motes.len();
+        workers.for_each(&mut self.motes, |mote| mote.age += dt);
+        self.motes.retain(|m| m.age < FLIGHT_SECS);
+        before - self.motes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.motes.is_empty()
+    }
+
+    pub fn draw(&self, canvas: &mut WindowCanvas) -> Result<(), String> {
+        for mote in self.motes.iter() {
+            let (x, y) = mote.pos(self.target);
+            // Shrink a little on the way in
+            let size = (SIZE as f64 * (1.0 - 0.5 * mote.age / FLIGHT_SECS)) as u32;
+            canvas.set_draw_color(mote.color);
+            canvas.fill_rect(rect!(
+                x as i32 - size as i32 / 2,
+                y as i32 - size as i32 / 2,
+                size,
+                size
+            ))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+// Background hill layers. Each biome lists its layers in data, back to front,
+// and the manager here keeps a column of heights for every layer, scrolling
+// each one at its own rate: the further back a layer is, the less often it
+// moves a column. Heights come from the same perlin hills as always, so a
+// layer is just the parameters for them plus a color from its biome.
+
+use crate::biome::Biome;
+use crate::proceduralgen;
+use crate::rng::GameRng;
+
+use sdl2::pixels::Color;
+
+use rand::Rng;
+
+pub struct HillLayer {
+    pub amp: (f32, f32),     // Range the layer's amplitude is picked from
+    pub modifier: f32,       // Scales the noise, higher is rougher
+    pub mul: f32,            // Scales the height
+    pub scroll_every: u32,   // Steps per column scrolled, more is further back
+    pub flat_columns: usize, // Heights held over this many columns, for blocky skylines
+    pub palette: usize,      // Index into the biome's palette
+}
+
+struct LayerState {
+    amp: f32,
+    next: usize, // Noise index of the next column scrolled in
+    heights: Vec<i16>,
+}
+
+pub struct Parallax {
+    biome: Biome,
+    freq: f32, // Shared by every layer so they roll together
+    tick: u32,
+    layers: Vec<LayerState>,
+}
+
+impl Parallax {
+    // Layers of `biome`, `columns` wide, shaped by draws from `rng`
+    pub fn new(biome: Biome, columns: usize, rng: &mut GameRng) -> Parallax {
+        let freq = rng.gen::<f32>() * 1000.0 + 100.0;
+        let layers = biome
+            .def()
+            .layers
+            .iter()
+            .map(|layer| {
+                let amp = rng.gen_range(layer.amp.0..=layer.amp.1);
+                let heights = (0..columns).map(|i| hill_point(layer, i, freq, amp)).collect();
+                LayerState {
+                    amp,
+                    next: columns,
+                    heights,
+                }
+            })
+            .collect();
+        Parallax {
+            biome,
+            freq,
+            tick: 0,
+            layers,
+        }
+    }
+
+    pub fn biome(&self) -> Biome {
+        self.biome
+    }
+
+    // Scrolls every layer that's due a new column this step
+    pub fn step(&mut self) {
+        self.tick = self.tick.wrapping_add(1);
+        let (tick, freq) = (self.tick, self.freq);
+        for (layer, state) in self.biome.def().layers.iter().zip(self.layers.iter_mut()) {
+            if !tick.is_multiple_of(layer.scroll_every) {
+                continue;
+            }
+            state.heights.rotate_left(1);
+            if let Some(last) = state.heights.last_mut() {
+                *last = hill_point(layer, state.next, freq, state.amp);
+            }
+            state.next += 1;
+        }
+    }
+
+    // Each layer back to front, with its color and column heights
+    pub fn layers(&self) -> impl Iterator<Item = (Color, &[i16])> + '_ {
+        let def = self.biome.def();
+        def.layers
+            .iter()
+            .zip(self.layers.iter())
+            .map(move |(layer, state)| (def.palette[layer.palette], state.heights.as_slice()))
+    }
+}
+
+fn hill_point(layer: &HillLayer, column: usize, freq: f32, amp: f32) -> i16 {
+    let column = column / layer.flat_columns.max(1) * layer.flat_columns.max(1);
+    proceduralgen::gen_perlin_hill_point(column, freq, amp, layer.modifier, layer.mul)
+}
@@ -1,13 +1,16 @@
-use inf_runner::ObstacleType;
-use inf_runner::PowerType;
-use inf_runner::TerrainType;
+use crate::layers::Layer;
+use crate::modifiers::{PowerEffect, Surroundings};
+use crate::ObstacleType;
+use crate::PowerType;
+use crate::TerrainType;
 use sdl2::rect::Point;
 use sdl2::rect::Rect;
 use sdl2::render::Texture;
 
 use std::time::{Duration, SystemTime};
 
-use crate::runner::TILE_SIZE as InitTILE_SIZE;
+use crate::TILE_SIZE as InitTILE_SIZE;
+use log::debug;
 use std::f64::consts::PI;
 
 const LOWER_SPEED: f64 = -5.0;
@@ -15,6 +18,212 @@ const UPPER_SPEED: f64 = 8.0;
 const OMEGA: f64 = PI / 18.0;
 const TILE_SIZE: f64 = InitTILE_SIZE as f64;
 
+// Obstacles are split into this many columns to tell when the player has
+// jumped all the way over one
+pub const VAULT_COLUMNS: u32 = 4;
+
+// Upward jump force by how long jump was held, the first tier whose hold time
+// isn't exceeded applies. Taps hop, holds past the last tier launch.
+pub const JUMP_TIERS: [(Duration, f64); 2] = [(Duration::from_millis(100), 60.0), (Duration::from_millis(200), 80.0)];
+pub const MAX_JUMP_FORCE: f64 = 100.0;
+// Running into the side of a statue or chest slowly enough while shielded or
+// mid-trick bounces the player back, keeping this share of their speed. The
+// player can move backwards for REBOUND_STEPS after.
+pub const WALL_RESTITUTION: f64 = 0.5;
+pub const WALL_BOUNCE_MAX_SPEED: f64 = 5.0;
+const REBOUND_STEPS: u32 = 20;
+
+// Share of their closing speed obstacles keep when they hit each other or
+// the ground, low so launched ones pile up rather than bounce around
+pub const OBSTACLE_RESTITUTION: f64 = 0.2;
+// Obstacles on near flat ground slower than this stop
+const REST_SPEED: f64 = 0.3;
+const REST_SLOPE: f64 = 0.05;
+
+// Downward speed a fast-fall adds, on top of any the player already had
+pub const FAST_FALL_SPEED: f64 = 12.0;
+
+// Times a coin or power spawn is lifted over something in its way before
+// it's dropped
+pub const SPAWN_NUDGES: u32 = 2;
+
+// Mud: share of forward speed kept each step while stuck on the ground, and
+// how much higher a jump goes from in it. Each jump press while stuck fills
+// the escape meter by MUD_MASH_FILL and it drains by MUD_METER_DRAIN a step;
+// a full meter pops the player out with MUD_BURST_SPEED forward, and mud
+// can't catch them again for MUD_ESCAPE_STEPS.
+pub const MUD_DRAG: f64 = 0.94;
+pub const MUD_JUMP_SCALE: f64 = 0.5;
+pub const MUD_MASH_FILL: f64 = 0.2;
+pub const MUD_METER_DRAIN: f64 = 0.01;
+pub const MUD_BURST_SPEED: f64 = 6.0;
+pub const MUD_ESCAPE_STEPS: u32 = 45;
+
+// Coins dropped from the sky: how fast they speed up falling, in pixels per
+// step per step, how much of their speed they keep bouncing off the ground,
+// and how many steps they lie there once settled
+const COIN_GRAVITY: f64 = 0.5;
+const COIN_BOUNCE: f64 = 0.45;
+pub const COIN_SETTLE_STEPS: u32 = 90;
+
+// How fast earthquake debris speeds up falling, in pixels per step per step
+const DEBRIS_GRAVITY: f64 = 0.4;
+
+// How long a power lasts once picked up, and how much of the end of it warns
+// that it's running out, in steps
+pub const POWER_STEPS: u32 = 360;
+pub const POWER_WARNING_STEPS: u32 = 60;
+
+// The player's foot sensor starts this far up inside the bottom of the
+// hitbox and reaches this far below it, in pixels
+pub const FOOT_SENSOR_INSET: i32 = 10;
+pub const FOOT_SENSOR_REACH: i32 = 8;
+
+pub fn jump_force(hold: Duration) -> f64 {
+    JUMP_TIERS
+        .iter()
+        .find(|(max_hold, _)| hold <= *max_hold)
+        .map_or(MAX_JUMP_FORCE, |(_, force)| *force)
+}
+
+// Thresholds for how bad an obstacle hit is. Severity is the relative speed of
+// the player and obstacle (pixels per step) plus the overlap depth of their
+// hitboxes (pixels) times `depth_weight`.
+pub struct ImpactConfig {
+    pub depth_weight: f64,
+    pub hit_severity: f64,    // At or above this a hit costs a life
+    pub severe_severity: f64, // At or above this the run ends
+}
+
+pub const IMPACT_CONFIG: ImpactConfig = ImpactConfig {
+    depth_weight: 0.1,
+    hit_severity: 3.0,
+    severe_severity: 8.0,
+};
+
+// How the slope under the player changes their speed, on top of gravity.
+// Both effects grow with the sine of the slope raised to `steepness_curve`,
+// so above 1 gentle slopes barely matter and steep ones matter a lot.
+pub struct SlopeConfig {
+    pub steepness_curve: f64,
+    pub downhill_gain: f64,      // Extra forward force per unit mass going down
+    pub downhill_max_speed: f64, // The boost fades out on the way up to this speed
+    pub uphill_drag: f64,        // Backward force per unit mass going up
+    pub pumping_drag: f64,       // Share of the uphill drag left while crouched to pump
+}
+
+pub const SLOPE_CONFIG: SlopeConfig = SlopeConfig {
+    steepness_curve: 1.5,
+    downhill_gain: 0.25,
+    downhill_max_speed: UPPER_SPEED,
+    uphill_drag: 0.3,
+    pumping_drag: 0.2,
+};
+
+// Crouching (pressing jump) right at the bottom of a dip pumps, turning the
+// speed the player had going down into forward speed. Timing is counted in
+// steps between the crouch and the bottom of the dip, either way round.
+pub struct PumpConfig {
+    pub window_steps: u32,  // Furthest off a pump can be and still count
+    pub perfect_steps: u32, // At most this far off is perfect
+    pub min_bend: f64,      // How much the slope has to turn at the bottom, in radians
+    pub gain: f64,          // Share of the downward speed turned forward on a perfect pump
+    pub max_boost: f64,     // Most forward speed one pump can add
+}
+
+pub const PUMP_CONFIG: PumpConfig = PumpConfig {
+    window_steps: 12,
+    perfect_steps: 3,
+    min_bend: 0.02,
+    gain: 0.8,
+    max_boost: 3.0,
+};
+
+// A pump that landed, see Player::track_pump
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Pump {
+    pub accuracy: f64, // 0 to 1, 1 when the crouch was right at the bottom
+    pub perfect: bool,
+    pub boost: f64, // Forward speed added
+}
+
+// Landings are graded by how far the player's rotation is from the slope
+// they land on, in radians. Each grade scales the player's speed.
+pub struct LandingConfig {
+    pub perfect_band: f64, // At most this far off is perfect
+    pub good_band: f64,    // At most this far off is good, anything more is sloppy
+    pub perfect_speed: f64,
+    pub good_speed: f64,
+    pub sloppy_speed: f64,
+    pub min_air_steps: u32, // Shorter hops than this aren't graded
+}
+
+pub const LANDING_CONFIG: LandingConfig = LandingConfig {
+    perfect_band: 0.1,
+    good_band: 0.35,
+    perfect_speed: 1.05,
+    good_speed: 1.0,
+    sloppy_speed: 0.8,
+    min_air_steps: 15,
+};
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum LandingGrade {
+    Perfect,
+    Good,
+    Sloppy,
+}
+
+impl LandingGrade {
+    // Grade for landing `off` radians from the slope, either way
+    pub fn from_offset(off: f64) -> LandingGrade {
+        if off <= LANDING_CONFIG.perfect_band {
+            LandingGrade::Perfect
+        } else if off <= LANDING_CONFIG.good_band {
+            LandingGrade::Good
+        } else {
+            LandingGrade::Sloppy
+        }
+    }
+
+    pub fn speed_scale(&self) -> f64 {
+        match self {
+            LandingGrade::Perfect => LANDING_CONFIG.perfect_speed,
+            LandingGrade::Good => LANDING_CONFIG.good_speed,
+            LandingGrade::Sloppy => LANDING_CONFIG.sloppy_speed,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LandingGrade::Perfect => "Perfect landing!",
+            LandingGrade::Good => "Good landing",
+            LandingGrade::Sloppy => "Sloppy landing",
+        }
+    }
+}
+
+// Outcome of a collision with an obstacle
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Impact {
+    None,
+    Graze,  // Only breaks the coin combo
+    Hit,    // Costs a life and knocks the player back
+    Severe, // Ends the run
+}
+
+impl Impact {
+    pub fn from_severity(severity: f64) -> Impact {
+        if severity >= IMPACT_CONFIG.severe_severity {
+            Impact::Severe
+        } else if severity >= IMPACT_CONFIG.hit_severity {
+            Impact::Hit
+        } else {
+            Impact::Graze
+        }
+    }
+}
+
 pub struct Physics;
 
 impl Physics {
@@ -22,14 +231,173 @@ impl Physics {
     // Params: entityA, entityB
     // Returns: true if entities are colliding, false otherwise
     pub fn check_collision<'a>(entity_a: &mut impl Entity<'a>, entity_b: &mut impl Entity<'a>) -> bool {
-        entity_a.hitbox().has_intersection(entity_b.hitbox())
+        // Nothing inside the outer hitboxes can touch if they don't
+        if !entity_a.hitbox().has_intersection(entity_b.hitbox()) {
+            return false;
+        }
+        let parts_b = entity_b.hitboxes();
+        entity_a
+            .hitboxes()
+            .iter()
+            .any(|(_, a)| parts_b.iter().any(|(_, b)| a.has_intersection(*b)))
+    }
+
+    /*  Which columns of `below` have all of `above` higher than them
+     *
+     *  - `below` is split into `columns` columns of equal width
+     *  - A column counts when `above` overlaps it horizontally and the
+     *    lowest point of `above` is over the top of `below`
+     *
+     *  - Returns a bit mask with bit 0 for the leftmost column
+     */
+    pub fn columns_above(below: PhysRect, above: PhysRect, columns: u32) -> u32 {
+        let a = above.coords();
+        let b = below.coords();
+        let a_left = a.iter().map(|p| p.x()).min().unwrap_or(0);
+        let a_right = a.iter().map(|p| p.x()).max().unwrap_or(0);
+        let a_bottom = a.iter().map(|p| p.y()).max().unwrap_or(0);
+        let b_left = b.iter().map(|p| p.x()).min().unwrap_or(0);
+        let b_right = b.iter().map(|p| p.x()).max().unwrap_or(0);
+        let b_top = b.iter().map(|p| p.y()).min().unwrap_or(0);
+        if a_bottom >= b_top {
+            return 0;
+        }
+
+        let width = (b_right - b_left).max(1);
+        let mut mask = 0;
+        for c in 0..columns as i32 {
+            let left = b_left + width * c / columns as i32;
+            let right = b_left + width * (c + 1) / columns as i32;
+            if a_left < right && a_right > left {
+                mask |= 1 << c;
+            }
+        }
+        mask
+    }
+
+    /*  Pushes launched obstacles out of each other
+     *
+     *  - Only pairs where at least one has been launched (collided with the
+     *    player) are checked, ones that haven't are fixed in place
+     *  - Each pair is separated along their minimum translation vector, split
+     *    by mass when both are moving
+     *  - Along that axis they then trade momentum, keeping
+     *    OBSTACLE_RESTITUTION of their closing speed
+     */
+    pub fn resolve_obstacles(obstacles: &mut [Obstacle]) {
+        for i in 0..obstacles.len() {
+            let (before, rest) = obstacles.split_at_mut(i + 1);
+            let a = &mut before[i];
+            for b in rest.iter_mut() {
+                if !a.collided() && !b.collided() {
+                    continue;
+                }
+                let (dx, dy) = match a.hitbox().mtv(b.hitbox()) {
+                    Some(mtv) => mtv,
+                    None => continue,
+                };
+                // Mass that moves, a fixed obstacle doesn't
+                let ma = if a.collided() { a.mass() } else { f64::INFINITY };
+                let mb = if b.collided() { b.mass() } else { f64::INFINITY };
+                let share_a = if ma.is_infinite() {
+                    0.0
+                } else if mb.is_infinite() {
+                    1.0
+                } else {
+                    mb / (ma + mb)
+                };
+                a.shift(dx as f64 * share_a, dy as f64 * share_a);
+                b.shift(-dx as f64 * (1.0 - share_a), -dy as f64 * (1.0 - share_a));
+
+                // Velocities along the push, in screen directions (down is +y)
+                let axis_x = dx != 0;
+                let sign = if axis_x { dx.signum() } else { dy.signum() } as f64;
+                let ua = if axis_x { a.kin.vel.0 } else { -a.kin.vel.1 } * sign;
+                let ub = if axis_x { b.kin.vel.0 } else { -b.kin.vel.1 } * sign;
+                // Only if they're still closing in on each other
+                if ua - ub >= 0.0 {
+                    continue;
+                }
+                let (ua_f, ub_f) = if ma.is_infinite() {
+                    (ua, -OBSTACLE_RESTITUTION * ub)
+                } else if mb.is_infinite() {
+                    (-OBSTACLE_RESTITUTION * ua, ub)
+                } else {
+                    let p = ma * ua + mb * ub;
+                    (
+                        (p - mb * OBSTACLE_RESTITUTION * (ua - ub)) / (ma + mb),
+                        (p + ma * OBSTACLE_RESTITUTION * (ua - ub)) / (ma + mb),
+                    )
+                };
+                if axis_x {
+                    a.kin.vel.0 = ua_f * sign;
+                    b.kin.vel.0 = ub_f * sign;
+                } else {
+                    a.kin.vel.1 = -ua_f * sign;
+                    b.kin.vel.1 = -ub_f * sign;
+                }
+            }
+        }
+    }
+
+    /*  Keeps a launched obstacle on top of the terrain
+     *
+     *  - `left` and `right` are the ground under its bottom corners
+     *  - Bodies rest 5% of a tile into the higher of the two (see
+     *    apply_terrain_forces), one that's sunk further is lifted back out
+     *    and stops falling, keeping OBSTACLE_RESTITUTION of its speed into
+     *    the ground
+     *  - On near flat ground it comes to rest once it's slow enough
+     */
+    pub fn settle_on_terrain(obstacle: &mut Obstacle, left: Point, right: Point) {
+        let surface = left.y().min(right.y());
+        let bottom = obstacle.y() + obstacle.hitbox().height() as i32;
+        if bottom < surface {
+            return;
+        }
+        let rest_depth = (0.05 * TILE_SIZE) as i32;
+        if bottom > surface + rest_depth {
+            obstacle.shift(0.0, (surface + rest_depth - bottom) as f64);
+            if obstacle.kin.vel.1 < 0.0 {
+                obstacle.kin.vel.1 *= -OBSTACLE_RESTITUTION;
+            }
+        }
+
+        let slope = ((right.y() - left.y()) as f64 / (right.x() - left.x()).max(1) as f64).atan();
+        let speed = obstacle.kin.vel.0.abs() + obstacle.kin.vel.1.abs();
+        if slope.abs() < REST_SLOPE && speed < REST_SPEED {
+            obstacle.kin.vel = (0.0, 0.0);
+        }
+    }
+
+    // Where a spawn at `candidate` can go without overlapping any of
+    // `occupied`. Each time it's in the way of something it's lifted to sit
+    // on top of it, up to SPAWN_NUDGES times, so a coin over a statue is
+    // still there for jumping it. None if it can't be placed.
+    pub fn place_spawn(candidate: PhysRect, occupied: &[PhysRect]) -> Option<PhysRect> {
+        let mut placed = candidate;
+        let mut nudges = 0;
+        while let Some(blocker) = occupied.iter().find(|r| placed.mtv(**r).is_some()) {
+            if nudges == SPAWN_NUDGES {
+                return None;
+            }
+            nudges += 1;
+            let top = blocker.coords().iter().map(|p| p.y()).min().unwrap_or(0);
+            placed.set_y(top - placed.height() as i32);
+        }
+        Some(placed)
+    }
+
+    // Drops every entity whose right edge is left of `min_x`
+    pub fn remove_offscreen<'a, T: Entity<'a>>(entities: &mut Vec<T>, min_x: i32) {
+        entities.retain(|e| e.x() + e.hitbox().width() as i32 > min_x);
     }
 
     // Checks if player hasn't landed on their head
-    // Params: player, ground position as SDL point, angle of ground
+    // Params: player, angle of ground
     // Returns: true if player is upright, false otherwise
-    pub fn check_player_upright<'a>(player: &Player, angle: f64, ground: Point) -> bool {
-        !player.hitbox().contains_point(ground)
+    pub fn check_player_upright<'a>(player: &Player, angle: f64) -> bool {
+        !player.is_grounded()
             || (player.theta() < OMEGA * 6.0 + angle || player.theta() > 2.0 * PI - OMEGA * 6.0 + angle)
     }
 
@@ -42,7 +410,7 @@ impl Physics {
         angle: f64,
         ground: Point,
         terrain_type: &TerrainType,
-        power_up: Option<PowerType>,
+        power: Option<PowerEffect>,
     ) {
         // Set Gravity & Friction Strength From TerrainType
         let fric_coeff: f64;
@@ -66,11 +434,19 @@ impl Physics {
                 //NOT YET CONFIGURED
                 fric_coeff = 0.2;
             }
+            TerrainType::Mud => {
+                //bogs down, the player's drag is in Player::step_footing
+                fric_coeff = 0.2;
+            }
         }
 
-        // Lower gravity if power is low gravity
-        if let Some(PowerType::LowerGravity) = power_up {
-            g = g * 2.0 / 3.0;
+        // Lower gravity if power is low gravity, by a third at normal strength
+        if let Some(PowerEffect {
+            power: PowerType::LowerGravity,
+            strength,
+        }) = power
+        {
+            g *= 1.0 - (strength / 3.0).min(0.9);
         }
 
         // Gravity: mg
@@ -85,7 +461,7 @@ impl Physics {
         */
 
         // If body is on ground, apply normal
-        if body.hitbox().contains_point(ground) {
+        if body.touches_ground(ground) {
             // Land on ground
             if body.vel_y() < 0.0 || (body.x() as f64 + 0.9 * TILE_SIZE) > ground.y() as f64 {
                 body.hard_set_pos((body.x() as f64, ground.y() as f64 - 0.95 * TILE_SIZE));
@@ -125,20 +501,41 @@ impl Physics {
 
     // Applies forward motion to player, as if they're propelling themselves
     // Serves to oppose and overcome backwards forces (friction and normal)
-    // Params: player, angle of ground, ground position is as SDL Point
+    // Downhills push the player along until they're going fast, uphills
+    // slow them down unless they crouch (charge a jump) to pump, see
+    // SLOPE_CONFIG
+    // Params: player, angle of ground
     // Returns: None
-    pub fn apply_skate_force(player: &mut Player, angle: f64, ground: Point) {
+    pub fn apply_skate_force(player: &mut Player, angle: f64) {
         // Skate force
-        let mut skate_force = 1.0 / 8.0 * player.mass();
-        if let Some(PowerType::SpeedBoost) = player.power_up() {
-            // Speed up with powerup
-            skate_force *= 2.0;
+        let mut skate_force = 1.0 / 8.0 * player.mass() * player.speed_scale;
+        if let Some(PowerEffect {
+            power: PowerType::SpeedBoost,
+            strength,
+        }) = player.power_effect()
+        {
+            // Speed up with powerup, doubled at normal strength
+            skate_force *= 1.0 + strength;
         }
 
-        if player.hitbox().contains_point(ground) {
+        if player.is_grounded() {
             // (+x, +y) on an uphill
             // (+x, -y) on a downhill
             player.apply_force((skate_force * angle.cos(), -skate_force * angle.sin()));
+
+            let config = &SLOPE_CONFIG;
+            let steepness = angle.sin().abs().powf(config.steepness_curve);
+            let slope_force = if angle > 0.0 {
+                let headroom = (1.0 - player.vel_x() / config.downhill_max_speed).max(0.0);
+                config.downhill_gain * steepness * headroom
+            } else if player.is_charging_jump() {
+                -config.uphill_drag * config.pumping_drag * steepness
+            } else {
+                -config.uphill_drag * steepness
+            };
+            // Along the ground, same direction as the skate force
+            let slope_force = slope_force * player.mass();
+            player.apply_force((slope_force * angle.cos(), -slope_force * angle.sin()));
         }
     }
 
@@ -170,9 +567,13 @@ impl Physics {
 
         // Acceleration of gravity
         let mut g: f64 = 1.0;
-        if let Some(PowerType::LowerGravity) = player.power_up() {
+        if let Some(PowerEffect {
+            power: PowerType::LowerGravity,
+            strength,
+        }) = player.power_effect()
+        {
             // Lower gravity if power is low gravity
-            g = 2.0 / 3.0;
+            g = 1.0 - (strength / 3.0).min(0.9);
         }
 
         // Calculate player's 2D-volume beneath water
@@ -189,9 +590,67 @@ impl Physics {
 
 /******************************* TRAITS ****************************** */
 
-pub trait Entity<'a> {
+// Anything drawn with its own texture. Kept apart from Entity so entities
+// without one (obstacles, drawn by type) stay Send and can be simulated on
+// worker threads.
+pub trait Sprite<'a> {
     fn texture(&self) -> &Texture<'a>;
+}
+
+// Named pieces of an entity's hitbox
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum HitboxPart {
+    Body,
+    Head,
+    Feet,
+}
+
+// One piece of a hitbox, offset from the top left of the entity's sprite
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct HitboxShape {
+    pub part: HitboxPart,
+    pub offset: (i32, i32),
+    pub size: (u32, u32),
+}
+
+impl HitboxShape {
+    pub const fn new(part: HitboxPart, x: i32, y: i32, width: u32, height: u32) -> HitboxShape {
+        HitboxShape {
+            part,
+            offset: (x, y),
+            size: (width, height),
+        }
+    }
+
+    // Where this piece is on an entity whose sprite is at `origin`
+    pub fn place(&self, origin: PhysRect) -> PhysRect {
+        PhysRect::new(
+            origin.x() + self.offset.0,
+            origin.y() + self.offset.1,
+            self.size.0,
+            self.size.1,
+        )
+    }
+}
+
+// Pieces of the player's sprite that collide: the head, the body and the
+// board under the feet
+const PLAYER_HITBOXES: [HitboxShape; 3] = [
+    HitboxShape::new(HitboxPart::Head, 50, 0, 40, 30),
+    HitboxShape::new(HitboxPart::Body, 15, 25, 60, 55),
+    HitboxShape::new(HitboxPart::Feet, 15, 80, 70, 20),
+];
+
+// Statues are the figure on top of a wider base
+const STATUE_HITBOXES: [HitboxShape; 2] = [
+    HitboxShape::new(HitboxPart::Body, 15, 0, 70, 80),
+    HitboxShape::new(HitboxPart::Feet, 5, 80, 90, 20),
+];
+// Only the balloon itself, the string passes through things
+const BALLOON_HITBOXES: [HitboxShape; 1] = [HitboxShape::new(HitboxPart::Body, 20, 0, 60, 60)];
+const CHEST_HITBOXES: [HitboxShape; 1] = [HitboxShape::new(HitboxPart::Body, 0, 0, 100, 100)];
 
+pub trait Entity<'a> {
     fn x(&self) -> i32 {
         self.hitbox().x()
     }
@@ -203,11 +662,83 @@ pub trait Entity<'a> {
     }
 
     fn hitbox(&self) -> PhysRect;
+    // The pieces collisions are checked against, all inside hitbox(). Just
+    // the one body for anything that doesn't say otherwise.
+    fn hitboxes(&self) -> Vec<(HitboxPart, PhysRect)> {
+        vec![(HitboxPart::Body, self.hitbox())]
+    }
     fn align_hitbox_to_pos(&mut self); // After the pos is set with f64s, this method moves hitbox
                                        // to proper SDL coordinates using i32s
 
     // Adjusts terrain postion in runner.rs based on camera_adj_x & camera_adj_y
     fn camera_adj(&mut self, x_adj: i32, y_adj: i32);
+
+    // What it's drawn on, see layers::DrawQueue
+    fn layer(&self) -> Layer {
+        Layer::Obstacles
+    }
+}
+
+// Where a body is and how it's moving. Every body keeps one and steps it the
+// same way, they only differ in their speed limits and what they do at the
+// ground.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Kinematics {
+    pub pos: (f64, f64),
+    pub prev_pos: (f64, f64), // Where the current step started, see Body::drawn_pos
+    pub vel: (f64, f64), // +y is up, unlike pos
+    pub accel: (f64, f64),
+    pub theta: f64, // angle of rotation, in radians
+    pub omega: f64, // angular speed
+    pub vel_x_limits: (f64, f64),
+    pub vel_y_limits: (f64, f64),
+}
+
+impl Kinematics {
+    pub fn new(pos: (f64, f64), vel_x_limits: (f64, f64), vel_y_limits: (f64, f64)) -> Kinematics {
+        Kinematics {
+            pos,
+            prev_pos: pos,
+            vel: (0.0, 0.0),
+            accel: (0.0, 0.0),
+            theta: 0.0,
+            omega: 0.0,
+            vel_x_limits,
+            vel_y_limits,
+        }
+    }
+
+    pub fn apply_force(&mut self, force: (f64, f64), mass: f64) {
+        self.accel.0 += force.0 / mass;
+        self.accel.1 += force.1 / mass;
+    }
+
+    // Adds this step's acceleration to the velocity, within the limits
+    pub fn integrate_vel(&mut self) {
+        self.vel.0 = (self.vel.0 + self.accel.0).clamp(self.vel_x_limits.0, self.vel_x_limits.1);
+        self.vel.1 = (self.vel.1 + self.accel.1).clamp(self.vel_y_limits.0, self.vel_y_limits.1);
+    }
+
+    // Moves by this step's velocity, bodies the world scrolls past instead
+    // (the player) only move vertically
+    pub fn integrate_pos(&mut self, move_x: bool) {
+        if move_x {
+            self.pos.0 += self.vel.0;
+        }
+        self.pos.1 -= self.vel.1;
+    }
+
+    pub fn rotate(&mut self) {
+        self.theta = (self.theta - self.omega + 2.0 * PI) % (2.0 * PI);
+    }
+
+    // `alpha` of the way from where the step started to where it is now
+    pub fn lerp_pos(&self, alpha: f64) -> (f64, f64) {
+        (
+            self.prev_pos.0 + (self.pos.0 - self.prev_pos.0) * alpha,
+            self.prev_pos.1 + (self.pos.1 - self.prev_pos.1) * alpha,
+        )
+    }
 }
 
 pub trait Body<'a>: Entity<'a> {
@@ -216,23 +747,70 @@ pub trait Body<'a>: Entity<'a> {
         let radius = (self.hitbox().width() as f64) / 2.0;
         self.mass() * radius * radius
     }
+    fn kinematics(&self) -> &Kinematics;
+    fn kinematics_mut(&mut self) -> &mut Kinematics;
+
     fn update_pos(&mut self, ground: Point, angle: f64, game_over: bool);
-    fn hard_set_pos(&mut self, pos: (f64, f64)); // Official method to hardcode position
+    // Whether the body is standing on `ground`
+    fn touches_ground(&self, ground: Point) -> bool {
+        self.hitbox().contains_point(ground)
+    }
+    // Official method to hardcode position
+    fn hard_set_pos(&mut self, pos: (f64, f64)) {
+        self.kinematics_mut().pos = pos;
+    }
 
-    fn vel_x(&self) -> f64;
-    fn vel_y(&self) -> f64;
-    fn update_vel(&mut self, game_over: bool);
-    fn hard_set_vel(&mut self, vel: (f64, f64)); // Official method to hardcode velocity
+    fn vel_x(&self) -> f64 {
+        self.kinematics().vel.0
+    }
+    fn vel_y(&self) -> f64 {
+        self.kinematics().vel.1
+    }
+    fn update_vel(&mut self, _game_over: bool) {
+        self.kinematics_mut().integrate_vel();
+    }
+    // Official method to hardcode velocity
+    fn hard_set_vel(&mut self, vel: (f64, f64)) {
+        self.kinematics_mut().vel = vel;
+    }
 
-    fn accel_x(&self) -> f64;
-    fn accel_y(&self) -> f64;
-    fn apply_force(&mut self, force: (f64, f64));
-    fn reset_accel(&mut self);
+    fn accel_x(&self) -> f64 {
+        self.kinematics().accel.0
+    }
+    fn accel_y(&self) -> f64 {
+        self.kinematics().accel.1
+    }
+    fn apply_force(&mut self, force: (f64, f64)) {
+        let mass = self.mass();
+        self.kinematics_mut().apply_force(force, mass);
+    }
+    fn reset_accel(&mut self) {
+        self.kinematics_mut().accel = (0.0, 0.0);
+    }
 
-    fn theta(&self) -> f64;
-    fn rotate(&mut self);
+    fn theta(&self) -> f64 {
+        self.kinematics().theta
+    }
+    fn rotate(&mut self) {
+        self.kinematics_mut().rotate();
+    }
+
+    fn omega(&self) -> f64 {
+        self.kinematics().omega
+    }
 
-    fn omega(&self) -> f64;
+    // Called as a step begins, drawn_pos goes from here to wherever the step
+    // leaves the body
+    fn remember_pos(&mut self) {
+        let kin = self.kinematics_mut();
+        kin.prev_pos = kin.pos;
+    }
+    // Where to draw the body `alpha` of a step on from where the last step
+    // started, see SimClock::alpha
+    fn drawn_pos(&self, alpha: f64) -> (i32, i32) {
+        let (x, y) = self.kinematics().lerp_pos(alpha);
+        (x as i32, y as i32)
+    }
 }
 
 pub trait Collectible<'a>: Entity<'a> {
@@ -246,47 +824,220 @@ pub trait Collectible<'a>: Entity<'a> {
 /****************************** PLAYER ******************************* */
 
 pub struct Player<'a> {
-    pub pos: (f64, f64),
-    velocity: (f64, f64),
-    accel: (f64, f64),
+    kin: Kinematics,
     drawbox: Rect,
     hitbox: PhysRect,
 
-    theta: f64, // angle of rotation, in radians
-    omega: f64, // angular speed
-
     mass: f64,
     texture: &'a Texture<'a>,
     power_up: Option<PowerType>,
+    power_steps: u32, // Steps left of the current power
+    speed_scale: f64, // Top speed and push, 1 unless a modifier changes it
+    surroundings: Surroundings,
 
-    jump_time: SystemTime,
-    lock_jump_time: bool,
+    charging_jump: bool, // Jump went down while on the ground, released to jump
     jumping: bool,
     flipping: bool,
     second_jump: bool,
+    fast_falling: bool, // Until the player lands
+    rebound_steps: u32, // Steps left that the player may move backwards after a wall bounce
+
+    crouch_steps: Option<u32>,     // Steps since the last crouch, while it could still pump
+    dip_steps: Option<(u32, f64)>, // Steps since the bottom of a dip and the downward speed there
+    last_angle: Option<f64>,       // Ground angle the step before, while on the ground
+
+    air_steps: u32,                // Steps since the player was last on the ground
+    landing: Option<LandingGrade>, // Last landing, until the runner takes it
+
+    grounded: bool,    // From the foot sensor, see sense_ground
+    ground_angle: f64, // Angle of the ground last sensed under the player
+
+    footing: Footing, // See step_footing
+    carry: f64,       // Speed of the platform under the player, see ride
+}
+
+// Whether mud has hold of the player
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Footing {
+    Free,
+    Stuck { meter: f64 },    // Escape meter, from 0 to 1
+    Escaping { steps: u32 }, // Steps left that mud can't catch them
 }
 
 impl<'a> Player<'a> {
     pub fn new(hitbox: PhysRect, drawbox: Rect, mass: f64, texture: &'a Texture<'a>) -> Player<'a> {
         Player {
-            pos: (hitbox.x() as f64, hitbox.y() as f64),
-            velocity: (0.0, 0.0),
-            accel: (0.0, 0.0),
+            kin: Kinematics::new(
+                (hitbox.x() as f64, hitbox.y() as f64),
+                (1.0, UPPER_SPEED),
+                (3.0 * LOWER_SPEED, 5.0 * UPPER_SPEED),
+            ),
             hitbox,
             drawbox,
 
-            theta: 0.0,
-            omega: 0.0,
-
             texture,
             mass,
             power_up: None,
+            power_steps: 0,
+            speed_scale: 1.0,
+            surroundings: Surroundings::default(),
 
-            jump_time: SystemTime::now(),
-            lock_jump_time: false,
+            charging_jump: false,
             jumping: true,
             flipping: false,
             second_jump: false,
+            fast_falling: false,
+            rebound_steps: 0,
+
+            crouch_steps: None,
+            dip_steps: None,
+            last_angle: None,
+
+            air_steps: 0,
+            landing: None,
+
+            grounded: false,
+            ground_angle: 0.0,
+
+            footing: Footing::Free,
+            carry: 0.0,
+        }
+    }
+
+    /*  Moves the player's footing along, call once a step after
+     *  set_surroundings and sense_ground
+     *
+     *  - Landing on mud gets the player stuck, and they stay stuck until
+     *    they leave it or mash their way free, see mash
+     *  - Stuck on the ground, forward speed drains by MUD_DRAG a step
+     */
+    pub fn step_footing(&mut self) {
+        let in_mud = self.surroundings.terrain == TerrainType::Mud;
+        self.footing = match self.footing {
+            Footing::Free if in_mud && self.grounded => Footing::Stuck { meter: 0.0 },
+            Footing::Stuck { .. } if !in_mud => Footing::Free,
+            Footing::Stuck { meter } => Footing::Stuck {
+                meter: (meter - MUD_METER_DRAIN).max(0.0),
+            },
+            Footing::Escaping { steps } if steps <= 1 => Footing::Free,
+            Footing::Escaping { steps } => Footing::Escaping { steps: steps - 1 },
+            footing => footing,
+        };
+        if matches!(self.footing, Footing::Stuck { .. }) && self.grounded {
+            self.kin.vel.0 = (self.kin.vel.0 * MUD_DRAG).max(self.kin.vel_x_limits.0);
+        }
+    }
+
+    // Fills the escape meter on a jump press while stuck. Returns true when
+    // that pops the player free.
+    pub fn mash(&mut self) -> bool {
+        if let Footing::Stuck { meter } = self.footing {
+            if meter + MUD_MASH_FILL >= 1.0 {
+                self.footing = Footing::Escaping {
+                    steps: MUD_ESCAPE_STEPS,
+                };
+                self.kin.vel.0 += MUD_BURST_SPEED;
+                return true;
+            }
+            self.footing = Footing::Stuck {
+                meter: meter + MUD_MASH_FILL,
+            };
+        }
+        false
+    }
+
+    pub fn footing(&self) -> Footing {
+        self.footing
+    }
+
+    /*  Tells the player what they're standing on, call once a step after
+     *  sense_ground with the speed of the platform under them, None for
+     *  plain ground
+     *
+     *  - The player's own velocity stays relative to what they stand on, and
+     *    the platform's speed is added on top of it when the world scrolls,
+     *    see travel
+     *  - Airborne players keep whatever they were carried at until they land,
+     *    so jumping off a platform doesn't lose its speed
+     */
+    pub fn ride(&mut self, platform_vel: Option<f64>) {
+        if self.grounded {
+            self.carry = platform_vel.unwrap_or(0.0);
+        }
+    }
+
+    /*  Keeps the player under a ceiling whose underside is at `ceiling_y`,
+     *  call after update_pos
+     *
+     *  - A player above it is put back right under it and loses any upward
+     *    speed, so jumps in a tunnel are cut short instead of fatal
+     *  - Returns whether the player hit it
+     */
+    pub fn hit_ceiling(&mut self, ceiling_y: i32) -> bool {
+        if self.hitbox.y() >= ceiling_y {
+            return false;
+        }
+        self.kin.pos.1 = ceiling_y as f64;
+        self.kin.vel.1 = self.kin.vel.1.min(0.0);
+        self.align_hitbox_to_pos();
+        true
+    }
+
+    // Pixels the player covers this step, their own speed plus whatever
+    // they're being carried at
+    pub fn travel(&self) -> f64 {
+        self.vel_x() + self.carry
+    }
+
+    // Thin strip along the bottom of the player that reaches a little under
+    // their feet
+    pub fn foot_sensor(&self) -> PhysRect {
+        PhysRect::new(
+            self.hitbox.x(),
+            self.hitbox.y() + self.hitbox.height() as i32 - FOOT_SENSOR_INSET,
+            self.hitbox.width(),
+            (FOOT_SENSOR_INSET + FOOT_SENSOR_REACH) as u32,
+        )
+    }
+
+    /*  Works out whether the player is on the ground, call once a step before
+     *  anything asks and again whenever the player moves
+     *
+     *  - `ground` is the ground under the player's left edge and `angle` its
+     *    slope, the ground under the right edge is followed along that slope
+     *  - Grounded when the foot sensor touches the ground at either edge, so
+     *    the ground dropping away under one edge at a seam or on a slope
+     *    doesn't count as airborne
+     *  - Also grounded when the player has sunk into the ground past the
+     *    sensor, after a fast landing
+     */
+    pub fn sense_ground(&mut self, ground: Point, angle: f64) {
+        let sensor = self.foot_sensor();
+        let far = Point::new(
+            ground.x() + sensor.width() as i32 - 1,
+            ground.y() + ((sensor.width() - 1) as f64 * angle.tan()) as i32,
+        );
+        self.grounded =
+            sensor.contains_point(ground) || sensor.contains_point(far) || self.hitbox.contains_point(ground);
+        self.ground_angle = angle;
+    }
+
+    // The grade of the last landing, once
+    pub fn take_landing(&mut self) -> Option<LandingGrade> {
+        self.landing.take()
+    }
+
+    pub fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+
+    // Unit normal of the ground under the player, pointing up out of it in
+    // screen coordinates. None while airborne.
+    pub fn ground_normal(&self) -> Option<(f64, f64)> {
+        if self.grounded {
+            Some((self.ground_angle.sin(), -self.ground_angle.cos()))
+        } else {
+            None
         }
     }
 
@@ -294,8 +1045,8 @@ impl<'a> Player<'a> {
         self.jumping
     }
 
-    pub fn jumpmoment_lock(&self) -> bool {
-        self.lock_jump_time
+    pub fn is_charging_jump(&self) -> bool {
+        self.charging_jump
     }
 
     pub fn is_flipping(&self) -> bool {
@@ -308,49 +1059,158 @@ impl<'a> Player<'a> {
         self.power_up
     }
 
-    // Setter for power-up
+    // Setter for power-up, which starts it from full
     pub fn set_power_up(&mut self, power_up: Option<PowerType>) {
         self.power_up = power_up;
+        self.power_steps = if power_up.is_some() { POWER_STEPS } else { 0 };
+    }
+
+    pub fn power_steps_left(&self) -> u32 {
+        self.power_steps
+    }
+
+    // Scales the player's top speed and how hard they push, for the whole run
+    pub fn set_speed_scale(&mut self, scale: f64) {
+        self.speed_scale = scale;
+        self.kin.vel_x_limits.1 = UPPER_SPEED * scale;
+    }
+
+    // In the last stretch of the current power
+    pub fn is_power_expiring(&self) -> bool {
+        self.power_up.is_some() && self.power_steps <= POWER_WARNING_STEPS
+    }
+
+    // Counts the current power down by a step. Returns the power if it just
+    // ran out.
+    pub fn tick_power(&mut self) -> Option<PowerType> {
+        self.power_up?;
+        self.power_steps = self.power_steps.saturating_sub(1);
+        if self.power_steps > 0 {
+            return None;
+        }
+        self.power_up.take()
+    }
+
+    // Tells the player what's under it, which changes how strong powers are
+    pub fn set_surroundings(&mut self, surroundings: Surroundings) {
+        self.surroundings = surroundings;
+    }
+
+    // Current power-up along with its strength in the current surroundings.
+    // Lower gravity stops floating the player for the rest of a fast-fall.
+    pub fn power_effect(&self) -> Option<PowerEffect> {
+        self.power_up
+            .filter(|&p| !(self.fast_falling && p == PowerType::LowerGravity))
+            .map(|p| PowerEffect::new(p, &self.surroundings))
+    }
+
+    // Drives the player down while they're in the air. Returns true if they
+    // started falling.
+    pub fn fast_fall(&mut self) -> bool {
+        if self.grounded || self.fast_falling {
+            return false;
+        }
+        self.kin.vel.1 = self.kin.vel.1.min(0.0) - FAST_FALL_SPEED;
+        self.fast_falling = true;
+        true
+    }
+
+    pub fn is_fast_falling(&self) -> bool {
+        self.fast_falling
     }
 
     // Brings player's rotational velocity to a stop
     pub fn stop_flipping(&mut self) {
         self.flipping = false;
-        self.omega = 0.0;
+        self.kin.omega = 0.0;
     }
 
     // Gives player rotational velocity
     pub fn resume_flipping(&mut self) {
         self.flipping = true;
-        self.omega = OMEGA;
+        self.kin.omega = OMEGA;
     }
 
-    pub fn set_jumpmoment(&mut self, time: SystemTime) {
-        self.jump_time = time;
-        self.lock_jump_time = true;
+    // Jump was pressed, it's charged for as long as it's held
+    pub fn start_jump_charge(&mut self) {
+        if !self.jumping {
+            self.charging_jump = true;
+            self.crouch_steps = Some(0);
+        }
+    }
+
+    /*  Follows the ground going by for pumps, call once a step after inputs
+     *
+     *  - The bottom of a dip is where the ground stops going down and starts
+     *    going up, turning by at least PUMP_CONFIG.min_bend
+     *  - A crouch within PUMP_CONFIG.window_steps of the bottom, before or
+     *    after, pumps: the speed the player was heading down at is turned
+     *    into forward speed, less the further off the timing was
+     *
+     *  - Returns the pump on the step one lands
+     */
+    pub fn track_pump(&mut self, angle: f64) -> Option<Pump> {
+        let config = &PUMP_CONFIG;
+        let last_angle = if self.grounded { self.last_angle } else { None };
+        self.last_angle = if self.grounded { Some(angle) } else { None };
+        if let Some(last_angle) = last_angle {
+            if last_angle > 0.0 && angle <= 0.0 && last_angle - angle >= config.min_bend {
+                self.dip_steps = Some((0, self.kin.vel.0 * last_angle.sin()));
+            }
+        }
+
+        let pump = match (self.crouch_steps, self.dip_steps) {
+            (Some(crouch), Some((dip, down_speed))) => {
+                let off = crouch.abs_diff(dip);
+                let accuracy = 1.0 - off as f64 / (config.window_steps + 1) as f64;
+                let boost = (down_speed * config.gain * accuracy).min(config.max_boost);
+                self.kin.vel.0 += boost;
+                self.crouch_steps = None;
+                self.dip_steps = None;
+                Some(Pump {
+                    accuracy,
+                    perfect: off <= config.perfect_steps,
+                    boost,
+                })
+            }
+            _ => None,
+        };
+
+        // Anything too old to pair up any more is dropped
+        self.crouch_steps = self.crouch_steps.map(|s| s + 1).filter(|&s| s <= config.window_steps);
+        self.dip_steps = self
+            .dip_steps
+            .map(|(s, v)| (s + 1, v))
+            .filter(|&(s, _)| s <= config.window_steps);
+        pump
     }
 
-    pub fn jump_moment(&mut self) -> SystemTime {
-        self.jump_time
+    // Jump was released after being held for `hold`. Returns true if a jump
+    // was initiated.
+    pub fn release_jump(&mut self, ground: Point, hold: Duration) -> bool {
+        std::mem::take(&mut self.charging_jump) && self.jump(ground, hold)
     }
 
     // Returns true if a jump was initiated
     pub fn jump(&mut self, ground: Point, duration: Duration) -> bool {
-        if self.hitbox().contains_point(ground) {
+        self.jump_scaled(ground, duration, 1.0)
+    }
+
+    // Same as jump, but with the upward force multiplied by `scale`
+    pub fn jump_scaled(&mut self, ground: Point, duration: Duration, scale: f64) -> bool {
+        if self.grounded {
             // Starting from the position of the ground
-            self.hard_set_pos((self.pos.0, ground.y() as f64 - TILE_SIZE));
+            self.hard_set_pos((self.kin.pos.0, ground.y() as f64 - TILE_SIZE));
             self.align_hitbox_to_pos();
-            // Apply upward force
-            let duration_millis: u128 = duration.as_millis();
-            if duration_millis <= Duration::new(0, 100000000).as_millis() {
-                self.apply_force((0.0, 60.0));
-            } else if duration_millis <= Duration::new(0, 200000000).as_millis() {
-                self.apply_force((0.0, 80.0));
+            // Apply upward force, mud holds jumps back
+            let scale = if matches!(self.footing, Footing::Stuck { .. }) {
+                scale * MUD_JUMP_SCALE
             } else {
-                self.apply_force((0.0, 100.0));
-            }
-            //self.apply_force((0.0, 100.0));
+                scale
+            };
+            self.apply_force((0.0, jump_force(duration) * scale));
             self.jumping = true;
+            self.grounded = false;
             true
         } else {
             false
@@ -365,14 +1225,33 @@ impl<'a> Player<'a> {
 
     // Handles collisions with player and any type of obstacle
     // Params: obstacle to collide with
-    // Returns: true if real game-ending collision occurs, false otherwise
-    pub fn collide_obstacle(&mut self, obstacle: &mut Obstacle) -> bool {
-        let mut shielded = false;
-        if let Some(PowerType::Shield) = self.power_up() {
-            // Put on shield if applicable
-            shielded = true;
-        }
+    // Returns: how bad the collision was, Impact::None if it did no harm
+    pub fn collide_obstacle(&mut self, obstacle: &mut Obstacle) -> Impact {
+        // Put on shield if applicable
+        let shielded = matches!(self.power_up(), Some(PowerType::Shield));
+        self.collide_obstacle_with(obstacle, shielded)
+    }
+
+    // Same as collide_obstacle, but an active shield doesn't help (risk zones)
+    pub fn collide_obstacle_unshielded(&mut self, obstacle: &mut Obstacle) -> Impact {
+        self.collide_obstacle_with(obstacle, false)
+    }
 
+    // Severity of hitting the obstacle as things are right now, from the
+    // relative velocity and how far the hitboxes overlap along `axis_x` or y
+    fn impact_severity(&self, obstacle: &Obstacle, axis_x: bool) -> f64 {
+        let rel_vx = self.kin.vel.0 - obstacle.vel_x();
+        let rel_vy = self.kin.vel.1 - obstacle.vel_y();
+        let depth = if axis_x {
+            TILE_SIZE - (self.center().x() - obstacle.center().x()).abs() as f64
+        } else {
+            TILE_SIZE - (self.center().y() - obstacle.center().y()).abs() as f64
+        };
+        (rel_vx.powi(2) + rel_vy.powi(2)).sqrt() + depth.max(0.0) * IMPACT_CONFIG.depth_weight
+    }
+
+    fn collide_obstacle_with(&mut self, obstacle: &mut Obstacle, shielded: bool) -> Impact {
+        obstacle.touched = true;
         // nearest_side checks for which side of the obstacle had the closest midpoint
         // to any point on the player rectangle
         let collision_side = self.hitbox.nearest_side(obstacle.hitbox());
@@ -380,11 +1259,25 @@ impl<'a> Player<'a> {
             // Response to collision dependent on type of obstacle
             match obstacle.obstacle_type {
                 // For statue and chest, elastic collision
-                ObstacleType::Statue | ObstacleType::Chest => {
-                    if shielded || obstacle.collided() {
+                ObstacleType::Statue | ObstacleType::GoldenStatue | ObstacleType::Chest => {
+                    let rel_vx = self.kin.vel.0 - obstacle.vel_x();
+                    let bounces = (shielded || self.flipping)
+                        && obstacle.obstacle_type != ObstacleType::GoldenStatue
+                        && rel_vx.abs() <= WALL_BOUNCE_MAX_SPEED;
+                    if bounces && !obstacle.collided() {
+                        // Bounce back off it, losing some energy
+                        debug!("Wall bounce at relative speed {:.2}", rel_vx);
+                        obstacle.collided = true;
+                        self.kin.vel.0 = obstacle.vel_x() - WALL_RESTITUTION * rel_vx;
+                        self.rebound_steps = REBOUND_STEPS;
+                        Impact::None
+                    } else if shielded || obstacle.collided() {
                         // If shielded or collision already happened, pretend nothing happened
-                        false
+                        Impact::None
                     } else {
+                        // Measured before the collision changes any velocities
+                        let impact = Impact::from_severity(self.impact_severity(obstacle, true));
+
                         /********** ELASTIC COLLISION CALCULATION ********* */
                         // https://en.wikipedia.org/wiki/Elastic_collision#One-dimensional_Newtonian
                         // Assumed object has velocity (0,0)
@@ -394,8 +1287,8 @@ impl<'a> Player<'a> {
                             .atan();
                         let p_mass = self.mass();
                         let o_mass = obstacle.mass();
-                        let p_vx = self.velocity.0;
-                        let p_vy = if self.jumping { self.velocity.1 } else { 0.0 };
+                        let p_vx = self.kin.vel.0;
+                        let p_vy = if self.jumping { self.kin.vel.1 } else { 0.0 };
                         let p_vx_f = 2.0 * (p_mass - o_mass) * (p_vx) / (p_mass + o_mass);
                         let p_vy_f = 2.0 * (p_mass - o_mass) * (p_vy) / (p_mass + o_mass);
                         let o_vx_f = 2.0 * (2.0 * p_mass) * (p_vx) / (p_mass + o_mass);
@@ -407,55 +1300,72 @@ impl<'a> Player<'a> {
                         // For ease of calculation, just set omega = alpha
 
                         /************************************************** */
+                        debug!(
+                            "Elastic collision, player v=({:.2}, {:.2}) obstacle v=({:.2}, {:.2})",
+                            p_vx_f, p_vy_f, o_vx_f, o_vy_f
+                        );
+
                         // Move obstacle
                         obstacle.collided = true;
+                        obstacle.launched = true;
                         obstacle.hard_set_vel((o_vx_f, o_vy_f));
 
                         // Move player
                         self.hard_set_vel((p_vx_f, p_vy_f));
                         self.hard_set_pos((obstacle.x() as f64 - 1.05 * TILE_SIZE, self.y() as f64));
                         self.align_hitbox_to_pos();
-                        true
+                        if obstacle.obstacle_type == ObstacleType::GoldenStatue {
+                            Impact::Severe
+                        } else {
+                            impact
+                        }
                     }
                 }
                 // For Balloon, do nothing upon SIDE collision
-                ObstacleType::Balloon => false,
+                ObstacleType::Balloon => Impact::None,
             }
         } else if self.vel_y() < 0.0 {
             match obstacle.obstacle_type {
                 // On top collision with chest, treat the chest as if it's normal ground
                 ObstacleType::Chest => {
                     // obstacle.collided = true;
-                    self.pos.1 = (obstacle.y() as f64 - 0.95 * (TILE_SIZE as f64));
+                    self.kin.pos.1 = (obstacle.y() as f64 - 0.95 * (TILE_SIZE as f64));
                     self.align_hitbox_to_pos();
-                    self.velocity.1 = 0.0;
+                    self.kin.vel.1 = 0.0;
                     self.jumping = false;
-                    self.lock_jump_time = false;
                     self.apply_force((0.0, self.mass()));
-                    self.omega = 0.0;
+                    self.kin.omega = 0.0;
                     obstacle.collided = true;
 
                     if self.theta() < OMEGA * 6.0 || self.theta() > 360.0 - OMEGA * 6.0 {
-                        self.theta = 0.0;
-                        false
+                        self.kin.theta = 0.0;
+                        Impact::None
                     } else {
-                        true
+                        // Landing on it sideways is the same as on the ground
+                        Impact::Severe
                     }
                 }
-                // For irregularly shaped statue, player gets hurt and game over
+                // For irregularly shaped statue, player gets hurt
                 ObstacleType::Statue => {
+                    let impact = Impact::from_severity(self.impact_severity(obstacle, false));
                     // bounce for fun
                     Physics::apply_bounce(self, obstacle);
-                    true
+                    impact
+                }
+                // Landing on a golden one is as fatal as running into it
+                ObstacleType::GoldenStatue => {
+                    obstacle.collided = true;
+                    Physics::apply_bounce(self, obstacle);
+                    Impact::Severe
                 }
                 // For spring, bounce off with Hooke's law force
                 ObstacleType::Balloon => {
                     Physics::apply_bounce(self, obstacle);
-                    false
+                    Impact::None
                 }
             }
         } else {
-            false
+            Impact::None
         }
     }
 
@@ -486,27 +1396,37 @@ impl<'a> Player<'a> {
     }
 }
 
-impl<'a> Entity<'a> for Player<'a> {
+impl<'a> Sprite<'a> for Player<'a> {
     fn texture(&self) -> &Texture<'a> {
         self.texture
     }
+}
 
+impl<'a> Entity<'a> for Player<'a> {
     fn hitbox(&self) -> PhysRect {
         self.hitbox
     }
 
+    fn hitboxes(&self) -> Vec<(HitboxPart, PhysRect)> {
+        PLAYER_HITBOXES.iter().map(|s| (s.part, s.place(self.hitbox))).collect()
+    }
+
     fn align_hitbox_to_pos(&mut self) {
-        self.hitbox.set_x(self.pos.0 as i32);
-        self.hitbox.set_y(self.pos.1 as i32);
+        self.hitbox.set_x(self.kin.pos.0 as i32);
+        self.hitbox.set_y(self.kin.pos.1 as i32);
     }
 
     // Adjusts terrain postion in runner.rs based on camera_adj_x & camera_adj_y
     fn camera_adj(&mut self, x_adj: i32, y_adj: i32) {
-        self.pos.0 += (x_adj as f64);
-        self.pos.1 += (y_adj as f64);
+        self.kin.pos.0 += (x_adj as f64);
+        self.kin.pos.1 += (y_adj as f64);
 
         self.align_hitbox_to_pos();
     }
+
+    fn layer(&self) -> Layer {
+        Layer::Player
+    }
 }
 
 impl<'a> Body<'a> for Player<'a> {
@@ -514,87 +1434,62 @@ impl<'a> Body<'a> for Player<'a> {
         self.mass
     }
 
+    fn kinematics(&self) -> &Kinematics {
+        &self.kin
+    }
+
+    fn kinematics_mut(&mut self) -> &mut Kinematics {
+        &mut self.kin
+    }
+
     fn update_pos(&mut self, ground: Point, angle: f64, game_over: bool) {
-        if self.hitbox.contains_point(ground) {
-            self.theta = angle;
+        if self.grounded {
+            self.kin.theta = angle;
         }
 
         /*
         // TEMPORARY: Player's x position is fixed until camera freezes on game ending
         // Will change when camera follows player
         if game_over {
-            self.pos.0 += self.vel_x();
+            self.kin.pos.0 += self.vel_x();
         }
         */
-        self.pos.1 -= self.vel_y();
+        self.kin.integrate_pos(false);
+        self.align_hitbox_to_pos();
+        self.sense_ground(ground, angle);
 
         // Match the angle of the ground if on ground
-        if self.hitbox.contains_point(ground) && !game_over {
-            self.theta = angle;
+        if self.grounded && !game_over {
+            // Landing, graded on how well the player matched the slope
+            if self.jumping && self.air_steps >= LANDING_CONFIG.min_air_steps {
+                let off = ((self.kin.theta - angle + PI).rem_euclid(2.0 * PI) - PI).abs();
+                let grade = LandingGrade::from_offset(off);
+                self.kin.vel.0 *= grade.speed_scale();
+                self.landing = Some(grade);
+            }
+            self.kin.theta = angle;
             if self.jumping {
                 self.jumping = false;
-                self.lock_jump_time = false;
             }
+            self.fast_falling = false;
         }
-
-        self.align_hitbox_to_pos();
-    }
-
-    fn hard_set_pos(&mut self, pos: (f64, f64)) {
-        self.pos.0 = pos.0;
-        self.pos.1 = pos.1;
+        self.air_steps = if self.grounded { 0 } else { self.air_steps + 1 };
     }
 
-    fn vel_x(&self) -> f64 {
-        self.velocity.0
-    }
-
-    fn vel_y(&self) -> f64 {
-        self.velocity.1
+    // The foot sensor already looked at the ground this step
+    fn touches_ground(&self, _ground: Point) -> bool {
+        self.grounded
     }
 
     fn update_vel(&mut self, game_over: bool) {
-        if game_over {
-            self.velocity.0 = (self.velocity.0 + self.accel.0).clamp(LOWER_SPEED, UPPER_SPEED);
+        // Only game over or a wall bounce can send the player backwards
+        self.kin.vel_x_limits.0 = if game_over || self.rebound_steps > 0 {
+            LOWER_SPEED
         } else {
-            self.velocity.0 = (self.velocity.0 + self.accel.0).clamp(1.0, UPPER_SPEED);
-        }
-
-        self.velocity.1 = (self.velocity.1 + self.accel.1).clamp(3.0 * LOWER_SPEED, 5.0 * UPPER_SPEED);
-    }
-
-    fn hard_set_vel(&mut self, vel: (f64, f64)) {
-        self.velocity.0 = vel.0;
-        self.velocity.1 = vel.1;
-    }
-
-    fn accel_x(&self) -> f64 {
-        self.accel.0
-    }
-
-    fn accel_y(&self) -> f64 {
-        self.accel.1
-    }
-
-    fn apply_force(&mut self, force: (f64, f64)) {
-        self.accel.0 += force.0 / self.mass();
-        self.accel.1 += force.1 / self.mass();
-    }
-
-    fn reset_accel(&mut self) {
-        self.accel = (0.0, 0.0);
-    }
-
-    fn theta(&self) -> f64 {
-        self.theta
-    }
-
-    fn rotate(&mut self) {
-        self.theta = (self.theta - self.omega() + 2.0 * PI) % (2.0 * PI);
-    }
-
-    fn omega(&self) -> f64 {
-        self.omega
+            1.0
+        };
+        self.rebound_steps = self.rebound_steps.saturating_sub(1);
+        self.kin.integrate_vel();
     }
 }
 
@@ -602,43 +1497,77 @@ impl<'a> Body<'a> for Player<'a> {
 
 /*************************** OBSTACLE ******************************** */
 
-pub struct Obstacle<'a> {
-    pub pos: (f64, f64),
-    velocity: (f64, f64),
-    accel: (f64, f64),
+// Drawn by the runner according to its type rather than holding a texture,
+// see Sprite
+#[derive(Clone)]
+pub struct Obstacle {
+    kin: Kinematics,
     hitbox: PhysRect,
 
     mass: f64,
-    texture: &'a Texture<'a>,
     obstacle_type: ObstacleType,
 
-    theta: f64,
-    omega: f64,
-
     pub collided: bool,
     pub spawned: bool,
     pub delete_me: bool,
+
+    vault_mask: u32, // Columns passed over in the current jump
+    vaulted: bool,
+    touched: bool,  // The player has touched it at all, even harmlessly
+    launched: bool, // Knocked flying by the player running into it
+    passed: bool,
+    drift: f64, // Pixels per step it moves left on its own, until it's hit
 }
 
-impl<'a> Obstacle<'a> {
-    pub fn new(hitbox: PhysRect, mass: f64, texture: &'a Texture<'a>, obstacle_type: ObstacleType) -> Obstacle<'a> {
+impl Obstacle {
+    pub fn new(hitbox: PhysRect, mass: f64, obstacle_type: ObstacleType) -> Obstacle {
         Obstacle {
-            pos: (hitbox.x() as f64, hitbox.y() as f64),
-            velocity: (0.0, 0.0),
-            accel: (0.0, 0.0),
+            kin: Kinematics::new((hitbox.x() as f64, hitbox.y() as f64), (-20.0, 20.0), (-20.0, 20.0)),
             hitbox,
 
             mass,
-            texture,
             obstacle_type,
 
-            theta: 0.0,
-            omega: 0.0,
-
             collided: false,
             spawned: false,
             delete_me: false,
+
+            vault_mask: 0,
+            vaulted: false,
+            touched: false,
+            launched: false,
+            passed: false,
+            drift: 0.0,
+        }
+    }
+
+    // Has the obstacle move `drift` pixels left every step on top of the
+    // terrain scrolling
+    pub fn set_drift(&mut self, drift: f64) {
+        self.drift = drift;
+    }
+
+    // Returns true on the step the obstacle falls behind `player_x` if the
+    // player never touched it
+    pub fn check_dodged(&mut self, player_x: i32) -> bool {
+        if self.passed || self.hitbox.right().x() >= player_x {
+            return false;
+        }
+        self.passed = true;
+        !self.touched
+    }
+
+    // Follows the player jumping over the obstacle. Returns true on the step
+    // they've passed over every column of it in one jump without touching it,
+    // only once per obstacle. Being on the ground or hitting it starts over.
+    pub fn track_vault(&mut self, player: PhysRect, airborne: bool) -> bool {
+        if self.vaulted || self.collided || !airborne {
+            self.vault_mask = 0;
+            return false;
         }
+        self.vault_mask |= Physics::columns_above(self.hitbox, player, VAULT_COLUMNS);
+        self.vaulted = self.vault_mask == (1 << VAULT_COLUMNS) - 1;
+        self.vaulted
     }
 
     pub fn obstacle_type(&self) -> ObstacleType {
@@ -649,100 +1578,74 @@ impl<'a> Obstacle<'a> {
         self.collided
     }
 
+    pub fn launched(&self) -> bool {
+        self.launched
+    }
+
     // Shifts objects left with the terrain in runner.rs
     pub fn travel_update(&mut self, travel_adj: i32) {
-        self.pos.0 -= (travel_adj as f64);
+        self.kin.pos.0 -= (travel_adj as f64);
+        if !self.collided {
+            self.kin.pos.0 -= self.drift;
+        }
     }
-}
 
-impl<'a> Entity<'a> for Obstacle<'a> {
-    fn texture(&self) -> &Texture<'a> {
-        self.texture
+    // Moves the obstacle by (dx, dy) pixels on screen
+    fn shift(&mut self, dx: f64, dy: f64) {
+        self.kin.pos.0 += dx;
+        self.kin.pos.1 += dy;
+        self.align_hitbox_to_pos();
     }
+}
 
+impl<'a> Entity<'a> for Obstacle {
     fn hitbox(&self) -> PhysRect {
         self.hitbox
     }
 
+    fn hitboxes(&self) -> Vec<(HitboxPart, PhysRect)> {
+        let shapes: &[HitboxShape] = match self.obstacle_type {
+            ObstacleType::Statue | ObstacleType::GoldenStatue => &STATUE_HITBOXES,
+            ObstacleType::Balloon => &BALLOON_HITBOXES,
+            ObstacleType::Chest => &CHEST_HITBOXES,
+        };
+        shapes.iter().map(|s| (s.part, s.place(self.hitbox))).collect()
+    }
+
     fn align_hitbox_to_pos(&mut self) {
-        self.hitbox.set_x(self.pos.0 as i32);
-        self.hitbox.set_y(self.pos.1 as i32);
+        self.hitbox.set_x(self.kin.pos.0 as i32);
+        self.hitbox.set_y(self.kin.pos.1 as i32);
     }
 
     // Adjusts terrain postion in runner.rs based on camera_adj_x & camera_adj_y
     fn camera_adj(&mut self, x_adj: i32, y_adj: i32) {
-        self.pos.0 += (x_adj as f64);
-        self.pos.1 += (y_adj as f64);
+        self.kin.pos.0 += (x_adj as f64);
+        self.kin.pos.1 += (y_adj as f64);
 
         self.align_hitbox_to_pos();
     }
 }
 
-impl<'a> Body<'a> for Obstacle<'a> {
+impl<'a> Body<'a> for Obstacle {
     fn mass(&self) -> f64 {
         self.mass
     }
 
-    fn update_pos(&mut self, ground: Point, angle: f64, game_over: bool) {
-        if self.hitbox.contains_point(ground) && !game_over {
-            self.theta = angle;
-        }
-
-        self.pos.0 += self.vel_x();
-        self.pos.1 -= self.vel_y();
-        self.align_hitbox_to_pos();
-    }
-
-    fn hard_set_pos(&mut self, pos: (f64, f64)) {
-        self.pos.0 = pos.0;
-        self.pos.1 = pos.1;
-    }
-
-    fn vel_x(&self) -> f64 {
-        self.velocity.0
-    }
-
-    fn vel_y(&self) -> f64 {
-        self.velocity.1
-    }
-
-    fn update_vel(&mut self, game_over: bool) {
-        self.velocity.0 = (self.velocity.0 + self.accel.0).clamp(-20.0, 20.0);
-        self.velocity.1 = (self.velocity.1 + self.accel.1).clamp(-20.0, 20.0);
-    }
-
-    fn hard_set_vel(&mut self, vel: (f64, f64)) {
-        self.velocity.0 = vel.0;
-        self.velocity.1 = vel.1;
-    }
-
-    fn accel_x(&self) -> f64 {
-        self.accel.0
-    }
-
-    fn accel_y(&self) -> f64 {
-        self.accel.1
-    }
-
-    fn apply_force(&mut self, force: (f64, f64)) {
-        self.accel.0 += force.0 / self.mass();
-        self.accel.1 += force.1 / self.mass();
-    }
-
-    fn reset_accel(&mut self) {
-        self.accel = (0.0, 0.0);
+    fn kinematics(&self) -> &Kinematics {
+        &self.kin
     }
 
-    fn theta(&self) -> f64 {
-        self.theta
+    fn kinematics_mut(&mut self) -> &mut Kinematics {
+        &mut self.kin
     }
 
-    fn rotate(&mut self) {
-        self.theta = (self.theta - self.omega() + 2.0 * PI) % (2.0 * PI);
-    }
+    fn update_pos(&mut self, ground: Point, angle: f64, game_over: bool) {
+        if self.hitbox.contains_point(ground) && !game_over {
+            self.kin.theta = angle;
+        }
 
-    fn omega(&self) -> f64 {
-        self.omega
+        self.kin.integrate_pos(true);
+        self.align_hitbox_to_pos();
     }
 }
 
@@ -752,20 +1655,43 @@ impl<'a> Body<'a> for Obstacle<'a> {
 
 pub struct Coin<'a> {
     pub pos: (i32, i32),
+    prev_pos: (i32, i32), // Where the current step started, see drawn_pos
     hitbox: PhysRect,
     texture: &'a Texture<'a>,
     value: i32,
     collected: bool,
+    drop: Option<CoinDrop>, // Only for coins dropped from the sky
+}
+
+// How a coin dropped from the sky is moving, see Coin::fall
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum CoinDrop {
+    Falling { y: f64, vel: f64, bounced: bool }, // vel is downwards
+    Settled { steps: u32 },                      // Steps left before it's gone
 }
 
 impl<'a> Coin<'a> {
     pub fn new(hitbox: PhysRect, texture: &'a Texture<'a>, value: i32) -> Coin<'a> {
         Coin {
             pos: (hitbox.x(), hitbox.y()),
+            prev_pos: (hitbox.x(), hitbox.y()),
             texture,
             hitbox,
             value,
             collected: false,
+            drop: None,
+        }
+    }
+
+    // Coin let go at `hitbox`, to fall from there
+    pub fn dropped(hitbox: PhysRect, texture: &'a Texture<'a>, value: i32) -> Coin<'a> {
+        Coin {
+            drop: Some(CoinDrop::Falling {
+                y: hitbox.y() as f64,
+                vel: 0.0,
+                bounced: false,
+            }),
+            ..Coin::new(hitbox, texture, value)
         }
     }
 
@@ -773,17 +1699,79 @@ impl<'a> Coin<'a> {
         self.value
     }
 
+    pub fn drop_state(&self) -> Option<CoinDrop> {
+        self.drop
+    }
+
+    /*  Moves a dropped coin a step, `ground_y` is the ground under it
+     *
+     *  - Falls until it reaches the ground, bounces off it once and then
+     *    settles there for COIN_SETTLE_STEPS
+     *  - Coins that weren't dropped don't move
+     */
+    pub fn fall(&mut self, ground_y: i32) {
+        let rest_y = (ground_y - self.hitbox.height() as i32) as f64;
+        self.drop = match self.drop {
+            Some(CoinDrop::Falling { y, vel, bounced }) => {
+                let vel = vel + COIN_GRAVITY;
+                let y = y + vel;
+                if y < rest_y {
+                    Some(CoinDrop::Falling { y, vel, bounced })
+                } else if !bounced {
+                    Some(CoinDrop::Falling {
+                        y: rest_y,
+                        vel: -vel * COIN_BOUNCE,
+                        bounced: true,
+                    })
+                } else {
+                    Some(CoinDrop::Settled {
+                        steps: COIN_SETTLE_STEPS,
+                    })
+                }
+            }
+            Some(CoinDrop::Settled { steps }) => Some(CoinDrop::Settled {
+                steps: steps.saturating_sub(1),
+            }),
+            None => None,
+        };
+        match self.drop {
+            Some(CoinDrop::Falling { y, .. }) => self.pos.1 = y as i32,
+            Some(CoinDrop::Settled { .. }) => self.pos.1 = rest_y as i32,
+            None => {}
+        }
+        self.align_hitbox_to_pos();
+    }
+
+    // Whether a dropped coin has lain on the ground as long as it will
+    pub fn is_expired(&self) -> bool {
+        self.drop == Some(CoinDrop::Settled { steps: 0 })
+    }
+
     // Shifts objects left with the terrain in runner.rs
     pub fn travel_update(&mut self, travel_adj: i32) {
         self.pos.0 -= travel_adj;
     }
+
+    // Same as Body::remember_pos and Body::drawn_pos
+    pub fn remember_pos(&mut self) {
+        self.prev_pos = self.pos;
+    }
+
+    pub fn drawn_pos(&self, alpha: f64) -> (i32, i32) {
+        (
+            self.prev_pos.0 + ((self.pos.0 - self.prev_pos.0) as f64 * alpha).round() as i32,
+            self.prev_pos.1 + ((self.pos.1 - self.prev_pos.1) as f64 * alpha).round() as i32,
+        )
+    }
 }
 
-impl<'a> Entity<'a> for Coin<'a> {
+impl<'a> Sprite<'a> for Coin<'a> {
     fn texture(&self) -> &Texture<'a> {
         self.texture
     }
+}
 
+impl<'a> Entity<'a> for Coin<'a> {
     fn hitbox(&self) -> PhysRect {
         self.hitbox
     }
@@ -797,9 +1785,16 @@ impl<'a> Entity<'a> for Coin<'a> {
     fn camera_adj(&mut self, x_adj: i32, y_adj: i32) {
         self.pos.0 += x_adj;
         self.pos.1 += y_adj;
+        if let Some(CoinDrop::Falling { y, .. }) = self.drop.as_mut() {
+            *y += y_adj as f64;
+        }
 
         self.align_hitbox_to_pos();
     }
+
+    fn layer(&self) -> Layer {
+        Layer::Pickups
+    }
 }
 
 impl<'a> Collectible<'a> for Coin<'a> {
@@ -819,6 +1814,71 @@ impl<'a> Collectible<'a> for Coin<'a> {
 
 /********************************************************************* */
 
+/*************************** DEBRIS *********************************** */
+
+// Rock shaken loose by an earthquake, falls straight down and breaks on the
+// ground
+pub struct Debris {
+    hitbox: PhysRect,
+    y: f64,
+    vel: f64, // Downwards
+    landed: bool,
+}
+
+impl Debris {
+    // Debris `size` pixels across, let go just above the top of the screen
+    pub fn new(x: i32, size: u32) -> Debris {
+        let y = -(size as i32);
+        Debris {
+            hitbox: PhysRect::new(x, y, size, size),
+            y: y as f64,
+            vel: 0.0,
+            landed: false,
+        }
+    }
+
+    // Falls a step towards the ground at `ground_y` under it
+    pub fn fall(&mut self, ground_y: i32) {
+        self.vel += DEBRIS_GRAVITY;
+        self.y += self.vel;
+        let rest_y = (ground_y - self.hitbox.height() as i32) as f64;
+        if self.y >= rest_y {
+            self.y = rest_y;
+            self.landed = true;
+        }
+        self.align_hitbox_to_pos();
+    }
+
+    // Whether it's hit the ground and broken
+    pub fn landed(&self) -> bool {
+        self.landed
+    }
+
+    // Shifts debris left with the terrain in runner.rs
+    pub fn travel_update(&mut self, travel_adj: i32) {
+        self.hitbox.set_x(self.hitbox.x() - travel_adj);
+    }
+}
+
+impl<'a> Entity<'a> for Debris {
+    fn hitbox(&self) -> PhysRect {
+        self.hitbox
+    }
+
+    fn align_hitbox_to_pos(&mut self) {
+        self.hitbox.set_y(self.y as i32);
+    }
+
+    // Adjusts terrain postion in runner.rs based on camera_adj_x & camera_adj_y
+    fn camera_adj(&mut self, x_adj: i32, y_adj: i32) {
+        self.hitbox.set_x(self.hitbox.x() + x_adj);
+        self.y += y_adj as f64;
+        self.align_hitbox_to_pos();
+    }
+}
+
+/********************************************************************* */
+
 /*************************** POWER *********************************** */
 
 pub struct Power<'a> {
@@ -850,11 +1910,13 @@ impl<'a> Power<'a> {
     }
 }
 
-impl<'a> Entity<'a> for Power<'a> {
+impl<'a> Sprite<'a> for Power<'a> {
     fn texture(&self) -> &Texture<'a> {
         self.texture
     }
+}
 
+impl<'a> Entity<'a> for Power<'a> {
     fn hitbox(&self) -> PhysRect {
         self.hitbox
     }
@@ -871,6 +1933,10 @@ impl<'a> Entity<'a> for Power<'a> {
 
         self.align_hitbox_to_pos();
     }
+
+    fn layer(&self) -> Layer {
+        Layer::Pickups
+    }
 }
 
 impl<'a> Collectible<'a> for Power<'a> {
@@ -888,6 +1954,75 @@ impl<'a> Collectible<'a> for Power<'a> {
     }
 }
 
+/********************************************************************* */
+
+/*************************** DRONE *********************************** */
+
+// Spring constant and damping of the drone's pull towards its hover point.
+// Damping just under 2 * sqrt(k) so it settles with a slight overshoot.
+const DRONE_STIFFNESS: f64 = 0.02;
+const DRONE_DAMPING: f64 = 0.25;
+pub const DRONE_SIZE: u32 = 40;
+
+// Companion that hovers behind the player and picks up nearby coins. Not a
+// Body, it isn't affected by terrain, gravity or collisions.
+pub struct Drone {
+    pos: (f64, f64),
+    velocity: (f64, f64),
+    radius: f64, // Coin pickup radius, from the drone's center
+}
+
+impl Drone {
+    pub fn new(pos: Point, radius: f64) -> Drone {
+        Drone {
+            pos: (pos.x() as f64, pos.y() as f64),
+            velocity: (0.0, 0.0),
+            radius,
+        }
+    }
+
+    // One step of spring-damper motion towards `target` (top-left corner)
+    pub fn follow(&mut self, target: Point) {
+        let accel_x = DRONE_STIFFNESS * (target.x() as f64 - self.pos.0) - DRONE_DAMPING * self.velocity.0;
+        let accel_y = DRONE_STIFFNESS * (target.y() as f64 - self.pos.1) - DRONE_DAMPING * self.velocity.1;
+        self.velocity.0 += accel_x;
+        self.velocity.1 += accel_y;
+        self.pos.0 += self.velocity.0;
+        self.pos.1 += self.velocity.1;
+    }
+
+    // Whether a coin is close enough to be grabbed
+    pub fn in_reach<'a>(&self, entity: &impl Entity<'a>) -> bool {
+        let center = self.center();
+        let other = entity.center();
+        let dx = (other.x() - center.x()) as f64;
+        let dy = (other.y() - center.y()) as f64;
+        dx * dx + dy * dy <= self.radius * self.radius
+    }
+
+    pub fn x(&self) -> i32 {
+        self.pos.0 as i32
+    }
+
+    pub fn y(&self) -> i32 {
+        self.pos.1 as i32
+    }
+
+    pub fn center(&self) -> Point {
+        Point::new(self.x() + DRONE_SIZE as i32 / 2, self.y() + DRONE_SIZE as i32 / 2)
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    // Moves with the rest of the world when the camera shifts
+    pub fn camera_adj(&mut self, x_adj: i32, y_adj: i32) {
+        self.pos.0 += x_adj as f64;
+        self.pos.1 += y_adj as f64;
+    }
+}
+
 /******************************ROTATING
  * HITBOX******************************* */
 
@@ -1232,6 +2367,36 @@ impl PhysRect {
         false
     }
 
+    /// The smallest move of this rect, along x or y, that takes it out of the
+    /// given rect, going by their bounding boxes. None if they don't overlap.
+    pub fn mtv(&self, other: PhysRect) -> Option<(i32, i32)> {
+        let bounds = |r: &PhysRect| {
+            let xs = r.coords.iter().map(|p| p.x());
+            let ys = r.coords.iter().map(|p| p.y());
+            (
+                xs.clone().min().unwrap_or(0),
+                xs.max().unwrap_or(0),
+                ys.clone().min().unwrap_or(0),
+                ys.max().unwrap_or(0),
+            )
+        };
+        let (a_left, a_right, a_top, a_bottom) = bounds(self);
+        let (b_left, b_right, b_top, b_bottom) = bounds(&other);
+        let overlap_x = a_right.min(b_right) - a_left.max(b_left);
+        let overlap_y = a_bottom.min(b_bottom) - a_top.max(b_top);
+        if overlap_x <= 0 || overlap_y <= 0 {
+            return None;
+        }
+        // Away from the other rect's center
+        let away_x = if self.center().x() < other.center().x() { -1 } else { 1 };
+        let away_y = if self.center().y() < other.center().y() { -1 } else { 1 };
+        if overlap_x < overlap_y {
+            Some((away_x * overlap_x, 0))
+        } else {
+            Some((0, away_y * overlap_y))
+        }
+    }
+
     /// Returns an integer corresponding to the side of this rect that the given
     /// rect's points are closest to 0, 1, 2, and 3 correspond to top,
     /// right, bottom, and left respectively Mainly used for collision logic
@@ -1265,3 +2430,493 @@ impl PhysRect {
         min_side
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sdl2::pixels::PixelFormatEnum;
+    use sdl2::surface::Surface;
+
+    /*  Scenario harness: the player and any launched obstacles on endless
+     *  straight ground, stepped in the same order as the runner
+     *
+     *  - The ground runs through (START_X, GROUND_Y) at `slope` radians,
+     *    positive downhill, and scrolls past the player as they travel
+     *  - Inputs are scripted by step
+     *  - Collisions between the player and obstacles aren't simulated
+     */
+    const START_X: i32 = 200;
+    const GROUND_Y: i32 = 600;
+    // Where the player's top edge is when they're resting on flat ground
+    const REST_Y: i32 = GROUND_Y - 95;
+
+    enum Script {
+        Press,
+        Release(u64), // ms held
+    }
+
+    struct Scenario<'a> {
+        player: Player<'a>,
+        obstacles: Vec<Obstacle>,
+        slope: f64,
+        terrain: TerrainType,
+        platform: Option<f64>, // Speed of a platform under the player
+        ceiling: Option<i32>,  // Underside of a ceiling over the player
+        bonks: u32,            // Times the player hit it
+        travelled: f64,
+        steps: u32,
+        script: Vec<(u32, Script)>,
+    }
+
+    impl<'a> Scenario<'a> {
+        // The player resting on the ground
+        fn new(texture: &'a Texture<'a>, slope: f64) -> Scenario<'a> {
+            Scenario {
+                player: Player::new(
+                    PhysRect::new(START_X, REST_Y, 100, 100),
+                    Rect::new(START_X, REST_Y, 100, 100),
+                    3.0,
+                    texture,
+                ),
+                obstacles: Vec::new(),
+                slope,
+                terrain: TerrainType::Grass,
+                platform: None,
+                ceiling: None,
+                bonks: 0,
+                travelled: 0.0,
+                steps: 0,
+                script: Vec::new(),
+            }
+        }
+
+        fn script(&mut self, step: u32, input: Script) -> &mut Scenario<'a> {
+            self.script.push((step, input));
+            self
+        }
+
+        // Ground at `x` on the screen
+        fn ground_at(&self, x: i32) -> Point {
+            let along = (x - START_X) as f64 + self.travelled;
+            Point::new(x, GROUND_Y + (along * self.slope.tan()) as i32)
+        }
+
+        fn step(&mut self) {
+            let ground = self.ground_at(START_X);
+            let angle = self.slope;
+            let player = &mut self.player;
+            player.sense_ground(ground, angle);
+            player.ride(self.platform);
+            player.set_surroundings(Surroundings {
+                terrain: self.terrain,
+                updraft: false,
+            });
+            player.step_footing();
+
+            let now = self.steps;
+            for (_, input) in self.script.iter().filter(|(at, _)| *at == now) {
+                match input {
+                    Script::Press => {
+                        player.mash();
+                        if player.is_jumping() {
+                            player.resume_flipping();
+                        } else {
+                            player.start_jump_charge();
+                        }
+                    }
+                    Script::Release(ms) => {
+                        player.release_jump(ground, Duration::from_millis(*ms));
+                        player.stop_flipping();
+                    }
+                }
+            }
+            if let Some(effect) = player.power_effect() {
+                if effect.power == PowerType::BouncyShoes && !player.is_jumping() {
+                    player.jump_scaled(ground, Duration::new(1111, 0), effect.strength);
+                }
+            }
+            player.track_pump(angle);
+
+            let power = player.power_effect();
+            Physics::apply_terrain_forces(player, angle, ground, &self.terrain, power);
+            Physics::apply_skate_force(player, angle);
+            player.update_vel(false);
+            player.update_pos(ground, angle, false);
+            player.flip();
+            if let Some(ceiling) = self.ceiling {
+                if player.hit_ceiling(ceiling) {
+                    self.bonks += 1;
+                }
+            }
+            player.reset_accel();
+
+            let mut obstacles = std::mem::take(&mut self.obstacles);
+            for o in obstacles.iter_mut().filter(|o| o.collided()) {
+                let left = self.ground_at(o.x());
+                let right = self.ground_at(o.x() + o.hitbox().width() as i32 - 1);
+                Physics::apply_terrain_forces(o, angle, left, &TerrainType::Grass, None);
+                o.update_vel(false);
+                o.update_pos(left, angle, false);
+                o.reset_accel();
+                Physics::settle_on_terrain(o, left, right);
+            }
+            Physics::resolve_obstacles(&mut obstacles);
+
+            let travel = self.player.travel();
+            for o in obstacles.iter_mut() {
+                o.travel_update(travel as i32);
+            }
+            self.obstacles = obstacles;
+            self.travelled += travel;
+            self.steps += 1;
+        }
+
+        fn run(&mut self, steps: u32) {
+            for _ in 0..steps {
+                self.step();
+            }
+        }
+
+        // How far above its resting height the player's top edge got
+        fn apex(&mut self, steps: u32) -> i32 {
+            let mut highest = self.player.y();
+            for _ in 0..steps {
+                self.step();
+                highest = highest.min(self.player.y());
+            }
+            REST_Y - highest
+        }
+    }
+
+    // Runs `f` with a texture from a software renderer, so the player can be
+    // made without a window
+    fn with_texture<F: FnOnce(&Texture)>(f: F) {
+        let surface = Surface::new(100, 100, PixelFormatEnum::RGBA8888).unwrap();
+        let canvas = surface.into_canvas().unwrap();
+        let texture_creator = canvas.texture_creator();
+        let texture = texture_creator.create_texture_target(None, 100, 100).unwrap();
+        f(&texture);
+    }
+
+    #[test]
+    fn dropped_player_comes_to_rest() {
+        with_texture(|texture| {
+            let mut scenario = Scenario::new(texture, 0.0);
+            scenario.player.hard_set_pos((START_X as f64, 300.0));
+            scenario.player.align_hitbox_to_pos();
+            scenario.run(200);
+
+            assert!(scenario.player.is_grounded());
+            assert_eq!(scenario.player.vel_y(), 0.0);
+            assert_eq!(scenario.player.y(), REST_Y);
+        });
+    }
+
+    // Gravity takes 1.5 off the rising speed each step, so a jump with
+    // upward acceleration a peaks at the sum of a - 1.5, a - 3, ... above
+    // where it started, 5 pixels above resting
+    #[test]
+    fn tapped_jump_peaks_within_tolerance() {
+        with_texture(|texture| {
+            let mut scenario = Scenario::new(texture, 0.0);
+            scenario.script(1, Script::Press).script(2, Script::Release(0));
+            // 60 / 3 upward, 13 rising steps from 18.5 down to 0.5
+            let apex = scenario.apex(60);
+            assert!((apex - 129).abs() <= 3, "apex {}", apex);
+        });
+    }
+
+    #[test]
+    fn held_jump_peaks_within_tolerance() {
+        with_texture(|texture| {
+            let mut scenario = Scenario::new(texture, 0.0);
+            scenario.script(1, Script::Press).script(2, Script::Release(1000));
+            // MAX_JUMP_FORCE / 3 upward, 22 rising steps
+            let apex = scenario.apex(90);
+            assert!((apex - 359).abs() <= 3, "apex {}", apex);
+        });
+    }
+
+    #[test]
+    fn bouncy_shoes_keep_bouncing_as_high() {
+        with_texture(|texture| {
+            let mut scenario = Scenario::new(texture, 0.0);
+            scenario.player.set_power_up(Some(PowerType::BouncyShoes));
+            // Full strength on grass, the same as a held jump
+            let first = scenario.apex(45);
+            let second = scenario.apex(55);
+            assert!((first - 359).abs() <= 3, "first apex {}", first);
+            assert!((second - first).abs() <= 3, "second apex {}", second);
+        });
+    }
+
+    #[test]
+    fn mud_halves_jumps() {
+        with_texture(|texture| {
+            let mut scenario = Scenario::new(texture, 0.0);
+            scenario.terrain = TerrainType::Mud;
+            scenario.script(1, Script::Press).script(2, Script::Release(1000));
+            let apex = scenario.apex(90);
+            // Half the held jump's force, 11 rising steps
+            assert!((apex - 88).abs() <= 3, "apex {}", apex);
+        });
+    }
+
+    #[test]
+    fn mashing_pops_the_player_out_of_mud() {
+        with_texture(|texture| {
+            let mut scenario = Scenario::new(texture, 0.0);
+            scenario.terrain = TerrainType::Mud;
+            scenario.run(60);
+            assert!(matches!(scenario.player.footing(), Footing::Stuck { .. }));
+            assert!(scenario.player.vel_x() < 1.5);
+
+            // Presses without releases, so the player stays on the ground.
+            // The meter drains a little between them, so it takes six.
+            for step in 60..66 {
+                scenario.script(step, Script::Press);
+            }
+            scenario.run(7);
+            assert!(matches!(scenario.player.footing(), Footing::Escaping { .. }));
+            assert!(scenario.player.vel_x() > 5.0);
+        });
+    }
+
+    #[test]
+    fn platforms_carry_the_player_through_a_jump() {
+        with_texture(|texture| {
+            let mut scenario = Scenario::new(texture, 0.0);
+            scenario.platform = Some(0.8);
+            scenario.run(30);
+            assert!((scenario.player.travel() - scenario.player.vel_x() - 0.8).abs() < 1e-9);
+
+            // Off the platform mid-jump, the carried speed stays until landing
+            scenario.platform = None;
+            scenario.script(30, Script::Press).script(31, Script::Release(0));
+            scenario.run(6);
+            assert!(!scenario.player.is_grounded());
+            assert!((scenario.player.travel() - scenario.player.vel_x() - 0.8).abs() < 1e-9);
+            scenario.run(60);
+            assert!(scenario.player.is_grounded());
+            assert_eq!(scenario.player.travel(), scenario.player.vel_x());
+        });
+    }
+
+    #[test]
+    fn ceilings_cut_jumps_short() {
+        with_texture(|texture| {
+            let mut scenario = Scenario::new(texture, 0.0);
+            scenario.ceiling = Some(REST_Y - 60);
+            scenario.script(1, Script::Press).script(2, Script::Release(1000));
+            let apex = scenario.apex(90);
+            assert_eq!(apex, 60);
+            assert_eq!(scenario.bonks, 1);
+            // Straight back down instead of hanging under the ceiling
+            assert!(scenario.player.is_grounded());
+        });
+    }
+
+    #[test]
+    fn dropped_coins_bounce_once_then_settle() {
+        with_texture(|texture| {
+            let mut coin = Coin::dropped(PhysRect::new(START_X, 0, 100, 100), texture, 1000);
+            let mut landings = 0;
+            let mut was_rising = false;
+            for _ in 0..200 {
+                coin.fall(GROUND_Y);
+                assert!(coin.y() + 100 <= GROUND_Y);
+                if let Some(CoinDrop::Falling { vel, .. }) = coin.drop_state() {
+                    if vel < 0.0 && !was_rising {
+                        landings += 1;
+                    }
+                    was_rising = vel < 0.0;
+                }
+                if matches!(coin.drop_state(), Some(CoinDrop::Settled { .. })) {
+                    break;
+                }
+            }
+            assert_eq!(landings, 1);
+            assert_eq!(coin.y(), GROUND_Y - 100);
+            for _ in 0..COIN_SETTLE_STEPS {
+                assert!(!coin.is_expired());
+                coin.fall(GROUND_Y);
+            }
+            assert!(coin.is_expired());
+        });
+    }
+
+    #[test]
+    fn friction_stops_a_sliding_obstacle() {
+        with_texture(|texture| {
+            let mut scenario = Scenario::new(texture, 0.0);
+            let mut chest = Obstacle::new(PhysRect::new(START_X + 300, REST_Y, 100, 100), 1.0, ObstacleType::Chest);
+            chest.collided = true;
+            chest.hard_set_vel((5.0, 0.0));
+            scenario.obstacles.push(chest);
+            scenario.run(120);
+
+            let chest = &scenario.obstacles[0];
+            assert_eq!((chest.vel_x(), chest.vel_y()), (0.0, 0.0));
+            assert_eq!(chest.y(), REST_Y);
+        });
+    }
+
+    #[test]
+    fn downhills_are_faster_than_flat_ground() {
+        with_texture(|texture| {
+            let mut flat = Scenario::new(texture, 0.0);
+            let mut downhill = Scenario::new(texture, 0.2);
+            flat.run(120);
+            downhill.run(120);
+            assert!(downhill.player.vel_x() > flat.player.vel_x() + 1.0);
+        });
+    }
+
+    #[test]
+    fn powers_warn_before_running_out() {
+        with_texture(|texture| {
+            let mut scenario = Scenario::new(texture, 0.0);
+            let player = &mut scenario.player;
+            player.set_power_up(Some(PowerType::SpeedBoost));
+            for _ in 0..POWER_STEPS - POWER_WARNING_STEPS - 1 {
+                assert_eq!(player.tick_power(), None);
+            }
+            assert!(!player.is_power_expiring());
+            assert_eq!(player.tick_power(), None);
+            assert!(player.is_power_expiring());
+            for _ in 0..POWER_WARNING_STEPS - 1 {
+                assert_eq!(player.tick_power(), None);
+            }
+            assert_eq!(player.tick_power(), Some(PowerType::SpeedBoost));
+            assert_eq!(player.power_up(), None);
+            assert!(!player.is_power_expiring());
+        });
+    }
+
+    #[test]
+    fn taps_hop() {
+        assert_eq!(jump_force(Duration::ZERO), 60.0);
+        assert_eq!(jump_force(Duration::from_millis(100)), 60.0);
+    }
+
+    #[test]
+    fn medium_holds_use_the_middle_tier() {
+        assert_eq!(jump_force(Duration::from_millis(101)), 80.0);
+        assert_eq!(jump_force(Duration::from_millis(200)), 80.0);
+    }
+
+    #[test]
+    fn long_holds_launch() {
+        assert_eq!(jump_force(Duration::from_millis(201)), MAX_JUMP_FORCE);
+        assert_eq!(jump_force(Duration::from_secs(5)), MAX_JUMP_FORCE);
+    }
+
+    #[test]
+    fn columns_only_count_when_fully_above() {
+        let statue = PhysRect::new(400, 500, 100, 100);
+        // Over the left half, clear of the top
+        assert_eq!(
+            Physics::columns_above(statue, PhysRect::new(350, 300, 100, 100), 4),
+            0b0011
+        );
+        // Same spot but low enough to touch the top
+        assert_eq!(Physics::columns_above(statue, PhysRect::new(350, 420, 100, 100), 4), 0);
+        // Off to the side
+        assert_eq!(Physics::columns_above(statue, PhysRect::new(600, 300, 100, 100), 4), 0);
+    }
+
+    #[test]
+    fn vault_needs_every_column_in_one_jump() {
+        let mut statue = Obstacle::new(PhysRect::new(400, 500, 100, 100), 50.0, ObstacleType::GoldenStatue);
+        assert!(!statue.track_vault(PhysRect::new(320, 300, 100, 100), true));
+        // Landing in between starts over
+        assert!(!statue.track_vault(PhysRect::new(380, 300, 100, 100), false));
+        assert!(!statue.track_vault(PhysRect::new(440, 300, 100, 100), true));
+
+        let mut statue = Obstacle::new(PhysRect::new(400, 500, 100, 100), 50.0, ObstacleType::GoldenStatue);
+        assert!(!statue.track_vault(PhysRect::new(320, 300, 100, 100), true));
+        assert!(statue.track_vault(PhysRect::new(420, 300, 100, 100), true));
+        // Only pays out once
+        assert!(!statue.track_vault(PhysRect::new(470, 300, 100, 100), true));
+    }
+
+    #[test]
+    fn balloon_strings_dont_collide() {
+        let mut balloon = Obstacle::new(PhysRect::new(400, 300, 100, 100), 1.0, ObstacleType::Balloon);
+        let parts = balloon.hitboxes();
+        assert_eq!(parts, vec![(HitboxPart::Body, PhysRect::new(420, 300, 60, 60))]);
+        // Under the balloon, where only the string is
+        let mut chest = Obstacle::new(PhysRect::new(400, 380, 100, 100), 1.0, ObstacleType::Chest);
+        assert!(!Physics::check_collision(&mut balloon, &mut chest));
+        let mut chest = Obstacle::new(PhysRect::new(400, 340, 100, 100), 1.0, ObstacleType::Chest);
+        assert!(Physics::check_collision(&mut balloon, &mut chest));
+    }
+
+    #[test]
+    fn landings_grade_by_band() {
+        assert_eq!(LandingGrade::from_offset(0.0), LandingGrade::Perfect);
+        assert_eq!(
+            LandingGrade::from_offset(LANDING_CONFIG.perfect_band),
+            LandingGrade::Perfect
+        );
+        assert_eq!(LandingGrade::from_offset(0.2), LandingGrade::Good);
+        assert_eq!(
+            LandingGrade::from_offset(LANDING_CONFIG.good_band + 0.01),
+            LandingGrade::Sloppy
+        );
+        assert!(LandingGrade::Perfect.speed_scale() > LandingGrade::Sloppy.speed_scale());
+    }
+
+    #[test]
+    fn spawns_are_lifted_over_what_is_in_the_way() {
+        let statue = PhysRect::new(400, 500, 100, 100);
+        let coin = PhysRect::new(450, 500, 100, 100);
+        // Nothing in the way
+        assert_eq!(Physics::place_spawn(coin, &[]), Some(coin));
+        // Lifted to sit on the statue
+        let placed = Physics::place_spawn(coin, &[statue]).unwrap();
+        assert_eq!((placed.x(), placed.y()), (450, 400));
+        // Stacked as high as it's allowed to go, then dropped
+        let stack: Vec<PhysRect> = (0..=SPAWN_NUDGES as i32)
+            .map(|i| PhysRect::new(400, 500 - 100 * i, 100, 100))
+            .collect();
+        assert_eq!(Physics::place_spawn(coin, &stack), None);
+    }
+
+    #[test]
+    fn launched_obstacles_stop_against_fixed_ones() {
+        let mut statue = Obstacle::new(PhysRect::new(330, 500, 100, 100), 50.0, ObstacleType::Statue);
+        statue.collided = true;
+        statue.hard_set_vel((10.0, 0.0));
+        let chest = Obstacle::new(PhysRect::new(400, 500, 100, 100), 1.0, ObstacleType::Chest);
+        let mut obstacles = vec![statue, chest];
+        Physics::resolve_obstacles(&mut obstacles);
+
+        // Pushed back out of the chest, which doesn't move
+        assert_eq!(obstacles[0].x(), 300);
+        assert_eq!(obstacles[1].x(), 400);
+        assert_eq!(obstacles[0].vel_x(), -10.0 * OBSTACLE_RESTITUTION);
+        assert!(obstacles[0].hitbox().mtv(obstacles[1].hitbox()).is_none());
+    }
+
+    #[test]
+    fn kinematics_clamp_and_move() {
+        let mut kin = Kinematics::new((0.0, 100.0), (-2.0, 2.0), (-5.0, 5.0));
+        kin.apply_force((30.0, 6.0), 3.0);
+        kin.integrate_vel();
+        assert_eq!(kin.vel, (2.0, 2.0));
+        kin.integrate_pos(false);
+        assert_eq!(kin.pos, (0.0, 98.0));
+        kin.integrate_pos(true);
+        assert_eq!(kin.pos, (2.0, 96.0));
+    }
+
+    #[test]
+    fn force_never_drops_with_longer_holds() {
+        let forces: Vec<f64> = (0..500)
+            .step_by(10)
+            .map(|ms| jump_force(Duration::from_millis(ms)))
+            .collect();
+        assert!(forces.windows(2).all(|w| w[0] <= w[1]));
+    }
+}
@@ -0,0 +1,57 @@
+// Battery awareness. On battery, menus drop to the low-spec preset and a 30
+// FPS cap to save power, and runs go back to the player's own settings. SDL
+// says whether the machine is on battery; the `on_battery` config key
+// overrides it for platforms where SDL can't tell. The `battery_saver`
+// setting turns all of this off.
+
+use crate::config::Config;
+use crate::framerate::FrameCap;
+use crate::quality::Quality;
+use crate::settings::Settings;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct PowerSaver {
+    active: bool,
+}
+
+impl PowerSaver {
+    // Checks the power source now, screens call this once when they start
+    pub fn from_config(config: &Config) -> PowerSaver {
+        let enabled = Settings::from_config(config).battery_saver;
+        let on_battery = match config.get("on_battery") {
+            Some(forced) => forced == "true",
+            None => on_battery(),
+        };
+        PowerSaver {
+            active: enabled && on_battery,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    // Quality a menu should draw at, given the player's own
+    pub fn menu_quality(&self, quality: Quality) -> Quality {
+        if self.active {
+            Quality::low_spec()
+        } else {
+            quality
+        }
+    }
+
+    // Frame cap a menu should keep to, given the player's own
+    pub fn menu_frame_cap(&self, frame_cap: FrameCap) -> FrameCap {
+        if self.active {
+            FrameCap::Fps30
+        } else {
+            frame_cap
+        }
+    }
+}
+
+fn on_battery() -> bool {
+    // Not wrapped by the sdl2 crate
+    let state = unsafe { sdl2::sys::SDL_GetPowerInfo(std::ptr::null_mut(), std::ptr::null_mut()) };
+    state == sdl2::sys::SDL_PowerState::SDL_POWERSTATE_ON_BATTERY
+}
@@ -1,9 +1,16 @@
-use inf_runner::PowerType;
-use inf_runner::StaticObject;
-use inf_runner::TerrainType;
+use crate::Hazard;
+use crate::PowerType;
+use crate::StaticObject;
+use crate::TerrainType;
+use crate::TILE_SIZE;
 
+use crate::decor::Decor;
 use crate::physics::Power;
 use crate::rect;
+use crate::rng::GameRng;
+use crate::rng::RngStream;
+
+use log::trace;
 
 use rand::distributions::Distribution;
 use rand::distributions::Standard;
@@ -15,6 +22,71 @@ use sdl2::render::Texture;
 
 const CAM_W: u32 = 1280;
 
+// Chance that a spawn is a whole obstacle pattern instead of a single object,
+// at the start of a run and at its cap
+const PATTERN_CHANCE_MIN: f64 = 0.1;
+const PATTERN_CHANCE_MAX: f64 = 0.35;
+
+// Chance that a new terrain segment is a risk zone: denser spawns and double
+// score, but no shield
+const RISK_ZONE_CHANCE: f64 = 0.15;
+
+// Chance that a new terrain segment has rising air over it, which strengthens
+// the LowerGravity power
+const UPDRAFT_CHANCE: f64 = 0.1;
+
+// Chance that a new terrain segment has a mud patch, and how long one is in
+// pixels
+const MUD_CHANCE: f64 = 0.08;
+const MUD_LENGTH: (i32, i32) = (250, 500);
+pub const MUD_COLOR: Color = Color::RGB(92, 64, 40);
+
+// Chance that a new terrain segment without mud has a hazard strip, and how
+// long one is in pixels, short enough to jump
+const HAZARD_CHANCE: f64 = 0.06;
+const HAZARD_LENGTH: (i32, i32) = (100, 180);
+
+// Chance that a new terrain segment without mud or a hazard has a gap with a
+// platform going back and forth across it, how long a gap is in pixels, and
+// the platform's width and speed in pixels per step. Platforms stay slower
+// than the player's slowest run so riding one never moves them backwards.
+const GAP_CHANCE: f64 = 0.05;
+const GAP_LENGTH: (i32, i32) = (240, 400);
+pub const PLATFORM_W: i32 = 160;
+const PLATFORM_SPEED: (f64, f64) = (0.5, 0.9);
+
+// Chance that a new terrain segment without a hazard or gap runs through a
+// tunnel, how long one is in pixels, and how far its ceiling is above the
+// ground, room for a short hop
+const TUNNEL_CHANCE: f64 = 0.05;
+const TUNNEL_LENGTH: (i32, i32) = (400, 800);
+pub const TUNNEL_CLEARANCE: i32 = 200;
+
+// Generated ground is two slopes a segment, each rising or dropping up to
+// SLOPE_MAX_RISE pixels. Noise picks how much, offset by SLOPE_DOWNHILL_BIAS
+// of that so runs head downhill on the whole.
+const SLOPE_MAX_RISE: f64 = 180.0;
+const SLOPE_DOWNHILL_BIAS: f64 = 0.25;
+
+// Terrain segments a biome lasts, a gate near the start of the next one leads
+// into a new biome. Gates stand GATE_OFFSET into their segment and are
+// GATE_W across, wide enough for the player to pass through.
+const BIOME_SEGMENTS: u32 = 12;
+const GATE_OFFSET: i32 = 2 * TILE_SIZE as i32;
+pub const GATE_W: i32 = 3 * TILE_SIZE as i32;
+
+// Chance that a single statue on the ground is a golden one
+const GOLDEN_STATUE_CHANCE: f64 = 0.08;
+
+// Coin value before risk, and how much each kind of risk adds to it as a
+// fraction of that. Closeness to an obstacle fades out over COIN_NEAR_RANGE.
+pub const COIN_VALUE: i32 = 1000;
+const COIN_HEIGHT_RISK: f64 = 0.5; // Per tile above the ground
+const COIN_NEAR_RISK: f64 = 0.75;
+const COIN_NEAR_RANGE: f64 = 2.0 * TILE_SIZE as f64;
+const COIN_RISK_ZONE_RISK: f64 = 0.5;
+const COIN_MAX_RISK: f64 = 2.0;
+
 // BG_CURVES_SIZE relates to the length of the background hills array.
 // Used to convert width of drawn rectangles to fill up the screen.
 // Reason for it being 1/10th width is that it was the highest resolution we
@@ -28,7 +100,37 @@ const BG_CURVES_SIZE: usize = CAM_W as usize / 10; // 1/10 of screen for good pe
 // screen width specifically?
 
 // Where all the math is done?
-pub struct ProceduralGen;
+// Holds the random streams for terrain and spawns so a run can be driven by
+// a single injected GameRng
+pub struct ProceduralGen {
+    terrain_rng: GameRng,
+    spawn_rng: GameRng,
+    segments: u32, // Terrain segments counted off by biome_transition
+}
+
+// Deliberate arrangements of several obstacles, spawned as one group
+#[derive(Copy, Clone, PartialEq)]
+pub enum SpawnPattern {
+    Alternating,    // Statues alternating on the ground and floating overhead
+    SpringStairs,   // Springs stepping upwards, bounce from one to the next
+    StatueCorridor, // Ground statues under a ceiling of statues, one lane to jump through
+}
+
+impl SpawnPattern {
+    pub const ALL: [SpawnPattern; 3] = [
+        SpawnPattern::Alternating,
+        SpawnPattern::SpringStairs,
+        SpawnPattern::StatueCorridor,
+    ];
+}
+
+// One object of a spawn pattern
+#[derive(Copy, Clone)]
+pub struct PatternPiece {
+    pub object: StaticObject,
+    pub delay: i32,  // Steps after the pattern starts that this piece spawns
+    pub height: i32, // Pixels above the ground
+}
 
 // Representation of a single bezier curve
 pub struct TerrainSegment {
@@ -39,6 +141,90 @@ pub struct TerrainSegment {
                              * downward on average */
     terrain_type: TerrainType,
     color: Color,
+    risk_zone: bool,
+    updraft: bool,
+    mud: Option<(i32, i32)>, // Start and end of a mud patch, from the segment's x
+    hazard: Option<HazardStrip>,
+    gap: Option<Gap>,
+    gate: Option<BiomeGate>,
+    ceiling: Option<Vec<(i32, i32)>>, // Points of a tunnel's ceiling, like the curve's
+    decor: Vec<Decor>,
+}
+
+// Strip of hazardous ground on a terrain segment
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct HazardStrip {
+    pub hazard: Hazard,
+    pub start: i32, // From the segment's x
+    pub end: i32,
+}
+
+// Gap in a terrain segment, with a platform that goes back and forth across
+// it on a fixed path
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Gap {
+    pub start: i32, // From the segment's x
+    pub end: i32,
+    pub platform_w: i32,
+    pub speed: f64, // Pixels per step
+    offset: f64,    // Platform's left edge from the gap's start
+    dir: f64,       // 1 heading for the gap's end, -1 heading back
+}
+
+impl Gap {
+    // Gap with its platform at the near edge, about to set off
+    pub fn new(start: i32, end: i32, platform_w: i32, speed: f64) -> Gap {
+        Gap {
+            start,
+            end,
+            platform_w,
+            speed,
+            offset: 0.0,
+            dir: 1.0,
+        }
+    }
+
+    // Moves the platform a step along its path, turning it around at either
+    // edge of the gap
+    pub fn step(&mut self) {
+        let travel = (self.end - self.start - self.platform_w).max(0) as f64;
+        self.offset += self.dir * self.speed;
+        if self.offset >= travel {
+            self.offset = travel;
+            self.dir = -1.0;
+        } else if self.offset <= 0.0 {
+            self.offset = 0.0;
+            self.dir = 1.0;
+        }
+    }
+
+    // Left and right edges of the platform, from the segment's x
+    pub fn platform(&self) -> (i32, i32) {
+        let left = self.start + self.offset as i32;
+        (left, left + self.platform_w)
+    }
+
+    // Pixels per step the platform is moving, forwards is positive
+    pub fn platform_vel(&self) -> f64 {
+        self.dir * self.speed
+    }
+}
+
+// Archway between biomes, standing on a terrain segment
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BiomeGate {
+    pub at: i32, // Left edge, from the segment's x
+    passed: bool,
+}
+
+impl BiomeGate {
+    pub fn new(at: i32) -> BiomeGate {
+        BiomeGate { at, passed: false }
+    }
+
+    pub fn is_passed(&self) -> bool {
+        self.passed
+    }
 }
 
 // Terrain Segment Definitions
@@ -57,6 +243,14 @@ impl TerrainSegment {
             angle_from_last: angle_from_last,
             terrain_type: terrain_type,
             color: color,
+            risk_zone: false,
+            updraft: false,
+            mud: None,
+            hazard: None,
+            gap: None,
+            gate: None,
+            ceiling: None,
+            decor: Vec::new(),
         }
     }
 
@@ -75,6 +269,10 @@ impl TerrainSegment {
             tuple.0 += x_adj;
             tuple.1 += y_adj;
         }
+        for tuple in self.ceiling.iter_mut().flatten() {
+            tuple.0 += x_adj;
+            tuple.1 += y_adj;
+        }
     }
 
     // Shifts terrain left so player can "move forward"
@@ -88,6 +286,9 @@ impl TerrainSegment {
         for tuple in self.curve.iter_mut() {
             tuple.0 -= travel_adj;
         }
+        for tuple in self.ceiling.iter_mut().flatten() {
+            tuple.0 -= travel_adj;
+        }
     }
 
     // Accessors
@@ -123,9 +324,146 @@ impl TerrainSegment {
         self.color
     }
 
+    // Nothing on or over the ground, which is all drawn on level ground, so
+    // only a plain segment can have slopes
+    pub fn is_plain(&self) -> bool {
+        !self.risk_zone
+            && !self.updraft
+            && self.mud.is_none()
+            && self.hazard.is_none()
+            && self.gap.is_none()
+            && self.gate.is_none()
+            && self.ceiling.is_none()
+    }
+
+    pub fn is_risk_zone(&self) -> bool {
+        self.risk_zone
+    }
+
+    pub fn set_risk_zone(&mut self, risk_zone: bool) {
+        self.risk_zone = risk_zone;
+    }
+
+    pub fn has_updraft(&self) -> bool {
+        self.updraft
+    }
+
+    pub fn set_updraft(&mut self, updraft: bool) {
+        self.updraft = updraft;
+    }
+
+    // Decoration in front of the segment, only for looks
+    pub fn decor(&self) -> &[Decor] {
+        &self.decor
+    }
+
+    pub fn set_decor(&mut self, decor: Vec<Decor>) {
+        self.decor = decor;
+    }
+
+    // Mud patch, from the segment's x
+    pub fn mud(&self) -> Option<(i32, i32)> {
+        self.mud
+    }
+
+    pub fn set_mud(&mut self, mud: Option<(i32, i32)>) {
+        self.mud = mud;
+    }
+
+    pub fn hazard(&self) -> Option<HazardStrip> {
+        self.hazard
+    }
+
+    pub fn set_hazard(&mut self, hazard: Option<HazardStrip>) {
+        self.hazard = hazard;
+    }
+
+    pub fn gap(&self) -> Option<Gap> {
+        self.gap
+    }
+
+    pub fn set_gap(&mut self, gap: Option<Gap>) {
+        self.gap = gap;
+    }
+
+    // Moves the gap's platform, if there is one
+    pub fn step_gap(&mut self) {
+        if let Some(gap) = self.gap.as_mut() {
+            gap.step();
+        }
+    }
+
+    // Tunnel ceiling, one point per pixel across like the curve
+    pub fn ceiling(&self) -> Option<&Vec<(i32, i32)>> {
+        self.ceiling.as_ref()
+    }
+
+    pub fn set_ceiling(&mut self, ceiling: Option<Vec<(i32, i32)>>) {
+        self.ceiling = ceiling;
+    }
+
+    // Height of the ceiling at the given x of the screen, if there's one
+    // there
+    pub fn ceiling_at(&self, screen_x: i32) -> Option<i32> {
+        let ceiling = self.ceiling.as_ref()?;
+        let first = ceiling.first()?.0;
+        if screen_x < first {
+            return None;
+        }
+        ceiling.get((screen_x - first) as usize).map(|&(_, y)| y)
+    }
+
+    pub fn gate(&self) -> Option<BiomeGate> {
+        self.gate
+    }
+
+    pub fn set_gate(&mut self, gate: Option<BiomeGate>) {
+        self.gate = gate;
+    }
+
+    // Whether the segment's gate has just been passed by something at the
+    // given x of the screen, true only the first time
+    pub fn pass_gate(&mut self, screen_x: i32) -> bool {
+        let x = self.x();
+        match self.gate.as_mut() {
+            Some(gate) if !gate.passed && x + gate.at + GATE_W / 2 <= screen_x => {
+                gate.passed = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Whether the given x of the screen is over the segment's gap
+    pub fn in_gap(&self, screen_x: i32) -> bool {
+        self.gap
+            .is_some_and(|g| (g.start..g.end).contains(&(screen_x - self.x())))
+    }
+
+    // Hazard on the surface at the given x of the screen, if any
+    pub fn hazard_at(&self, screen_x: i32) -> Option<Hazard> {
+        self.hazard
+            .filter(|h| (h.start..h.end).contains(&(screen_x - self.x())))
+            .map(|h| h.hazard)
+    }
+
+    // Terrain at the given x of the screen, which is the segment's own type
+    // outside a mud patch
+    pub fn terrain_at(&self, screen_x: i32) -> &TerrainType {
+        match self.mud {
+            Some((start, end)) if (start..end).contains(&(screen_x - self.x())) => &TerrainType::Mud,
+            _ => &self.terrain_type,
+        }
+    }
+
     pub fn curve(&self) -> &Vec<(i32, i32)> {
         &(self.curve)
     }
+
+    // Gives up the segment, keeping its curve buffer for reuse
+    pub fn into_curve(self) -> Vec<(i32, i32)> {
+        self.curve
+    }
 }
 
 impl PartialEq for TerrainSegment {
@@ -141,7 +479,149 @@ impl PartialEq for TerrainSegment {
 #[allow(dead_code)]
 impl ProceduralGen {
     pub fn init() -> Result<Self, String> {
-        Ok(ProceduralGen {})
+        Ok(ProceduralGen::new(&GameRng::from_entropy()))
+    }
+
+    // Splits independent terrain and spawn streams off of the given generator
+    pub fn new(rng: &GameRng) -> ProceduralGen {
+        ProceduralGen {
+            terrain_rng: rng.split(RngStream::Terrain),
+            spawn_rng: rng.split(RngStream::Spawns),
+            segments: 0,
+        }
+    }
+
+    // Everything a run generates comes from `seed`, so runs on the same seed
+    // get the same terrain, spawns and powers
+    pub fn with_seed(seed: u64) -> ProceduralGen {
+        ProceduralGen::new(&GameRng::new(seed))
+    }
+
+    // Stream used for everything terrain related (perlin gradients, curves)
+    pub fn terrain_rng(&mut self) -> &mut GameRng {
+        &mut self.terrain_rng
+    }
+
+    // Stream used for everything spawn related (spawn timing, object choice)
+    pub fn spawn_rng(&mut self) -> &mut GameRng {
+        &mut self.spawn_rng
+    }
+
+    // Counts off a new terrain segment, giving the gate it should have if it
+    // starts a new biome
+    pub fn biome_transition(&mut self) -> Option<BiomeGate> {
+        self.segments += 1;
+        if self.segments.is_multiple_of(BIOME_SEGMENTS) {
+            Some(BiomeGate::new(GATE_OFFSET))
+        } else {
+            None
+        }
+    }
+
+    // Decides whether the next terrain segment is a risk zone
+    pub fn roll_risk_zone(&mut self) -> bool {
+        self.terrain_rng.gen_bool(RISK_ZONE_CHANCE)
+    }
+
+    // Decides whether the next terrain segment has an updraft
+    pub fn roll_updraft(&mut self) -> bool {
+        self.terrain_rng.gen_bool(UPDRAFT_CHANCE)
+    }
+
+    // Sometimes picks a mud patch for the next terrain segment, `width` wide
+    pub fn roll_mud(&mut self, width: i32) -> Option<(i32, i32)> {
+        if !self.terrain_rng.gen_bool(MUD_CHANCE) {
+            return None;
+        }
+        let length = self.terrain_rng.gen_range(MUD_LENGTH.0..=MUD_LENGTH.1).min(width);
+        let start = self.terrain_rng.gen_range(0..=width - length);
+        Some((start, start + length))
+    }
+
+    // Sometimes picks a hazard strip for the next terrain segment, `width`
+    // wide. Kept off the first and last tiles so it never runs into the
+    // next segment's.
+    pub fn roll_hazard(&mut self, width: i32) -> Option<HazardStrip> {
+        if !self.terrain_rng.gen_bool(HAZARD_CHANCE) {
+            return None;
+        }
+        let hazard = if self.terrain_rng.gen_bool(0.5) {
+            Hazard::Lava
+        } else {
+            Hazard::Spikes
+        };
+        let margin = TILE_SIZE as i32;
+        let length = self.terrain_rng.gen_range(HAZARD_LENGTH.0..=HAZARD_LENGTH.1);
+        let start = self
+            .terrain_rng
+            .gen_range(margin..=(width - margin - length).max(margin));
+        Some(HazardStrip {
+            hazard,
+            start,
+            end: start + length,
+        })
+    }
+
+    // Sometimes picks a gap for the next terrain segment, `width` wide. Kept
+    // off the first and last tiles like hazard strips.
+    pub fn roll_gap(&mut self, width: i32) -> Option<Gap> {
+        if !self.terrain_rng.gen_bool(GAP_CHANCE) {
+            return None;
+        }
+        let margin = TILE_SIZE as i32;
+        let length = self.terrain_rng.gen_range(GAP_LENGTH.0..=GAP_LENGTH.1);
+        let start = self
+            .terrain_rng
+            .gen_range(margin..=(width - margin - length).max(margin));
+        let speed = self.terrain_rng.gen_range(PLATFORM_SPEED.0..=PLATFORM_SPEED.1);
+        Some(Gap::new(start, start + length, PLATFORM_W, speed))
+    }
+
+    // Sometimes picks a tunnel for a new terrain segment with the given
+    // curve, giving its ceiling, which follows the ground TUNNEL_CLEARANCE
+    // above it
+    pub fn roll_tunnel(&mut self, curve: &[(i32, i32)]) -> Option<Vec<(i32, i32)>> {
+        if !self.terrain_rng.gen_bool(TUNNEL_CHANCE) {
+            return None;
+        }
+        let width = curve.len() as i32;
+        let length = self.terrain_rng.gen_range(TUNNEL_LENGTH.0..=TUNNEL_LENGTH.1).min(width);
+        let start = self.terrain_rng.gen_range(0..=width - length) as usize;
+        Some(
+            curve[start..start + length as usize]
+                .iter()
+                .map(|&(x, y)| (x, y - TUNNEL_CLEARANCE))
+                .collect(),
+        )
+    }
+
+    // Decides whether a ground statue that's about to spawn is golden
+    pub fn roll_golden_statue(&mut self) -> bool {
+        self.spawn_rng.gen_bool(GOLDEN_STATUE_CHANCE)
+    }
+
+    // Sometimes picks an obstacle pattern instead of a single spawn. Patterns
+    // get more likely, longer and tighter as the score goes up.
+    pub fn roll_pattern(&mut self, score: i32) -> Option<Vec<PatternPiece>> {
+        let difficulty = difficulty(score);
+        if !self.spawn_rng.gen_bool(pattern_chance(score)) {
+            return None;
+        }
+
+        let pattern = SpawnPattern::ALL[self.spawn_rng.gen_range(0..SpawnPattern::ALL.len())];
+        let count = 3 + (difficulty * 2.0).round() as usize; // 3 to 5 pieces
+        let spacing = (45.0 - 15.0 * difficulty) as i32; // Steps between pieces
+        Some(build_pattern(pattern, count, spacing))
+    }
+
+    // Randomly choose the next StaticObject to spawn
+    pub fn choose_static_object(&mut self) -> StaticObject {
+        choose_static_object(&mut self.spawn_rng)
+    }
+
+    // Randomly choose the PowerType of a spawned power
+    pub fn choose_power_up(&mut self) -> PowerType {
+        choose_power_up(&mut self.spawn_rng)
     }
 
     /*
@@ -153,14 +633,17 @@ impl ProceduralGen {
     */
 
     /*  Initilization of terrain segments
+     *  Draws all of its randomness from the terrain stream
      *
      *  - Takes in `random` which is the array of random tuples of (i32, i32)
      *    Needs to be the same values on each run for porper noise output
      *    Represents the gradient value for points. Passed into gen_point_mod
-     *  - Takes in `prev_point` which is the x (assumes 0) and y of the last part
-     *    of generated terrain
+     *  - Takes in `prev_point` which is the x and y of the last point of the
+     *    terrain before this segment, it starts on the next column
      *  - Takes in `cam_w` which is the width of the camera (1280)
      *  - Takes in `cam_h` which is the height of the camera (720)
+     *  - Takes in `curve` which is an empty buffer for the curve, see
+     *    TerrainPool::take_curve
      *  - Takes in `_is_pit` boolean which will generate a pit within this land
      *    segment *NOT IMPLEMENTED YET*
      *  - Takes in `_is_flat` boolean which will make the generated control point
@@ -169,18 +652,24 @@ impl ProceduralGen {
      *  - Takes in `_is_cliff` boolean which will make a cliff within the next
      *    land segment *NOT IMPLEMENTED YET*
      *
-     *  - Returns array of tuples associated with the output curve.
+     *  - Returns the segment, its curve has a point for every column so it
+     *    can be looked up by x like the runner's flat ones. Both halves are a
+     *    cubic bezier that's level at either end, so segments join smoothly
+     *    and there's a dip to pump through at the bottom of every drop.
      */
+    #[allow(clippy::too_many_arguments)]
     pub fn gen_terrain(
+        &mut self,
         random: &[[(i32, i32); 256]; 256],
         mut prev_point: (f64, f64),
         cam_w: i32,
         cam_h: i32,
+        mut curve: Vec<(i32, i32)>,
         _is_pit: bool,
         _is_flat: bool,
         _is_cliff: bool,
     ) -> TerrainSegment {
-        let mut rng = rand::thread_rng();
+        let rng = &mut self.terrain_rng;
 
         let flat_mod: f64 = 0.25;
         let cliff_min_mod: f64 = 2.0;
@@ -195,62 +684,23 @@ impl ProceduralGen {
             rng.gen::<f64>()
         };
 
-        // Generates perlin noise for random point instead of whole map
+        // Generates perlin noise for random point instead of whole map, one
+        // for each half's rise
         let map_size = 128;
-        let point_mod_1a: f64 = gen_point_mod(
-            &random,
-            (
-                (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
-                (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
-            ),
-            freq,
-            amp,
-        );
-        let point_mod_1b: f64 = gen_point_mod(
-            &random,
-            (
-                (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
-                (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
-            ),
-            freq,
-            amp,
-        );
-        let point_mod_2a: f64 = gen_point_mod(
-            &random,
-            (
-                (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
-                (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
-            ),
-            freq,
-            amp,
-        );
-        let point_mod_2b: f64 = gen_point_mod(
-            &random,
-            (
-                (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
-                (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
-            ),
-            freq,
-            amp,
-        );
-        let point_mod_3a: f64 = gen_point_mod(
-            &random,
-            (
-                (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
-                (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
-            ),
-            freq,
-            amp,
-        );
-        let point_mod_3b: f64 = gen_point_mod(
-            &random,
-            (
-                (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
-                (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
-            ),
-            freq,
-            amp,
-        );
+        let mut rise = || {
+            let point_mod = gen_point_mod(
+                random,
+                (
+                    (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
+                    (rng.gen_range(0.0..(map_size - 1) as f64).floor()) as i32,
+                ),
+                freq,
+                amp,
+            );
+            // Noise is centered on 0.5, +y is down the screen
+            ((point_mod - 0.5 + SLOPE_DOWNHILL_BIAS) * 2.0 * SLOPE_MAX_RISE).clamp(-SLOPE_MAX_RISE, SLOPE_MAX_RISE)
+        };
+        let (rise_1, rise_2) = (rise(), rise());
 
         prev_point = if _is_pit || _is_cliff {
             (prev_point.0, prev_point.1 + 100.0)
@@ -258,31 +708,38 @@ impl ProceduralGen {
             prev_point
         };
 
-        // Extract x and y point from last terrain segment
-        // let mut curve = gen_bezier_curve(
-        let curve = gen_bezier_curve(
-            prev_point,
-            cam_w,
-            cam_h,
-            (point_mod_1a, point_mod_1b),
-            (point_mod_2a, point_mod_2b),
-            (point_mod_3a, point_mod_3b),
-            100,
-        );
+        let start_x = prev_point.0 as i32 + 1;
+        let half = cam_w / 2;
+        let heights = [prev_point.1, prev_point.1 + rise_1, prev_point.1 + rise_1 + rise_2];
+        curve.clear();
+        for i in 0..cam_w {
+            // Control points a third of the way along apart keep x linear in
+            // t, so t is just how far across the half the column is
+            let (part, t) = if i < half {
+                (0, i as f64 / half as f64)
+            } else {
+                (1, (i - half) as f64 / (cam_w - half) as f64)
+            };
+            let (y0, y1) = (heights[part], heights[part + 1]);
+            let (_, y) = cubic_bezier_curve_point((0.0, y0), (1.0, y0), (2.0, y1), (3.0, y1), t);
+            curve.push((start_x + i, y));
+        }
 
         // Due to weird rust semantics, need to make a var to hold curve length
         let curve_len = curve.len();
 
-        let rect = rect!(0, 0, 10, 10); // ?
+        let rect = rect!(start_x, prev_point.1 as i32, cam_w, cam_h * 2 / 3);
         let angle_from_last = 0.0; // ?
-        let terrain_type = choose_terrain_type(10);
+        let terrain_type = choose_terrain_type(rng, 10);
         let color = match (terrain_type) {
             TerrainType::Asphalt => Color::RGB(19, 10, 6),
             TerrainType::Sand => Color::RGB(194, 178, 128),
             TerrainType::Water => Color::RGB(212, 241, 249),
             TerrainType::Grass => Color::RGB(86, 125, 70),
+            TerrainType::Mud => MUD_COLOR,
         };
 
+        trace!("Generated {} point terrain segment from {:?}", curve_len, prev_point);
         let terrain = TerrainSegment::new(rect, curve, angle_from_last, terrain_type, color);
 
         return terrain;
@@ -318,68 +775,6 @@ pub fn extend_cubic_bezier_curve(
 
 /* ~~~~~~     Bezier primary functions      ~~~~~~ */
 
-/*  Handler for getting either quadratic or cubic bezier curve representation
- *
- *  - Takes in `p0` which is the last place the previously generated land
- *    ended
- *  - Takes in `length` which is a control parameter
- *  - Takes in `height` which is a control parameter
- *  - Takes in `point_mod_x` which are the Perlin Noise Modifiers to help
- *    generate control points
- *  - Takes in `buffer` which is a control parameter saying how close control
- *    points can be in the x direction
- *
- *  - Returns Bezier Curve representation
- */
-fn gen_bezier_curve(
-    p0: (f64, f64),
-    length: i32, // Needs to be static which is stupid so 1280
-    height: i32,
-    point_mod_1: (f64, f64),
-    point_mod_2: (f64, f64),
-    point_mod_3: (f64, f64),
-    buffer: i32,
-) -> Vec<(i32, i32)> {
-    //TODO - CONTROL POINT LOGIC NEEDS TO BE REFINED
-    //Bezier curve
-
-    let mut rng = rand::thread_rng();
-
-    if rng.gen::<f64>() < 0.5 {
-        //Quadratic
-        let p1: (f64, f64) = (
-            (point_mod_1.0 * (length - buffer) as f64 + p0.0 + buffer as f64)
-                .clamp(p0.0 + buffer as f64, (length - buffer) as f64),
-            (point_mod_1.1 * p0.1 - p0.1).clamp(p0.1 - buffer as f64, height as f64),
-        );
-
-        let p2: (f64, f64) = (length as f64 + p0.0, point_mod_2.1 * (height / 3) as f64);
-
-        let group_of_points: Vec<(i32, i32)> = gen_quadratic_bezier_curve_points(p0, p1, p2);
-
-        return group_of_points;
-    } else {
-        //Cubic
-        let p1: (f64, f64) = (
-            (point_mod_1.0 * (length / 2 + buffer) as f64 + p0.0 + buffer as f64 + (length / 2) as f64)
-                .clamp(p0.0 + buffer as f64 + (length / 2) as f64, (length - buffer) as f64),
-            (point_mod_1.1 * p0.1 * 2.0 - p0.1).clamp(p0.1 - buffer as f64, height as f64),
-        );
-
-        let p2: (f64, f64) = (
-            (point_mod_2.0 * (length / 2 - buffer) as f64 + p0.0 + buffer as f64)
-                .clamp(p0.0 + buffer as f64, (length / 2 - buffer) as f64),
-            (point_mod_2.1 * p0.1 * 2.0 - p0.1).clamp(p0.1 - buffer as f64, height as f64),
-        );
-
-        let p3: (f64, f64) = (length as f64 + p0.0, point_mod_3.1 * (height / 3) as f64);
-
-        let group_of_points: Vec<(i32, i32)> = gen_cubic_bezier_curve_points(p0, p1, p2, p3);
-
-        return group_of_points;
-    }
-}
-
 /*
  *
  *
@@ -675,15 +1070,14 @@ fn noise_1d(p: f32) -> f32 {
 /* ~~~~~~ Random Distributions ~~~~~~ */
 
 /* Randomly choose a TerrainType. Heavily weighted to pick Grass.
+ *  - Takes in `rng` to draw from, should be the terrain stream
  *  - Takes in `upper` which is the top of of the gen_range. Should be >= 3.
  *    Higher it is, more weighted to choose Grass
  *
  *  - Returns a random TerrainType
  */
 // Renamed from get_random_terrain
-fn choose_terrain_type(upper: i32) -> TerrainType {
-    let mut rng = rand::thread_rng();
-
+fn choose_terrain_type(rng: &mut GameRng, upper: i32) -> TerrainType {
     let upper = upper.clamp(3, i32::MAX);
 
     match rng.gen_range(0..=upper) {
@@ -694,12 +1088,56 @@ fn choose_terrain_type(upper: i32) -> TerrainType {
     }
 }
 
+// How far along the difficulty ramp a score is, from 0 to 1
+pub fn difficulty(score: i32) -> f64 {
+    (score as f64 / 100000.0).clamp(0.0, 1.0)
+}
+
+// Tier of the difficulty ramp a score is on, from 1 to DIFFICULTY_TIERS, for
+// telling the player roughly how hard things are
+pub const DIFFICULTY_TIERS: u32 = 5;
+pub fn difficulty_tier(score: i32) -> u32 {
+    ((difficulty(score) * DIFFICULTY_TIERS as f64) as u32 + 1).min(DIFFICULTY_TIERS)
+}
+
+// Chance that a spawn at `score` is an obstacle pattern
+pub fn pattern_chance(score: i32) -> f64 {
+    PATTERN_CHANCE_MIN + (PATTERN_CHANCE_MAX - PATTERN_CHANCE_MIN) * difficulty(score)
+}
+
+/*  Worth of a coin, by how risky it is to grab where it spawned
+ *
+ *  - Takes in `height` which is how far the coin was lifted off the ground
+ *  - Takes in `nearest_obstacle` which is the distance between its center and
+ *    the closest obstacle's, None without one nearby
+ *  - Takes in `in_risk_zone` which is whether it spawned in a risk zone
+ *
+ *  - Returns the coin's value, from COIN_VALUE up to (1 + COIN_MAX_RISK) times that
+ */
+pub fn coin_value(height: i32, nearest_obstacle: Option<f64>, in_risk_zone: bool) -> i32 {
+    let mut risk = COIN_HEIGHT_RISK * height.max(0) as f64 / TILE_SIZE as f64;
+    if let Some(distance) = nearest_obstacle {
+        risk += COIN_NEAR_RISK * (1.0 - distance / COIN_NEAR_RANGE).max(0.0);
+    }
+    if in_risk_zone {
+        risk += COIN_RISK_ZONE_RISK;
+    }
+    (COIN_VALUE as f64 * (1.0 + risk.min(COIN_MAX_RISK))).round() as i32
+}
+
+// How risky a coin worth `value` was placed, from 0 for a safe one to 1 at the
+// most it can be
+pub fn coin_risk(value: i32) -> f64 {
+    ((value as f64 / COIN_VALUE as f64 - 1.0) / COIN_MAX_RISK).clamp(0.0, 1.0)
+}
+
 /*  Randomly choose a StaticObject
+ *
+ *  - Takes in `rng` to draw from, should be the spawn stream
  *
  *  - Returns a random StaticObject
  */
-pub fn choose_static_object() -> StaticObject {
-    let mut rng = rand::thread_rng();
+pub fn choose_static_object(rng: &mut GameRng) -> StaticObject {
     match rng.gen_range(0..=4) {
         0 => StaticObject::Statue,
         1 => StaticObject::Balloon,
@@ -710,12 +1148,13 @@ pub fn choose_static_object() -> StaticObject {
 }
 
 /*  Randomly choose a PowerUp
+ *
+ *  - Takes in `rng` to draw from, should be the spawn stream
  *
  *  - Returns a random PowerUp
  */
 // Probably shouldn't be pub when call is moved to procgen.rs
-pub fn choose_power_up() -> PowerType {
-    let mut rng = rand::thread_rng();
+pub fn choose_power_up(rng: &mut GameRng) -> PowerType {
     match rng.gen_range(0..=4) {
         // rand 0.8
         0 => PowerType::SpeedBoost,
@@ -725,3 +1164,84 @@ pub fn choose_power_up() -> PowerType {
         _ => PowerType::Shield,
     }
 }
+
+/*  Lays out the pieces of a spawn pattern
+ *
+ *  - Takes in `count` which is the number of columns of the pattern
+ *  - Takes in `spacing` which is the number of steps between columns
+ *
+ *  - Returns every piece with its delay and height, in spawn order
+ */
+pub fn build_pattern(pattern: SpawnPattern, count: usize, spacing: i32) -> Vec<PatternPiece> {
+    let tile = crate::TILE_SIZE as i32;
+    let mut pieces = Vec::new();
+    for i in 0..count {
+        let delay = i as i32 * spacing;
+        match pattern {
+            SpawnPattern::Alternating => pieces.push(PatternPiece {
+                object: StaticObject::Statue,
+                delay,
+                height: if i % 2 == 0 { 0 } else { tile * 3 / 2 },
+            }),
+            SpawnPattern::SpringStairs => pieces.push(PatternPiece {
+                object: StaticObject::Balloon,
+                delay,
+                height: i as i32 * tile / 2,
+            }),
+            SpawnPattern::StatueCorridor => {
+                // Only the middle column has a ground statue to jump over, the
+                // ceiling keeps the jump low
+                if i == count / 2 {
+                    pieces.push(PatternPiece {
+                        object: StaticObject::Statue,
+                        delay,
+                        height: 0,
+                    });
+                }
+                pieces.push(PatternPiece {
+                    object: StaticObject::Statue,
+                    delay,
+                    height: tile * 3,
+                });
+            }
+        }
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient_table(procgen: &mut ProceduralGen) -> Box<[[(i32, i32); 256]; 256]> {
+        let mut random = Box::new([[(0, 0); 256]; 256]);
+        let rng = procgen.terrain_rng();
+        for row in random.iter_mut() {
+            for cell in row.iter_mut() {
+                *cell = (rng.gen_range(0..256), rng.gen_range(0..256));
+            }
+        }
+        random
+    }
+
+    #[test]
+    fn generated_segments_chain_one_point_per_column() {
+        let mut procgen = ProceduralGen::with_seed(1666);
+        let random = gradient_table(&mut procgen);
+        let mut prev = (CAM_W as f64 - 1.0, 480.0);
+        for _ in 0..50 {
+            let segment = procgen.gen_terrain(&random, prev, CAM_W as i32, 720, Vec::new(), false, false, false);
+            let curve = segment.curve();
+            assert_eq!(curve.len(), CAM_W as usize);
+            assert_eq!(segment.x(), prev.0 as i32 + 1);
+            for (i, &(x, y)) in curve.iter().enumerate() {
+                assert_eq!(x, segment.x() + i as i32);
+                // Level where it joins the last segment, and never a cliff
+                let last_y = if i == 0 { prev.1 as i32 } else { curve[i - 1].1 };
+                assert!((y - last_y).abs() <= 2, "step of {} at column {}", y - last_y, i);
+            }
+            let &(x, y) = curve.last().unwrap();
+            prev = (x as f64, y as f64);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+// Save profiles, so several people sharing one machine keep separate saves.
+// Every profile owns a folder under saves/ and any module that persists
+// something per player (scores, settings, cosmetics, progression) should put
+// its file at `profile.path(...)` rather than a fixed location.
+
+use crate::config::Config;
+
+use std::fs;
+use std::path::PathBuf;
+
+pub const SAVE_DIR: &str = "saves";
+const PROFILES_FILE: &str = "profiles.txt";
+
+pub const MAX_NAME_LEN: usize = 16;
+
+#[derive(Clone, PartialEq)]
+pub struct Profile {
+    name: String,
+}
+
+impl Profile {
+    // The profile that was selected last, if one was ever created
+    pub fn current() -> Option<Profile> {
+        let name = profiles_config().get("current")?.to_string();
+        if profile_dir(&name).is_dir() {
+            Some(Profile { name })
+        } else {
+            None
+        }
+    }
+
+    // All existing profiles, sorted by name
+    pub fn all() -> Vec<Profile> {
+        let mut profiles: Vec<Profile> = match fs::read_dir(SAVE_DIR) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().is_dir())
+                .filter_map(|e| e.file_name().into_string().ok())
+                .filter(|name| is_valid_name(name))
+                .map(|name| Profile { name })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        profiles.sort_by(|a, b| a.name.cmp(&b.name));
+        profiles
+    }
+
+    // Creates the folder for a new profile. Creating a profile that already
+    // exists just returns it.
+    pub fn create(name: &str) -> Result<Profile, String> {
+        let name = name.trim();
+        if !is_valid_name(name) {
+            return Err(format!(
+                "Profile names must be 1-{} letters, digits, spaces, '-' or '_'",
+                MAX_NAME_LEN
+            ));
+        }
+        fs::create_dir_all(profile_dir(name)).map_err(|e| e.to_string())?;
+        Ok(Profile { name: name.to_string() })
+    }
+
+    // Makes this the profile used from now on, including on the next launch
+    pub fn select(&self) -> Result<(), String> {
+        let mut config = profiles_config();
+        config.set("current", &self.name);
+        config.save()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // Where this profile keeps the given save file
+    pub fn path(&self, file: &str) -> PathBuf {
+        profile_dir(&self.name).join(file)
+    }
+}
+
+// Characters are limited so a name is always a safe folder name
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name.trim() == name
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '-' || c == '_')
+}
+
+fn profile_dir(name: &str) -> PathBuf {
+    PathBuf::from(SAVE_DIR).join(name)
+}
+
+fn profiles_config() -> Config {
+    let _ = fs::create_dir_all(SAVE_DIR);
+    Config::load(PathBuf::from(SAVE_DIR).join(PROFILES_FILE))
+}
@@ -0,0 +1,143 @@
+// Tiny per-frame profiler used to attribute frame time to subsystems
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// Weight given to the newest frame in the smoothed timings
+const SMOOTHING: f64 = 0.1;
+
+// Number of recent frame times kept for the FPS graph
+pub const HISTORY_LEN: usize = 240;
+
+// Named sections of a single frame, in the order they run
+#[derive(Copy, Clone, PartialEq)]
+pub enum Scope {
+    Input,
+    Physics,
+    Collision,
+    Procgen,
+    DrawWorld,
+    DrawHud,
+    Present,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 7] = [
+        Scope::Input,
+        Scope::Physics,
+        Scope::Collision,
+        Scope::Procgen,
+        Scope::DrawWorld,
+        Scope::DrawHud,
+        Scope::Present,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scope::Input => "input",
+            Scope::Physics => "physics",
+            Scope::Collision => "collision",
+            Scope::Procgen => "procgen",
+            Scope::DrawWorld => "draw-world",
+            Scope::DrawHud => "draw-hud",
+            Scope::Present => "present",
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+pub struct Profiler {
+    open: Option<(Scope, Instant)>, // Scope currently being timed
+    frame: [Duration; 7],           // Time spent in each scope this frame
+    smoothed: [f64; 7],             // Moving average of each scope, in ms
+    frame_start: Option<Instant>,
+    smoothed_total: f64, // Moving average of the whole frame, in ms
+    last_frame_end: Option<Instant>,
+    history: VecDeque<f64>, // Time between the last frames, including sleep, in ms
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler {
+            open: None,
+            frame: [Duration::ZERO; 7],
+            smoothed: [0.0; 7],
+            frame_start: None,
+            smoothed_total: 0.0,
+            last_frame_end: None,
+            history: VecDeque::with_capacity(HISTORY_LEN),
+        }
+    }
+
+    // Starts timing the given scope, closing whichever scope was open before.
+    // A scope can be entered more than once a frame, the times add up.
+    pub fn begin(&mut self, scope: Scope) {
+        let now = Instant::now();
+        self.close(now);
+        if self.frame_start.is_none() {
+            self.frame_start = Some(now);
+        }
+        self.open = Some((scope, now));
+    }
+
+    // Stops timing the currently open scope, if any
+    pub fn end(&mut self) {
+        self.close(Instant::now());
+    }
+
+    // Folds this frame's timings into the averages and starts a new frame
+    pub fn end_frame(&mut self) {
+        let now = Instant::now();
+        self.close(now);
+
+        for scope in Scope::ALL.iter() {
+            let i = scope.index();
+            let ms = self.frame[i].as_secs_f64() * 1000.0;
+            self.smoothed[i] += (ms - self.smoothed[i]) * SMOOTHING;
+            self.frame[i] = Duration::ZERO;
+        }
+
+        if let Some(start) = self.frame_start.take() {
+            let ms = (now - start).as_secs_f64() * 1000.0;
+            self.smoothed_total += (ms - self.smoothed_total) * SMOOTHING;
+        }
+
+        if let Some(last) = self.last_frame_end.replace(now) {
+            if self.history.len() == HISTORY_LEN {
+                self.history.pop_front();
+            }
+            self.history.push_back((now - last).as_secs_f64() * 1000.0);
+        }
+    }
+
+    // Smoothed time spent in the given scope per frame, in milliseconds
+    pub fn millis(&self, scope: Scope) -> f64 {
+        self.smoothed[scope.index()]
+    }
+
+    // Smoothed time of the whole measured frame, in milliseconds
+    pub fn total_millis(&self) -> f64 {
+        self.smoothed_total
+    }
+
+    // Time from one frame to the next for the most recent frames, oldest
+    // first, in milliseconds. Unlike total_millis this includes frame pacing.
+    pub fn history(&self) -> impl Iterator<Item = f64> + '_ {
+        self.history.iter().copied()
+    }
+
+    fn close(&mut self, now: Instant) {
+        if let Some((scope, start)) = self.open.take() {
+            self.frame[scope.index()] += now - start;
+        }
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Profiler::new()
+    }
+}
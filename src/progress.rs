@@ -0,0 +1,302 @@
+// Per-profile progression: unlocks and upgrades that carry over between runs.
+// Stored as `key = value` lines in the profile's progress.txt. The best run's
+// input log is kept next to it, signed, so the best score can be checked.
+//
+// Each board of bests (see loadout::BOARDS) keeps its own best and best run.
+// The standard board uses the keys and file from before there were boards.
+
+use crate::config::Config;
+use crate::cosmetics::{Cosmetics, JumpSound, TrailStyle};
+use crate::loadout::BOARDS;
+use crate::profile::Profile;
+use crate::replay::InputLog;
+use crate::GameConfig;
+use crate::RunMode;
+
+use log::warn;
+
+use std::path::PathBuf;
+
+const PROGRESS_FILE: &str = "progress.txt";
+const BEST_RUN_FILE: &str = "best_run.txt";
+
+// Score a single run has to reach to unlock the companion drone
+pub const DRONE_UNLOCK_SCORE: i32 = 20000;
+pub const DRONE_MAX_LEVEL: u32 = 3;
+
+// Highest ranked run on one board
+#[derive(Copy, Clone, Default)]
+struct Best {
+    score: i32,
+    signature: Option<u64>, // Signs score with the best run's input log
+    auto_jump: bool,        // Reached with the auto-jump assist on
+}
+
+pub struct Progress {
+    config: Option<Config>, // None without a profile, nothing is saved then
+    drone_level: u32,       // 0 while the drone is locked
+    bests: [Best; 4],       // Indexed like BOARDS
+    profile: Option<Profile>,
+    coin_bank: u32,     // Coins from every ranked run, for spending
+    golden_vaults: u32, // Golden statues vaulted over in every ranked run
+    last_mode: RunMode,
+    last_race_code: Option<String>, // Share code of the last race, for racing it again
+    seen_callouts: Vec<String>,     // Keys of the tutorial callouts already shown
+    cosmetics: Cosmetics,
+}
+
+impl Progress {
+    // Progress of the given profile, or a blank one if there's no profile
+    pub fn load(profile: Option<&Profile>) -> Progress {
+        let config = profile.map(|p| Config::load(p.path(PROGRESS_FILE)));
+        let drone_level = config
+            .as_ref()
+            .map(|c| c.get_or("drone_level", 0))
+            .unwrap_or(0)
+            .min(DRONE_MAX_LEVEL);
+        let coin_bank = config.as_ref().map(|c| c.get_or("coin_bank", 0)).unwrap_or(0);
+        let golden_vaults = config.as_ref().map(|c| c.get_or("golden_vaults", 0)).unwrap_or(0);
+        let mut bests = [Best::default(); 4];
+        for (best, board) in bests.iter_mut().zip(BOARDS.iter()) {
+            if let Some(config) = config.as_ref() {
+                best.score = config.get_or(&board_key("best_score", board), 0);
+                best.signature = config
+                    .get(&board_key("best_signature", board))
+                    .and_then(|s| u64::from_str_radix(s, 16).ok());
+                best.auto_jump = config.get_or(&board_key("best_auto_jump", board), false);
+            }
+        }
+        let last_mode = config
+            .as_ref()
+            .and_then(|c| c.get("last_mode"))
+            .and_then(RunMode::from_key)
+            .unwrap_or(RunMode::Solo);
+        let last_race_code = config
+            .as_ref()
+            .and_then(|c| c.get("last_race_code"))
+            .map(str::to_string);
+        let seen_callouts = config
+            .as_ref()
+            .and_then(|c| c.get("seen_callouts"))
+            .map(|s| s.split(',').filter(|k| !k.is_empty()).map(str::to_string).collect())
+            .unwrap_or_default();
+        let defaults = Cosmetics::default();
+        let cosmetics = Cosmetics {
+            trail: config
+                .as_ref()
+                .and_then(|c| c.get("trail"))
+                .and_then(TrailStyle::from_key)
+                .unwrap_or(defaults.trail),
+            jump_sound: config
+                .as_ref()
+                .and_then(|c| c.get("jump_sound"))
+                .and_then(JumpSound::from_key)
+                .unwrap_or(defaults.jump_sound),
+        };
+        Progress {
+            config,
+            drone_level,
+            bests,
+            profile: profile.cloned(),
+            coin_bank,
+            golden_vaults,
+            last_mode,
+            last_race_code,
+            seen_callouts,
+            cosmetics,
+        }
+    }
+
+    pub fn save(&mut self) -> Result<(), String> {
+        match self.config.as_mut() {
+            Some(config) => {
+                config.set("drone_level", &self.drone_level.to_string());
+                for (best, board) in self.bests.iter().zip(BOARDS.iter()) {
+                    config.set(&board_key("best_score", board), &best.score.to_string());
+                    if let Some(signature) = best.signature {
+                        config.set(&board_key("best_signature", board), &format!("{:016x}", signature));
+                    }
+                    config.set(&board_key("best_auto_jump", board), &best.auto_jump.to_string());
+                }
+                config.set("coin_bank", &self.coin_bank.to_string());
+                config.set("golden_vaults", &self.golden_vaults.to_string());
+                config.set("last_mode", self.last_mode.key());
+                if let Some(code) = &self.last_race_code {
+                    config.set("last_race_code", code);
+                }
+                config.set("seen_callouts", &self.seen_callouts.join(","));
+                config.set("trail", self.cosmetics.trail.def().key);
+                config.set("jump_sound", self.cosmetics.jump_sound.def().key);
+                config.save()
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub fn drone_unlocked(&self) -> bool {
+        self.drone_level > 0
+    }
+
+    pub fn drone_level(&self) -> u32 {
+        self.drone_level
+    }
+
+    pub fn best_score(&self, board: &str) -> i32 {
+        self.bests[board_index(board)].score
+    }
+
+    // Keeps `score` if it beats the personal best on `board`, along with the
+    // input log that reached it. Returns whether it did.
+    pub fn record_score(&mut self, board: &str, score: i32, log: &InputLog) -> bool {
+        let path = self.best_run_path(board);
+        let best = &mut self.bests[board_index(board)];
+        if score <= best.score {
+            return false;
+        }
+        best.score = score;
+        best.signature = None;
+        best.auto_jump = log.is_auto_jump();
+        if let Some(path) = path {
+            match log.save(path) {
+                Ok(()) => best.signature = Some(log.sign(score)),
+                Err(e) => warn!("Could not save the best run: {}", e),
+            }
+        }
+        true
+    }
+
+    pub fn best_auto_jump(&self, board: &str) -> bool {
+        self.bests[board_index(board)].auto_jump
+    }
+
+    // Whether the personal best on `board` is backed by a signed input log.
+    // Bests from before logs were kept, or edited by hand, aren't.
+    pub fn best_verified(&self, board: &str) -> bool {
+        let best = self.bests[board_index(board)];
+        if best.score <= 0 {
+            return true;
+        }
+        match (self.best_run_path(board), best.signature) {
+            (Some(path), Some(signature)) => InputLog::load(path)
+                .map(|log| log.verify(best.score, signature))
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    // Input log of the personal best on `board`, if it was kept
+    pub fn best_run(&self, board: &str) -> Option<InputLog> {
+        self.best_run_path(board).and_then(|path| InputLog::load(path).ok())
+    }
+
+    fn best_run_path(&self, board: &str) -> Option<PathBuf> {
+        let file = match board_index(board) {
+            0 => BEST_RUN_FILE.to_string(),
+            _ => format!("best_run_{}.txt", board),
+        };
+        self.profile.as_ref().map(|p| p.path(&file))
+    }
+
+    pub fn coin_bank(&self) -> u32 {
+        self.coin_bank
+    }
+
+    pub fn bank_coins(&mut self, coins: u32) {
+        self.coin_bank = self.coin_bank.saturating_add(coins);
+    }
+
+    // Takes `coins` out of the bank. Returns false, taking nothing, if there
+    // aren't enough.
+    pub fn spend_coins(&mut self, coins: u32) -> bool {
+        if coins > self.coin_bank {
+            return false;
+        }
+        self.coin_bank -= coins;
+        true
+    }
+
+    // Lifetime count, for the golden statue mission
+    pub fn golden_vaults(&self) -> u32 {
+        self.golden_vaults
+    }
+
+    pub fn add_golden_vaults(&mut self, count: u32) {
+        self.golden_vaults = self.golden_vaults.saturating_add(count);
+    }
+
+    pub fn last_mode(&self) -> RunMode {
+        self.last_mode
+    }
+
+    pub fn last_race_code(&self) -> Option<&str> {
+        self.last_race_code.as_deref()
+    }
+
+    // Remembers what a run was started in, so the menu can start the same
+    // again
+    pub fn remember_mode(&mut self, config: &GameConfig) {
+        self.last_mode = RunMode::of(config);
+        if let Some(code) = &config.race {
+            self.last_race_code = Some(code.encode());
+        }
+    }
+
+    pub fn seen_callout(&self, key: &str) -> bool {
+        self.seen_callouts.iter().any(|k| k == key)
+    }
+
+    pub fn mark_callout_seen(&mut self, key: &str) {
+        if !self.seen_callout(key) {
+            self.seen_callouts.push(key.to_string());
+        }
+    }
+
+    pub fn cosmetics(&self) -> Cosmetics {
+        self.cosmetics
+    }
+
+    pub fn set_cosmetics(&mut self, cosmetics: Cosmetics) {
+        self.cosmetics = cosmetics;
+    }
+
+    // Unlocks the drone at level 1. Returns whether it was newly unlocked.
+    pub fn unlock_drone(&mut self) -> bool {
+        if self.drone_level == 0 {
+            self.drone_level = 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    // Raises the drone's level, returning false if it's locked or maxed
+    pub fn upgrade_drone(&mut self) -> bool {
+        if self.drone_level == 0 || self.drone_level >= DRONE_MAX_LEVEL {
+            return false;
+        }
+        self.drone_level += 1;
+        true
+    }
+}
+
+// Position of `board` in BOARDS, unknown boards count as standard
+fn board_index(board: &str) -> usize {
+    BOARDS.iter().position(|b| *b == board).unwrap_or(0)
+}
+
+// Config key for `key` on `board`, standard keeps the plain key
+fn board_key(key: &str, board: &str) -> String {
+    match board_index(board) {
+        0 => key.to_string(),
+        _ => format!("{}_{}", key, board),
+    }
+}
+
+// Coin pickup radius of the drone at each level, in pixels
+pub fn drone_radius(level: u32) -> f64 {
+    match level {
+        0 | 1 => 120.0,
+        2 => 180.0,
+        _ => 240.0,
+    }
+}
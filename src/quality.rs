@@ -0,0 +1,68 @@
+// Render quality settings. Anything with an optional visual cost (particles,
+// parallax layers, full-screen overlays, ...) checks these instead of reading
+// the config itself, so low-spec mode stays a single switch.
+
+use crate::config::Config;
+
+#[derive(Copy, Clone, PartialEq)]
+pub struct Quality {
+    pub low_spec: bool,
+    pub particles: bool,
+    // Perlin hill layers behind the terrain
+    pub parallax: bool,
+    pub sunset_gradient: bool,
+    // Grass, fences and lamps in front of the terrain
+    pub foreground: bool,
+    // Render at the display's full pixel density instead of the window size
+    pub high_dpi: bool,
+    // Frames between redraws of the scrolling background. Frames in between
+    // reuse the last one.
+    pub background_interval: i32,
+}
+
+impl Quality {
+    pub fn full() -> Quality {
+        Quality {
+            low_spec: false,
+            particles: true,
+            parallax: true,
+            sunset_gradient: true,
+            foreground: true,
+            high_dpi: true,
+            background_interval: 1,
+        }
+    }
+
+    // Keeps 60 FPS on weak integrated GPUs
+    pub fn low_spec() -> Quality {
+        Quality {
+            low_spec: true,
+            particles: false,
+            parallax: false,
+            sunset_gradient: false,
+            foreground: false,
+            high_dpi: false,
+            background_interval: 4,
+        }
+    }
+
+    // Reads the `low_spec` key of the config file
+    pub fn from_config(config: &Config) -> Quality {
+        if config.get_or("low_spec", false) {
+            Quality::low_spec()
+        } else {
+            Quality::full()
+        }
+    }
+
+    pub fn save(&self, config: &mut Config) -> Result<(), String> {
+        config.set("low_spec", &self.low_spec.to_string());
+        config.save()
+    }
+}
+
+impl Default for Quality {
+    fn default() -> Self {
+        Quality::full()
+    }
+}
@@ -0,0 +1,240 @@
+// Share codes for racing a friend's run. A code holds the run's seed, how far
+// it got, and the step count at every SPLIT_DISTANCE along the way, so a run
+// on the same seed can flag where the friend was and show the pace difference.
+//
+// Codes are base32 (Crockford alphabet) so they survive being read out or
+// typed: no I, L or O, and those are read as 1, 1 and 0 when decoding.
+//
+// Pace marks do the same for the personal best, by time instead of distance:
+// where the best run had got to every so often, taken from its input log.
+
+use crate::replay::InputLog;
+
+use std::convert::TryInto;
+
+// Pixels of travel between splits
+pub const SPLIT_DISTANCE: i32 = 10000;
+// Splits past this aren't kept, it keeps codes short enough to type
+pub const MAX_SPLITS: usize = 16;
+
+const PREFIX: &str = "IR1-";
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ShareCode {
+    seed: u64,
+    distance: i32,
+    splits: Vec<i32>, // Steps taken when reaching each split
+}
+
+impl ShareCode {
+    pub fn new(seed: u64, distance: i32, splits: &[i32]) -> ShareCode {
+        ShareCode {
+            seed,
+            distance: distance.max(0),
+            splits: splits.iter().take(MAX_SPLITS).copied().collect(),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn distance(&self) -> i32 {
+        self.distance
+    }
+
+    pub fn splits(&self) -> &[i32] {
+        &self.splits
+    }
+
+    pub fn encode(&self) -> String {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&self.seed.to_be_bytes());
+        bytes.extend_from_slice(&(self.distance as u32).to_be_bytes());
+        bytes.push(self.splits.len() as u8);
+        // Splits are stored as the steps since the previous one
+        let mut last = 0;
+        for &split in self.splits.iter() {
+            let delta = (split - last).clamp(0, u16::MAX as i32) as u16;
+            bytes.extend_from_slice(&delta.to_be_bytes());
+            last = split;
+        }
+        bytes.push(checksum(&bytes));
+        format!("{}{}", PREFIX, to_base32(&bytes))
+    }
+
+    pub fn decode(code: &str) -> Result<ShareCode, String> {
+        let code = code.trim().to_uppercase();
+        let body = code.strip_prefix(PREFIX).unwrap_or(&code);
+        let bytes = from_base32(body)?;
+
+        let (data, sum) = bytes.split_at(bytes.len().saturating_sub(1));
+        if data.len() < 13 || sum.first() != Some(&checksum(data)) {
+            return Err("Not a valid share code".to_string());
+        }
+        let seed = u64::from_be_bytes(data[0..8].try_into().unwrap());
+        let distance = u32::from_be_bytes(data[8..12].try_into().unwrap()) as i32;
+        let count = data[12] as usize;
+        if count > MAX_SPLITS || data.len() != 13 + 2 * count {
+            return Err("Not a valid share code".to_string());
+        }
+
+        let mut splits = Vec::with_capacity(count);
+        let mut last = 0;
+        for pair in data[13..].chunks(2) {
+            last += u16::from_be_bytes([pair[0], pair[1]]) as i32;
+            splits.push(last);
+        }
+        Ok(ShareCode::new(seed, distance, &splits))
+    }
+
+    // Steps the friend took to get `distance` far, interpolated between
+    // splits. None past their last split.
+    pub fn steps_at(&self, distance: i32) -> Option<f64> {
+        if distance < 0 {
+            return None;
+        }
+        let i = (distance / SPLIT_DISTANCE) as usize;
+        let next = *self.splits.get(i)?;
+        let prev = if i == 0 { 0 } else { self.splits[i - 1] };
+        let t = (distance % SPLIT_DISTANCE) as f64 / SPLIT_DISTANCE as f64;
+        Some(prev as f64 + (next - prev) as f64 * t)
+    }
+}
+
+// Tracks distance and splits over the course of a run
+pub struct SplitRecorder {
+    distance: f64,
+    steps: i32,
+    splits: Vec<i32>,
+}
+
+impl SplitRecorder {
+    pub fn new() -> SplitRecorder {
+        SplitRecorder {
+            distance: 0.0,
+            steps: 0,
+            splits: Vec::new(),
+        }
+    }
+
+    // Call once per simulation step with how far the player moved
+    pub fn step(&mut self, travel: f64) {
+        self.steps += 1;
+        self.distance += travel.max(0.0);
+        while self.splits.len() < MAX_SPLITS
+            && self.distance >= ((self.splits.len() as i32 + 1) * SPLIT_DISTANCE) as f64
+        {
+            self.splits.push(self.steps);
+        }
+    }
+
+    pub fn distance(&self) -> i32 {
+        self.distance as i32
+    }
+
+    pub fn steps(&self) -> i32 {
+        self.steps
+    }
+
+    pub fn to_code(&self, seed: u64) -> ShareCode {
+        ShareCode::new(seed, self.distance(), &self.splits)
+    }
+}
+
+impl Default for SplitRecorder {
+    fn default() -> Self {
+        SplitRecorder::new()
+    }
+}
+
+// Where an earlier run was at `step`, and whether this run got there first
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct PaceMark {
+    pub step: u32,
+    pub distance: f64,
+    beaten: Option<bool>, // None until this run reaches it
+}
+
+impl PaceMark {
+    // Marks every `every` steps of the run `log` recorded, as far as it went
+    pub fn from_log(log: &InputLog, every: u32) -> Vec<PaceMark> {
+        let end = match log.end().or_else(|| log.poses().last().map(|&(step, _)| step)) {
+            Some(end) => end,
+            None => return Vec::new(),
+        };
+        (1..=end / every.max(1))
+            .filter_map(|i| {
+                let step = i * every;
+                log.pose_at(step as f64).map(|pose| PaceMark {
+                    step,
+                    distance: pose.distance,
+                    beaten: None,
+                })
+            })
+            .collect()
+    }
+
+    // Settles whether the mark was beaten once `distance` reaches it on `step`
+    pub fn check(&mut self, step: u32, distance: i32) {
+        if self.beaten.is_none() && distance as f64 >= self.distance {
+            self.beaten = Some(step <= self.step);
+        }
+    }
+
+    // Whether this run got there first, None while it's still ahead and
+    // could be
+    pub fn beaten(&self, step: u32) -> Option<bool> {
+        match self.beaten {
+            None if step > self.step => Some(false),
+            beaten => beaten,
+        }
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0x5a, |sum: u8, b| sum.rotate_left(3) ^ b)
+}
+
+fn to_base32(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &b in bytes {
+        buffer = ((buffer << 8) | b as u32) & 0xffff;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+fn from_base32(text: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in text.chars().filter(|c| !matches!(c, '-' | ' ')) {
+        let c = match c {
+            'O' => '0',
+            'I' | 'L' => '1',
+            c => c,
+        };
+        let value = ALPHABET
+            .iter()
+            .position(|&a| a as char == c)
+            .ok_or_else(|| format!("Unexpected '{}' in share code", c))?;
+        buffer = ((buffer << 5) | value as u32) & 0xffff;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(out)
+}
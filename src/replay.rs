@@ -0,0 +1,338 @@
+// Input logs of runs, and the keyed checksum that ties a score to one.
+// The simulation steps at a fixed rate and every random decision comes from
+// the run seed, so a seed plus the inputs at each step is enough to
+// re-simulate a run and check the score it claims.
+//
+// Logs are saved as text:
+//
+//     IRR1 <seed> [<loadout>]   loadout left off without modifiers
+//     <step> j          jump pressed
+//     <step> r <ms>     jump released after being held <ms>
+//     <step> a          assist toggled
+//     <step> u          auto-jump turned on
+//     <step> f          fast-fall pressed
+//     <step> p <distance> <y> <theta>   where the player was
+//     <step> e          run ended
+//
+// Player poses are only sampled a few times a second, to keep files small,
+// and are what a ghost of the run is drawn from. Between samples the ghost is
+// interpolated, and past the last one it's extrapolated.
+//
+// Exported replays are the same files, kept in the replays folder.
+
+use crate::archive::fnv1a;
+use crate::loadout::Loadout;
+
+use std::f64::consts::PI;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const HEADER: &str = "IRR1";
+pub const REPLAY_DIR: &str = "replays";
+pub const EXTENSION: &str = "replay";
+// Mixed into every signature. It ships in the binary, so it only stops scores
+// being edited by hand, not someone willing to dig it out.
+const KEY: &[u8] = b"inf_runner score key 1666";
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Input {
+    JumpPress,
+    JumpRelease(u32), // How long jump was held, in ms
+    Assist,
+    AutoJump,
+    FastFall,
+}
+
+// Where the player was: distance travelled, height with the camera taken
+// out, and rotation in radians
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Pose {
+    pub distance: f64,
+    pub y: f64,
+    pub theta: f64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct InputLog {
+    seed: u64,
+    loadout: Loadout,
+    events: Vec<(u32, Input)>, // Simulation step each input landed on
+    poses: Vec<(u32, Pose)>,   // Sampled every few steps
+    end: Option<u32>,          // Step the run ended on, None while it's going
+}
+
+impl Default for InputLog {
+    fn default() -> Self {
+        InputLog::new(0)
+    }
+}
+
+impl InputLog {
+    pub fn new(seed: u64) -> InputLog {
+        InputLog {
+            seed,
+            loadout: Loadout::default(),
+            events: Vec::new(),
+            poses: Vec::new(),
+            end: None,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn loadout(&self) -> Loadout {
+        self.loadout
+    }
+
+    pub fn set_loadout(&mut self, loadout: Loadout) {
+        self.loadout = loadout;
+    }
+
+    pub fn events(&self) -> &[(u32, Input)] {
+        &self.events
+    }
+
+    pub fn end(&self) -> Option<u32> {
+        self.end
+    }
+
+    // Whether the auto-jump assist played any part in the run
+    pub fn is_auto_jump(&self) -> bool {
+        self.events.iter().any(|&(_, input)| input == Input::AutoJump)
+    }
+
+    pub fn record(&mut self, step: u32, input: Input) {
+        self.events.push((step, input));
+    }
+
+    pub fn poses(&self) -> &[(u32, Pose)] {
+        &self.poses
+    }
+
+    pub fn record_pose(&mut self, step: u32, pose: Pose) {
+        self.poses.push((step, pose));
+    }
+
+    /*  Where the player was at `step`, which can fall between steps
+     *
+     *  - Between samples, position follows a Catmull-Rom curve through the
+     *    samples around it and rotation turns the short way round
+     *  - Past the last sample it carries on at the last sampled velocity, for
+     *    at most one more sample's worth of steps
+     *  - None before the first sample or after the run ended
+     */
+    pub fn pose_at(&self, step: f64) -> Option<Pose> {
+        let poses = &self.poses;
+        if poses.is_empty() || step < poses[0].0 as f64 || self.end.is_some_and(|end| step > end as f64) {
+            return None;
+        }
+        let i = poses.iter().rposition(|&(at, _)| at as f64 <= step)?;
+        let (at, pose) = poses[i];
+
+        if i + 1 == poses.len() {
+            let (prev_at, prev) = match i.checked_sub(1) {
+                Some(p) => poses[p],
+                None => return Some(pose),
+            };
+            let gap = (at - prev_at).max(1) as f64;
+            let t = ((step - at as f64) / gap).min(1.0);
+            return Some(Pose {
+                distance: pose.distance + (pose.distance - prev.distance) * t,
+                y: pose.y + (pose.y - prev.y) * t,
+                theta: pose.theta + angle_between(prev.theta, pose.theta) * t,
+            });
+        }
+
+        let (next_at, next) = poses[i + 1];
+        let before = poses[i.saturating_sub(1)].1;
+        let after = poses[(i + 2).min(poses.len() - 1)].1;
+        let t = (step - at as f64) / (next_at - at).max(1) as f64;
+        Some(Pose {
+            distance: catmull_rom(before.distance, pose.distance, next.distance, after.distance, t),
+            y: catmull_rom(before.y, pose.y, next.y, after.y, t),
+            theta: pose.theta + angle_between(pose.theta, next.theta) * t,
+        })
+    }
+
+    // Marks the run as over at `step`
+    pub fn finish(&mut self, step: u32) {
+        self.end = Some(step);
+    }
+
+    pub fn encode(&self) -> String {
+        let mut text = if self.loadout.is_empty() {
+            format!("{} {}\n", HEADER, self.seed)
+        } else {
+            format!("{} {} {}\n", HEADER, self.seed, self.loadout.encode())
+        };
+        for (step, input) in self.events.iter() {
+            match input {
+                Input::JumpPress => text.push_str(&format!("{} j\n", step)),
+                Input::JumpRelease(ms) => text.push_str(&format!("{} r {}\n", step, ms)),
+                Input::Assist => text.push_str(&format!("{} a\n", step)),
+                Input::AutoJump => text.push_str(&format!("{} u\n", step)),
+                Input::FastFall => text.push_str(&format!("{} f\n", step)),
+            }
+        }
+        for (step, pose) in self.poses.iter() {
+            text.push_str(&format!(
+                "{} p {:.1} {:.1} {:.3}\n",
+                step, pose.distance, pose.y, pose.theta
+            ));
+        }
+        if let Some(end) = self.end {
+            text.push_str(&format!("{} e\n", end));
+        }
+        text
+    }
+
+    pub fn decode(text: &str) -> Result<InputLog, String> {
+        let mut lines = text.lines();
+        let mut header = lines
+            .next()
+            .and_then(|l| l.strip_prefix(HEADER))
+            .ok_or("Not an input log")?
+            .split_whitespace();
+        let seed = header.next().and_then(|s| s.parse().ok()).ok_or("Not an input log")?;
+
+        let mut log = InputLog::new(seed);
+        if let Some(loadout) = header.next() {
+            log.set_loadout(Loadout::decode(loadout)?);
+        }
+        let mut last_step = 0;
+        let mut last_pose_step = 0;
+        for line in lines.filter(|l| !l.trim().is_empty()) {
+            let bad_line = || format!("Bad input log line '{}'", line);
+            if log.end.is_some() {
+                return Err("Input log goes on after the run ended".to_string());
+            }
+            let mut parts = line.split_whitespace();
+            let step: u32 = parts.next().and_then(|s| s.parse().ok()).ok_or_else(bad_line)?;
+            let kind = parts.next();
+            if kind == Some("p") {
+                let values: Vec<f64> = parts
+                    .map(|v| v.parse().map_err(|_| bad_line()))
+                    .collect::<Result<_, _>>()?;
+                if values.len() != 3 || step < last_pose_step {
+                    return Err(bad_line());
+                }
+                last_pose_step = step;
+                let pose = Pose {
+                    distance: values[0],
+                    y: values[1],
+                    theta: values[2],
+                };
+                log.record_pose(step, pose);
+                continue;
+            }
+            // Inputs are only ever recorded in order
+            if step < last_step {
+                return Err(bad_line());
+            }
+            last_step = step;
+            let input = match (kind, parts.next()) {
+                (Some("j"), None) => Input::JumpPress,
+                (Some("r"), Some(ms)) => Input::JumpRelease(ms.parse().map_err(|_| bad_line())?),
+                (Some("a"), None) => Input::Assist,
+                (Some("u"), None) => Input::AutoJump,
+                (Some("f"), None) => Input::FastFall,
+                (Some("e"), None) => {
+                    log.finish(step);
+                    continue;
+                }
+                _ => return Err(bad_line()),
+            };
+            log.record(step, input);
+        }
+        Ok(log)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        fs::write(path, self.encode()).map_err(|e| e.to_string())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<InputLog, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        InputLog::decode(&text)
+    }
+
+    // Keyed checksum of this log scoring `score`
+    pub fn sign(&self, score: i32) -> u64 {
+        let mut bytes = KEY.to_vec();
+        bytes.extend_from_slice(self.encode().as_bytes());
+        bytes.extend_from_slice(&score.to_be_bytes());
+        let inner = fnv1a(&bytes);
+
+        // Keyed again on the outside so the key can't be peeled off the end
+        let mut bytes = KEY.to_vec();
+        bytes.extend_from_slice(&inner.to_be_bytes());
+        fnv1a(&bytes)
+    }
+
+    // Whether `signature` came from this log scoring `score`. Re-simulating
+    // the log is what proves the score was actually reached.
+    pub fn verify(&self, score: i32, signature: u64) -> bool {
+        self.sign(score) == signature
+    }
+}
+
+// Value between `b` and `c` at `t`, curving through `a` before and `d` after
+fn catmull_rom(a: f64, b: f64, c: f64, d: f64, t: f64) -> f64 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    0.5 * (2.0 * b + (c - a) * t + (2.0 * a - 5.0 * b + 4.0 * c - d) * t2 + (3.0 * b - a - 3.0 * c + d) * t3)
+}
+
+// Signed turn from `from` to `to` the short way round, in radians
+fn angle_between(from: f64, to: f64) -> f64 {
+    (to - from + PI).rem_euclid(2.0 * PI) - PI
+}
+
+// Feeds a log's inputs back in, step by step
+pub struct Playback {
+    log: InputLog,
+    next: usize, // First event that hasn't been played
+}
+
+impl Playback {
+    pub fn new(log: InputLog) -> Playback {
+        Playback { log, next: 0 }
+    }
+
+    pub fn log(&self) -> &InputLog {
+        &self.log
+    }
+
+    // Inputs that landed on `step`, call once per step in order
+    pub fn take(&mut self, step: u32) -> Vec<Input> {
+        let mut inputs = Vec::new();
+        while let Some(&(at, input)) = self.log.events.get(self.next) {
+            if at > step {
+                break;
+            }
+            inputs.push(input);
+            self.next += 1;
+        }
+        inputs
+    }
+}
+
+// Saves a run to the replays folder, named after its seed and score
+pub fn export(log: &InputLog, score: i32) -> Result<PathBuf, String> {
+    fs::create_dir_all(REPLAY_DIR).map_err(|e| e.to_string())?;
+    let path = Path::new(REPLAY_DIR).join(format!("{}-{}.{}", log.seed, score, EXTENSION));
+    log.save(&path)?;
+    Ok(path)
+}
+
+// The most recently saved replay in the replays folder
+pub fn latest() -> Option<PathBuf> {
+    fs::read_dir(REPLAY_DIR)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == EXTENSION))
+        .max_by_key(|p| fs::metadata(p).and_then(|m| m.modified()).ok())
+}
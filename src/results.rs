@@ -0,0 +1,546 @@
+// Results of the run that just ended. The coins collected are tallied into
+// the score first, each one worth COIN_BONUS. A run that then beats the
+// profile's best gets a celebration on top: a confetti burst with a fanfare,
+// the best counting up from the old one, then its row lighting up. The steps
+// are spaced out by a small timeline, any key skips to the end of it. The
+// run can be watched again from here or saved as a replay file. Off to the
+// side, bars break the score down by where it came from, the coin bonus bar
+// filling as it's tallied.
+
+use inf_runner::rect;
+
+use inf_runner::assets;
+use inf_runner::audio;
+use inf_runner::audio::Audio;
+use inf_runner::config::Config;
+use inf_runner::framerate::FrameCap;
+use inf_runner::loadout::BOARDS;
+use inf_runner::power::PowerSaver;
+use inf_runner::quality::Quality;
+use inf_runner::replay;
+use inf_runner::score::ScoreSource;
+use inf_runner::settings::Settings;
+use inf_runner::DeathCause;
+use inf_runner::Game;
+use inf_runner::GameConfig;
+use inf_runner::GameState;
+use inf_runner::Hazard;
+use inf_runner::ObstacleType;
+use inf_runner::RunStats;
+use inf_runner::SDLCore;
+use inf_runner::COIN_BONUS;
+
+use rand::Rng;
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::keyboard::Mod;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{TextureCreator, TextureQuery};
+use sdl2::ttf::Font;
+use sdl2::video::WindowContext;
+
+use log::{info, warn};
+
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+const CAM_W: u32 = 1280;
+const CAM_H: u32 = 720;
+
+const BACKGROUND: Color = Color::RGBA(3, 120, 206, 255);
+const TEXT: Color = Color::RGBA(255, 255, 255, 255);
+const GOLD: Color = Color::RGBA(255, 215, 0, 255);
+
+// Steps of the results screen
+#[derive(Copy, Clone, PartialEq)]
+enum Cue {
+    Tally,     // Coins start turning into score
+    Burst,     // Confetti and fanfare
+    CountUp,   // Best starts counting from the old one
+    Highlight, // New best's scoreboard row lights up
+}
+
+impl Cue {
+    // Seconds the step keeps animating after it's due
+    fn duration(&self) -> f64 {
+        match self {
+            Cue::Tally => TALLY_SECS,
+            Cue::CountUp => COUNT_UP_SECS,
+            Cue::Burst | Cue::Highlight => 0.0,
+        }
+    }
+}
+
+// Cues and when they're due, in seconds after the screen opens
+const TIMELINE: [(f64, Cue); 1] = [(0.4, Cue::Tally)];
+const NEW_BEST_TIMELINE: [(f64, Cue); 4] = [
+    (0.4, Cue::Tally),
+    (1.9, Cue::Burst),
+    (2.2, Cue::CountUp),
+    (4.0, Cue::Highlight),
+];
+const TALLY_SECS: f64 = 1.2;
+const COUNT_UP_SECS: f64 = 1.8;
+
+// Score breakdown, off to the right of the scoreboard
+const LEDGER_POS: (i32, i32) = (850, 405);
+const LEDGER_ROW_H: i32 = 30;
+const LEDGER_BAR_W: u32 = 140;
+
+const CONFETTI_COUNT: usize = 160;
+const CONFETTI_GRAVITY: f64 = 900.0; // px/s^2
+const CONFETTI_COLORS: [Color; 5] = [
+    Color::RGB(255, 215, 0),
+    Color::RGB(255, 80, 80),
+    Color::RGB(80, 200, 255),
+    Color::RGB(120, 230, 120),
+    Color::RGB(230, 120, 255),
+];
+
+pub struct Results {
+    stats: Option<RunStats>,
+}
+
+impl Results {
+    pub fn set_stats(&mut self, stats: RunStats) {
+        self.stats = Some(stats);
+    }
+}
+
+// Hands out the timeline's cues as they come due
+struct Timeline {
+    cues: &'static [(f64, Cue)],
+    start: Instant,
+    skipped: f64, // Seconds skipped ahead
+    next: usize,
+}
+
+impl Timeline {
+    fn new(cues: &'static [(f64, Cue)]) -> Timeline {
+        Timeline {
+            cues,
+            start: Instant::now(),
+            skipped: 0.0,
+            next: 0,
+        }
+    }
+
+    fn elapsed(&self) -> f64 {
+        self.start.elapsed().as_secs_f64() + self.skipped
+    }
+
+    // Next cue that's due along with when it was due, None if there isn't
+    // one yet
+    fn poll(&mut self) -> Option<(f64, Cue)> {
+        let (at, cue) = *self.cues.get(self.next)?;
+        if self.elapsed() >= at {
+            self.next += 1;
+            Some((at, cue))
+        } else {
+            None
+        }
+    }
+
+    // When the last step is done animating
+    fn end(&self) -> f64 {
+        self.cues
+            .iter()
+            .map(|(at, cue)| at + cue.duration())
+            .fold(0.0, f64::max)
+    }
+
+    fn finished(&self) -> bool {
+        self.next >= self.cues.len() && self.elapsed() >= self.end()
+    }
+
+    // Jumps to the end, every remaining cue comes due at once
+    fn skip(&mut self) {
+        self.skipped += (self.end() - self.elapsed()).max(0.0);
+    }
+
+    // How far along the step that started at `at` is, 0 to 1 and eased out
+    fn progress(&self, at: Option<f64>, cue: Cue) -> f64 {
+        match at {
+            Some(at) => {
+                let t = ((self.elapsed() - at) / cue.duration()).clamp(0.0, 1.0);
+                1.0 - (1.0 - t).powi(3)
+            }
+            None => 0.0,
+        }
+    }
+}
+
+struct Confetti {
+    x: f64,
+    y: f64,
+    vx: f64,
+    vy: f64,
+    spin: f64, // Radians, the piece is drawn narrower as it turns
+    spin_rate: f64,
+    color: Color,
+}
+
+// Two cannons in the bottom corners firing up and inward
+fn burst(rng: &mut impl Rng) -> Vec<Confetti> {
+    (0..CONFETTI_COUNT)
+        .map(|i| {
+            let left = i % 2 == 0;
+            let angle: f64 = rng.gen_range(55.0_f64..80.0).to_radians();
+            let speed = rng.gen_range(700.0..1100.0);
+            Confetti {
+                x: if left { 0.0 } else { CAM_W as f64 },
+                y: CAM_H as f64,
+                vx: angle.cos() * speed * if left { 1.0 } else { -1.0 },
+                vy: -angle.sin() * speed,
+                spin: rng.gen_range(0.0..std::f64::consts::TAU),
+                spin_rate: rng.gen_range(4.0..12.0),
+                color: CONFETTI_COLORS[rng.gen_range(0..CONFETTI_COLORS.len())],
+            }
+        })
+        .collect()
+}
+
+impl Game for Results {
+    fn init() -> Result<Self, String> {
+        Ok(Results { stats: None })
+    }
+
+    fn run(&mut self, core: &mut SDLCore) -> Result<GameState, String> {
+        let stats = match self.stats.take() {
+            Some(stats) => stats,
+            None => return Ok(GameState::ToMenu),
+        };
+        core.wincan.set_blend_mode(sdl2::render::BlendMode::Blend);
+
+        let texture_creator = core.wincan.texture_creator();
+        let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
+        let mut font = assets::load_font(&ttf_context, 128)?;
+        font.set_style(sdl2::ttf::FontStyle::BOLD);
+
+        let config = Config::current();
+        // Lighter on battery
+        let power = PowerSaver::from_config(&config);
+        let frame_cap = power.menu_frame_cap(FrameCap::from_config(&config));
+        let quality = power.menu_quality(Quality::from_config(&config));
+        let mut sound = if stats.new_best {
+            Audio::open(core, Settings::from_config(&config).volume)
+        } else {
+            Audio::disabled()
+        };
+
+        let mut rng = rand::thread_rng();
+        let mut timeline = Timeline::new(if stats.new_best { &NEW_BEST_TIMELINE } else { &TIMELINE });
+        let mut confetti: Vec<Confetti> = Vec::new();
+        let mut tally_at: Option<f64> = None;
+        let mut count_up_at: Option<f64> = None;
+        let mut highlight_at: Option<f64> = None;
+        let mut last_frame = Instant::now();
+        // Where the replay was saved to, or why it couldn't be
+        let mut replay_message: Option<String> = None;
+
+        let next_state;
+        'gameloop: loop {
+            for event in core.event_pump.poll_iter() {
+                match event {
+                    Event::Quit { .. } => {
+                        next_state = GameState::Quit;
+                        break 'gameloop;
+                    }
+                    Event::KeyDown {
+                        keycode: Some(k),
+                        keymod,
+                        repeat: false,
+                        ..
+                    } => {
+                        // The first key finishes the animations, the next one leaves
+                        if !timeline.finished() {
+                            timeline.skip();
+                            continue;
+                        }
+                        match k {
+                            // Quick restart in the same mode, Shift keeps the seed too
+                            Keycode::R => {
+                                let same_seed = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+                                next_state = GameState::ToGame(GameConfig {
+                                    seed: if same_seed { Some(stats.seed) } else { None },
+                                    ..stats.config.clone()
+                                });
+                                break 'gameloop;
+                            }
+                            // Practice the seed, seeing what's coming
+                            Keycode::P => {
+                                next_state = GameState::ToGame(GameConfig {
+                                    seed: Some(stats.seed),
+                                    practice: true,
+                                    ..stats.config.clone()
+                                });
+                                break 'gameloop;
+                            }
+                            Keycode::W => {
+                                next_state = GameState::ToGame(GameConfig {
+                                    replay: Some(stats.inputs.clone()),
+                                    ..GameConfig::default()
+                                });
+                                break 'gameloop;
+                            }
+                            Keycode::X => {
+                                replay_message = Some(match replay::export(&stats.inputs, stats.total()) {
+                                    Ok(path) => {
+                                        info!("Saved replay to {}", path.display());
+                                        format!("Replay saved to {}", path.display())
+                                    }
+                                    Err(e) => {
+                                        warn!("Could not save replay: {}", e);
+                                        format!("Could not save replay: {}", e)
+                                    }
+                                });
+                            }
+                            Keycode::Return | Keycode::Space | Keycode::Escape | Keycode::M => {
+                                next_state = GameState::ToMenu;
+                                break 'gameloop;
+                            }
+                            _ => {}
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            while let Some((at, cue)) = timeline.poll() {
+                match cue {
+                    Cue::Tally => tally_at = Some(at),
+                    Cue::Burst => {
+                        if quality.particles {
+                            confetti = burst(&mut rng);
+                        }
+                        sound.play(&audio::FANFARE);
+                    }
+                    Cue::CountUp => count_up_at = Some(at),
+                    Cue::Highlight => highlight_at = Some(at),
+                }
+            }
+
+            let dt = last_frame.elapsed().as_secs_f64();
+            last_frame = Instant::now();
+            for piece in confetti.iter_mut() {
+                piece.vy += CONFETTI_GRAVITY * dt;
+                // Air resistance, so pieces flutter down instead of dropping
+                piece.vx *= 1.0 - 1.5 * dt;
+                piece.vy = piece.vy.min(180.0);
+                piece.x += piece.vx * dt;
+                piece.y += piece.vy * dt;
+                piece.spin += piece.spin_rate * dt;
+            }
+            confetti.retain(|p| p.y < CAM_H as f64 + 20.0);
+
+            // Coins move into the score, then the best counts up to it
+            let tallied = timeline.progress(tally_at, Cue::Tally);
+            let shown_coins = stats.coins - (stats.coins as f64 * tallied) as i32;
+            let shown_score = stats.score - stats.coin_bonus() + (stats.coin_bonus() as f64 * tallied) as i32;
+            let shown_best = if stats.new_best {
+                let t = timeline.progress(count_up_at, Cue::CountUp);
+                stats.previous_best + ((stats.total() - stats.previous_best) as f64 * t) as i32
+            } else {
+                stats.previous_best
+            };
+
+            /* ~~~~~~ Draw ~~~~~~ */
+            core.wincan.set_draw_color(BACKGROUND);
+            core.wincan.clear();
+            core.wincan.set_draw_color(Color::RGBA(0, 0, 0, 128));
+            core.wincan.fill_rect(rect!(0, 0, CAM_W, CAM_H))?;
+
+            let heading = if stats.new_best && highlight_at.is_some() {
+                "New personal best!"
+            } else {
+                "Run over"
+            };
+            draw_text(core, &texture_creator, &font, heading, TEXT, (125, 60), 80)?;
+            draw_text(
+                core,
+                &texture_creator,
+                &font,
+                &format!("{} points", shown_score),
+                GOLD,
+                (125, 170),
+                110,
+            )?;
+
+            let mut details = format!("Distance {}   Seed {}", stats.distance, stats.seed);
+            if let Some(cause) = stats.death_cause {
+                details.push_str(&format!("   {}", death_label(cause)));
+            }
+            if stats.golden_vaults > 0 {
+                details.push_str(&format!("   Golden vaults {}", stats.golden_vaults));
+            }
+            if stats.config.practice {
+                details.push_str("   (practice, not ranked)");
+            } else if !stats.ranked {
+                details.push_str("   (assisted, not ranked)");
+            }
+            draw_text(
+                core,
+                &texture_creator,
+                &font,
+                &format!("Coins {} x {}", shown_coins, COIN_BONUS),
+                GOLD,
+                (125, 290),
+                40,
+            )?;
+            draw_text(core, &texture_creator, &font, &details, TEXT, (125, 350), 30)?;
+
+            // Where the score came from, bars against the biggest source
+            let (x, y) = LEDGER_POS;
+            draw_text(core, &texture_creator, &font, "Where it came from", TEXT, (x, y), 28)?;
+            let largest = stats.ledger.largest();
+            for (i, (source, mut points)) in stats.ledger.entries().enumerate() {
+                if source == ScoreSource::CoinBonus {
+                    points = (points as f64 * tallied) as i32;
+                }
+                let row_y = y + 40 + i as i32 * LEDGER_ROW_H;
+                let def = source.def();
+                draw_text(core, &texture_creator, &font, def.label, TEXT, (x, row_y), 22)?;
+                let bar_w = if largest > 0 {
+                    (LEDGER_BAR_W as f64 * points.max(0) as f64 / largest as f64) as u32
+                } else {
+                    0
+                };
+                core.wincan.set_draw_color(Color::RGBA(255, 255, 255, 40));
+                core.wincan.fill_rect(rect!(x + 140, row_y + 3, LEDGER_BAR_W, 16))?;
+                if bar_w > 0 {
+                    core.wincan.set_draw_color(def.color);
+                    core.wincan.fill_rect(rect!(x + 140, row_y + 3, bar_w, 16))?;
+                }
+                draw_text(
+                    core,
+                    &texture_creator,
+                    &font,
+                    &points.to_string(),
+                    TEXT,
+                    (x + 150 + LEDGER_BAR_W as i32, row_y),
+                    22,
+                )?;
+            }
+
+            // Scoreboard
+            // Bests without a signed input log behind them are flagged, as
+            // are runs played with auto-jump
+            // Runs with mutators have a board of their own
+            let loadout = stats.inputs.loadout();
+            let best_label = if loadout.board() == BOARDS[0] {
+                "Personal best".to_string()
+            } else {
+                format!("{} best", loadout.board_label())
+            };
+            let rows = [
+                (
+                    best_label.as_str(),
+                    shown_best,
+                    stats.new_best,
+                    stats.best_verified,
+                    stats.best_auto_jump,
+                ),
+                ("This run", shown_score, false, true, stats.auto_jump),
+            ];
+            for (i, &(label, score, is_new, verified, auto_jump)) in rows.iter().enumerate() {
+                let y = 420 + i as i32 * 70;
+                if let (true, Some(at)) = (is_new, highlight_at) {
+                    // Flash in, then a slow pulse
+                    let t = timeline.elapsed() - at;
+                    let flash = (1.0 - t * 2.0).max(0.0);
+                    let pulse = 0.5 + 0.5 * (t * 4.0).sin();
+                    let alpha = (70.0 + 60.0 * pulse + 120.0 * flash).min(255.0) as u8;
+                    core.wincan.set_draw_color(Color::RGBA(GOLD.r, GOLD.g, GOLD.b, alpha));
+                    core.wincan.fill_rect(rect!(105, y - 10, 700, 60))?;
+                }
+                let mut mark = if verified { "" } else { " (unverified)" }.to_string();
+                if auto_jump {
+                    mark.push_str(" (auto-jump)");
+                }
+                let text = format!("{:<14}{:>10}{}", label, score, mark);
+                draw_text(core, &texture_creator, &font, &text, TEXT, (125, y), 40)?;
+            }
+
+            draw_text(
+                core,
+                &texture_creator,
+                &font,
+                "Enter - Menu   R - Run again   W - Watch   X - Save replay",
+                TEXT,
+                (125, 640),
+                36,
+            )?;
+            draw_text(
+                core,
+                &texture_creator,
+                &font,
+                "Shift+R - Run again on the same seed   P - Practice it",
+                TEXT,
+                (125, 680),
+                30,
+            )?;
+            if let Some(msg) = &replay_message {
+                draw_text(core, &texture_creator, &font, msg, GOLD, (125, 590), 30)?;
+            }
+
+            for piece in confetti.iter() {
+                let w = (10.0 * piece.spin.cos().abs()).max(2.0) as u32;
+                core.wincan.set_draw_color(piece.color);
+                core.wincan
+                    .fill_rect(rect!(piece.x as i32 - w as i32 / 2, piece.y as i32 - 3, w, 6))?;
+            }
+
+            core.wincan.present();
+            /* ~~~~~~~~~~~~~~~~~~ */
+
+            let delay = frame_cap.frame_time().unwrap_or(0.0) - last_frame.elapsed().as_secs_f64();
+            if delay > 0.0 {
+                sleep(Duration::from_secs_f64(delay));
+            }
+        }
+
+        sound.stop();
+        Ok(next_state)
+    }
+}
+
+// Draws a line of text at the given height, keeping the text's aspect ratio
+fn draw_text(
+    core: &mut SDLCore,
+    texture_creator: &TextureCreator<WindowContext>,
+    font: &Font,
+    text: &str,
+    color: Color,
+    pos: (i32, i32),
+    h: u32,
+) -> Result<(), String> {
+    let texture = texture_creator
+        .create_texture_from_surface(&font.render(text).blended(color).map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?;
+    let TextureQuery { width, height, .. } = texture.query();
+    let w = (width as f64 * h as f64 / height as f64) as u32;
+    core.wincan.copy(&texture, None, rect!(pos.0, pos.1, w, h))
+}
+
+fn death_label(cause: DeathCause) -> String {
+    match cause {
+        DeathCause::BadLanding => "Bad landing".to_string(),
+        DeathCause::Crash(obstacle) => format!("Crashed into a {}", obstacle_name(obstacle)),
+        DeathCause::OutOfLives(obstacle) => format!("Out of lives, last hit a {}", obstacle_name(obstacle)),
+        DeathCause::Hazard(Hazard::Lava) => "Out of lives, burned in lava".to_string(),
+        DeathCause::Hazard(Hazard::Spikes) => "Out of lives, ran onto spikes".to_string(),
+        DeathCause::Fell => "Fell into a gap".to_string(),
+        DeathCause::Debris => "Out of lives, hit by falling debris".to_string(),
+        DeathCause::RiskZone(obstacle) => format!("Hit a {} in a risk zone", obstacle_name(obstacle)),
+    }
+}
+
+fn obstacle_name(obstacle: ObstacleType) -> &'static str {
+    match obstacle {
+        ObstacleType::Statue => "statue",
+        ObstacleType::GoldenStatue => "golden statue",
+        ObstacleType::Balloon => "balloon",
+        ObstacleType::Chest => "chest",
+    }
+}
@@ -0,0 +1,89 @@
+use rand::rngs::StdRng;
+use rand::Error;
+use rand::Rng;
+use rand::RngCore;
+use rand::SeedableRng;
+
+// Independent random streams split off of a single run seed.
+// Terrain and spawns draw from their own streams so that adding a roll to one
+// system doesn't shift every value the other one produces. Purely visual
+// randomness has a stream too, so a seed looks the same every time it's
+// played without anything drawn for looks touching gameplay.
+#[derive(Copy, Clone)]
+pub enum RngStream {
+    Terrain,
+    Spawns,
+    Visuals,
+}
+
+impl RngStream {
+    // Fixed per-stream key mixed into the parent seed when splitting
+    fn key(&self) -> u64 {
+        match self {
+            RngStream::Terrain => 0x7465_7272_6169_6e00,
+            RngStream::Spawns => 0x7370_6177_6e73_0000,
+            RngStream::Visuals => 0x7669_7375_616c_7300,
+        }
+    }
+}
+
+// Seedable, cloneable random number generator used in place of
+// rand::thread_rng() anywhere gameplay needs randomness. Cloning a GameRng
+// duplicates its current state, so the clone produces the same values.
+#[derive(Clone)]
+pub struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> GameRng {
+        GameRng {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    // Picks a fresh random seed, for runs that don't need to be reproduced
+    pub fn from_entropy() -> GameRng {
+        GameRng::new(rand::thread_rng().gen())
+    }
+
+    // The seed this generator was created from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // Creates a new generator for the given stream. The result only depends on
+    // this generator's seed and the stream, not on how many values have been
+    // drawn from this generator so far.
+    pub fn split(&self, stream: RngStream) -> GameRng {
+        GameRng::new(mix_seed(self.seed ^ stream.key()))
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+// SplitMix64 finalizer, spreads similar seeds far apart from each other
+fn mix_seed(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
@@ -1,71 +1,259 @@
-use crate::physics::Body;
-use crate::physics::Coin;
-use crate::physics::Collectible;
-use crate::physics::Entity;
-use crate::physics::Obstacle;
-use crate::physics::PhysRect;
-use crate::physics::Physics;
-use crate::physics::Player;
-use crate::physics::Power;
-
-use crate::proceduralgen;
-use crate::proceduralgen::ProceduralGen;
-use crate::proceduralgen::TerrainSegment;
-
-use crate::p_rect;
-use crate::rect;
-
+use inf_runner::animation::PlayerAnimator;
+use inf_runner::animation::PlayerVisual;
+use inf_runner::parallax::Parallax;
+use inf_runner::physics::Body;
+use inf_runner::physics::Coin;
+use inf_runner::physics::CoinDrop;
+use inf_runner::physics::Collectible;
+use inf_runner::physics::Debris;
+use inf_runner::physics::Drone;
+use inf_runner::physics::Entity;
+use inf_runner::physics::Footing;
+use inf_runner::physics::HitboxPart;
+use inf_runner::physics::Impact;
+use inf_runner::physics::LandingGrade;
+use inf_runner::physics::Obstacle;
+use inf_runner::physics::PhysRect;
+use inf_runner::physics::Physics;
+use inf_runner::physics::Player;
+use inf_runner::physics::Power;
+use inf_runner::physics::Sprite;
+use inf_runner::physics::DRONE_SIZE;
+use inf_runner::physics::POWER_STEPS;
+
+use inf_runner::proceduralgen;
+use inf_runner::terrainpool::TerrainMemory;
+use inf_runner::tutorial;
+use inf_runner::tutorial::Callout;
+use inf_runner::world::get_floor;
+use inf_runner::world::get_ground_coord;
+use inf_runner::world::ground_ahead;
+use inf_runner::world::StepContext;
+use inf_runner::world::World;
+use inf_runner::world::PLAYER_X;
+
+use inf_runner::profile::Profile;
+use inf_runner::profiler;
+use inf_runner::profiler::Profiler;
+use inf_runner::profiler::Scope;
+
+use inf_runner::progress;
+use inf_runner::progress::Progress;
+
+use inf_runner::rng::GameRng;
+use inf_runner::rng::RngStream;
+
+use inf_runner::framerate::FrameCap;
+use inf_runner::framerate::SIM_FPS;
+use inf_runner::framerate::SIM_STEP;
+use inf_runner::heatmap;
+use inf_runner::heatmap::SpawnHeatmap;
+use inf_runner::highscores::HighScores;
+use inf_runner::input::{Action, InputDisplay, InputState};
+use inf_runner::layers::DrawQueue;
+use inf_runner::layers::Layer;
+use inf_runner::layout;
+use inf_runner::layout::Anchor;
+use inf_runner::lighting;
+use inf_runner::loadout::Loadout;
+use inf_runner::loadout::Modifier;
+use inf_runner::menu::{PauseMenu, PauseOption};
+
+use inf_runner::assets;
+use inf_runner::audio;
+use inf_runner::audio::Audio;
+use inf_runner::audio::Stinger;
+use inf_runner::autopilot::Autopilot;
+use inf_runner::biome::Biome;
+use inf_runner::bugreport::BugReport;
+use inf_runner::camera::{Camera, CameraConfig};
+use inf_runner::clock::SimClock;
+use inf_runner::clock::Timer;
+use inf_runner::config::Config;
+use inf_runner::cosmetics::Trail;
+use inf_runner::crash;
+use inf_runner::decals;
+use inf_runner::decals::{DecalKind, Decals};
+use inf_runner::decor;
+use inf_runner::events::{EventScheduler, RandomEvent};
+use inf_runner::motes::Motes;
+use inf_runner::quality::Quality;
+use inf_runner::race::{PaceMark, ShareCode, SplitRecorder, SPLIT_DISTANCE};
+use inf_runner::replay::{Input, InputLog, Playback, Pose};
+use inf_runner::score::{Score, ScoreSource};
+use inf_runner::settings::Settings;
+use inf_runner::speech::Announcer;
+use inf_runner::stall::{StallConfig, StallRule};
+
+use inf_runner::p_rect;
+use inf_runner::rect;
+
+use inf_runner::DeathCause;
 use inf_runner::Game;
+use inf_runner::GameConfig;
 use inf_runner::GameState;
 use inf_runner::GameStatus;
+use inf_runner::Hazard;
 use inf_runner::ObstacleType;
 use inf_runner::PowerType;
+use inf_runner::RunStats;
 use inf_runner::SDLCore;
 use inf_runner::StaticObject;
-use inf_runner::TerrainType;
+use inf_runner::COIN_BONUS;
+use inf_runner::DODGE_BONUS;
+use inf_runner::POWER_DEFS;
+use inf_runner::TILE_SIZE;
 
 use std::thread::sleep;
 use std::time::{Duration, Instant, SystemTime};
 
 use sdl2::event::Event;
-use sdl2::image::LoadTexture;
 use sdl2::keyboard::Keycode;
 use sdl2::pixels::Color;
 use sdl2::rect::Point;
 use sdl2::rect::Rect;
+use sdl2::render::Canvas;
+use sdl2::render::RenderTarget;
+use sdl2::render::Texture;
+use sdl2::render::TextureCreator;
+use sdl2::render::TextureQuery;
+use sdl2::ttf::Font;
+use sdl2::video::WindowContext;
+
+use log::{debug, info, trace, warn};
 
 use rand::distributions::Distribution;
 use rand::distributions::Standard;
 use rand::Rng;
 
-const FPS: f64 = 60.0;
-const FRAME_TIME: f64 = 1.0 / FPS as f64;
+// Steps a statue or spring is telegraphed on the ground before it spawns
+const TELEGRAPH_STEPS: i32 = 60;
+// Practice runs show what's planned in a strip this wide at the right edge,
+// covering this many steps ahead
+const PREVIEW_W: i32 = 320;
+const PREVIEW_STEPS: i32 = 300;
+
+// Thickness of a gap's platform
+const PLATFORM_H: u32 = 24;
+// Width of the strips sloped ground is drawn in
+const TERRAIN_STRIP_W: usize = 4;
+
+// Where the companion drone hovers, relative to the player's top left corner
+const DRONE_OFFSET: (i32, i32) = (-90, -80);
+
+// Lives at the start of a run, medium obstacle hits cost one
+const START_LIVES: i32 = 3;
+// Steps after losing a life where further hits are ignored
+const HIT_COOLDOWN_STEPS: u32 = 90;
+// Steps the value of the last coin or bonus stays on screen
+const COIN_VALUE_STEPS: u32 = 60;
+// Extra points per coin for each coin in the current combo, up to MAX_COMBO
+const COMBO_BONUS: i32 = 10;
+const MAX_COMBO: i32 = 10;
+// Points for jumping clean over a golden statue
+const GOLDEN_VAULT_BONUS: i32 = 5000;
+// Motes sent from an obstacle passed without touching it, and from a vault
+const DODGE_MOTES: usize = 8;
+const VAULT_MOTES: usize = 24;
+// Points for passing through a biome gate, plus more for every coin in the
+// combo at the time
+const GATE_BONUS: i32 = 10000;
+const GATE_COMBO_BONUS: i32 = 1000;
+const GATE_MOTES: usize = 32;
+// Steps between coins falling during a coin rain, and steps before a settled
+// one is gone that it starts blinking
+const COIN_RAIN_EVERY: u32 = 8;
+const COIN_BLINK_STEPS: u32 = 30;
+// Earthquakes: how many pixels the camera shakes and how many steps between
+// falling debris, at no difficulty and at full, and how big debris is
+const QUAKE_SHAKE: (f64, f64) = (4.0, 10.0);
+const DEBRIS_EVERY: (f64, f64) = (50.0, 20.0);
+const DEBRIS_SIZE: u32 = 60;
+// Center of the HUD score counter, where score motes fly to
+const SCORE_HUD_CENTER: (i32, i32) = (60, 35);
+// Steps the score counter stays enlarged after motes land in it
+const SCORE_PULSE_STEPS: u32 = 8;
+// A banner and stinger mark every multiple of this score
+const MILESTONE_EVERY: i32 = 10000;
+const MILESTONE_BANNER_STEPS: u32 = 120;
+const PUMP_BANNER_STEPS: u32 = 45;
+const LANDING_BANNER_STEPS: u32 = 45;
+// While a power is running out, a tick plays and its HUD icon blinks this
+// often, in steps
+const POWER_TICK_STEPS: u32 = 15;
+const POWER_BLINK_STEPS: u32 = 8;
+// How much wider a lantern makes the light
+const LANTERN_LIGHT_SCALE: f64 = 1.8;
+// Frames the game freezes for on a big impact, unless motion is reduced
+const HIT_STOP_FRAMES: u32 = 4;
+// Seconds R has to be held to restart a run on the spot
+const QUICK_RESTART_SECS: f64 = 1.0;
+// Seconds the path of a saved bug report stays on screen
+const REPORT_NOTICE_SECS: f64 = 4.0;
+// Steps between the game ending and leaving the run
+const GAME_OVER_STEPS: i32 = 120;
+// Replay playback speeds, and how far the seek keys jump in seconds
+const PLAYBACK_SPEEDS: [f64; 3] = [0.5, 1.0, 2.0];
+const SEEK_SECS: u32 = 5;
+// Most steps simulated in one frame while seeking, keeps the window responsive
+const MAX_SEEK_STEPS: u32 = 600;
+// Player poses recorded per second for ghosts, unless the config says otherwise
+const GHOST_SAMPLE_RATE: f64 = 10.0;
+const GHOST_ALPHA: u8 = 110;
+// Seconds between flags marking where the personal best was
+const PACE_MARK_SECS: f64 = 30.0;
 
 const CAM_H: u32 = 720;
 const CAM_W: u32 = 1280;
-pub const TILE_SIZE: u32 = 100;
 
-// Background sine wave stuff
-const IND_BACKGROUND_MID: usize = 0;
-const IND_BACKGROUND_BACK: usize = 1;
-const BG_CURVES_SIZE: usize = CAM_W as usize / 10;
+// One hill column every BG_COLUMN_W pixels, enough to cover the widest view
+const BG_COLUMN_W: usize = 10;
+const BG_CURVES_SIZE: usize = MAX_VIEW_W as usize / BG_COLUMN_W;
+// Widest the view gets on ultrawide windows, see layout::MAX_ASPECT
+const MAX_VIEW_W: u32 = (CAM_H as f64 * layout::MAX_ASPECT) as u32;
+// Scale the world is drawn at with supersampling on, before it's scaled back
+// down to the window
+const SUPERSAMPLE: u32 = 2;
 // const BUFF_LENGTH: usize = CAM_W as usize / 4;
 
-// Bounds to keep the player within
-// Used for camera postioning
+// Height the player starts at, camera bounds come from CameraConfig
 const TERRAIN_UPPER_BOUND: i32 = 2 * TILE_SIZE as i32;
-const TERRAIN_LOWER_BOUND: i32 = CAM_H as i32 - TERRAIN_UPPER_BOUND;
-const PLAYER_X: i32 = 2 * TILE_SIZE as i32;
 
-// Max total number of coins, obstacles, and powers that can exist at
-// once. Could be split up later for more complicated procgen
-const MAX_NUM_OBJECTS: i32 = 10;
+// Seed of the attract demo, picked for a good variety early on
+const DEMO_SEED: u64 = 1666;
+
+pub struct Runner {
+    rng: GameRng,       // Seeds each new run, one draw per run
+    config: GameConfig, // What the next run starts with, used up by that run
+    bot: bool,          // Every run is played by the autopilot and restarts on its own
+}
+
+impl Runner {
+    // Runner whose runs are all derived from the given generator
+    pub fn new(rng: GameRng) -> Runner {
+        Runner {
+            rng,
+            config: GameConfig::default(),
+            bot: false,
+        }
+    }
+
+    // Soak testing: the autopilot plays every run and a new one starts right
+    // after each game over
+    pub fn set_bot(&mut self, bot: bool) {
+        self.bot = bot;
+    }
 
-pub struct Runner;
+    // Settings for the next run: its seed, a share code to race, or whether
+    // it's the attract demo
+    pub fn set_config(&mut self, config: GameConfig) {
+        self.config = config;
+    }
+}
 
 impl Game for Runner {
     fn init() -> Result<Self, String> {
-        Ok(Runner {})
+        Ok(Runner::new(GameRng::from_entropy()))
     }
 
     fn run(&mut self, core: &mut SDLCore) -> Result<GameState, String> {
@@ -73,73 +261,80 @@ impl Game for Runner {
         let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
 
         // Font
-        let mut font = ttf_context.load_font("./assets/DroidSansMono.ttf", 128)?;
+        let mut font = assets::load_font(&ttf_context, 128)?;
         font.set_style(sdl2::ttf::FontStyle::BOLD);
 
         // Load in all textures
         let texture_creator = core.wincan.texture_creator();
-        let tex_bg = texture_creator.load_texture("assets/bg.png")?;
-        let tex_sky = texture_creator.load_texture("assets/sky.png")?;
-        let tex_grad = texture_creator.load_texture("assets/sunset_gradient.png")?;
-
-        let tex_statue = texture_creator.load_texture("assets/obstacles/statue.png")?;
-        let tex_balloon = texture_creator.load_texture("assets/obstacles/balloon.png")?;
-        let tex_chest = texture_creator.load_texture("assets/obstacles/box.png")?;
-        let tex_coin = texture_creator.load_texture("assets/obstacles/coin.png")?;
-        let tex_powerup = texture_creator.load_texture("assets/obstacles/powerup.png")?;
-
-        let tex_speed = texture_creator.load_texture("assets/powers/speed.png")?;
-        let tex_multiplier = texture_creator.load_texture("assets/powers/multiplier.png")?;
-        let tex_bouncy = texture_creator.load_texture("assets/powers/bouncy.png")?;
-        let tex_floaty = texture_creator.load_texture("assets/powers/floaty.png")?;
-        let tex_shield = texture_creator.load_texture("assets/powers/shield.png")?;
-
-        let tex_player = texture_creator.load_texture("assets/player/player.png")?;
-        let tex_shielded = texture_creator.load_texture("assets/player/shielded_player.png")?;
-        let tex_winged = texture_creator.load_texture("assets/player/winged_player.png")?;
-        let tex_springed = texture_creator.load_texture("assets/player/bouncy_player.png")?;
-        let tex_fast = texture_creator.load_texture("assets/player/speed_player.png")?;
-
-        let tex_resume = texture_creator
+        let mut textures = assets::TextureCache::new(&texture_creator);
+        let tex_bg = textures.get(&core.assets, "assets/bg.png")?;
+        let tex_sky = textures.get(&core.assets, "assets/sky.png")?;
+        let tex_grad = textures.get(&core.assets, "assets/sunset_gradient.png")?;
+
+        let tex_statue = textures.get(&core.assets, "assets/obstacles/statue.png")?;
+        // Tinted, so it's a copy of its own
+        let mut tex_golden_statue = core.assets.texture(&texture_creator, "assets/obstacles/statue.png")?;
+        tex_golden_statue.set_color_mod(255, 200, 40);
+        let tex_balloon = textures.get(&core.assets, "assets/obstacles/balloon.png")?;
+        let tex_chest = textures.get(&core.assets, "assets/obstacles/box.png")?;
+        let tex_coin = textures.get(&core.assets, "assets/obstacles/coin.png")?;
+
+        // Power icons, indexed by PowerType::index
+        let tex_powers = POWER_DEFS
+            .iter()
+            .map(|def| textures.get(&core.assets, def.icon))
+            .collect::<Result<Vec<_>, String>>()?;
+        let tex_light = lighting::light_texture(&texture_creator)?;
+
+        let tex_player = textures.get(&core.assets, "assets/player/player.png")?;
+        // Faded, so it's a copy of its own
+        let mut tex_ghost = core.assets.texture(&texture_creator, "assets/player/player.png")?;
+        tex_ghost.set_alpha_mod(GHOST_ALPHA);
+        let tex_shielded = textures.get(&core.assets, "assets/player/shielded_player.png")?;
+        let tex_winged = textures.get(&core.assets, "assets/player/winged_player.png")?;
+        let tex_springed = textures.get(&core.assets, "assets/player/bouncy_player.png")?;
+        let tex_fast = textures.get(&core.assets, "assets/player/speed_player.png")?;
+
+        let tex_demo = texture_creator
             .create_texture_from_surface(
                 &font
-                    .render("Escape/Space - Resume Play")
-                    .blended(Color::RGBA(119, 3, 252, 255))
+                    .render("DEMO")
+                    .blended(Color::RGBA(255, 255, 255, 255))
                     .map_err(|e| e.to_string())?,
             )
             .map_err(|e| e.to_string())?;
 
-        let tex_restart = texture_creator
+        let tex_assist = texture_creator
             .create_texture_from_surface(
                 &font
-                    .render("R - Restart game")
-                    .blended(Color::RGBA(119, 3, 252, 255))
+                    .render("ASSIST")
+                    .blended(Color::RGBA(255, 255, 255, 180))
                     .map_err(|e| e.to_string())?,
             )
             .map_err(|e| e.to_string())?;
 
-        let tex_main = texture_creator
+        let game_over_texture = texture_creator
             .create_texture_from_surface(
                 &font
-                    .render("M - Main menu")
-                    .blended(Color::RGBA(119, 3, 252, 255))
+                    .render("GAME OVER")
+                    .blended(Color::RGBA(255, 0, 0, 255))
                     .map_err(|e| e.to_string())?,
             )
             .map_err(|e| e.to_string())?;
 
-        let tex_quit = texture_creator
+        let tex_risk_banner = texture_creator
             .create_texture_from_surface(
                 &font
-                    .render("Q - Quit game")
-                    .blended(Color::RGBA(119, 3, 252, 255))
+                    .render("RISK")
+                    .blended(Color::RGBA(255, 255, 255, 255))
                     .map_err(|e| e.to_string())?,
             )
             .map_err(|e| e.to_string())?;
 
-        let game_over_texture = texture_creator
+        let tex_risk_hud = texture_creator
             .create_texture_from_surface(
                 &font
-                    .render("GAME OVER")
+                    .render("RISK ZONE x2")
                     .blended(Color::RGBA(255, 0, 0, 255))
                     .map_err(|e| e.to_string())?,
             )
@@ -153,847 +348,1691 @@ impl Game for Runner {
             &tex_player,
         );
 
-        let mut power_timer: i32 = 0; // Current powerup expires when it reaches 0
-        let mut coin_timer: i32 = 0; // Timer to show +coin_value
+        // Companion drone, once the profile has unlocked it
+        let mut progress = Progress::load(Profile::current().as_ref());
+        let mut drone = if progress.drone_unlocked() {
+            Some(Drone::new(
+                Point::new(player.x() + DRONE_OFFSET.0, player.y() + DRONE_OFFSET.1),
+                progress::drone_radius(progress.drone_level()),
+            ))
+        } else {
+            None
+        };
+
+        // Simulation time, every timer below runs out against it
+        let mut clock = SimClock::new();
+        let mut coin_timer = Timer::done(); // Shows +coin_value until it runs out
         let mut last_coin_val: i32 = 0; // Last collected coin's value
-
-        // Initialize ground / object vectors
-        let mut all_terrain: Vec<TerrainSegment> = Vec::new();
-        let mut all_obstacles: Vec<Obstacle> = Vec::new();
-        let mut all_coins: Vec<Coin> = Vec::new();
-        let mut all_powers: Vec<Power> = Vec::new(); // Refers to powers currently spawned on the
-                                                     // ground, not active powers
+        let mut coin_combo: i32 = 0; // Coins collected since the last obstacle hit
+        let mut coins_collected: i32 = 0; // By the player or the drone, this run
+        let mut golden_vaults: i32 = 0;
+        let mut motes = Motes::new(SCORE_HUD_CENTER);
+        // Scorches and skid marks on the ground, and whether this run's
+        // crash has left its scorch yet
+        let mut decals = Decals::new();
+        let mut scorched = false;
+        // The profile's trail and jump sound
+        let cosmetics = progress.cosmetics();
+        let mut trail = Trail::new(cosmetics.trail);
+        let mut animator = PlayerAnimator::new();
+        let mut score_pulse = Timer::done();
+        let mut lives: i32 = START_LIVES;
+        let mut hit_cooldown = Timer::done();
+        let mut death_cause: Option<DeathCause> = None;
 
         // Used to keep track of animation status
         let mut coin_anim: i32 = 0; // 60 frames of animation
 
-        // Score of an entire run
-        let mut total_score: i32 = 0;
+        // Score of an entire run, by where it came from
+        let mut score = Score::new();
 
         // let mut test_stepper = 0;
 
         let mut game_paused: bool = false;
         let mut initial_pause: bool = false;
+        // The pause menu, drawn over a still of the frame the run paused on
+        let mut pause_menu = PauseMenu::new();
+        let mut pause_backdrop: Option<Texture> = None;
         let mut game_over: bool = false;
 
         // Number of frames to delay the end of the game by for demonstrating player
         // collision this should be removed once the camera tracks the player
         // properly
-        let mut game_over_timer = 120;
+        let mut game_over_timer = GAME_OVER_STEPS;
 
         // FPS tracking
         let mut all_frames: i32 = 0;
         let mut last_raw_time;
         let mut last_measurement_time = Instant::now();
 
+        // Frame-time breakdown, shown on the debug overlay (F3)
+        let mut profiler = Profiler::new();
+        // Spawn density over the last minute, shown with the profiler
+        let mut spawn_heatmap = SpawnHeatmap::new();
+        let mut show_profiler: bool = false;
+
+        let config = Config::current();
+
+        // Spoken pause menu and run results, when accessibility speech is on
+        let mut announcer = Announcer::from_config(&config);
+
+        // Rendering runs at the configured cap, the simulation in fixed steps
+        let frame_cap = FrameCap::from_config(&config);
+
+        // Low-spec mode trims the background, see draw_background
+        let quality = Quality::from_config(&config);
+        let settings = Settings::from_config(&config);
+        let mut sound = Audio::open(core, settings.volume);
+        let mut show_inputs = settings.show_inputs;
+        let mut input_display = InputDisplay::new();
+        let mut next_milestone = MILESTONE_EVERY;
+        // Banners are shown until their timer runs out
+        let mut milestone_banner: Option<(i32, Timer)> = None; // Score shown
+        let mut gate_banner: Option<(i32, Timer)> = None; // Bonus shown
+        let mut events = EventScheduler::new();
+        let mut event_banner: Option<(RandomEvent, Timer)> = None;
+        let mut all_debris: Vec<Debris> = Vec::new();
+        let mut debris_timer = clock.timer(0);
+        let mut quake_shake: i32 = 0; // Camera offset the shake added last step
+        let mut callout: Option<(&Callout, Timer)> = None; // Obstacle introduced
+        let mut pump_banner: Option<(bool, Timer)> = None; // Whether it was perfect
+        let mut landing_banner: Option<(LandingGrade, Timer)> = None; // Grade shown
+
+        // Decays scoring while the player isn't making forward progress
+        let mut stall = StallRule::new(StallConfig::from_config(&config));
+        let mut bg_cache = if quality.background_interval > 1 {
+            Some(
+                texture_creator
+                    .create_texture_target(None, MAX_VIEW_W, CAM_H)
+                    .map_err(|e| e.to_string())?,
+            )
+        } else {
+            None
+        };
+        let mut bg_frame: i32 = 0;
+
         // Used to transition to credits or back to title screen
         let mut next_status = GameStatus::Main;
 
-        // Object spawning vars
-        let mut spawn_timer: i32 = 500; // Can spawn a new object when it reaches 0
+        // Keyboard state, polled once per simulation step or paused frame
+        let mut input = InputState::new();
+
+        // Whether the player is inside a risk zone this step
+        let mut in_risk_zone: bool = false;
 
         /* ~~~~~~~~ Stuff for background sine waves ~~~~~~~~~~~~~~ */
         // Background & sine wave vars
         let mut bg_buff = 0;
         let mut bg_tick = 0;
-        let mut buff_1: usize = 0;
-        let mut buff_2: usize = 0;
-        // Perlin noise curves the player can't interact with, for visuals only
-        // Use IND_BACKGROUND_BACK and IND_BACKGROUND_MID
-        let mut background_curves: [[i16; BG_CURVES_SIZE]; 2] = [[0; BG_CURVES_SIZE]; 2];
-
-        // Rand thread to be utilized within runner
-        let mut rng = rand::thread_rng();
-
-        // Frequency control modifier for background sine waves
-        let freq: f32 = rng.gen::<f32>() * 1000.0 + 100.0;
-
-        // Amplitude control modifiers for background sine waves
-        let amp_1: f32 = rng.gen::<f32>() * 4.0 + 1.0;
-        let amp_2: f32 = rng.gen::<f32>() * 2.0 + amp_1;
-
-        // Pre-Generate perlin curves for background hills
-        for i in 0..BG_CURVES_SIZE {
-            background_curves[IND_BACKGROUND_MID][i] =
-                proceduralgen::gen_perlin_hill_point((i + buff_1), freq, amp_1, 0.5, 600.0);
-            background_curves[IND_BACKGROUND_BACK][i] =
-                proceduralgen::gen_perlin_hill_point((i + buff_2), freq, amp_2, 1.0, 820.0);
-        }
-        /* ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~ */
 
-        // Perlin Noise init
-        let mut random: [[(i32, i32); 256]; 256] = [[(0, 0); 256]; 256];
-        for i in 0..random.len() - 1 {
-            for j in 0..random.len() - 1 {
-                random[i][j] = (rng.gen_range(0..256), rng.gen_range(0..256));
+        // Every random decision of this run comes from procgen's streams
+        // Replays and racing a share code reuse their seed, otherwise use the
+        // configured one or draw a fresh one
+        let GameConfig {
+            seed,
+            race,
+            demo,
+            replay,
+            replay_from,
+            ghost,
+            loadout,
+            practice,
+        } = std::mem::take(&mut self.config);
+        let restart_config = GameConfig {
+            race: race.clone(),
+            ghost: ghost.clone(),
+            loadout,
+            practice,
+            ..GameConfig::default()
+        };
+        // Modifiers come out of the coin bank as the run starts, replays play
+        // back whatever the recorded run had for free
+        let loadout = match replay.as_ref() {
+            Some(log) => log.loadout(),
+            None if demo => Loadout::default(),
+            None if loadout.cost() == 0 => loadout,
+            None if progress.spend_coins(loadout.cost()) => {
+                if let Err(e) = progress.save() {
+                    warn!("Could not save progress: {}", e);
+                }
+                info!("Bought modifiers {} for {} coins", loadout.encode(), loadout.cost());
+                loadout
             }
+            None => {
+                warn!("Not enough coins for modifiers {}, running without", loadout.encode());
+                Loadout::default()
+            }
+        };
+        player.set_speed_scale(loadout.speed_scale());
+        input.set_mirrored(loadout.is_mirrored());
+        // What the world is drawn into when it's mirrored or supersampled,
+        // at the supersampling scale
+        let world_scale: u32 = if settings.supersample { SUPERSAMPLE } else { 1 };
+        let world_target = if loadout.is_mirrored() || settings.supersample {
+            Some(
+                core.assets
+                    .render_target(&texture_creator, MAX_VIEW_W * world_scale, CAM_H * world_scale)?,
+            )
+        } else {
+            None
+        };
+        if loadout.has(Modifier::StartShield) {
+            player.set_power_up(Some(PowerType::Shield));
         }
-
-        // Initialize the starting terrain segments
-        // Rectangles
-        let mut init_curve_1: Vec<(i32, i32)> = vec![(0, CAM_H as i32 * 2 / 3)];
-        for i in 1..CAM_W {
-            init_curve_1.push((i as i32, CAM_H as i32 * 2 / 3));
-        }
-        let init_terrain_1 = TerrainSegment::new(
-            rect!(0, CAM_H as i32 * 2 / 3, CAM_W, CAM_H as i32 * 2 / 3),
-            init_curve_1,
-            0.0,
-            TerrainType::Grass,
-            Color::GREEN,
+        let run_seed: u64 = match (replay.as_ref().or(ghost.as_ref()), &race) {
+            (Some(log), _) => log.seed(),
+            (None, Some(code)) => code.seed(),
+            (None, None) if demo => DEMO_SEED,
+            (None, None) => seed.unwrap_or_else(|| self.rng.gen()),
+        };
+        let autopilot = Autopilot::new();
+        // F2 lets the autopilot take over mid-run, runs it helped with don't
+        // unlock anything
+        let mut assist = false;
+        // Practice runs don't count either
+        let mut assist_used = practice;
+        // When R started being held, and whether holding it ended the run
+        let mut restart_held: Option<Instant> = None;
+        let mut quick_restart = false;
+        // F9 saves a bug report once the frame is drawn, then says where
+        let mut capture_report = false;
+        let mut report_notice: Option<(String, Instant)> = None;
+        // Auto-jump only clears obstacles and leaves the rest to the player,
+        // so runs with it stay ranked but are flagged
+        let mut auto_jump = false;
+        // Terrain and everything on it, stepped along with the player
+        let mut world = World::new(
+            run_seed,
+            &clock,
+            Camera::new(CameraConfig::from_config(&config, CAM_H as i32)),
+            &tex_coin,
+            tex_powers.iter().map(|t| &**t).collect(),
         );
-        let mut init_curve_2: Vec<(i32, i32)> = vec![(CAM_W as i32, CAM_H as i32 * 2 / 3)];
-        for i in (CAM_W + 1)..(CAM_W * 2) {
-            init_curve_2.push((i as i32, CAM_H as i32 * 2 / 3));
+        info!("Starting run with seed {}", run_seed);
+        crash::begin_run(run_seed);
+        let mut splits = SplitRecorder::new();
+        let mut share_code: Option<String> = None;
+        // Where the personal best was every PACE_MARK_SECS, for plain runs
+        // that aren't already racing something
+        let mut pace_marks = match (&replay, &ghost, &race) {
+            (None, None, None) if !demo && !self.bot => progress
+                .best_run(loadout.board())
+                .map(|log| PaceMark::from_log(&log, (PACE_MARK_SECS * SIM_FPS) as u32))
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        // Every input by the step it landed on, signs the score at the end
+        let mut input_log = InputLog::new(run_seed);
+        input_log.set_loadout(loadout);
+        if settings.auto_jump && replay.is_none() && !demo && !self.bot {
+            auto_jump = true;
+            input_log.record(0, Input::AutoJump);
+        }
+
+        // A replay feeds its recorded inputs in place of the player's, the
+        // keyboard only controls playback
+        let mut playback = replay.map(Playback::new);
+        let mut playback_paused = false;
+        let mut playback_speed: usize = 1; // Index into PLAYBACK_SPEEDS
+        let mut seek_to = if replay_from > 0 { Some(replay_from) } else { None };
+        let mut replay_restart: Option<u32> = None; // Step to start the replay over from
+
+        // Poses are recorded every few steps for ghosts. Their height is kept
+        // without the camera's adjustments, which add up in camera_offset.
+        let ghost_every = (SIM_FPS
+            / config
+                .get_or("ghost_sample_rate", GHOST_SAMPLE_RATE)
+                .clamp(1.0, SIM_FPS))
+        .round() as u32;
+        let mut camera_offset: i32 = 0;
+        // How far the view has scrolled and been moved by the camera, and
+        // where it was when the step started. Frames draw the world between
+        // the two, and bodies between where they were and where they are, as
+        // far along as the part of a step the clock has left over.
+        let mut view_pos = (0.0, 0.0);
+        let mut prev_view_pos = view_pos;
+
+        // Purely visual randomness, from a stream of its own so the same
+        // seed looks the same in screenshots and replays
+        let mut rng = GameRng::new(run_seed).split(RngStream::Visuals);
+
+        // Hill layers the player can't interact with, for visuals only. The
+        // config can pin the biome, for trying one out.
+        let picked_biome = Biome::pick(&mut rng);
+        let mut biome = config.get("biome").and_then(Biome::from_key).unwrap_or(picked_biome);
+        let mut parallax = Parallax::new(biome, BG_CURVES_SIZE, &mut rng);
+        sound.set_biome(biome);
+        /* ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~ */
+
+        // The starting terrain gets decor like the rest
+        if quality.foreground {
+            for ground in world.terrain.iter_mut() {
+                ground.set_decor(decor::scatter(biome.def().decor, CAM_W as i32, &mut rng));
+            }
         }
-        let init_terrain_2 = TerrainSegment::new(
-            rect!(CAM_W, CAM_H as i32 * 2 / 3, CAM_W, CAM_H as i32 * 2 / 3),
-            init_curve_2,
-            0.0,
-            TerrainType::Grass,
-            Color::BLUE,
-        );
-        all_terrain.push(init_terrain_1);
-        all_terrain.push(init_terrain_2);
 
         /* ~~~~~~ Main Game Loop ~~~~~~ */
         'gameloop: loop {
             last_raw_time = Instant::now(); // FPS tracking
 
-            // Score collected in a single iteration of the game loop
-            let mut curr_step_score: i32 = 0;
+            // Wider windows see further ahead, everything spawns and is
+            // generated past the right edge of the view
+            core.fit_view()?;
+            let layout = core.layout();
+            let view_w = layout.width();
 
             /* ~~~~~~ Pausing Handler ~~~~~~ */
             if game_paused {
+                // Time spent paused isn't simulated
+                clock.stop();
+
+                input.begin_frame();
                 for event in core.event_pump.poll_iter() {
-                    match event {
-                        Event::Quit { .. }
-                        | Event::KeyDown {
-                            keycode: Some(Keycode::Q),
-                            ..
-                        } => {
-                            next_status = GameStatus::Credits;
-                            break 'gameloop;
-                        }
-                        Event::KeyDown { keycode: Some(k), .. } => match k {
-                            Keycode::Escape => {
-                                game_paused = false;
-                            }
-                            Keycode::R => {
-                                next_status = GameStatus::Game;
-                                break 'gameloop;
-                            }
-                            Keycode::M => {
-                                next_status = GameStatus::Main;
-                                break 'gameloop;
-                            }
-                            _ => {}
-                        },
-                        Event::KeyUp { keycode: Some(k), .. } => match k {
-                            Keycode::Space => {
-                                game_paused = false;
-                            }
-                            _ => {}
-                        },
-                        _ => {}
-                    }
-                } // End Loop
+                    input.handle(&event);
+                    pause_menu.handle(&event);
+                }
+                if input.quit_requested() {
+                    next_status = GameStatus::Quit;
+                    break 'gameloop;
+                }
 
-                // Draw pause screen once due to BlendMode setting
+                // The frame the run paused on is still there to be kept
                 if initial_pause {
+                    pause_backdrop = Some(core.snapshot(&texture_creator)?);
+                    pause_menu = PauseMenu::new();
+                    initial_pause = false;
+                }
+
+                let mut picked = pause_menu.shortcut(&input);
+                if pause_menu.needs_redraw() {
+                    if let Some(backdrop) = pause_backdrop.as_ref() {
+                        core.wincan.copy(backdrop, None, rect!(0, 0, view_w, CAM_H))?;
+                    }
                     // Pause screen background, semitransparent grey
                     core.wincan.set_draw_color(Color::RGBA(0, 0, 0, 128));
-                    core.wincan.fill_rect(rect!(0, 0, CAM_W, CAM_H))?;
-
-                    // Draw pause screen text
-                    core.wincan.copy(&tex_resume, None, Some(rect!(100, 100, 1000, 125)))?;
-                    core.wincan.copy(&tex_restart, None, Some(rect!(100, 250, 700, 125)))?;
-                    core.wincan.copy(&tex_main, None, Some(rect!(100, 400, 600, 125)))?;
-                    core.wincan.copy(&tex_quit, None, Some(rect!(100, 550, 600, 125)))?;
+                    core.wincan.fill_rect(rect!(0, 0, view_w, CAM_H))?;
+
+                    let (chosen, focused) = pause_menu.draw(
+                        &mut core.wincan,
+                        &texture_creator,
+                        &font,
+                        settings.ui_scale(),
+                        (100, 150),
+                    )?;
+                    if let Some(label) = focused {
+                        announcer.announce(&label);
+                    }
+                    picked = picked.or(chosen);
+
+                    // How the run is going so far
+                    let stats = RunStats {
+                        seed: run_seed,
+                        score: score.total(),
+                        ledger: score.ledger(),
+                        coins: coins_collected,
+                        golden_vaults,
+                        distance: splits.distance(),
+                        steps: clock.step(),
+                        score_scale: loadout.score_scale(),
+                        ..RunStats::default()
+                    };
+                    let mission = if progress.drone_unlocked() {
+                        format!(
+                            "Golden vaults {} all time",
+                            progress.golden_vaults() + golden_vaults as u32
+                        )
+                    } else {
+                        format!("Drone {} / {}", score.total(), progress::DRONE_UNLOCK_SCORE)
+                    };
+                    draw_run_info(core, &font, &texture_creator, &stats, &mission)?;
 
                     core.wincan.present();
-                    initial_pause = false;
+                }
+
+                match picked {
+                    Some(PauseOption::Resume) => {
+                        game_paused = false;
+                        pause_backdrop = None;
+                        announcer.announce("Resumed");
+                    }
+                    Some(PauseOption::Restart) => {
+                        next_status = GameStatus::Game;
+                        break 'gameloop;
+                    }
+                    Some(PauseOption::MainMenu) => {
+                        next_status = GameStatus::Main;
+                        break 'gameloop;
+                    }
+                    Some(PauseOption::Quit) => {
+                        next_status = GameStatus::Quit;
+                        break 'gameloop;
+                    }
+                    None => {}
                 }
             }
             // Normal unpaused game state
             else {
-                // End game loop, 'player has lost' state
-                if game_over {
-                    game_over_timer -= 1; // Animation buffer
-                    if game_over_timer == 0 {
+                // Playback controls are read once a frame
+                if playback.is_some() {
+                    input.poll(&mut core.event_pump);
+                    if input.quit_requested() {
+                        next_status = GameStatus::Quit;
+                        break 'gameloop;
+                    }
+                    if input.pressed(Action::Menu) {
+                        next_status = GameStatus::Main;
                         break 'gameloop;
                     }
+                    if input.pressed(Action::Restart) {
+                        replay_restart = Some(0);
+                        break 'gameloop;
+                    }
+                    // Going back means simulating again from the start
+                    if input.pressed(Action::SeekBack) {
+                        replay_restart = Some(clock.step().saturating_sub(SEEK_SECS * SIM_FPS as u32));
+                        break 'gameloop;
+                    }
+                    if input.pressed(Action::SeekForward) {
+                        seek_to = Some(clock.step() + SEEK_SECS * SIM_FPS as u32);
+                    }
+                    if input.pressed(Action::Jump) {
+                        playback_paused = !playback_paused;
+                        announcer.announce(if playback_paused {
+                            "Replay paused"
+                        } else {
+                            "Replay playing"
+                        });
+                    }
+                    if input.pressed(Action::Slower) {
+                        playback_speed = playback_speed.saturating_sub(1);
+                    }
+                    if input.pressed(Action::Faster) {
+                        playback_speed = (playback_speed + 1).min(PLAYBACK_SPEEDS.len() - 1);
+                    }
+                    if input.pressed(Action::Pause) {
+                        game_paused = true;
+                        initial_pause = true;
+                    }
+                    if input.pressed(Action::Profiler) {
+                        show_profiler = !show_profiler;
+                    }
+                    if input.pressed(Action::InputDisplay) {
+                        show_inputs = !show_inputs;
+                    }
+                    if input.pressed(Action::BugReport) {
+                        capture_report = true;
+                    }
                 }
 
-                //  Get ground point at player and TILE_SIZE ahead of player
-                let curr_ground_point: Point = get_ground_coord(&all_terrain, PLAYER_X);
-                let next_ground_point: Point = get_ground_coord(&all_terrain, PLAYER_X + TILE_SIZE as i32);
-                let angle = ((next_ground_point.y() as f64 - curr_ground_point.y() as f64) / (TILE_SIZE as f64)).atan();
+                // Advance the simulation in fixed steps for however much time
+                // passed since the last frame. Input is polled by the first step,
+                // later steps in the same frame find the queue empty.
+                let speed = if playback.is_some() {
+                    PLAYBACK_SPEEDS[playback_speed]
+                } else {
+                    // Slowed while a callout introduces an obstacle
+                    callout.map_or(1.0, |(_, shown)| tutorial::time_scale(shown.steps_left(&clock)))
+                } * loadout.sim_speed();
+                clock.feed(Instant::now(), speed);
+                if playback_paused {
+                    clock.discard();
+                }
+                // Seeking runs as many steps as it can each frame until it
+                // gets there
+                if let Some(target) = seek_to {
+                    if clock.step() < target && !game_over {
+                        clock.queue_steps((target - clock.step()).min(MAX_SEEK_STEPS));
+                    } else {
+                        seek_to = None;
+                    }
+                }
 
-                /* ~~~~~~ Handle Input ~~~~~~ */
-                let mut keypress_moment: SystemTime;
-                for event in core.event_pump.poll_iter() {
-                    match event {
-                        Event::Quit { .. } => break 'gameloop,
-                        Event::KeyDown { keycode: Some(k), .. } => match k {
-                            Keycode::W | Keycode::Up | Keycode::Space => {
-                                if player.is_jumping() {
-                                    player.resume_flipping();
-                                } else if !player.jumpmoment_lock() {
-                                    keypress_moment = SystemTime::now();
-                                    player.set_jumpmoment(keypress_moment);
-                                }
+                while !game_paused && clock.tick() {
+                    // Set by a big impact this step
+                    let mut hit_stop = false;
+                    prev_view_pos = view_pos;
+                    player.remember_pos();
+                    for o in world.obstacles.iter_mut() {
+                        o.remember_pos();
+                    }
+                    for c in world.coins.iter_mut() {
+                        c.remember_pos();
+                    }
+
+                    // End game loop, 'player has lost' state
+                    if game_over {
+                        if game_over_timer == GAME_OVER_STEPS {
+                            sound.stop_music();
+                            sound.stinger(Stinger::GameOver);
+                        }
+                        game_over_timer -= 1; // Animation buffer
+                        if game_over_timer == 0 {
+                            announcer.announce(&format!("Game over. Score {}", score.total()));
+                            break 'gameloop;
+                        }
+                    }
+
+                    profiler.begin(Scope::Physics);
+
+                    // Ground under the player, before input moves them
+                    let under = world.sense(&mut player);
+                    let curr_ground_point = under.ground;
+                    let was_in_risk_zone = in_risk_zone;
+                    in_risk_zone = under.risk_zone;
+                    if in_risk_zone != was_in_risk_zone {
+                        crash::event(if in_risk_zone {
+                            "Entered risk zone"
+                        } else {
+                            "Left risk zone"
+                        });
+                    }
+
+                    /* ~~~~~~ Handle Input ~~~~~~ */
+                    profiler.begin(Scope::Input);
+                    // This step's inputs, from the keyboard or the replay.
+                    // Presses come before releases, a tap that starts and
+                    // ends within one frame still jumps.
+                    let mut step_inputs = Vec::new();
+                    match playback.as_mut() {
+                        Some(playback) => step_inputs = playback.take(clock.step()),
+                        None => {
+                            input.poll(&mut core.event_pump);
+                            if input.quit_requested() {
+                                next_status = GameStatus::Quit;
+                                break 'gameloop;
+                            }
+                            // Any input ends the demo
+                            if demo && input.any_pressed() {
+                                next_status = GameStatus::Main;
+                                break 'gameloop;
+                            }
+                            if input.pressed(Action::Jump) {
+                                step_inputs.push(Input::JumpPress);
+                            }
+                            // Rounded to what the log keeps, so a replay matches
+                            if let Some(hold) = input.hold_time(Action::Jump) {
+                                step_inputs.push(Input::JumpRelease(hold.as_millis() as u32));
                             }
-                            Keycode::Escape => {
+                            if input.pressed(Action::FastFall) {
+                                step_inputs.push(Input::FastFall);
+                            }
+                            if input.pressed(Action::Assist) {
+                                step_inputs.push(Input::Assist);
+                            }
+                            if input.pressed(Action::Pause) {
                                 game_paused = true;
                                 initial_pause = true;
+                                crash::event("Paused");
+                                announcer.announce("Paused. Up and Down to pick, Enter to choose. Resume");
                             }
-                            _ => {}
-                        },
-                        Event::KeyUp { keycode: Some(k), .. } => match k {
-                            Keycode::W | Keycode::Up | Keycode::Space => {
-                                let jump_moment: SystemTime = player.jump_moment();
-                                player.jump(
-                                    curr_ground_point,
-                                    SystemTime::now().duration_since(jump_moment).unwrap(),
-                                );
+                            if input.pressed(Action::Profiler) {
+                                show_profiler = !show_profiler;
+                            }
+                            if input.pressed(Action::InputDisplay) {
+                                show_inputs = !show_inputs;
+                            }
+                            if input.pressed(Action::BugReport) {
+                                capture_report = true;
+                            }
+                        }
+                    }
+                    for &step_input in step_inputs.iter() {
+                        match step_input {
+                            Input::JumpPress => input_display.press(Action::Jump),
+                            Input::JumpRelease(_) => input_display.release(Action::Jump),
+                            Input::FastFall => {
+                                input_display.press(Action::FastFall);
+                                input_display.release(Action::FastFall);
+                            }
+                            Input::Assist | Input::AutoJump => {}
+                        }
+                    }
+                    input_display.step();
+                    for &step_input in step_inputs.iter() {
+                        input_log.record(clock.step(), step_input);
+                        match step_input {
+                            Input::JumpPress => {
+                                if player.mash() {
+                                    sound.play(&audio::WHOOSH);
+                                    crash::event("Mashed free of mud");
+                                }
+                                if player.is_jumping() {
+                                    player.resume_flipping();
+                                } else {
+                                    player.start_jump_charge();
+                                }
+                            }
+                            // Short taps hop, long holds launch
+                            Input::JumpRelease(hold_ms) => {
+                                player.release_jump(curr_ground_point, Duration::from_millis(hold_ms as u64));
                                 player.stop_flipping();
                             }
-                            _ => {}
-                        },
-                        _ => {}
+                            Input::FastFall => {
+                                if player.fast_fall() {
+                                    sound.play(&audio::WHOOSH);
+                                }
+                            }
+                            Input::Assist => {
+                                assist = !assist;
+                                crash::event(if assist { "Assist on" } else { "Assist off" });
+                                assist_used |= assist;
+                                announcer.announce(if assist { "Assist on" } else { "Assist off" });
+                            }
+                            Input::AutoJump => auto_jump = true,
+                        }
                     }
-                }
 
-                //Power handling
-                if power_timer == 0 {
-                    power_timer -= 1;
-                    player.set_power_up(None);
-                } else if power_timer > 0 {
-                    power_timer -= 1;
-                }
-
-                // Apply bouncy shoes, if applicable
-                // Effectively just repeated jumps, independent of player input
-                if let Some(PowerType::BouncyShoes) = player.power_up() {
-                    if !player.is_jumping() {
-                        player.jump(curr_ground_point, Duration::new(1111, 0));
+                    let hazards = world.hazards();
+                    if demo || self.bot || assist {
+                        if let Some(hold) = autopilot.decide(&player, &world.obstacles, &hazards, |x| {
+                            get_ground_coord(&world.terrain, x).y()
+                        }) {
+                            player.jump(curr_ground_point, hold);
+                        }
+                    } else if auto_jump {
+                        if let Some(hold) = autopilot.auto_jump(&player, &world.obstacles, &hazards, |x| {
+                            get_ground_coord(&world.terrain, x).y()
+                        }) {
+                            player.jump(curr_ground_point, hold);
+                        }
                     }
-                }
 
-                /* ~~~~~~ Handle Player Collisions ~~~~~~ */
+                    // Earthquakes shake the camera, harder the harder the run
+                    // has got, unless motion is reduced
+                    let quaking = events.active() == Some(RandomEvent::Earthquake);
+                    let shake = if quaking && !settings.reduce_motion {
+                        let t = proceduralgen::difficulty(score.total());
+                        let amp = QUAKE_SHAKE.0 + (QUAKE_SHAKE.1 - QUAKE_SHAKE.0) * t;
+                        rng.gen_range(-amp..=amp) as i32
+                    } else {
+                        0
+                    };
+
+                    // Physics, collisions, spawning, new terrain, scrolling
+                    // and the camera, the same as the soak test runs
+                    let report = world.step(
+                        &mut player,
+                        &under,
+                        &StepContext {
+                            clock: &clock,
+                            game_over,
+                            score: score.total(),
+                            density: loadout.spawn_density(),
+                            lanterns: biome.def().light_radius.is_some(),
+                            view_w,
+                            shake: shake - quake_shake,
+                        },
+                        &mut profiler,
+                    );
+                    quake_shake = shake;
+                    let travel_update = report.travel;
+                    let camera_adj_y = report.camera_adj;
+
+                    /* ~~~~~~ React to the step ~~~~~~ */
+                    // Shields don't absorb anything past this point
+                    if report.power_expired == Some(PowerType::Shield) {
+                        sound.stinger(Stinger::ShieldBreak);
+                        hit_stop = true;
+                    } else if report.power_expired.is_none()
+                        && player.is_power_expiring()
+                        && player.power_steps_left().is_multiple_of(POWER_TICK_STEPS)
+                    {
+                        sound.play(&audio::TICK);
+                    }
 
-                // If the player doesn't land on ther feet, end game
-                if !Physics::check_player_upright(&player, angle, curr_ground_point) {
-                    game_over = true;
-                }
+                    // However the jump started, it makes the same sound
+                    if report.jumped {
+                        sound.play(cosmetics.jump_sound.def().notes);
+                    }
 
-                // Check through all collisions with obstacles
-                // End game if crash occurs
-                for o in all_obstacles.iter_mut() {
-                    if Physics::check_collision(&mut player, o) && player.collide_obstacle(o) {
+                    // If the player doesn't land on ther feet, end game
+                    if report.toppled {
                         game_over = true;
+                        death_cause.get_or_insert(DeathCause::BadLanding);
                     }
-                }
 
-                // Check for coin collection
-                // Add to score if collected
-                // Remove coins if player collects them
-                let mut to_remove_ind: i32 = -1;
-                let mut counter = 0;
-                for c in all_coins.iter_mut() {
-                    if Physics::check_collision(&mut player, c) {
-                        if player.collide_coin(c) {
-                            to_remove_ind = counter;
-                            curr_step_score += c.value(); //increments the
-                                                          // score based on the
-                                                          // coins value
-
-                            last_coin_val = c.value();
-                            coin_timer = 60; // Time to show last_coin_val on
-                                             // screen
+                    // Inside a risk zone any impact ends the game, lives and
+                    // shields don't help. Out on the open course grazes break
+                    // the coin combo, hits cost a life and severe hits end the
+                    // game.
+                    hit_stop |= report.launched;
+                    for &(impact, obstacle_type) in report.hits.iter() {
+                        if in_risk_zone {
+                            game_over = true;
+                            death_cause.get_or_insert(DeathCause::RiskZone(obstacle_type));
+                            continue;
+                        }
+                        match impact {
+                            Impact::None => {}
+                            Impact::Graze => coin_combo = 0,
+                            Impact::Hit if !hit_cooldown.is_done(&clock) => coin_combo = 0,
+                            Impact::Hit => {
+                                coin_combo = 0;
+                                lives -= 1;
+                                hit_cooldown = clock.timer(HIT_COOLDOWN_STEPS);
+                                if lives <= 0 {
+                                    game_over = true;
+                                    death_cause.get_or_insert(DeathCause::OutOfLives(obstacle_type));
+                                }
+                            }
+                            Impact::Severe => {
+                                game_over = true;
+                                death_cause.get_or_insert(DeathCause::Crash(obstacle_type));
+                            }
                         }
-                        continue;
                     }
-                    counter += 1;
-                }
-                if to_remove_ind != -1 {
-                    all_coins.remove(to_remove_ind as usize);
-                }
 
-                // Check for powerup pickups
-                // Apply to player and begin countdown if picked up
-                let mut to_remove_ind: i32 = -1;
-                let mut counter = 0;
-                for p in all_powers.iter_mut() {
-                    if Physics::check_collision(&mut player, p) {
-                        if player.collide_power(p) {
-                            to_remove_ind = counter;
-                            power_timer = 360;
+                    // Hazard strips cost a life on contact, shield or not
+                    if let Some(hazard) = report.hazard {
+                        coin_combo = 0;
+                        if hit_cooldown.is_done(&clock) {
+                            lives -= 1;
+                            hit_cooldown = clock.timer(HIT_COOLDOWN_STEPS);
+                            if lives <= 0 {
+                                game_over = true;
+                                death_cause.get_or_insert(DeathCause::Hazard(hazard));
+                            }
                         }
-                        continue;
                     }
-                    counter += 1;
-                }
-                if to_remove_ind != -1 {
-                    all_powers.remove(to_remove_ind as usize);
-                }
-
-                /* ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~ */
 
-                /* ~~~~~~ Handle Forces from Physics and move sprites ~~~~~~ */
+                    // Missing the platform over a gap ends the run
+                    if !game_over && report.fell {
+                        game_over = true;
+                        death_cause.get_or_insert(DeathCause::Fell);
+                        crash::event("Fell into a gap");
+                    }
 
-                // Apply forces on player
-                let current_power = player.power_up();
-                let curr_terrain_type = get_ground_type(&all_terrain, PLAYER_X); //for physics
+                    // Big impacts freeze the game for a moment, but not while
+                    // seeking through a replay
+                    if hit_stop && !settings.reduce_motion && seek_to.is_none() {
+                        clock.hit_stop(HIT_STOP_FRAMES);
+                    }
+                    if game_over && !scorched {
+                        decals.add(player.center().x(), DecalKind::Scorch);
+                        scorched = true;
+                    }
 
-                Physics::apply_terrain_forces(
-                    // Gravity, normal, and friction
-                    &mut player,
-                    angle,
-                    curr_ground_point,
-                    curr_terrain_type,
-                    current_power,
-                );
-                Physics::apply_skate_force(&mut player, angle, curr_ground_point); // Propel forward
-
-                //update player attributes
-                player.update_vel(game_over);
-                player.update_pos(curr_ground_point, angle, game_over);
-                player.flip();
-
-                //DEBUG PLAYER (Plz dont delete, just comment out)
-                //println!("A-> vx:{} ax:{}, vy:{}
-                // ay:{}",player.vel_x(),player.accel_x(),player.vel_y(),player.accel_y());
-
-                player.reset_accel();
-
-                //DEBUG PLAYER (Plz dont delete, just comment out)
-                //println!("B-> vx:{} ax:{}, vy:{}
-                // ay:{}",player.vel_x(),player.vel_y(),player.accel_x(),player.accel_y());
-
-                // apply forces to obstacles
-                for o in all_obstacles.iter_mut() {
-                    // Only actually apply forces after a collision occurs
-                    if o.collided() {
-                        let object_ground = get_ground_coord(&all_terrain, o.x());
-                        let object_terrain_type = get_ground_type(&all_terrain, o.x());
-                        // Very small friction coefficient because there's no
-                        // "skate force" to counteract friction
-                        Physics::apply_terrain_forces(o, angle, object_ground, object_terrain_type, None);
-                        o.update_vel(false);
-                        o.update_pos(object_ground, angle, game_over);
+                    if !game_over {
+                        for &center in report.vaults.iter() {
+                            score.add(ScoreSource::Tricks, GOLDEN_VAULT_BONUS);
+                            golden_vaults += 1;
+                            last_coin_val = GOLDEN_VAULT_BONUS;
+                            coin_timer = clock.timer(COIN_VALUE_STEPS);
+                            if quality.particles {
+                                motes.emit(center.into(), VAULT_MOTES, Color::RGB(255, 200, 40));
+                            }
+                        }
+                        // Near misses send a few motes flying to the score
+                        for &center in report.dodges.iter() {
+                            score.add(ScoreSource::NearMiss, DODGE_BONUS);
+                            if quality.particles {
+                                motes.emit(center.into(), DODGE_MOTES, Color::RGB(255, 255, 255));
+                            }
+                        }
                     }
-                }
 
-                /* ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~ */
+                    // Coins are worth their value plus the current combo
+                    for &(value, center) in report.coins.iter() {
+                        let coin_val = value + coin_combo.min(MAX_COMBO) * COMBO_BONUS;
+                        score.add(ScoreSource::Coins, coin_val);
+                        coin_combo += 1;
+                        coins_collected += 1;
+                        sound.play_at(&audio::COIN, center.x() - player.center().x());
+                        last_coin_val = coin_val;
+                        coin_timer = clock.timer(COIN_VALUE_STEPS);
+                    }
 
-                // Generate new terrain / objects if player hasn't died
-                if !game_over {
-                    /* ~~~~~~ Object Generation ~~~~~~ */
+                    // The drone trails the player and grabs coins in its radius,
+                    // but leaves powers alone
+                    if let Some(drone) = drone.as_mut() {
+                        drone.camera_adj(0, camera_adj_y);
+                        drone.follow(Point::new(player.x() + DRONE_OFFSET.0, player.y() + DRONE_OFFSET.1));
+                        world.coins.retain(|c| {
+                            if drone.in_reach(c) && !c.collected() {
+                                score.add(ScoreSource::Coins, c.value());
+                                sound.play_at(&audio::COIN, c.center().x() - player.center().x());
+                                coins_collected += 1;
+                                last_coin_val = c.value();
+                                coin_timer = clock.timer(COIN_VALUE_STEPS);
+                                false
+                            } else {
+                                true
+                            }
+                        });
+                    }
 
-                    // Every 3 ticks, build a new front mountain segment
-                    if bg_tick % 3 == 0 {
-                        for i in 0..(BG_CURVES_SIZE as usize - 1) {
-                            background_curves[IND_BACKGROUND_MID][i] = background_curves[IND_BACKGROUND_MID][i + 1];
+                    if report.bonked {
+                        sound.play(&audio::BONK);
+                    }
+                    if let Some(pump) = report.pump {
+                        pump_banner = Some((pump.perfect, clock.timer(PUMP_BANNER_STEPS)));
+                    }
+                    // Perfect landings keep the coin combo going
+                    if let Some(grade) = report.landing {
+                        if grade == LandingGrade::Perfect {
+                            coin_combo += 1;
                         }
-                        buff_1 += 1;
-                        let chunk_1 = proceduralgen::gen_perlin_hill_point(
-                            ((BG_CURVES_SIZE - 1) as usize + buff_1),
-                            freq,
-                            amp_1,
-                            0.5,
-                            600.0,
-                        );
-                        background_curves[IND_BACKGROUND_MID][(BG_CURVES_SIZE - 1) as usize] = chunk_1;
-                    }
-
-                    // Every 5 ticks, build a new back mountain segment
-                    if bg_tick % 5 == 0 {
-                        for i in 0..(BG_CURVES_SIZE as usize - 1) {
-                            background_curves[IND_BACKGROUND_BACK][i] = background_curves[IND_BACKGROUND_BACK][i + 1];
+                        if grade == LandingGrade::Sloppy {
+                            decals.add(player.center().x(), DecalKind::Skid);
                         }
-                        buff_2 += 1;
-                        let chunk_2 = proceduralgen::gen_perlin_hill_point(
-                            ((BG_CURVES_SIZE - 1) as usize + buff_2),
-                            freq,
-                            amp_2,
-                            1.0,
-                            820.0,
-                        );
-                        background_curves[IND_BACKGROUND_BACK][(BG_CURVES_SIZE - 1) as usize] = chunk_2;
-                    }
-
-                    // Value spawn_timer is reset to upon spawning an object.
-                    // Decreases to increase spawn rates based on total_score.
-                    // These numbers could be terrible, we should mess around with it
-                    let min_spawn_gap = if total_score > 100000 {
-                        300 // Cap
-                    } else if total_score > 90000 {
-                        320
-                    } else if total_score > 80000 {
-                        340
-                    } else if total_score > 70000 {
-                        360
-                    } else if total_score > 60000 {
-                        380
-                    } else if total_score > 50000 {
-                        400
-                    } else if total_score > 40000 {
-                        420
-                    } else if total_score > 30000 {
-                        440
-                    } else if total_score > 20000 {
-                        460
-                    } else if total_score > 10000 {
-                        480
-                    } else {
-                        500 // Default
-                    };
+                        landing_banner = Some((grade, clock.timer(LANDING_BANNER_STEPS)));
+                    }
 
-                    // Choose new object to generate
-                    let mut new_object: Option<StaticObject> = None;
-                    let curr_num_objects = all_obstacles.len() + all_coins.len() + all_powers.len();
-                    let spawn_trigger = rng.gen_range(0..MAX_NUM_OBJECTS);
-
-                    if spawn_timer > 0 {
-                        spawn_timer -= 1;
-                    } else if spawn_trigger >= curr_num_objects as i32 {
-                        new_object = Some(proceduralgen::choose_static_object());
-                        spawn_timer = min_spawn_gap;
-                    } else if spawn_trigger < curr_num_objects as i32 {
-                        // Min spawn gap can be replaced with basically any value for this random
-                        // range. Smaller values will spawn objects more often
-                        spawn_timer = rng.gen_range(0..min_spawn_gap);
-                    }
-
-                    // Spawn new object
-                    match new_object {
-                        Some(StaticObject::Statue) => {
-                            let spawn_coord: Point = get_ground_coord(&all_terrain, (CAM_W as i32) - 1);
-                            let obstacle = Obstacle::new(
-                                p_rect!(spawn_coord.x, spawn_coord.y - TILE_SIZE as i32, TILE_SIZE, TILE_SIZE),
-                                50.0, // mass
-                                &tex_statue,
-                                ObstacleType::Statue,
-                            );
-                            all_obstacles.push(obstacle);
+                    profiler.begin(Scope::Procgen);
+                    // Hill layers scroll in new columns, each at its own rate
+                    if quality.parallax && !game_over {
+                        parallax.step();
+                    }
+                    if quality.foreground && report.new_segment {
+                        let mut items = decor::scatter(biome.def().decor, CAM_W as i32, &mut rng);
+                        let new_terrain = world.terrain.last_mut().unwrap();
+                        // Nothing stands over thin air
+                        if let Some(gap) = new_terrain.gap() {
+                            items.retain(|d| d.at < gap.start || d.at > gap.end);
+                        }
+                        new_terrain.set_decor(items);
+                    }
+                    spawn_heatmap.record(
+                        report.spawned.len(),
+                        report.pickups_spawned,
+                        world.planner.gap(),
+                        proceduralgen::pattern_chance(score.total()),
+                    );
+
+                    // The first of each obstacle a profile sees gets a
+                    // callout, runs nobody is playing don't count
+                    if playback.is_none() && !demo && !self.bot {
+                        for &obstacle_type in report.spawned.iter() {
+                            let intro = Callout::of(obstacle_type);
+                            if !progress.seen_callout(intro.key) {
+                                info!("Introducing {}", intro.title);
+                                announcer.announce(&format!("{}. {}", intro.title, intro.hint));
+                                callout = Some((intro, clock.timer(tutorial::CALLOUT_STEPS)));
+                                progress.mark_callout_seen(intro.key);
+                                if let Err(e) = progress.save() {
+                                    warn!("Could not save progress: {}", e);
+                                }
+                            }
                         }
-                        Some(StaticObject::Balloon) => {
-                            let spawn_coord: Point = get_ground_coord(&all_terrain, (CAM_W as i32) - 1);
-                            let obstacle = Obstacle::new(
-                                p_rect!(spawn_coord.x, spawn_coord.y - TILE_SIZE as i32, TILE_SIZE, TILE_SIZE),
-                                1.0,
-                                &tex_balloon,
-                                ObstacleType::Balloon,
-                            );
-                            all_obstacles.push(obstacle);
+                    }
+
+                    // Passing through a gate pays out on the combo, gives back
+                    // a life and leads into the next biome. Checked before the
+                    // score update, so the bonus settles with this step.
+                    for ground in world.terrain.iter_mut() {
+                        if game_over || !ground.pass_gate(player.center().x()) {
+                            continue;
                         }
-                        Some(StaticObject::Chest) => {
-                            let spawn_coord: Point = get_ground_coord(&all_terrain, (CAM_W as i32) - 1);
-                            let obstacle = Obstacle::new(
-                                p_rect!(spawn_coord.x, spawn_coord.y - TILE_SIZE as i32, TILE_SIZE, TILE_SIZE),
-                                1.0,
-                                &tex_chest,
-                                ObstacleType::Chest,
-                            );
-                            all_obstacles.push(obstacle);
+                        let bonus = GATE_BONUS + GATE_COMBO_BONUS * coin_combo;
+                        score.add(ScoreSource::Milestones, bonus);
+                        last_coin_val = bonus;
+                        coin_timer = clock.timer(COIN_VALUE_STEPS);
+                        lives = (lives + 1).min(START_LIVES);
+                        gate_banner = Some((bonus, clock.timer(MILESTONE_BANNER_STEPS)));
+                        if quality.particles {
+                            motes.emit(player.center().into(), GATE_MOTES, Color::RGB(255, 215, 0));
                         }
-                        Some(StaticObject::Coin) => {
-                            let spawn_coord: Point = get_ground_coord(&all_terrain, (CAM_W as i32) - 1);
-                            let coin = Coin::new(
-                                p_rect!(spawn_coord.x, spawn_coord.y - TILE_SIZE as i32, TILE_SIZE, TILE_SIZE),
-                                &tex_coin,
-                                1000, // value
-                            );
-                            all_coins.push(coin);
+                        sound.stinger(Stinger::Milestone);
+                        biome = biome.pick_next(&mut rng);
+                        parallax = Parallax::new(biome, BG_CURVES_SIZE, &mut rng);
+                        sound.set_biome(biome);
+                        crash::event(&format!("Passed a gate into the {} biome", biome.def().label));
+                        announcer.announce(&format!("Entering {}", biome.def().label));
+                    }
+
+                    // Update the score
+                    if !game_over {
+                        // Hardcoded score increase per frame
+                        score.add(ScoreSource::Distance, 1);
+                        // Hardcoded power bonus, and the risk zone wager
+                        if in_risk_zone {
+                            score.wager();
                         }
-                        Some(StaticObject::Power) => {
-                            let spawn_coord: Point = get_ground_coord(&all_terrain, (CAM_W as i32) - 1);
-                            let pow = Power::new(
-                                p_rect!(spawn_coord.x, spawn_coord.y - TILE_SIZE as i32, TILE_SIZE, TILE_SIZE),
-                                &tex_powerup,
-                                proceduralgen::choose_power_up(),
-                            );
-                            all_powers.push(pow);
+                        let multiplier = match player.power_up() {
+                            Some(PowerType::ScoreMultiplier) => 2,
+                            _ => 1,
+                        };
+                        stall.step(splits.distance());
+                        if stall.drains_combo() {
+                            coin_combo = (coin_combo - 1).max(0);
                         }
-                        // Some(StaticObject::Chest) => {}
-                        // ... Add any new types of objects here ...
-                        _ => {}
+                        score.settle(multiplier, loadout.score_scale() * stall.multiplier());
                     }
 
-                    /* ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~ */
-                }
+                    profiler.begin(Scope::Physics);
 
-                // Update total_score
-                // Poorly placed rn, should be after postion / hitbox / collision update
-                // but before drawing
-                if !game_over {
-                    curr_step_score += 1; // Hardcoded score increase per frame
-                    if let Some(PowerType::ScoreMultiplier) = player.power_up() {
-                        curr_step_score *= 2; // Hardcoded power bonus
+                    // Everything else the world moves, the view followed along
+                    view_pos.0 += travel_update;
+                    if !game_over {
+                        splits.step(travel_update);
+                        for mark in pace_marks.iter_mut() {
+                            mark.check(clock.step(), splits.distance());
+                        }
+                    }
+                    decals.step(travel_update);
+
+                    // Random events, and what the running one does this step
+                    if !game_over {
+                        if let Some(event) =
+                            events.step(world.procgen.spawn_rng(), proceduralgen::difficulty(score.total()))
+                        {
+                            event_banner = Some((event, clock.timer(MILESTONE_BANNER_STEPS)));
+                            crash::event(&format!("Started {:?}", event));
+                            announcer.announce(event.def().banner);
+                        }
+                    }
+                    if events.active() == Some(RandomEvent::CoinRain)
+                        && events.steps_left().is_multiple_of(COIN_RAIN_EVERY)
+                    {
+                        let x = world.procgen.spawn_rng().gen_range(0..view_w - TILE_SIZE as i32);
+                        world.coins.push(Coin::dropped(
+                            p_rect!(x, -(TILE_SIZE as i32), TILE_SIZE, TILE_SIZE),
+                            &tex_coin,
+                            proceduralgen::COIN_VALUE,
+                        ));
                     }
-                    total_score += curr_step_score;
-                }
-
-                /* Update ground / object positions to move player forward
-                 * by the distance they should move this single iteration of the game loop
-                 */
-                let travel_update = player.vel_x();
-                for ground in all_terrain.iter_mut() {
-                    ground.travel_update(travel_update as i32);
-                }
 
-                for obs in all_obstacles.iter_mut() {
-                    obs.travel_update(travel_update as i32);
-                }
-                for coin in all_coins.iter_mut() {
-                    coin.travel_update(travel_update as i32);
-                }
-                for power_up in all_powers.iter_mut() {
-                    power_up.travel_update(travel_update as i32);
-                }
+                    // Earthquakes drop debris ahead of the player, more often
+                    // the harder the run has got
+                    if quaking && debris_timer.is_done(&clock) {
+                        let t = proceduralgen::difficulty(score.total());
+                        let every = DEBRIS_EVERY.0 + (DEBRIS_EVERY.1 - DEBRIS_EVERY.0) * t;
+                        debris_timer = clock.timer(every as u32);
+                        let x = world
+                            .procgen
+                            .spawn_rng()
+                            .gen_range(PLAYER_X + 2 * TILE_SIZE as i32..view_w - DEBRIS_SIZE as i32);
+                        all_debris.push(Debris::new(x, DEBRIS_SIZE));
+                    }
+                    for debris in all_debris.iter_mut() {
+                        debris.travel_update(travel_update as i32);
+                        debris.camera_adj(0, camera_adj_y);
+                        let (floor_y, _) = get_floor(&world.terrain, debris.center().x());
+                        debris.fall(floor_y);
+                    }
+                    // Debris costs a life like a hazard strip, and breaks on
+                    // whatever it hits
+                    let mut hit_by_debris = false;
+                    all_debris.retain(|d| {
+                        if d.landed() {
+                            sound.play_at(&audio::THUD, d.center().x() - player.center().x());
+                            return false;
+                        }
+                        if d.y() > CAM_H as i32 {
+                            return false;
+                        }
+                        if !game_over && d.hitbox().has_intersection(player.hitbox()) {
+                            hit_by_debris = true;
+                            return false;
+                        }
+                        true
+                    });
+                    if hit_by_debris {
+                        coin_combo = 0;
+                        crash::event("Hit by debris");
+                        if hit_cooldown.is_done(&clock) {
+                            lives -= 1;
+                            hit_cooldown = clock.timer(HIT_COOLDOWN_STEPS);
+                            if lives <= 0 {
+                                game_over = true;
+                                death_cause.get_or_insert(DeathCause::Debris);
+                            }
+                        }
+                    }
 
-                // Generate new ground when the last segment becomes visible
-                // All of this code is placeholder
-                let last_seg = all_terrain.get(all_terrain.len() - 1).unwrap();
-                if last_seg.x() < CAM_W as i32 {
-                    let last_x = last_seg.curve().get(last_seg.curve().len() - 1).unwrap().0;
-                    let last_y = last_seg.curve().get(last_seg.curve().len() - 1).unwrap().1;
-                    let mut new_curve: Vec<(i32, i32)> = vec![(last_x + 1, last_y)];
-                    for i in (last_x + 2)..(last_x + CAM_W as i32 + 1) {
-                        new_curve.push((i as i32, last_y));
-                    }
-                    let new_terrain = TerrainSegment::new(
-                        rect!(last_x + 1, last_y, CAM_W, CAM_H * 2 / 3),
-                        new_curve,
-                        0.0,
-                        TerrainType::Grass,
-                        Color::GREEN,
+                    // Heights are kept for ghosts without the camera's
+                    // adjustments, which add up in camera_offset
+                    camera_offset += camera_adj_y;
+                    view_pos.1 = camera_offset as f64;
+                    if clock.step().is_multiple_of(ghost_every) && !game_over {
+                        let pose = Pose {
+                            distance: splits.distance() as f64,
+                            y: (player.y() - camera_offset) as f64,
+                            theta: player.theta(),
+                        };
+                        input_log.record_pose(clock.step(), pose);
+                    }
+                    if quality.particles {
+                        trail.step(player.center().into(), travel_update, camera_adj_y);
+                    }
+                    crash::update(
+                        splits.distance(),
+                        world.obstacles.len(),
+                        world.coins.len(),
+                        world.powers.len(),
                     );
-                    all_terrain.push(new_terrain);
-                }
 
-                /* ~~~~~~ Begin Camera Section ~~~~~~ */
-                /* This should be the very last section of calcultions,
-                 * as the camera position relies upon updated math for
-                 * EVERYTHING ELSE. Below the camera section we have
-                 * removal of offscreen objects from their vectors,
-                 * animation updates, the drawing section, and FPS calculation only.
-                 */
-
-                // Adjust camera vertically based on y/height of the ground
-                let camera_adj_y = if curr_ground_point.y() < TERRAIN_UPPER_BOUND {
-                    TERRAIN_UPPER_BOUND - curr_ground_point.y()
-                } else if (curr_ground_point.y() + TILE_SIZE as i32) > TERRAIN_LOWER_BOUND {
-                    TERRAIN_LOWER_BOUND - curr_ground_point.y()
-                } else {
-                    0
-                };
+                    /* ~~~~~~ Animation Updates ~~~~~~ */
+                    bg_tick += 1;
 
-                // Add adjustment to terrain
-                for ground in all_terrain.iter_mut() {
-                    ground.camera_adj(0, camera_adj_y);
-                }
-
-                // Add adjustment to obstacles
-                for obs in all_obstacles.iter_mut() {
-                    obs.camera_adj(0, camera_adj_y);
-                }
-
-                // Add adjustment to coins
-                for coin in all_coins.iter_mut() {
-                    coin.camera_adj(0, camera_adj_y);
-                }
-
-                // Add adjustment to power ups
-                for power_up in all_powers.iter_mut() {
-                    power_up.camera_adj(0, camera_adj_y);
-                }
-
-                // Add adjustment to player
-                player.camera_adj(0, camera_adj_y);
-                /* ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~ */
-
-                /* ~~~~~~ Remove stuff which is now offscreen ~~~~~~ */
-                let mut remove_inds: Vec<i32> = Vec::new();
-                let mut ind: i32 = -1;
-
-                // Terrain
-                for ground in all_terrain.iter() {
-                    ind += 1;
-                    if ground.x() + ground.w() <= -1 * TILE_SIZE as i32 {
-                        remove_inds.push(ind);
+                    // Shift background images & sine waves?
+                    if bg_tick % 10 == 0 {
+                        bg_buff -= 1;
                     }
-                }
-                for i in remove_inds.iter() {
-                    all_terrain.remove(*i as usize);
-                }
-                remove_inds.clear();
 
-                //  Obstacles
-                ind = -1;
-                for obs in all_obstacles.iter() {
-                    ind += 1;
-                    if obs.x() + TILE_SIZE as i32 <= -1 * TILE_SIZE as i32 {
-                        remove_inds.push(ind);
+                    // Reset sine wave tick (to prevent large values?)
+                    if bg_tick % 3 == 0 && bg_tick % 5 == 0 {
+                        bg_tick = 0;
                     }
-                }
-                for i in remove_inds.iter() {
-                    all_obstacles.remove(*i as usize);
-                }
-                remove_inds.clear();
 
-                // Coins
-                ind = -1;
-                for coin in all_coins.iter() {
-                    ind += 1;
-                    if coin.x() + TILE_SIZE as i32 <= -1 * TILE_SIZE as i32 {
-                        remove_inds.push(ind);
+                    // Reset background image buffer upon leftmost bg image moving completely
+                    // offscreen
+                    if -bg_buff == CAM_W as i32 {
+                        bg_buff = 0;
                     }
-                }
-                for i in remove_inds.iter() {
-                    all_coins.remove(*i as usize);
-                }
-                remove_inds.clear();
 
-                // Power ups
-                ind = -1;
-                for power in all_powers.iter_mut() {
-                    ind += 1;
-                    if power.x() + TILE_SIZE as i32 <= -1 * TILE_SIZE as i32 {
-                        remove_inds.push(ind);
-                    }
-                }
-                for i in remove_inds.iter() {
-                    all_powers.remove(*i as usize);
-                }
-                /* ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~ */
-
-                /* ~~~~~~ Animation Updates ~~~~~~ */
-                bg_tick += 1;
+                    // Next frame for coin animation
+                    coin_anim += 1;
+                    coin_anim %= 60;
 
-                // Shift background images & sine waves?
-                if bg_tick % 10 == 0 {
-                    bg_buff -= 1;
-                }
-
-                // Reset sine wave tick (to prevent large values?)
-                if bg_tick % 3 == 0 && bg_tick % 5 == 0 {
-                    bg_tick = 0;
-                }
+                    if !game_over && score.total() >= next_milestone {
+                        milestone_banner = Some((next_milestone, clock.timer(MILESTONE_BANNER_STEPS)));
+                        announcer.announce(&format!("{} points!", next_milestone));
+                        sound.stinger(Stinger::Milestone);
+                        next_milestone += MILESTONE_EVERY;
+                    }
+                    animator.update(&player);
 
-                // Reset background image buffer upon leftmost bg image moving completely
-                // offscreen
-                if -bg_buff == CAM_W as i32 {
-                    bg_buff = 0;
+                    if motes.update(SIM_STEP, world.workers()) > 0 {
+                        score_pulse = clock.timer(SCORE_PULSE_STEPS);
+                    }
+                    /* ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~ */
+                } // End simulation step
+
+                // Holding R restarts without going through the pause menu.
+                // It's timed in real time, it shouldn't matter how fast the
+                // run is going.
+                if playback.is_none() && !demo && !self.bot && input.held(Action::Restart) {
+                    let held_since = *restart_held.get_or_insert_with(Instant::now);
+                    if held_since.elapsed().as_secs_f64() >= QUICK_RESTART_SECS {
+                        quick_restart = true;
+                        next_status = GameStatus::Game;
+                        break 'gameloop;
+                    }
+                } else {
+                    restart_held = None;
                 }
 
-                // Next frame for coin animation
-                coin_anim += 1;
-                coin_anim %= 60;
-                /* ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~ */
-
                 /* ~~~~~~ Draw All Elements ~~~~~~ */
-                // Wipe screen every frame
-                core.wincan.set_draw_color(Color::RGBA(3, 120, 206, 255));
-                core.wincan.clear();
-
-                // Bottom layer of background, black skybox
-                core.wincan.set_draw_color(Color::RGBA(0, 0, 0, 255));
-                core.wincan.fill_rect(rect!(0, 470, CAM_W, CAM_H))?;
-
-                // Sky
-                core.wincan.copy(&tex_sky, None, rect!(bg_buff, 0, CAM_W, CAM_H / 3))?;
-                core.wincan
-                    .copy(&tex_sky, None, rect!(CAM_W as i32 + bg_buff, 0, CAM_W, CAM_H / 3))?;
-
-                // Sunset gradient - doesn't need to scroll left
-                core.wincan.copy(&tex_grad, None, rect!(0, -128, CAM_W, CAM_H))?;
-
-                // Background
-                core.wincan.copy(&tex_bg, None, rect!(bg_buff, -150, CAM_W, CAM_H))?;
-                core.wincan
-                    .copy(&tex_bg, None, rect!(bg_buff + (CAM_W as i32), -150, CAM_W, CAM_H))?;
-
-                // Background perlin noise curves
-                for i in 0..background_curves[IND_BACKGROUND_MID].len() - 1 {
-                    // Furthest back perlin noise curves
-                    core.wincan.set_draw_color(Color::RGBA(128, 51, 6, 255));
-                    core.wincan.fill_rect(rect!(
-                        i * CAM_W as usize / BG_CURVES_SIZE + CAM_W as usize / BG_CURVES_SIZE / 2,
-                        CAM_H as i16 - background_curves[IND_BACKGROUND_BACK][i],
-                        CAM_W as usize / BG_CURVES_SIZE,
-                        CAM_H as i16
-                    ))?;
-
-                    // Midground perlin noise curves
-                    core.wincan.set_draw_color(Color::RGBA(96, 161, 152, 255));
-                    core.wincan.fill_rect(rect!(
-                        i * CAM_W as usize / BG_CURVES_SIZE + CAM_W as usize / BG_CURVES_SIZE / 2,
-                        CAM_H as i16 - background_curves[IND_BACKGROUND_MID][i],
-                        CAM_W as usize / BG_CURVES_SIZE,
-                        CAM_H as i16
-                    ))?;
+                profiler.begin(Scope::DrawWorld);
+                if let Some(target) = world_target.as_ref() {
+                    core.set_render_target(Some(target))?;
+                    core.wincan.set_scale(world_scale as f32, world_scale as f32)?;
                 }
-
-                // Active Power HUD Display
-                if player.power_up().is_some() {
-                    match player.power_up() {
-                        Some(PowerType::SpeedBoost) => {
+                // Background layers. Low-spec mode redraws them only every few
+                // frames into a cached texture and reuses that in between.
+                match bg_cache.as_mut() {
+                    Some(cache) => {
+                        if bg_frame % quality.background_interval == 0 {
+                            let mut result = Ok(());
                             core.wincan
-                                .copy(&tex_speed, None, rect!(10, 100, TILE_SIZE, TILE_SIZE))?;
+                                .with_texture_canvas(cache, |c| {
+                                    result = draw_background(
+                                        c, &tex_sky, &tex_grad, &tex_bg, bg_buff, &parallax, &quality, view_w,
+                                    );
+                                })
+                                .map_err(|e| e.to_string())?;
+                            result?;
+                            // Drawing into the cache leaves the window as the target
+                            if let Some(target) = world_target.as_ref() {
+                                core.set_render_target(Some(target))?;
+                                core.wincan.set_scale(world_scale as f32, world_scale as f32)?;
+                            }
                         }
-                        Some(PowerType::ScoreMultiplier) => {
+                        core.wincan.copy(cache, rect!(0, 0, view_w, CAM_H), None)?;
+                    }
+                    None => draw_background(
+                        &mut core.wincan,
+                        &tex_sky,
+                        &tex_grad,
+                        &tex_bg,
+                        bg_buff,
+                        &parallax,
+                        &quality,
+                        view_w,
+                    )?,
+                }
+                bg_frame = (bg_frame + 1) % quality.background_interval;
+
+                // The world is drawn between the last two steps, as far along
+                // as the time the clock has taken in. Whatever only moves with
+                // the view is shifted back by the viewport, by how far the view
+                // still has to go to get where it is. Bodies and coins are
+                // drawn between where they were and where they are, less
+                // that shift.
+                let alpha = clock.alpha();
+                let back = (
+                    ((view_pos.0 - prev_view_pos.0) * (1.0 - alpha)).round() as i32,
+                    ((prev_view_pos.1 - view_pos.1) * (1.0 - alpha)).round() as i32,
+                );
+                let player_drawn = player.drawn_pos(alpha);
+                let player_pos = (player_drawn.0 - back.0, player_drawn.1 - back.1);
+                core.wincan.set_viewport(rect!(
+                    back.0,
+                    back.1,
+                    view_w as u32 + back.0.unsigned_abs(),
+                    CAM_H + back.1.unsigned_abs()
+                ));
+
+                // Terrain, juddering in an earthquake unless motion is reduced
+                let quaking = events.active() == Some(RandomEvent::Earthquake);
+                for (i, ground) in world.terrain.iter().enumerate() {
+                    let mut pos = ground.pos();
+                    if quaking && !settings.reduce_motion {
+                        pos.offset(0, ((all_frames / 2 + i as i32) % 3 - 1) * 3);
+                    }
+                    core.wincan.set_draw_color(ground.color());
+                    // Plain ground can slope, it's drawn in strips that
+                    // follow the curve down from its surface
+                    if ground.is_plain() {
+                        let dy = pos.y() - ground.y();
+                        let strips: Vec<Rect> = ground
+                            .curve()
+                            .chunks(TERRAIN_STRIP_W)
+                            .map(|chunk| {
+                                let top = chunk.iter().map(|&(_, y)| y).min().unwrap_or(0) + dy;
+                                rect!(chunk[0].0, top, chunk.len(), pos.height())
+                            })
+                            .collect();
+                        core.wincan.fill_rects(&strips)?;
+                        continue;
+                    }
+                    match ground.gap() {
+                        Some(gap) => {
                             core.wincan
-                                .copy(&tex_multiplier, None, rect!(10, 100, TILE_SIZE, TILE_SIZE))?;
+                                .fill_rect(rect!(pos.x(), pos.y(), gap.start, pos.height()))?;
+                            core.wincan.fill_rect(rect!(
+                                pos.x() + gap.end,
+                                pos.y(),
+                                pos.width() as i32 - gap.end,
+                                pos.height()
+                            ))?;
+                        }
+                        None => core.wincan.fill_rect(pos)?,
+                    }
+                }
+                // Tunnel ceilings, rock down to the ceiling's underside
+                for ground in world.terrain.iter() {
+                    if let Some(ceiling) = ground.ceiling() {
+                        for chunk in ceiling.chunks(8) {
+                            let (x, y) = chunk[0];
+                            if y <= 0 {
+                                continue;
+                            }
+                            core.wincan.set_draw_color(Color::RGB(84, 72, 64));
+                            core.wincan.fill_rect(rect!(x, 0, chunk.len(), y))?;
+                            core.wincan.set_draw_color(Color::RGB(56, 48, 42));
+                            core.wincan.fill_rect(rect!(x, y - 8, chunk.len(), 8))?;
                         }
-                        Some(PowerType::BouncyShoes) => {
+                    }
+                }
+                // Everything else in the world goes in by layer and is drawn
+                // back to front once it's all queued
+                let mut queue = DrawQueue::new();
+                let terrain = &world.terrain;
+
+                // Biome gates, two pillars and a lintel, lit up once passed
+                queue.push(Layer::Scenery, |core| {
+                    for ground in terrain.iter() {
+                        if let Some(gate) = ground.gate() {
+                            let x = ground.x() + gate.at;
+                            let y = ground.y();
+                            core.wincan.set_draw_color(if gate.is_passed() {
+                                Color::RGB(230, 190, 60)
+                            } else {
+                                Color::RGB(150, 140, 130)
+                            });
+                            core.wincan.fill_rect(rect!(x, y - 260, 30, 260))?;
                             core.wincan
-                                .copy(&tex_bouncy, None, rect!(10, 100, TILE_SIZE, TILE_SIZE))?;
+                                .fill_rect(rect!(x + proceduralgen::GATE_W - 30, y - 260, 30, 260))?;
+                            core.wincan
+                                .fill_rect(rect!(x - 10, y - 290, proceduralgen::GATE_W + 20, 36))?;
                         }
-                        Some(PowerType::LowerGravity) => {
+                    }
+                    Ok(())
+                });
+                // Gap platforms, with the rail they run along
+                queue.push(Layer::Rails, |core| {
+                    for ground in terrain.iter() {
+                        if let Some(gap) = ground.gap() {
+                            let (left, right) = gap.platform();
+                            core.wincan.set_draw_color(Color::RGB(90, 90, 100));
+                            core.wincan.fill_rect(rect!(
+                                ground.x() + gap.start,
+                                ground.y() + PLATFORM_H as i32 / 2 - 2,
+                                gap.end - gap.start,
+                                4
+                            ))?;
+                            core.wincan.set_draw_color(Color::RGB(150, 110, 60));
                             core.wincan
-                                .copy(&tex_floaty, None, rect!(10, 100, TILE_SIZE, TILE_SIZE))?;
+                                .fill_rect(rect!(ground.x() + left, ground.y(), right - left, PLATFORM_H))?;
+                            core.wincan.set_draw_color(Color::RGB(110, 80, 40));
+                            core.wincan.fill_rect(rect!(
+                                ground.x() + left,
+                                ground.y() + PLATFORM_H as i32 - 6,
+                                right - left,
+                                6
+                            ))?;
                         }
-                        Some(PowerType::Shield) => {
+                    }
+                    Ok(())
+                });
+                // Falling debris, with a shadow where it's going to land
+                for debris in all_debris.iter() {
+                    queue.push(debris.layer(), move |core| {
+                        let center_x = debris.center().x();
+                        let (floor_y, _) = get_floor(terrain, center_x);
+                        let height = floor_y - (debris.y() + DEBRIS_SIZE as i32);
+                        decals::draw_shadow(&mut core.wincan, center_x, DEBRIS_SIZE, floor_y, height)?;
+                        core.wincan.set_draw_color(Color::RGB(110, 96, 84));
+                        core.wincan.fill_rect(debris.hitbox().as_rect())?;
+                        core.wincan.set_draw_color(Color::RGB(78, 66, 58));
+                        core.wincan.fill_rect(rect!(
+                            debris.x() + 10,
+                            debris.y() + 10,
+                            DEBRIS_SIZE - 20,
+                            DEBRIS_SIZE - 20
+                        ))?;
+                        Ok(())
+                    });
+                }
+                // Hazard strips: glowing lava or rows of spikes
+                queue.push(Layer::Ground, |core| {
+                    for ground in terrain.iter() {
+                        if let Some(strip) = ground.hazard() {
+                            let (x, len) = (ground.x() + strip.start, strip.end - strip.start);
+                            match strip.hazard {
+                                Hazard::Lava => {
+                                    let glow = ((all_frames as f64 / 10.0).sin() * 40.0) as i32;
+                                    core.wincan
+                                        .set_draw_color(Color::RGB(220, (70 + glow).clamp(0, 255) as u8, 20));
+                                    core.wincan.fill_rect(rect!(x, ground.y() - 6, len, 30))?;
+                                    core.wincan.set_draw_color(Color::RGB(255, 200, 60));
+                                    for bubble in (x + 10..x + len - 10).step_by(37) {
+                                        let rise = ((all_frames + bubble) / 4) % 8;
+                                        core.wincan.fill_rect(rect!(bubble, ground.y() - 4 - rise, 6, 6))?;
+                                    }
+                                }
+                                Hazard::Spikes => {
+                                    core.wincan.set_draw_color(Color::RGB(90, 90, 100));
+                                    core.wincan.fill_rect(rect!(x, ground.y() - 4, len, 8))?;
+                                    core.wincan.set_draw_color(Color::RGB(200, 200, 210));
+                                    // Each spike a stack of narrowing bars
+                                    for spike in (x..x + len - 16).step_by(20) {
+                                        for row in 0..4 {
+                                            core.wincan.fill_rect(rect!(
+                                                spike + 2 * row,
+                                                ground.y() - 8 - 6 * row,
+                                                16 - 4 * row,
+                                                6
+                                            ))?;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    // Mud patches, with darker clumps so they read as sticky
+                    for ground in terrain.iter() {
+                        if let Some((start, end)) = ground.mud() {
+                            let x = ground.x() + start;
+                            core.wincan.set_draw_color(proceduralgen::MUD_COLOR);
+                            core.wincan.fill_rect(rect!(x, ground.y() - 4, end - start, 28))?;
+                            core.wincan.set_draw_color(Color::RGB(64, 42, 26));
+                            for clump in (x + 15..x + end - start - 20).step_by(45) {
+                                core.wincan.fill_rect(rect!(clump, ground.y() - 8, 20, 8))?;
+                            }
+                        }
+                    }
+                    Ok(())
+                });
+
+                // Spawn telegraphs: a shadow on the ground at the right edge that
+                // darkens as the obstacle is about to appear there
+                queue.push(Layer::Ground, |core| {
+                    if !world.planner.planned().is_empty() {
+                        let ground = get_ground_coord(terrain, view_w - 1);
+                        // Later pattern pieces aren't shown until their own telegraph starts
+                        for pending in world.planner.planned().iter().filter(|p| {
+                            p.steps <= TELEGRAPH_STEPS
+                                && matches!(p.object, StaticObject::Statue | StaticObject::Balloon)
+                        }) {
+                            let t = 1.0 - pending.steps.max(0) as f64 / TELEGRAPH_STEPS as f64;
+                            core.wincan
+                                .set_draw_color(Color::RGBA(0, 0, 0, (40.0 + 100.0 * t) as u8));
+                            core.wincan.fill_rect(rect!(
+                                view_w - TILE_SIZE as i32 / 2,
+                                ground.y() - 6,
+                                TILE_SIZE / 2,
+                                12
+                            ))?;
                             core.wincan
-                                .copy(&tex_shield, None, rect!(10, 100, TILE_SIZE, TILE_SIZE))?;
+                                .set_draw_color(Color::RGBA(255, 40, 40, (60.0 + 160.0 * t) as u8));
+                            core.wincan
+                                .fill_rect(rect!(view_w - 30, ground.y() - 70 - pending.height, 10, 40))?;
+                            core.wincan
+                                .fill_rect(rect!(view_w - 30, ground.y() - 24 - pending.height, 10, 10))?;
+                        }
+                    }
+                    Ok(())
+                });
+
+                // Updrafts: faint streaks rising over the segment
+                queue.push(Layer::Scenery, |core| {
+                    core.wincan.set_draw_color(Color::RGBA(200, 230, 255, 70));
+                    for ground in terrain.iter().filter(|g| g.has_updraft()) {
+                        let rise = (all_frames * 6) % 240;
+                        for x in (ground.x() + 40..ground.x() + ground.w()).step_by(160) {
+                            let y = ground.y() - 60 - rise - (x / 7) % 120;
+                            core.wincan.fill_rect(rect!(x, y, 4, 50))?;
+                        }
+                    }
+                    Ok(())
+                });
+
+                // Flags where the raced run was at each split, and a checkered
+                // one where it ended
+                queue.push(Layer::Scenery, |core| {
+                    if let Some(code) = &race {
+                        let split_flags = (1..=code.splits().len() as i32).map(|i| (i * SPLIT_DISTANCE, false));
+                        for (distance, last) in split_flags.chain(std::iter::once((code.distance(), true))) {
+                            let x = PLAYER_X + distance - splits.distance();
+                            if x < 0 || x >= view_w {
+                                continue;
+                            }
+                            let ground = get_ground_coord(terrain, x);
+                            core.wincan.set_draw_color(Color::RGB(80, 80, 80));
+                            core.wincan.fill_rect(rect!(x - 2, ground.y() - 120, 4, 120))?;
+                            if last {
+                                for i in 0..8 {
+                                    core.wincan.set_draw_color(if (i + i / 4) % 2 == 0 {
+                                        Color::BLACK
+                                    } else {
+                                        Color::WHITE
+                                    });
+                                    core.wincan.fill_rect(rect!(
+                                        x + 2 + (i % 4) * 12,
+                                        ground.y() - 120 + (i / 4) * 12,
+                                        12,
+                                        12
+                                    ))?;
+                                }
+                            } else {
+                                core.wincan.set_draw_color(Color::RGB(30, 144, 255));
+                                core.wincan.fill_rect(rect!(x + 2, ground.y() - 120, 40, 24))?;
+                            }
                         }
-                        _ => {}
                     }
 
-                    // Power duration bar
-                    let m = power_timer as f64 / 360.0;
-                    let r = 256.0 * (1.0 - m);
-                    let g = 256.0 * (m);
-                    let w = TILE_SIZE as f64 * m;
-                    core.wincan.set_draw_color(Color::RGB(r as u8, g as u8, 0));
-                    core.wincan.fill_rect(rect!(10, 210, w as u8, 10))?;
-                }
+                    // Small flags where the personal best was every so often,
+                    // green when this run is there first and red when it isn't
+                    for mark in pace_marks.iter() {
+                        let x = PLAYER_X + (mark.distance - splits.distance() as f64) as i32;
+                        if x < 0 || x >= view_w {
+                            continue;
+                        }
+                        let ground = get_ground_coord(terrain, x);
+                        core.wincan.set_draw_color(Color::RGB(80, 80, 80));
+                        core.wincan.fill_rect(rect!(x - 1, ground.y() - 60, 3, 60))?;
+                        core.wincan.set_draw_color(match mark.beaten(clock.step()) {
+                            Some(true) => Color::RGB(60, 200, 90),
+                            Some(false) => Color::RGB(220, 60, 50),
+                            None => Color::RGB(240, 240, 240),
+                        });
+                        core.wincan.fill_rect(rect!(x + 2, ground.y() - 60, 18, 12))?;
+                    }
 
-                // Terrain
-                for ground in all_terrain.iter() {
-                    core.wincan.set_draw_color(ground.color());
-                    core.wincan.fill_rect(ground.pos())?;
+                    // Risk zone banners at the entry and exit of each zone
+                    for ground in terrain.iter().filter(|g| g.is_risk_zone()) {
+                        for x in [ground.x(), ground.x() + ground.w()].iter() {
+                            core.wincan.set_draw_color(Color::RGB(60, 60, 60));
+                            core.wincan.fill_rect(rect!(*x - 4, ground.y() - 160, 8, 160))?;
+                            core.wincan.set_draw_color(Color::RGB(200, 0, 0));
+                            core.wincan.fill_rect(rect!(*x + 4, ground.y() - 160, 90, 45))?;
+                            core.wincan
+                                .copy(&tex_risk_banner, None, Some(rect!(*x + 12, ground.y() - 155, 74, 35)))?;
+                        }
+                    }
+                    Ok(())
+                });
+
+                // Marks on the ground, then shadows under the player,
+                // obstacles and coins
+                queue.push(Layer::Ground, |core| {
+                    decals.draw(&mut core.wincan, |x| get_ground_coord(terrain, x).y())?;
+                    let casters = std::iter::once((player.hitbox(), TILE_SIZE))
+                        .chain(world.obstacles.iter().map(|o| (o.hitbox(), TILE_SIZE)))
+                        .chain(world.coins.iter().map(|c| (c.hitbox(), TILE_SIZE / 2)));
+                    for (hitbox, width) in casters {
+                        let x = hitbox.center().x();
+                        if x < 0 || x >= view_w {
+                            continue;
+                        }
+                        let ground_y = get_ground_coord(terrain, x).y();
+                        let height = ground_y - (hitbox.y() + hitbox.height() as i32);
+                        decals::draw_shadow(&mut core.wincan, x, width, ground_y, height)?;
+                    }
+                    Ok(())
+                });
+
+                if quality.particles {
+                    queue.push(Layer::Player, |core| trail.draw(&mut core.wincan));
                 }
 
                 // Set player texture
-                let tex_player = match player.power_up() {
-                    Some(PowerType::Shield) => &tex_shielded,
-                    Some(PowerType::LowerGravity) => &tex_winged,
-                    Some(PowerType::BouncyShoes) => &tex_springed,
-                    Some(PowerType::SpeedBoost) => &tex_fast,
-                    // ... Add more types of powered player textures here ...
-                    _ => player.texture(),
+                let tex_player = match animator.visual() {
+                    PlayerVisual::Shielded => &tex_shielded,
+                    PlayerVisual::Floating => &tex_winged,
+                    PlayerVisual::Bouncing => &tex_springed,
+                    PlayerVisual::Speeding => &tex_fast,
+                    PlayerVisual::Plain => player.texture(),
                 };
 
-                // Assert player.x() == PLAYER_X here
+                // Ghost of the raced replay, placed by how far ahead or behind
+                // it is, at the same point between steps as everything else
+                let ghost_step = clock.step() as f64 - 1.0 + alpha;
+                if let Some(pose) = ghost.as_ref().and_then(|g| g.pose_at(ghost_step)) {
+                    let x = PLAYER_X + (pose.distance - splits.distance() as f64) as i32;
+                    let y = pose.y as i32 + camera_offset;
+                    let tex_ghost = &tex_ghost;
+                    queue.push(Layer::Player, move |core| {
+                        core.wincan.copy_ex(
+                            tex_ghost,
+                            rect!(0, 0, TILE_SIZE, TILE_SIZE),
+                            rect!(x, y, TILE_SIZE, TILE_SIZE),
+                            pose.theta * 180.0 / std::f64::consts::PI,
+                            None,
+                            false,
+                            false,
+                        )
+                    });
+                }
 
-                // Player
-                core.wincan.copy_ex(
-                    tex_player,
-                    rect!(0, 0, TILE_SIZE, TILE_SIZE),
-                    rect!(player.x(), player.y(), TILE_SIZE, TILE_SIZE),
-                    player.theta() * 180.0 / std::f64::consts::PI,
-                    None,
-                    false,
-                    false,
-                )?;
+                // Assert player.x() == PLAYER_X here
 
-                core.wincan.set_draw_color(Color::BLACK);
+                // Player, over whatever effects their power has
+                queue.push(player.layer(), |core| {
+                    animator.draw_behind(&mut core.wincan, player_pos.0, player_pos.1)?;
+                    core.wincan.copy_ex(
+                        tex_player,
+                        rect!(0, 0, TILE_SIZE, TILE_SIZE),
+                        rect!(player_pos.0, player_pos.1, TILE_SIZE, TILE_SIZE),
+                        (player.theta() + animator.lean()) * 180.0 / std::f64::consts::PI,
+                        None,
+                        false,
+                        false,
+                    )?;
 
-                // Player's hitbox
-                core.wincan.draw_rect(player.hitbox().as_rect())?;
+                    // Player's hitbox
+                    if settings.show_hitboxes {
+                        draw_hitboxes(core, &player, Color::BLACK)?;
+                    }
+                    Ok(())
+                });
+
+                // Companion drone, a body with a rotor bar on top
+                if let Some(drone) = drone.as_ref() {
+                    queue.push(Layer::Companions, move |core| {
+                        core.wincan.set_draw_color(Color::RGB(90, 90, 100));
+                        core.wincan
+                            .fill_rect(rect!(drone.x(), drone.y() + 10, DRONE_SIZE, DRONE_SIZE - 20))?;
+                        core.wincan.set_draw_color(Color::RGB(40, 40, 40));
+                        core.wincan
+                            .fill_rect(rect!(drone.x() - 10, drone.y(), DRONE_SIZE + 20, 4))?;
+                        core.wincan.set_draw_color(Color::RGB(0, 220, 255));
+                        core.wincan.fill_rect(rect!(drone.x() + 15, drone.y() + 17, 10, 6))?;
+                        Ok(())
+                    });
+                }
 
                 // Obstacles
-                for obs in all_obstacles.iter() {
-                    // Collapse this match to just one ... all this code is repeated
-                    match obs.obstacle_type() {
-                        ObstacleType::Statue => {
-                            core.wincan.copy_ex(
-                                obs.texture(),
-                                None,
-                                rect!(obs.x(), obs.y(), TILE_SIZE, TILE_SIZE),
-                                obs.theta(),
-                                None,
-                                false,
-                                false,
-                            )?;
-                            core.wincan.set_draw_color(Color::RED);
-                            core.wincan.draw_rect(obs.hitbox().as_rect())?;
-                            break;
+                let obstacle_textures = (&tex_statue, &tex_golden_statue, &tex_balloon, &tex_chest);
+                for obs in world.obstacles.iter() {
+                    let (tex_statue, tex_golden_statue, tex_balloon, tex_chest) = obstacle_textures;
+                    queue.push(obs.layer(), move |core| {
+                        let (texture, hitbox_color): (&Texture, Color) = match obs.obstacle_type() {
+                            ObstacleType::Statue => (tex_statue, Color::RED),
+                            ObstacleType::GoldenStatue => (tex_golden_statue, Color::RED),
+                            ObstacleType::Balloon => (tex_balloon, Color::BLUE),
+                            ObstacleType::Chest => (tex_chest, Color::BLUE),
+                        };
+                        let (x, y) = obs.drawn_pos(alpha);
+                        core.wincan.copy_ex(
+                            texture,
+                            None,
+                            rect!(x - back.0, y - back.1, TILE_SIZE, TILE_SIZE),
+                            obs.theta(),
+                            None,
+                            false,
+                            false,
+                        )?;
+                        if settings.show_hitboxes {
+                            draw_hitboxes(core, obs, hitbox_color)?;
                         }
-                        ObstacleType::Balloon => {
-                            core.wincan.copy_ex(
-                                obs.texture(),
-                                None,
-                                rect!(obs.x(), obs.y(), TILE_SIZE, TILE_SIZE),
-                                obs.theta(),
-                                None,
-                                false,
-                                false,
-                            )?;
-                            core.wincan.set_draw_color(Color::BLUE);
-                            core.wincan.draw_rect(obs.hitbox().as_rect())?;
-                        }
-                        ObstacleType::Chest => {
-                            core.wincan.copy_ex(
-                                obs.texture(),
-                                None,
-                                rect!(obs.x(), obs.y(), TILE_SIZE, TILE_SIZE),
-                                obs.theta(),
-                                None,
-                                false,
-                                false,
-                            )?;
-                            core.wincan.set_draw_color(Color::BLUE);
-                            core.wincan.draw_rect(obs.hitbox().as_rect())?;
+                        Ok(())
+                    });
+                }
+
+                // Coins, bigger and redder the riskier they were to place,
+                // which is what they're worth
+                for coin in world.coins.iter() {
+                    if let Some(CoinDrop::Settled { steps }) = coin.drop_state() {
+                        if steps < COIN_BLINK_STEPS && (steps / 4).is_multiple_of(2) {
+                            continue;
                         }
                     }
+                    queue.push(coin.layer(), move |core| {
+                        let (x, y) = coin.drawn_pos(alpha);
+                        let (x, y) = (x - back.0, y - back.1);
+                        let risk = proceduralgen::coin_risk(coin.value());
+                        let grow = (risk * TILE_SIZE as f64 * 0.3) as i32;
+                        let size = TILE_SIZE as i32 + 2 * grow;
+                        if risk > 0.0 {
+                            core.wincan.set_draw_color(Color::RGBA(
+                                255,
+                                (200.0 * (1.0 - risk)) as u8,
+                                40,
+                                (60.0 + 100.0 * risk) as u8,
+                            ));
+                            core.wincan.fill_rect(rect!(
+                                x + 15 - grow,
+                                y + 15 - grow,
+                                size - 30,
+                                size - 30
+                            ))?;
+                        }
+                        core.wincan.copy_ex(
+                            coin.texture(),
+                            rect!(coin_anim * TILE_SIZE as i32, 0, TILE_SIZE, TILE_SIZE),
+                            rect!(x - grow, y - grow, size, size),
+                            0.0,
+                            None,
+                            false,
+                            false,
+                        )?;
+                        if settings.show_hitboxes {
+                            core.wincan.set_draw_color(Color::GREEN);
+                            core.wincan.draw_rect(coin.hitbox().as_rect())?;
+                        }
+                        Ok(())
+                    });
                 }
 
-                // Coins
-                for coin in all_coins.iter() {
-                    core.wincan.copy_ex(
-                        coin.texture(),
-                        rect!(coin_anim * TILE_SIZE as i32, 0, TILE_SIZE, TILE_SIZE),
-                        rect!(coin.x(), coin.y(), TILE_SIZE, TILE_SIZE),
-                        0.0,
-                        None,
-                        false,
-                        false,
-                    )?;
-                    core.wincan.set_draw_color(Color::GREEN);
-                    core.wincan.draw_rect(coin.hitbox().as_rect())?;
+                // Powerups (on the ground, not active or collected), each
+                // showing its icon over a pulsing glow of its color
+                let pulse = ((all_frames as f64 / 10.0).sin() + 1.0) / 2.0;
+                for power in world.powers.iter() {
+                    queue.push(power.layer(), move |core| {
+                        let glow = power.power_type().def().glow;
+                        for ring in 1..=3 {
+                            let grow = ring * 4 + (pulse * 6.0) as i32;
+                            let alpha = (90.0 - ring as f64 * 25.0 + pulse * 30.0) as u8;
+                            core.wincan.set_draw_color(Color::RGBA(glow.r, glow.g, glow.b, alpha));
+                            core.wincan.fill_rect(rect!(
+                                power.x() - grow,
+                                power.y() - grow,
+                                TILE_SIZE as i32 + 2 * grow,
+                                TILE_SIZE as i32 + 2 * grow
+                            ))?;
+                        }
+                        core.wincan
+                            .copy(power.texture(), None, rect!(power.x(), power.y(), TILE_SIZE, TILE_SIZE))?;
+                        if settings.show_hitboxes {
+                            core.wincan.set_draw_color(Color::YELLOW);
+                            core.wincan.draw_rect(power.hitbox().as_rect())?;
+                        }
+                        Ok(())
+                    });
                 }
 
-                // Powerups (on the ground, not active or collected)
-                for power in all_powers.iter() {
-                    core.wincan.copy_ex(
-                        power.texture(),
-                        rect!(0, 0, TILE_SIZE, TILE_SIZE),
-                        rect!(power.x(), power.y(), TILE_SIZE, TILE_SIZE),
-                        0.0,
-                        None,
-                        false,
-                        false,
+                // Decoration in front of it all, scrolling a little faster
+                queue.push(Layer::Foreground, |core| {
+                    for ground in terrain.iter() {
+                        for item in ground.decor() {
+                            let x = decor::screen_x(ground.x() + item.at, PLAYER_X);
+                            if x < -(TILE_SIZE as i32) || x >= view_w {
+                                continue;
+                            }
+                            let ground_y = get_ground_coord(terrain, x.max(0)).y();
+                            decor::draw(&mut core.wincan, item.kind, x, ground_y)?;
+                        }
+                    }
+                    Ok(())
+                });
+
+                // Practice runs show faint outlines of everything planned to
+                // spawn, in a strip at the right edge that runs from the edge
+                // of the screen now to PREVIEW_STEPS ahead, each standing on
+                // the terrain it'll arrive over
+                if practice {
+                    let scroll = player.vel_x().max(1.0);
+                    let planned = world.planner.planned();
+                    queue.push(Layer::Foreground, move |core| {
+                        let left = view_w - PREVIEW_W;
+                        core.wincan.set_draw_color(Color::RGBA(255, 255, 255, 20));
+                        core.wincan.fill_rect(rect!(left, 0, PREVIEW_W, CAM_H))?;
+                        for piece in planned.iter().filter(|p| p.steps < PREVIEW_STEPS) {
+                            let ground_y = match ground_ahead(terrain, piece.x_ahead(view_w - 1, scroll)) {
+                                Some(y) => y,
+                                None => continue,
+                            };
+                            let x = left + piece.steps.max(0) * PREVIEW_W / PREVIEW_STEPS;
+                            let y = ground_y - TILE_SIZE as i32 - piece.height;
+                            core.wincan.set_draw_color(match piece.object {
+                                StaticObject::Coin => Color::RGBA(255, 215, 0, 110),
+                                StaticObject::Power => Color::RGBA(120, 200, 255, 110),
+                                _ => Color::RGBA(255, 255, 255, 110),
+                            });
+                            core.wincan.draw_rect(rect!(x, y, TILE_SIZE, TILE_SIZE))?;
+                        }
+                        Ok(())
+                    });
+                }
+                queue.draw(core)?;
+
+                // Dark biomes are only lit around the player, wider with a
+                // lantern, and coins glow through the dark
+                if let Some(radius) = biome.def().light_radius {
+                    let radius = match player.power_up() {
+                        Some(PowerType::Lantern) => (radius as f64 * LANTERN_LIGHT_SCALE) as u32,
+                        _ => radius,
+                    };
+                    // Over the whole screen, not just the part the world was
+                    // drawn into
+                    core.wincan.set_viewport(None);
+                    let center = player.center();
+                    lighting::draw_darkness(
+                        &mut core.wincan,
+                        &tex_light,
+                        (
+                            center.x() + player_drawn.0 - player.x(),
+                            center.y() + player_drawn.1 - player.y(),
+                        ),
+                        radius,
+                        view_w as u32,
+                        CAM_H,
                     )?;
-                    core.wincan.set_draw_color(Color::YELLOW);
-                    core.wincan.draw_rect(power.hitbox().as_rect())?;
+                    for coin in world.coins.iter() {
+                        let (x, y) = coin.drawn_pos(alpha);
+                        lighting::draw_glow(&mut core.wincan, (x, y), TILE_SIZE, Color::RGB(255, 215, 0))?;
+                        core.wincan.copy(
+                            coin.texture(),
+                            rect!(coin_anim * TILE_SIZE as i32, 0, TILE_SIZE, TILE_SIZE),
+                            rect!(x, y, TILE_SIZE, TILE_SIZE),
+                        )?;
+                    }
+                }
+
+                // Mirrored and supersampled runs draw the world into a
+                // texture and copy it onto the window, flipped if it's
+                // mirrored, the HUD goes on top unflipped
+                profiler.begin(Scope::DrawHud);
+                core.wincan.set_viewport(None);
+                if let Some(target) = world_target.as_ref() {
+                    core.set_render_target(None)?;
+                    let source = rect!(0, 0, view_w as u32 * world_scale, CAM_H * world_scale);
+                    core.wincan
+                        .copy_ex(target, source, None, 0.0, None, loadout.is_mirrored(), false)?;
+                }
+
+                // With motion reduced an earthquake doesn't shake anything,
+                // it pulses bars along the top and bottom of the screen instead
+                if quaking && settings.reduce_motion {
+                    let pulse = ((all_frames as f64 / 6.0).sin() + 1.0) / 2.0;
+                    core.wincan
+                        .set_draw_color(Color::RGBA(150, 100, 50, (80.0 + 100.0 * pulse) as u8));
+                    core.wincan.fill_rect(rect!(0, 0, view_w, 16))?;
+                    core.wincan.fill_rect(rect!(0, CAM_H - 16, view_w, 16))?;
                 }
 
-                // Setup for the text of the total_score to be displayed
+                // Active Power HUD Display
+                if let Some(power) = player.power_up() {
+                    // Blinks while it's running out
+                    let blink =
+                        player.is_power_expiring() && (player.power_steps_left() / POWER_BLINK_STEPS).is_multiple_of(2);
+                    if !blink {
+                        core.wincan
+                            .copy(&tex_powers[power.index()], None, rect!(10, 100, TILE_SIZE, TILE_SIZE))?;
+                    }
+
+                    // Power duration bar
+                    let m = player.power_steps_left() as f64 / POWER_STEPS as f64;
+                    let r = 256.0 * (1.0 - m);
+                    let g = 256.0 * (m);
+                    let w = TILE_SIZE as f64 * m;
+                    core.wincan.set_draw_color(Color::RGB(r as u8, g as u8, 0));
+                    core.wincan.fill_rect(rect!(10, 210, w as u8, 10))?;
+                }
+
+                // Setup for the text of the score to be displayed
                 let tex_score = font
-                    .render(&format!("{:08}", total_score))
+                    .render(&format!("{:08}", score.total()))
                     .blended(Color::RGBA(255, 0, 0, 100))
                     .map_err(|e| e.to_string())?;
 
-                // Display total_score
+                // Display the score
                 let tex_score = texture_creator
                     .create_texture_from_surface(&tex_score)
                     .map_err(|e| e.to_string())?;
-                core.wincan.copy(&tex_score, None, Some(rect!(10, 10, 100, 50)))?;
+                // Grows a little while motes are landing in it
+                let grow = score_pulse.steps_left(&clock) as i32 * 2;
+                core.wincan.copy(
+                    &tex_score,
+                    None,
+                    Some(rect!(10 - grow, 10 - grow / 2, 100 + 2 * grow, 50 + grow)),
+                )?;
+                if quality.particles {
+                    motes.draw(&mut core.wincan)?;
+                }
+
+                // Display remaining lives, flashing while hits are ignored
+                if (hit_cooldown.steps_left(&clock) / 8).is_multiple_of(2) {
+                    core.wincan.set_draw_color(Color::RGBA(220, 30, 60, 255));
+                    for i in 0..lives {
+                        core.wincan
+                            .fill_rect(layout.place(Anchor::TopRight, (15 + i * 35, 20), 25, 25))?;
+                    }
+                }
 
                 // Display added coin value when coin is collected
                 let coin_surface = font
@@ -1004,28 +2043,298 @@ impl Game for Runner {
                     .create_texture_from_surface(&coin_surface)
                     .map_err(|e| e.to_string())?;
 
+                // Risk zone indicator
+                if in_risk_zone {
+                    core.wincan
+                        .copy(&tex_risk_hud, None, Some(layout.place(Anchor::Top, (0, 10), 300, 50)))?;
+                }
+
+                if let Some((milestone, _)) = milestone_banner.filter(|(_, shown)| !shown.is_done(&clock)) {
+                    let tex_milestone = texture_creator
+                        .create_texture_from_surface(
+                            &font
+                                .render(&format!("{} points!", milestone))
+                                .blended(Color::RGBA(255, 215, 0, 255))
+                                .map_err(|e| e.to_string())?,
+                        )
+                        .map_err(|e| e.to_string())?;
+                    core.wincan
+                        .copy(&tex_milestone, None, Some(layout.place(Anchor::Top, (0, 70), 360, 60)))?;
+                }
+
+                if let Some((bonus, _)) = gate_banner.filter(|(_, shown)| !shown.is_done(&clock)) {
+                    let tex_gate = texture_creator
+                        .create_texture_from_surface(
+                            &font
+                                .render(&format!("{}! +{}", biome.def().label, bonus))
+                                .blended(Color::RGBA(255, 215, 0, 255))
+                                .map_err(|e| e.to_string())?,
+                        )
+                        .map_err(|e| e.to_string())?;
+                    core.wincan
+                        .copy(&tex_gate, None, Some(layout.place(Anchor::Center, (0, -150), 420, 60)))?;
+                }
+
+                if let Some((event, _)) = event_banner.filter(|(_, shown)| !shown.is_done(&clock)) {
+                    let tex_event = texture_creator
+                        .create_texture_from_surface(
+                            &font
+                                .render(event.def().banner)
+                                .blended(Color::RGBA(255, 215, 0, 255))
+                                .map_err(|e| e.to_string())?,
+                        )
+                        .map_err(|e| e.to_string())?;
+                    let w = 24 * event.def().banner.len() as u32;
+                    core.wincan
+                        .copy(&tex_event, None, Some(layout.place(Anchor::Center, (0, -220), w, 60)))?;
+                }
+
+                if let Some((perfect, _)) = pump_banner.filter(|(_, shown)| !shown.is_done(&clock)) {
+                    let (text, color, w) = if perfect {
+                        ("Perfect pump!", Color::RGBA(255, 215, 0, 255), 260)
+                    } else {
+                        ("Pump", Color::RGBA(255, 255, 255, 255), 100)
+                    };
+                    let tex_pump = texture_creator
+                        .create_texture_from_surface(&font.render(text).blended(color).map_err(|e| e.to_string())?)
+                        .map_err(|e| e.to_string())?;
+                    core.wincan
+                        .copy(&tex_pump, None, Some(layout.place(Anchor::Top, (0, 140), w, 40)))?;
+                }
+
+                if let Some((grade, _)) = landing_banner.filter(|(_, shown)| !shown.is_done(&clock)) {
+                    let color = match grade {
+                        LandingGrade::Perfect => Color::RGBA(255, 215, 0, 255),
+                        LandingGrade::Good => Color::RGBA(255, 255, 255, 255),
+                        LandingGrade::Sloppy => Color::RGBA(255, 120, 80, 255),
+                    };
+                    let tex_landing = texture_creator
+                        .create_texture_from_surface(
+                            &font.render(grade.label()).blended(color).map_err(|e| e.to_string())?,
+                        )
+                        .map_err(|e| e.to_string())?;
+                    let w = 20 * grade.label().len() as u32;
+                    core.wincan
+                        .copy(&tex_landing, None, Some(layout.place(Anchor::Top, (0, 190), w, 40)))?;
+                }
+
+                // Callout card introducing an obstacle
+                if let Some((intro, _)) = callout.filter(|(_, shown)| !shown.is_done(&clock)) {
+                    let card = layout.place(Anchor::Top, (0, 250), 760, 130);
+                    core.wincan.set_draw_color(Color::RGBA(0, 0, 0, 180));
+                    core.wincan.fill_rect(card)?;
+                    core.wincan.set_draw_color(Color::RGBA(255, 215, 0, 255));
+                    core.wincan.draw_rect(card)?;
+                    let tex_title = texture_creator
+                        .create_texture_from_surface(
+                            &font
+                                .render(intro.title)
+                                .blended(Color::RGBA(255, 215, 0, 255))
+                                .map_err(|e| e.to_string())?,
+                        )
+                        .map_err(|e| e.to_string())?;
+                    let w = 26 * intro.title.len() as u32;
+                    core.wincan
+                        .copy(&tex_title, None, Some(layout.place(Anchor::Top, (0, 265), w, 50)))?;
+                    let tex_hint = texture_creator
+                        .create_texture_from_surface(
+                            &font
+                                .render(intro.hint)
+                                .blended(Color::RGBA(255, 255, 255, 255))
+                                .map_err(|e| e.to_string())?,
+                        )
+                        .map_err(|e| e.to_string())?;
+                    let w = (13 * intro.hint.len() as u32).min(740);
+                    core.wincan
+                        .copy(&tex_hint, None, Some(layout.place(Anchor::Top, (0, 330), w, 30)))?;
+                }
+
                 // Only show right after collecting a coin
-                if coin_timer > 0 {
+                if !coin_timer.is_done(&clock) {
                     core.wincan.copy(&tex_coin_val, None, Some(rect!(10, 50, 100, 50)))?;
-                    coin_timer -= 1;
+                }
+
+                // Pace against the raced run, positive when behind
+                if let Some(code) = &race {
+                    let pace = match code.steps_at(splits.distance()) {
+                        Some(their_steps) => {
+                            let delta = (splits.steps() as f64 - their_steps) / SIM_FPS;
+                            let color = if delta > 0.0 {
+                                Color::RGBA(255, 60, 60, 255)
+                            } else {
+                                Color::RGBA(60, 255, 60, 255)
+                            };
+                            Some((format!("{:+.2}s", delta), color))
+                        }
+                        None if splits.distance() > code.distance() => {
+                            Some(("Beat their run!".to_string(), Color::RGBA(255, 215, 0, 255)))
+                        }
+                        None => None,
+                    };
+                    if let Some((text, color)) = pace {
+                        let tex_pace = texture_creator
+                            .create_texture_from_surface(&font.render(&text).blended(color).map_err(|e| e.to_string())?)
+                            .map_err(|e| e.to_string())?;
+                        let w = 25 * text.len() as u32;
+                        core.wincan
+                            .copy(&tex_pace, None, Some(layout.place(Anchor::Top, (0, 65), w, 45)))?;
+                    }
                 }
 
                 if game_over {
                     // Cleaned up calculation of texture position
                     // Check previous versions if you want those calculations
+                    core.wincan.copy(
+                        &game_over_texture,
+                        None,
+                        Some(layout.place(Anchor::Center, (0, 0), 801, 149)),
+                    )?;
+                }
+                if game_over && !demo && playback.is_none() {
+                    // Share code for this run, also put on the clipboard
+                    let code = share_code.get_or_insert_with(|| {
+                        let code = splits.to_code(run_seed).encode();
+                        info!("Share code: {}", code);
+                        if let Err(e) = core.wincan.window().subsystem().clipboard().set_clipboard_text(&code) {
+                            warn!("Could not copy share code: {}", e);
+                        }
+                        code
+                    });
+                    let tex_code = texture_creator
+                        .create_texture_from_surface(
+                            &font
+                                .render(&format!("Share code (copied): {}", code))
+                                .blended(Color::RGBA(255, 255, 255, 255))
+                                .map_err(|e| e.to_string())?,
+                        )
+                        .map_err(|e| e.to_string())?;
+                    let TextureQuery { width, height, .. } = tex_code.query();
+                    let w = ((width as f64 * 30.0 / height as f64) as u32).min(view_w as u32 - 40);
+                    core.wincan
+                        .copy(&tex_code, None, Some(layout.place(Anchor::Center, (0, 105), w, 30)))?;
+                }
+
+                // Blinking demo banner
+                if demo && all_frames / 40 % 2 == 0 {
                     core.wincan
-                        .copy(&game_over_texture, None, Some(rect!(239, 285, 801, 149)))?;
+                        .copy(&tex_demo, None, Some(layout.place(Anchor::Top, (0, 120), 240, 90)))?;
+                }
+                if assist || self.bot {
+                    core.wincan.copy(
+                        &tex_assist,
+                        None,
+                        Some(layout.place(Anchor::BottomLeft, (10, 10), 140, 40)),
+                    )?;
+                }
+
+                if let Footing::Stuck { meter } = player.footing() {
+                    draw_mud_meter(core, &font, &texture_creator, player.hitbox(), meter)?;
+                }
+
+                if show_inputs {
+                    draw_input_display(core, &font, &texture_creator, &input_display)?;
+                }
+
+                if let Some(held_since) = restart_held {
+                    let fill = held_since.elapsed().as_secs_f64() / QUICK_RESTART_SECS;
+                    draw_restart_hold(core, &font, &texture_creator, fill)?;
+                }
+
+                if let Some(playback) = &playback {
+                    draw_replay_bar(
+                        core,
+                        &font,
+                        &texture_creator,
+                        clock.step(),
+                        playback.log().end(),
+                        PLAYBACK_SPEEDS[playback_speed],
+                        playback_paused,
+                    )?;
+                }
+
+                if show_profiler {
+                    draw_profiler(
+                        core,
+                        &font,
+                        &texture_creator,
+                        &profiler,
+                        frame_cap,
+                        world.pool.memory(&world.terrain),
+                    )?;
+                }
+                if show_profiler {
+                    draw_spawn_heatmap(core, &font, &texture_creator, &spawn_heatmap)?;
+                }
+                if show_profiler || frame_cap == FrameCap::Uncapped {
+                    draw_fps_graph(core, &font, &texture_creator, &profiler)?;
+                }
+
+                if let Some((text, _)) = report_notice
+                    .as_ref()
+                    .filter(|(_, shown)| shown.elapsed().as_secs_f64() < REPORT_NOTICE_SECS)
+                {
+                    let tex_notice = texture_creator
+                        .create_texture_from_surface(
+                            &font.render(text).blended(Color::WHITE).map_err(|e| e.to_string())?,
+                        )
+                        .map_err(|e| e.to_string())?;
+                    let w = text.len() as u32 * 14;
+                    core.wincan.set_draw_color(Color::RGBA(0, 0, 0, 160));
+                    core.wincan
+                        .fill_rect(layout.place(Anchor::Bottom, (0, -20), w + 20, 44))?;
+                    core.wincan
+                        .copy(&tex_notice, None, Some(layout.place(Anchor::Bottom, (0, -24), w, 36)))?;
+                }
+
+                if capture_report {
+                    capture_report = false;
+                    let mut report = BugReport::new();
+                    if let Err(e) = report.add_screenshot(&core.wincan) {
+                        log::warn!("Bug report left out the screenshot: {}", e);
+                    }
+                    let run = format!(
+                        "Urban Odyssey {}\nSeed: {}\nStep: {}\nDistance: {}\nPlayer: x {} y {}, velocity ({:.3}, {:.3}), power {:?}\nBiome: {}\nReplay: {}\nGame over: {}\n",
+                        env!("CARGO_PKG_VERSION"),
+                        run_seed,
+                        clock.step(),
+                        splits.distance(),
+                        player.x(),
+                        player.y(),
+                        player.vel_x(),
+                        player.vel_y(),
+                        player.power_up(),
+                        biome.def().label,
+                        playback.is_some(),
+                        game_over,
+                    );
+                    report.add("run.txt", run.into_bytes());
+                    report.add("events.txt", crash::context().into_bytes());
+                    report.add("inputs.txt", input_log.encode().into_bytes());
+                    let notice = match report.write() {
+                        Ok(path) => {
+                            log::info!("Saved a bug report to {}", path.display());
+                            format!("Bug report saved to {}", path.display())
+                        }
+                        Err(e) => {
+                            log::error!("Couldn't save a bug report: {}", e);
+                            "Couldn't save the bug report".to_string()
+                        }
+                    };
+                    report_notice = Some((notice, Instant::now()));
                 }
 
+                profiler.begin(Scope::Present);
                 core.wincan.present();
+                profiler.end_frame();
                 /* ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~ */
 
                 /* ~~~~~~ FPS Calculation ~~~~~~ */
                 // Time taken to display the last frame
                 let raw_frame_time = last_raw_time.elapsed().as_secs_f64();
-                let delay = FRAME_TIME - raw_frame_time;
+                let delay = frame_cap.frame_time().unwrap_or(0.0) - raw_frame_time;
                 // If the amount of time to display the last frame was less than expected, sleep
-                // until the expected amount of time has passed
+                // until the expected amount of time has passed. The SimClock still decides how
+                // many steps run, this only holds drawing to the cap.
                 if delay > 0.0 {
                     // Using sleep to delay will always cause slightly more delay than intended due
                     // to CPU scheduling; possibly find a better way to delay
@@ -1035,55 +2344,654 @@ impl Game for Runner {
                 let time_since_last_measurement = last_measurement_time.elapsed();
                 // Measures the FPS once per second
                 if time_since_last_measurement > Duration::from_secs(1) {
-                    //println!("{} FPS", all_frames);
-                    // println!(
-                    //     "Average FPS: {:.2}",
-                    //     (all_frames as f64) / time_since_last_measurement.as_secs_f64()
-                    // );
+                    debug!(
+                        "Average FPS: {:.2}",
+                        (all_frames as f64) / time_since_last_measurement.as_secs_f64()
+                    );
                     all_frames = 0;
                     last_measurement_time = Instant::now();
                 }
                 /* ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~ */
             }
+        } // End gameloop
+        core.reset_view()?;
 
-            /* ~~~~~~ Helper Functions ~~~~~ */
-            // Given the current terrain and an x coordinate of the screen,
-            // returns the (x, y) of the ground at that x
-            fn get_ground_coord(all_terrain: &Vec<TerrainSegment>, screen_x: i32) -> Point {
-                // Loop backwards
-                for ground in all_terrain.iter().rev() {
-                    // The first segment starting at or behind
-                    // the given x, which it must be above
-                    if ground.x() <= screen_x {
-                        let point_ind: usize = (screen_x - ground.x()) as usize;
-                        return Point::new(
-                            ground.curve().get(point_ind).unwrap().0,
-                            ground.curve().get(point_ind).unwrap().1,
-                        );
-                    }
-                }
-                return Point::new(-1, -1);
+        info!("Run ended with score {}", score.total());
+        if let Some(cause) = death_cause {
+            info!("Cause of death: {:?}", cause);
+            crash::event(&format!("Died: {:?}", cause));
+        }
+        crash::end_run();
+        // Nothing more should play while the game shuts down
+        if next_status == GameStatus::Quit {
+            sound.stop();
+        }
+
+        if self.bot {
+            info!(
+                "Bot run: seed {}, score {}, distance {}, {} steps",
+                run_seed,
+                score.total(),
+                splits.distance(),
+                splits.steps()
+            );
+            // Only a game over restarts, quitting or the menu still work
+            if game_over && next_status == GameStatus::Main {
+                next_status = GameStatus::Game;
             }
-            // Given the current terrain and an x coordinate of the screen,
-            // returns the (x, y) of the ground at that x
-            fn get_ground_type(all_terrain: &Vec<TerrainSegment>, screen_x: i32) -> &TerrainType {
-                // Loop backwards
-                for ground in all_terrain.iter().rev() {
-                    // The first segment starting at or behind
-                    // the given x, which it must be above
-                    if ground.x() <= screen_x {
-                        let point_ind: usize = (screen_x - ground.x()) as usize;
-                        return ground.get_type();
-                    }
+        }
+
+        // Replays don't count for anything, restarting or seeking back plays
+        // the same log again
+        if let Some(playback) = playback {
+            let restart_from = match replay_restart {
+                Some(step) => Some(step),
+                None if next_status == GameStatus::Game => Some(0),
+                None => None,
+            };
+            return Ok(match restart_from {
+                Some(step) => GameState::ToGame(GameConfig {
+                    replay: Some(playback.log().clone()),
+                    replay_from: step,
+                    ..GameConfig::default()
+                }),
+                None => GameState::to(next_status),
+            });
+        }
+
+        if demo || self.bot {
+            return Ok(GameState::to(next_status));
+        }
+
+        // Runs that ended in a game over go on to their results
+        input_log.finish(clock.step());
+        // The coin bonus scores like everything else
+        score.add(ScoreSource::CoinBonus, coins_collected * COIN_BONUS);
+        score.settle(1, loadout.score_scale());
+        let board = loadout.board();
+        let previous_best = progress.best_score(board);
+        let mut stats = RunStats {
+            seed: run_seed,
+            score: score.total(),
+            ledger: score.ledger(),
+            coins: coins_collected,
+            golden_vaults,
+            distance: splits.distance(),
+            steps: clock.step(),
+            death_cause,
+            ranked: !assist_used,
+            auto_jump,
+            previous_best,
+            new_best: false,
+            best_verified: true,
+            best_auto_jump: false,
+            inputs: input_log.clone(),
+            score_scale: loadout.score_scale(),
+            config: restart_config,
+        };
+        let run_total = stats.total();
+        stats.new_best = stats.ranked && run_total > previous_best;
+        stats.best_verified = stats.new_best || progress.best_verified(board);
+        stats.best_auto_jump = if stats.new_best {
+            auto_jump
+        } else {
+            progress.best_auto_jump(board)
+        };
+        let next_state = if quick_restart {
+            // Straight into the next run, on this seed again if the
+            // settings say so
+            let seed = if settings.restart_same_seed {
+                Some(run_seed)
+            } else {
+                None
+            };
+            GameState::ToGame(GameConfig {
+                seed,
+                ..stats.config.clone()
+            })
+        } else if game_over && next_status == GameStatus::Main {
+            GameState::ToResults(stats)
+        } else {
+            GameState::to(next_status)
+        };
+
+        // Assisted runs don't count for anything
+        if assist_used {
+            return Ok(next_state);
+        }
+
+        progress.bank_coins(coins_collected as u32);
+        progress.add_golden_vaults(golden_vaults as u32);
+        let mut progress_changed = coins_collected > 0 || golden_vaults > 0;
+        if progress.record_score(board, run_total, &input_log) {
+            info!("New personal best on {}: {} (was {})", board, run_total, previous_best);
+            progress_changed = true;
+        }
+        if score.total() >= progress::DRONE_UNLOCK_SCORE && progress.unlock_drone() {
+            info!("Unlocked the companion drone");
+            announcer.announce("Companion drone unlocked");
+            progress_changed = true;
+        }
+        if progress_changed {
+            if let Err(e) = progress.save() {
+                warn!("Could not save progress: {}", e);
+            }
+        }
+
+        // Runs that ended in a game over are up for the high score table
+        if game_over {
+            let mut high_scores = HighScores::load(Profile::current().as_ref());
+            if let Some(place) = high_scores.add(run_total, run_seed) {
+                info!("High score #{}: {}", place + 1, run_total);
+                if let Err(e) = high_scores.save() {
+                    warn!("Could not save high scores: {}", e);
                 }
-                return &TerrainType::Grass; //default to grass
             }
-            /* ~~~~~~~~~~~~~~~~~~~~~~~~~~~~~ */
-        } // End gameloop
+        }
 
-        Ok(GameState {
-            status: Some(next_status),
-            score: total_score,
-        })
+        Ok(next_state)
     } // End run fn
 } // End impl
+
+// Everything behind the terrain: sky, sunset gradient, city skyline and the
+// perlin hill layers
+#[allow(clippy::too_many_arguments)]
+fn draw_background<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    tex_sky: &Texture,
+    tex_grad: &Texture,
+    tex_bg: &Texture,
+    bg_buff: i32,
+    parallax: &Parallax,
+    quality: &Quality,
+    view_w: i32,
+) -> Result<(), String> {
+    // Wipe screen every frame
+    canvas.set_draw_color(Color::RGBA(3, 120, 206, 255));
+    canvas.clear();
+
+    // Bottom layer of background, black skybox
+    canvas.set_draw_color(Color::RGBA(0, 0, 0, 255));
+    canvas.fill_rect(rect!(0, 470, view_w, CAM_H))?;
+
+    // Sky and background images are CAM_W wide, tiled across wider views
+    let tiles = view_w / CAM_W as i32 + 2;
+    for i in 0..tiles {
+        canvas.copy(tex_sky, None, rect!(i * CAM_W as i32 + bg_buff, 0, CAM_W, CAM_H / 3))?;
+    }
+
+    // Sunset gradient - doesn't need to scroll left
+    if quality.sunset_gradient {
+        canvas.copy(tex_grad, None, rect!(0, -128, view_w, CAM_H))?;
+    }
+
+    // Background
+    for i in 0..tiles {
+        canvas.copy(tex_bg, None, rect!(i * CAM_W as i32 + bg_buff, -150, CAM_W, CAM_H))?;
+    }
+
+    // Background perlin noise curves
+    if !quality.parallax {
+        return Ok(());
+    }
+    let columns = (view_w as usize / BG_COLUMN_W).min(BG_CURVES_SIZE - 1);
+    // Back to front, so nearer layers cover the ones behind
+    for (color, heights) in parallax.layers() {
+        canvas.set_draw_color(color);
+        for (i, h) in heights.iter().enumerate().take(columns) {
+            canvas.fill_rect(rect!(
+                i * BG_COLUMN_W + BG_COLUMN_W / 2,
+                CAM_H as i16 - h,
+                BG_COLUMN_W,
+                CAM_H as i16
+            ))?;
+        }
+    }
+    Ok(())
+}
+
+// Graph of recent frame times along the bottom of the screen, one bar per
+// frame, with lines at the 30/60/120 FPS marks
+fn draw_fps_graph(
+    core: &mut SDLCore,
+    font: &Font,
+    texture_creator: &TextureCreator<WindowContext>,
+    profiler: &Profiler,
+) -> Result<(), String> {
+    let graph_h: f64 = 120.0;
+    let graph_w = profiler::HISTORY_LEN as i32 * 2;
+    let graph_x = core.cam.width() as i32 - graph_w - 10;
+    let graph_y = CAM_H as i32 - graph_h as i32 - 10;
+    // Top of the graph is 30 FPS, anything slower is clipped
+    let max_ms = 1000.0 / 30.0;
+
+    core.wincan.set_draw_color(Color::RGBA(0, 0, 0, 160));
+    core.wincan.fill_rect(rect!(graph_x, graph_y, graph_w, graph_h))?;
+
+    for (i, ms) in profiler.history().enumerate() {
+        let h = (ms / max_ms).min(1.0) * graph_h;
+        let color = if ms > 1000.0 / 59.0 {
+            Color::RED
+        } else if ms > 1000.0 / 119.0 {
+            Color::YELLOW
+        } else {
+            Color::GREEN
+        };
+        core.wincan.set_draw_color(color);
+        core.wincan.fill_rect(rect!(
+            graph_x + i as i32 * 2,
+            graph_y + (graph_h - h) as i32,
+            2,
+            h.max(1.0)
+        ))?;
+    }
+
+    core.wincan.set_draw_color(Color::WHITE);
+    for fps in [60.0, 120.0].iter() {
+        let y = graph_y + (graph_h - (1000.0 / fps) / max_ms * graph_h) as i32;
+        core.wincan
+            .draw_line(Point::new(graph_x, y), Point::new(graph_x + graph_w, y))?;
+    }
+
+    let count = profiler.history().count().max(1);
+    let avg_ms = profiler.history().sum::<f64>() / count as f64;
+    let surface = font
+        .render(&format!("{:6.1} FPS", if avg_ms > 0.0 { 1000.0 / avg_ms } else { 0.0 }))
+        .blended(Color::WHITE)
+        .map_err(|e| e.to_string())?;
+    let texture = texture_creator
+        .create_texture_from_surface(&surface)
+        .map_err(|e| e.to_string())?;
+    core.wincan
+        .copy(&texture, None, Some(rect!(graph_x, graph_y - 22, 120, 20)))?;
+
+    Ok(())
+}
+
+// Spawns per second over the last minute as a strip over the ground, hotter
+// colors for busier seconds, with the spawn gap and pattern chance at the time
+// drawn over it as lines
+fn draw_spawn_heatmap(
+    core: &mut SDLCore,
+    font: &Font,
+    texture_creator: &TextureCreator<WindowContext>,
+    heatmap: &SpawnHeatmap,
+) -> Result<(), String> {
+    let col_w = 8;
+    let strip_h = 60.0;
+    let strip_w = heatmap::BUCKETS as i32 * col_w;
+    let strip_x = 10;
+    let strip_y = CAM_H as i32 - strip_h as i32 - 10;
+    // Gaps are drawn against the slowest the curve gets, chances against 50%
+    let max_gap = 500.0;
+    let max_chance = 0.5;
+
+    core.wincan.set_draw_color(Color::RGBA(0, 0, 0, 160));
+    core.wincan.fill_rect(rect!(strip_x, strip_y, strip_w, strip_h))?;
+
+    let peak = heatmap.peak().max(1) as f64;
+    let mut gap_line = Vec::new();
+    let mut chance_line = Vec::new();
+    for (i, bucket) in heatmap.buckets().enumerate() {
+        let x = strip_x + i as i32 * col_w;
+        let heat = bucket.spawns() as f64 / peak;
+        if bucket.spawns() > 0 {
+            core.wincan.set_draw_color(Color::RGBA(
+                (255.0 * heat) as u8,
+                (80.0 * (1.0 - heat)) as u8,
+                (255.0 * (1.0 - heat)) as u8,
+                200,
+            ));
+            core.wincan.fill_rect(rect!(x, strip_y, col_w, strip_h))?;
+        }
+        let mid = x + col_w / 2;
+        gap_line.push(Point::new(
+            mid,
+            strip_y + (strip_h * (1.0 - (bucket.spawn_gap as f64 / max_gap).min(1.0))) as i32,
+        ));
+        chance_line.push(Point::new(
+            mid,
+            strip_y + (strip_h * (1.0 - (bucket.pattern_chance / max_chance).min(1.0))) as i32,
+        ));
+    }
+    core.wincan.set_draw_color(Color::WHITE);
+    core.wincan.draw_lines(&gap_line[..])?;
+    core.wincan.set_draw_color(Color::YELLOW);
+    core.wincan.draw_lines(&chance_line[..])?;
+
+    let latest = heatmap.buckets().last().copied().unwrap_or_default();
+    let surface = font
+        .render(&format!(
+            "spawns/s peak {}  gap {} (white)  pattern {:.0}% (yellow)",
+            heatmap.peak(),
+            latest.spawn_gap,
+            latest.pattern_chance * 100.0
+        ))
+        .blended(Color::WHITE)
+        .map_err(|e| e.to_string())?;
+    let texture = texture_creator
+        .create_texture_from_surface(&surface)
+        .map_err(|e| e.to_string())?;
+    core.wincan
+        .copy(&texture, None, Some(rect!(strip_x, strip_y - 22, strip_w, 20)))?;
+
+    Ok(())
+}
+
+// Key caps for the gameplay actions in the bottom right, lit while they're
+// pressed, above where the FPS graph goes
+// Ring that fills clockwise while R is held for a quick restart
+fn draw_restart_hold(
+    core: &mut SDLCore,
+    font: &Font,
+    texture_creator: &TextureCreator<WindowContext>,
+    fill: f64,
+) -> Result<(), String> {
+    let layout = core.layout();
+    let size = 90;
+    let area = layout.place(Anchor::Center, (0, 0), size, size);
+    let center = area.center();
+    let (outer, inner) = (size as f64 / 2.0, size as f64 / 2.0 - 14.0);
+
+    core.wincan.set_draw_color(Color::RGBA(0, 0, 0, 140));
+    core.wincan.fill_rect(area)?;
+    // One spoke per degree filled, from the top
+    core.wincan.set_draw_color(Color::RGBA(255, 215, 0, 230));
+    let degrees = (fill.clamp(0.0, 1.0) * 360.0) as i32;
+    for degree in 0..degrees {
+        let angle = (degree as f64 - 90.0).to_radians();
+        let (cos, sin) = (angle.cos(), angle.sin());
+        core.wincan.draw_line(
+            Point::new(center.x() + (cos * inner) as i32, center.y() + (sin * inner) as i32),
+            Point::new(center.x() + (cos * outer) as i32, center.y() + (sin * outer) as i32),
+        )?;
+    }
+
+    let surface = font.render("R").blended(Color::WHITE).map_err(|e| e.to_string())?;
+    let texture = texture_creator
+        .create_texture_from_surface(&surface)
+        .map_err(|e| e.to_string())?;
+    core.wincan
+        .copy(&texture, None, Some(rect!(center.x() - 12, center.y() - 20, 24, 40)))?;
+    Ok(())
+}
+
+// Escape meter over the player while mud has them stuck
+fn draw_mud_meter(
+    core: &mut SDLCore,
+    font: &Font,
+    texture_creator: &TextureCreator<WindowContext>,
+    player: PhysRect,
+    meter: f64,
+) -> Result<(), String> {
+    let (w, h) = (100, 12);
+    let x = player.center().x() - w / 2;
+    let y = player.y() - 30;
+    core.wincan.set_draw_color(Color::RGBA(0, 0, 0, 160));
+    core.wincan.fill_rect(rect!(x - 2, y - 2, w + 4, h + 4))?;
+    core.wincan.set_draw_color(Color::RGB(230, 170, 60));
+    core.wincan
+        .fill_rect(rect!(x, y, (w as f64 * meter.clamp(0.0, 1.0)) as i32, h))?;
+
+    let surface = font
+        .render("Mash jump!")
+        .blended(Color::WHITE)
+        .map_err(|e| e.to_string())?;
+    let texture = texture_creator
+        .create_texture_from_surface(&surface)
+        .map_err(|e| e.to_string())?;
+    core.wincan.copy(&texture, None, Some(rect!(x, y - 30, w, 26)))?;
+    Ok(())
+}
+
+fn draw_input_display(
+    core: &mut SDLCore,
+    font: &Font,
+    texture_creator: &TextureCreator<WindowContext>,
+    display: &InputDisplay,
+) -> Result<(), String> {
+    let layout = core.layout();
+    let (cap_w, cap_h) = (110, 50);
+    for (i, action) in InputDisplay::ACTIONS.iter().enumerate() {
+        let offset = (
+            10 + (InputDisplay::ACTIONS.len() - 1 - i) as i32 * (cap_w as i32 + 10),
+            170,
+        );
+        let cap = layout.place(Anchor::BottomRight, offset, cap_w, cap_h);
+        let lit = display.is_lit(*action);
+        core.wincan.set_draw_color(if lit {
+            Color::RGBA(255, 215, 0, 230)
+        } else {
+            Color::RGBA(0, 0, 0, 160)
+        });
+        core.wincan.fill_rect(cap)?;
+        core.wincan.set_draw_color(Color::WHITE);
+        core.wincan.draw_rect(cap)?;
+
+        let label = match action {
+            Action::Jump => "Jump",
+            _ => "Fall",
+        };
+        let surface = font
+            .render(label)
+            .blended(if lit { Color::BLACK } else { Color::WHITE })
+            .map_err(|e| e.to_string())?;
+        let texture = texture_creator
+            .create_texture_from_surface(&surface)
+            .map_err(|e| e.to_string())?;
+        core.wincan.copy(
+            &texture,
+            None,
+            Some(rect!(cap.x() + 15, cap.y() + 8, cap_w - 30, cap_h - 16)),
+        )?;
+    }
+    Ok(())
+}
+
+// Replay progress along the bottom of the view, with the playback controls
+fn draw_replay_bar(
+    core: &mut SDLCore,
+    font: &Font,
+    texture_creator: &TextureCreator<WindowContext>,
+    step: u32,
+    end: Option<u32>,
+    speed: f64,
+    paused: bool,
+) -> Result<(), String> {
+    let layout = core.layout();
+    let bar_w = layout.width() as u32 - 200;
+    let bar = layout.place(Anchor::Bottom, (0, 20), bar_w, 10);
+    let clock = |step: u32| {
+        let secs = step / SIM_FPS as u32;
+        format!("{}:{:02}", secs / 60, secs % 60)
+    };
+
+    core.wincan.set_draw_color(Color::RGBA(0, 0, 0, 160));
+    core.wincan.fill_rect(bar)?;
+    if let Some(end) = end.filter(|&end| end > 0) {
+        let filled = (bar_w as f64 * (step.min(end) as f64 / end as f64)) as u32;
+        core.wincan.set_draw_color(Color::RGBA(255, 255, 255, 220));
+        core.wincan
+            .fill_rect(rect!(bar.x(), bar.y(), filled.max(1), bar.height()))?;
+        // Playhead
+        core.wincan
+            .fill_rect(rect!(bar.x() + filled as i32 - 3, bar.y() - 6, 6, bar.height() + 12))?;
+    }
+
+    let text = format!(
+        "REPLAY{}  {} / {}  {}x   Space - Pause  -/+ - Speed  Left/Right - Seek  R - Restart",
+        if paused { " (paused)" } else { "" },
+        clock(step),
+        end.map(clock).unwrap_or_else(|| "?".to_string()),
+        speed
+    );
+    let surface = font
+        .render(&text)
+        .blended(Color::RGBA(255, 255, 255, 255))
+        .map_err(|e| e.to_string())?;
+    let texture = texture_creator
+        .create_texture_from_surface(&surface)
+        .map_err(|e| e.to_string())?;
+    let TextureQuery { width, height, .. } = texture.query();
+    let w = ((width as f64 * 24.0 / height as f64) as u32).min(bar_w);
+    core.wincan
+        .copy(&texture, None, Some(rect!(bar.x(), bar.y() - 34, w, 24)))?;
+    Ok(())
+}
+
+// Panel on the pause screen summing up the run so far, with the progress of
+// the mission it's working towards
+fn draw_run_info(
+    core: &mut SDLCore,
+    font: &Font,
+    texture_creator: &TextureCreator<WindowContext>,
+    stats: &RunStats,
+    mission: &str,
+) -> Result<(), String> {
+    let layout = core.layout();
+    let panel = layout.place(Anchor::Right, (40, 60), 380, 300);
+    core.wincan.set_draw_color(Color::RGBA(0, 0, 0, 170));
+    core.wincan.fill_rect(panel)?;
+
+    let secs = stats.steps / SIM_FPS as u32;
+    let lines = [
+        format!("Seed {}", stats.seed),
+        format!("Distance {}", stats.distance),
+        format!("Coins {}", stats.coins),
+        mission.to_string(),
+        format!(
+            "Difficulty tier {} / {}",
+            proceduralgen::difficulty_tier(stats.score),
+            proceduralgen::DIFFICULTY_TIERS
+        ),
+        format!("Time {}:{:02}", secs / 60, secs % 60),
+    ];
+    for (i, line) in lines.iter().enumerate() {
+        let surface = font
+            .render(line)
+            .blended(Color::RGBA(255, 255, 255, 255))
+            .map_err(|e| e.to_string())?;
+        let texture = texture_creator
+            .create_texture_from_surface(&surface)
+            .map_err(|e| e.to_string())?;
+        let TextureQuery { width, height, .. } = texture.query();
+        let w = ((width as f64 * 30.0 / height as f64) as u32).min(panel.width() - 40);
+        core.wincan.copy(
+            &texture,
+            None,
+            Some(rect!(panel.x() + 20, panel.y() + 25 + i as i32 * 44, w, 30)),
+        )?;
+    }
+    Ok(())
+}
+
+// Debug overlay for the profiler: one stacked bar where the full width is one
+// frame's time budget, plus a millisecond readout per subsystem
+fn draw_profiler(
+    core: &mut SDLCore,
+    font: &Font,
+    texture_creator: &TextureCreator<WindowContext>,
+    profiler: &Profiler,
+    frame_cap: FrameCap,
+    terrain_memory: TerrainMemory,
+) -> Result<(), String> {
+    let bar_w: f64 = 400.0;
+    let bar_x = core.cam.width() as i32 - bar_w as i32 - 10;
+    let bar_y = 10;
+    let ms_budget = frame_cap.frame_time().unwrap_or(SIM_STEP) * 1000.0;
+
+    // Backing panel
+    core.wincan.set_draw_color(Color::RGBA(0, 0, 0, 160));
+    core.wincan.fill_rect(rect!(
+        bar_x - 5,
+        bar_y - 5,
+        bar_w + 10.0,
+        30 + 20 * (Scope::ALL.len() + 2)
+    ))?;
+
+    let mut x = bar_x as f64;
+    for (i, scope) in Scope::ALL.iter().enumerate() {
+        let color = profiler_color(i);
+        let w = profiler.millis(*scope) / ms_budget * bar_w;
+        core.wincan.set_draw_color(color);
+        core.wincan.fill_rect(rect!(x, bar_y, w.max(1.0), 20))?;
+        x += w;
+
+        let surface = font
+            .render(&format!("{:<10} {:5.2} ms", scope.name(), profiler.millis(*scope)))
+            .blended(color)
+            .map_err(|e| e.to_string())?;
+        let texture = texture_creator
+            .create_texture_from_surface(&surface)
+            .map_err(|e| e.to_string())?;
+        core.wincan
+            .copy(&texture, None, Some(rect!(bar_x, bar_y + 25 + 20 * i as i32, 200, 18)))?;
+    }
+
+    // Frame budget outline, anything past it is a dropped frame
+    core.wincan.set_draw_color(Color::WHITE);
+    core.wincan.draw_rect(rect!(bar_x, bar_y, bar_w, 20))?;
+
+    let surface = font
+        .render(&format!("{:<10} {:5.2} ms", "frame", profiler.total_millis()))
+        .blended(Color::WHITE)
+        .map_err(|e| e.to_string())?;
+    let texture = texture_creator
+        .create_texture_from_surface(&surface)
+        .map_err(|e| e.to_string())?;
+    core.wincan.copy(
+        &texture,
+        None,
+        Some(rect!(bar_x, bar_y + 25 + 20 * Scope::ALL.len() as i32, 200, 18)),
+    )?;
+
+    // Terrain memory, live segments plus spare curve buffers in the pool
+    let surface = font
+        .render(&format!(
+            "{:<10} {} segs {:4} KiB (+{} KiB pooled)",
+            "terrain",
+            terrain_memory.live_segments,
+            terrain_memory.live_bytes / 1024,
+            terrain_memory.pooled_bytes / 1024
+        ))
+        .blended(Color::WHITE)
+        .map_err(|e| e.to_string())?;
+    let texture = texture_creator
+        .create_texture_from_surface(&surface)
+        .map_err(|e| e.to_string())?;
+    core.wincan.copy(
+        &texture,
+        None,
+        Some(rect!(bar_x, bar_y + 25 + 20 * (Scope::ALL.len() as i32 + 1), 390, 18)),
+    )?;
+
+    Ok(())
+}
+
+// Outline of an entity's hitbox in `color`, with each named piece of it inside
+fn draw_hitboxes<'a>(core: &mut SDLCore, entity: &impl Entity<'a>, color: Color) -> Result<(), String> {
+    core.wincan.set_draw_color(color);
+    core.wincan.draw_rect(entity.hitbox().as_rect())?;
+    for (part, rect) in entity.hitboxes() {
+        core.wincan.set_draw_color(match part {
+            HitboxPart::Body => Color::RGB(255, 140, 0),
+            HitboxPart::Head => Color::MAGENTA,
+            HitboxPart::Feet => Color::CYAN,
+        });
+        core.wincan.draw_rect(rect.as_rect())?;
+    }
+    Ok(())
+}
+
+// Distinct color for each profiler scope
+fn profiler_color(i: usize) -> Color {
+    match i % 7 {
+        0 => Color::RGB(230, 25, 75),
+        1 => Color::RGB(60, 180, 75),
+        2 => Color::RGB(255, 225, 25),
+        3 => Color::RGB(0, 130, 200),
+        4 => Color::RGB(245, 130, 48),
+        5 => Color::RGB(145, 30, 180),
+        _ => Color::RGB(70, 240, 240),
+    }
+}
@@ -0,0 +1,162 @@
+// A run's score, and where it came from. Everything that scores goes through
+// Score::add with the source it's for, the end of run coin bonus included.
+// Points added during a step wait there until the step settles, when the
+// step's multipliers are applied to all of them at once and they join the
+// total. Alongside the total a ledger keeps what each source has brought in,
+// for the results screen. A risk zone's wager doubles the whole step by adding
+// it again as risk zone points.
+
+use sdl2::pixels::Color;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ScoreSource {
+    Distance,   // A point every step the run goes on
+    Coins,      // Picked up, by the player or the drone
+    Tricks,     // Clean vaults over golden statues
+    NearMiss,   // Hazards passed untouched
+    Milestones, // Gates between biomes
+    RiskZones,  // What the wager doubled
+    CoinBonus,  // COIN_BONUS for every coin, added as the run ends
+}
+
+pub struct SourceDef {
+    pub source: ScoreSource,
+    pub label: &'static str,
+    pub color: Color, // Its bar on the results screen
+}
+
+pub const SOURCE_DEFS: [SourceDef; 7] = [
+    SourceDef {
+        source: ScoreSource::Distance,
+        label: "Distance",
+        color: Color::RGB(120, 200, 255),
+    },
+    SourceDef {
+        source: ScoreSource::Coins,
+        label: "Coins",
+        color: Color::RGB(255, 215, 0),
+    },
+    SourceDef {
+        source: ScoreSource::Tricks,
+        label: "Tricks",
+        color: Color::RGB(230, 120, 255),
+    },
+    SourceDef {
+        source: ScoreSource::NearMiss,
+        label: "Near misses",
+        color: Color::RGB(235, 235, 235),
+    },
+    SourceDef {
+        source: ScoreSource::Milestones,
+        label: "Milestones",
+        color: Color::RGB(120, 230, 120),
+    },
+    SourceDef {
+        source: ScoreSource::RiskZones,
+        label: "Risk zones",
+        color: Color::RGB(255, 90, 70),
+    },
+    SourceDef {
+        source: ScoreSource::CoinBonus,
+        label: "Coin bonus",
+        color: Color::RGB(255, 170, 60),
+    },
+];
+
+impl ScoreSource {
+    // Position of the source in SOURCE_DEFS
+    pub fn index(&self) -> usize {
+        SOURCE_DEFS.iter().position(|d| d.source == *self).unwrap()
+    }
+
+    pub fn def(&self) -> &'static SourceDef {
+        &SOURCE_DEFS[self.index()]
+    }
+}
+
+// Points by source, after multipliers. Fractions are kept so that the
+// sources add up to the total as closely as they can.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Ledger {
+    points: [f64; SOURCE_DEFS.len()],
+}
+
+impl Ledger {
+    pub fn get(&self, source: ScoreSource) -> i32 {
+        self.points[source.index()].round() as i32
+    }
+
+    // Every source with what it brought in, in SOURCE_DEFS order
+    pub fn entries(&self) -> impl Iterator<Item = (ScoreSource, i32)> + '_ {
+        SOURCE_DEFS.iter().map(move |d| (d.source, self.get(d.source)))
+    }
+
+    // What the biggest source brought in, bars are drawn against it
+    pub fn largest(&self) -> i32 {
+        self.entries().map(|(_, points)| points).max().unwrap_or(0)
+    }
+}
+
+#[derive(Default)]
+pub struct Score {
+    total: i32,
+    remainder: f64, // Scaled fractions, kept until they add up to a point
+    step: [i32; SOURCE_DEFS.len()],
+    ledger: Ledger,
+}
+
+impl Score {
+    pub fn new() -> Score {
+        Score::default()
+    }
+
+    // Adds `amount` points from `source` to this step
+    pub fn add(&mut self, source: ScoreSource, amount: i32) {
+        self.step[source.index()] += amount;
+    }
+
+    // A risk zone's wager: doubles the points added this step so far
+    pub fn wager(&mut self) {
+        let step = self.step.iter().sum();
+        self.add(ScoreSource::RiskZones, step);
+    }
+
+    // Ends the step: its points are multiplied by `multiplier`, then scaled
+    // by `scale` and added to the total
+    pub fn settle(&mut self, multiplier: i32, scale: f64) {
+        let points = self.step.iter().sum::<i32>() * multiplier;
+        let scaled = points as f64 * scale + self.remainder;
+        self.remainder = scaled - scaled.floor();
+        self.total += scaled.floor() as i32;
+
+        for (booked, &step) in self.ledger.points.iter_mut().zip(self.step.iter()) {
+            *booked += (step * multiplier) as f64 * scale;
+        }
+        self.step = [0; SOURCE_DEFS.len()];
+    }
+
+    pub fn total(&self) -> i32 {
+        self.total
+    }
+
+    pub fn ledger(&self) -> Ledger {
+        self.ledger
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DODGE_BONUS;
+
+    #[test]
+    fn near_misses_score_the_dodge_bonus() {
+        let mut score = Score::new();
+        score.add(ScoreSource::NearMiss, DODGE_BONUS);
+        score.add(ScoreSource::NearMiss, DODGE_BONUS);
+        score.settle(2, 1.0);
+        assert_eq!(score.ledger().get(ScoreSource::NearMiss), DODGE_BONUS * 4);
+        assert_eq!(score.total(), DODGE_BONUS * 4);
+        assert_ne!(score.total(), 0);
+    }
+}
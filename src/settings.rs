@@ -0,0 +1,101 @@
+// Player settings from the settings screen that don't belong to another
+// module. Render quality and the frame cap keep their own types.
+
+use crate::assets::TextureFilter;
+use crate::config::Config;
+
+#[derive(Copy, Clone, PartialEq)]
+pub struct Settings {
+    pub fullscreen: bool,
+    pub show_hitboxes: bool,
+    pub volume: i32,         // 0 to 100
+    pub ui_scale: i32,       // Percent, 75 to 150
+    pub pixel_perfect: bool, // Integer scaling instead of smooth stretching
+    pub texture_filter: TextureFilter,
+    pub supersample: bool,       // Runs draw the world at twice the size and scale it down
+    pub show_inputs: bool,       // On-screen input display during runs
+    pub auto_jump: bool,         // Assist that jumps over obstacles
+    pub reduce_motion: bool,     // Leaves out hit-stops
+    pub battery_saver: bool,     // Menus run lighter on battery, see power::PowerSaver
+    pub restart_same_seed: bool, // Quick restarts replay the seed instead of drawing a new one
+}
+
+impl Settings {
+    pub const VOLUME_RANGE: (i32, i32) = (0, 100);
+    pub const UI_SCALE_RANGE: (i32, i32) = (75, 150);
+
+    // Reads the `fullscreen`, `show_hitboxes`, `volume`, `ui_scale`,
+    // `pixel_perfect`, `texture_filter`, `supersample`, `show_inputs`,
+    // `auto_jump`, `reduce_motion`, `battery_saver` and `restart_same_seed`
+    // keys of the config file
+    pub fn from_config(config: &Config) -> Settings {
+        let defaults = Settings::default();
+        let (vol_min, vol_max) = Settings::VOLUME_RANGE;
+        let (scale_min, scale_max) = Settings::UI_SCALE_RANGE;
+        let pixel_perfect = config.get_or("pixel_perfect", defaults.pixel_perfect);
+        // Pixel perfect used to mean nearest filtering as well, configs from
+        // before the filter was its own setting keep it
+        let filter_default = if pixel_perfect {
+            TextureFilter::Nearest
+        } else {
+            defaults.texture_filter
+        };
+        Settings {
+            fullscreen: config.get_or("fullscreen", defaults.fullscreen),
+            show_hitboxes: config.get_or("show_hitboxes", defaults.show_hitboxes),
+            volume: config.get_or("volume", defaults.volume).clamp(vol_min, vol_max),
+            ui_scale: config.get_or("ui_scale", defaults.ui_scale).clamp(scale_min, scale_max),
+            pixel_perfect,
+            texture_filter: config
+                .get("texture_filter")
+                .and_then(TextureFilter::from_key)
+                .unwrap_or(filter_default),
+            supersample: config.get_or("supersample", defaults.supersample),
+            show_inputs: config.get_or("show_inputs", defaults.show_inputs),
+            auto_jump: config.get_or("auto_jump", defaults.auto_jump),
+            reduce_motion: config.get_or("reduce_motion", defaults.reduce_motion),
+            battery_saver: config.get_or("battery_saver", defaults.battery_saver),
+            restart_same_seed: config.get_or("restart_same_seed", defaults.restart_same_seed),
+        }
+    }
+
+    pub fn save(&self, config: &mut Config) -> Result<(), String> {
+        config.set("fullscreen", &self.fullscreen.to_string());
+        config.set("show_hitboxes", &self.show_hitboxes.to_string());
+        config.set("volume", &self.volume.to_string());
+        config.set("ui_scale", &self.ui_scale.to_string());
+        config.set("pixel_perfect", &self.pixel_perfect.to_string());
+        config.set("texture_filter", self.texture_filter.key());
+        config.set("supersample", &self.supersample.to_string());
+        config.set("show_inputs", &self.show_inputs.to_string());
+        config.set("auto_jump", &self.auto_jump.to_string());
+        config.set("reduce_motion", &self.reduce_motion.to_string());
+        config.set("battery_saver", &self.battery_saver.to_string());
+        config.set("restart_same_seed", &self.restart_same_seed.to_string());
+        config.save()
+    }
+
+    // UI scale as a multiplier
+    pub fn ui_scale(&self) -> f64 {
+        self.ui_scale as f64 / 100.0
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            fullscreen: false,
+            show_hitboxes: true,
+            volume: 80,
+            ui_scale: 100,
+            pixel_perfect: false,
+            texture_filter: TextureFilter::Linear,
+            supersample: false,
+            show_inputs: false,
+            auto_jump: false,
+            reduce_motion: false,
+            battery_saver: true,
+            restart_same_seed: false,
+        }
+    }
+}
@@ -0,0 +1,222 @@
+// A miniature run behind the main menu, made of the real game's pieces in a
+// reduced mode: a biome's parallax layers, the player's physics on gently
+// rolling ground, the autopilot jumping the odd statue, and the profile's own
+// trail and drone along for the ride. It runs off its own clock at a fraction
+// of normal speed and never ends, nothing in it can hurt the player.
+
+use crate::autopilot::Autopilot;
+use crate::biome::Biome;
+use crate::clock::{SimClock, Timer};
+use crate::cosmetics::{Trail, TrailStyle};
+use crate::modifiers::Surroundings;
+use crate::parallax::Parallax;
+use crate::physics::{Body, Drone, Entity, Obstacle, PhysRect, Physics, Player, Sprite, DRONE_SIZE};
+use crate::rect;
+use crate::rng::GameRng;
+use crate::ObstacleType;
+use crate::TerrainType;
+use crate::TILE_SIZE;
+
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+use sdl2::render::{Texture, WindowCanvas};
+
+use rand::Rng;
+
+use std::time::Instant;
+
+// Fraction of real time the showcase runs at
+const SPEED: f64 = 0.6;
+// Where the player skates, and the height the ground rolls around
+const PLAYER_X: i32 = 3 * TILE_SIZE as i32;
+const GROUND_Y: i32 = 620;
+// How far the ground rises and falls, and the pixels of travel between rises
+const ROLL_AMP: f64 = 40.0;
+const ROLL_LEN: f64 = 1400.0;
+// Steps between statues, picked from this range
+const OBSTACLE_GAP: (u32, u32) = (150, 320);
+// Width of a background column, as in a run
+const COLUMN_W: usize = 10;
+// Where the drone hovers from the player's top-left corner
+const DRONE_OFFSET: (i32, i32) = (-70, -60);
+const GROUND_COLOR: Color = Color::RGB(58, 84, 46);
+
+pub struct Showcase<'a> {
+    clock: SimClock,
+    rng: GameRng,
+    size: (i32, i32),
+    parallax: Parallax,
+    player: Player<'a>,
+    autopilot: Autopilot,
+    obstacles: Vec<Obstacle>,
+    next_obstacle: Timer,
+    trail_style: TrailStyle,
+    trail: Trail,
+    drone: Option<Drone>,
+    travelled: f64,
+}
+
+impl<'a> Showcase<'a> {
+    // A showcase filling a `size` screen in `biome`, with the player drawn
+    // from `texture`
+    pub fn new(biome: Biome, size: (i32, i32), texture: &'a Texture<'a>) -> Showcase<'a> {
+        let mut rng = GameRng::from_entropy();
+        let parallax = Parallax::new(biome, size.0 as usize / COLUMN_W + 1, &mut rng);
+        let start = ground_at(0.0, PLAYER_X + TILE_SIZE as i32 / 2);
+        let clock = SimClock::new();
+        let next_obstacle = clock.timer(OBSTACLE_GAP.0);
+        Showcase {
+            clock,
+            rng,
+            size,
+            parallax,
+            player: Player::new(
+                PhysRect::new(PLAYER_X, start.y() - TILE_SIZE as i32, TILE_SIZE, TILE_SIZE),
+                Rect::new(PLAYER_X, start.y() - TILE_SIZE as i32, TILE_SIZE, TILE_SIZE),
+                3.0,
+                texture,
+            ),
+            autopilot: Autopilot::new(),
+            obstacles: Vec::new(),
+            next_obstacle,
+            trail_style: TrailStyle::Off,
+            trail: Trail::new(TrailStyle::Off),
+            drone: None,
+            travelled: 0.0,
+        }
+    }
+
+    // Shows off `trail` and, once it's unlocked, the drone. Only starts them
+    // over when they change.
+    pub fn dress(&mut self, trail: TrailStyle, drone: bool) {
+        if trail != self.trail_style {
+            self.trail_style = trail;
+            self.trail = Trail::new(trail);
+        }
+        match (&self.drone, drone) {
+            (None, true) => {
+                let at = Point::new(self.player.x() + DRONE_OFFSET.0, self.player.y() + DRONE_OFFSET.1);
+                self.drone = Some(Drone::new(at, 0.0));
+            }
+            (Some(_), false) => self.drone = None,
+            _ => {}
+        }
+    }
+
+    // Steps the showcase for the real time that's passed since the last call
+    pub fn update(&mut self, now: Instant) {
+        self.clock.feed(now, SPEED);
+        while self.clock.tick() {
+            self.step();
+        }
+    }
+
+    fn step(&mut self) {
+        // Ground under the player's middle, as in a run
+        let travelled = self.travelled;
+        let ground = ground_at(travelled, PLAYER_X + TILE_SIZE as i32 / 2);
+        let angle = slope_at(travelled, PLAYER_X + TILE_SIZE as i32 / 2);
+
+        let player = &mut self.player;
+        player.sense_ground(ground, angle);
+        player.set_surroundings(Surroundings {
+            terrain: TerrainType::Grass,
+            updraft: false,
+        });
+        player.step_footing();
+        if let Some(hold) = self
+            .autopilot
+            .decide(player, &self.obstacles, &[], |x| ground_at(travelled, x).y())
+        {
+            player.jump(ground, hold);
+        }
+        Physics::apply_terrain_forces(player, angle, ground, &TerrainType::Grass, None);
+        Physics::apply_skate_force(player, angle);
+        player.update_vel(false);
+        player.update_pos(ground, angle, false);
+        player.reset_accel();
+
+        let travel = player.travel();
+        self.travelled += travel;
+        for obstacle in self.obstacles.iter_mut() {
+            obstacle.travel_update(travel as i32);
+        }
+        Physics::remove_offscreen(&mut self.obstacles, -(TILE_SIZE as i32));
+        if self.next_obstacle.is_done(&self.clock) {
+            let x = self.size.0 + TILE_SIZE as i32;
+            let y = ground_at(self.travelled, x + TILE_SIZE as i32 / 2).y() - TILE_SIZE as i32;
+            self.obstacles.push(Obstacle::new(
+                PhysRect::new(x, y, TILE_SIZE, TILE_SIZE),
+                50.0,
+                ObstacleType::Statue,
+            ));
+            let gap = self.rng.gen_range(OBSTACLE_GAP.0..=OBSTACLE_GAP.1);
+            self.next_obstacle = self.clock.timer(gap);
+        }
+
+        self.parallax.step();
+        self.trail.step(self.player.center().into(), travel, 0);
+        if let Some(drone) = self.drone.as_mut() {
+            drone.follow(Point::new(
+                self.player.x() + DRONE_OFFSET.0,
+                self.player.y() + DRONE_OFFSET.1,
+            ));
+        }
+    }
+
+    pub fn draw(&self, canvas: &mut WindowCanvas) -> Result<(), String> {
+        let (width, height) = self.size;
+        // Back to front, so nearer layers cover the ones behind
+        for (color, heights) in self.parallax.layers() {
+            canvas.set_draw_color(color);
+            for (i, h) in heights.iter().enumerate() {
+                let top = height - *h as i32;
+                canvas.fill_rect(rect!(i * COLUMN_W, top, COLUMN_W, *h))?;
+            }
+        }
+
+        canvas.set_draw_color(GROUND_COLOR);
+        for x in (0..width).step_by(COLUMN_W) {
+            let top = ground_at(self.travelled, x + COLUMN_W as i32 / 2).y();
+            canvas.fill_rect(rect!(x, top, COLUMN_W, height - top))?;
+        }
+
+        canvas.set_draw_color(Color::RGB(120, 120, 130));
+        for obstacle in self.obstacles.iter() {
+            canvas.fill_rect(obstacle.hitbox().as_rect())?;
+        }
+
+        self.trail.draw(canvas)?;
+        if let Some(drone) = self.drone.as_ref() {
+            canvas.set_draw_color(Color::RGB(90, 90, 100));
+            canvas.fill_rect(rect!(drone.x(), drone.y() + 10, DRONE_SIZE, DRONE_SIZE - 20))?;
+            canvas.set_draw_color(Color::RGB(40, 40, 40));
+            canvas.fill_rect(rect!(drone.x() - 10, drone.y(), DRONE_SIZE + 20, 4))?;
+        }
+        canvas.copy_ex(
+            self.player.texture(),
+            rect!(0, 0, TILE_SIZE, TILE_SIZE),
+            rect!(self.player.x(), self.player.y(), TILE_SIZE, TILE_SIZE),
+            self.player.theta().to_degrees(),
+            None,
+            false,
+            false,
+        )
+    }
+}
+
+// Ground at `x` on the screen, once the showcase has travelled `travelled`
+fn ground_at(travelled: f64, x: i32) -> Point {
+    let along = x as f64 + travelled;
+    Point::new(
+        x,
+        GROUND_Y + (ROLL_AMP * (along / ROLL_LEN * std::f64::consts::TAU).sin()) as i32,
+    )
+}
+
+// Angle of the ground at `x`, positive downhill, from the ground a tile on
+// as in a run
+fn slope_at(travelled: f64, x: i32) -> f64 {
+    let (here, ahead) = (ground_at(travelled, x), ground_at(travelled, x + TILE_SIZE as i32));
+    ((ahead.y() - here.y()) as f64 / TILE_SIZE as f64).atan()
+}
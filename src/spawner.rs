@@ -0,0 +1,201 @@
+// Plans what spawns at the right edge of the screen, ahead of time. Each step
+// the planner rolls for the step PLAN_STEPS away, the same die the runner used
+// to roll for the step it was on, and anything it decides joins the queue of
+// planned spawns. The runner takes the one that's arrived each step and puts
+// it on the terrain at the edge. Since everything's known before it's on
+// screen, telegraphs, the practice preview and fairness checks can all look
+// at the queue.
+//
+// Decisions are made from the world as it is when they're planned, so the
+// object count and obstacle caps they go by are a little behind where they'll
+// arrive. Whether there's ground to stand on is still checked on arrival.
+
+use crate::clock::{SimClock, Timer};
+use crate::crash;
+use crate::proceduralgen::{self, ProceduralGen};
+use crate::ObstacleType;
+use crate::StaticObject;
+
+use rand::Rng;
+
+// Steps before it arrives that every spawn is decided
+pub const PLAN_STEPS: i32 = 90;
+// Fewest steps between obstacles from separate rolls, enough to land from
+// one jump and take off for the next
+pub const FAIR_STEPS: i32 = 24;
+// Max total number of coins, obstacles, and powers that can exist at
+// once, planned ones included. Could be split up later for more
+// complicated procgen
+const MAX_NUM_OBJECTS: i32 = 10;
+// Steps into a run before the first roll
+const FIRST_ROLL: u32 = 500;
+
+// Something planned to spawn
+#[derive(Copy, Clone, PartialEq)]
+pub struct PlannedSpawn {
+    pub object: StaticObject,
+    pub height: i32, // Pixels above the ground, only pattern pieces float
+    pub steps: i32,  // Until it arrives at the edge
+}
+
+impl PlannedSpawn {
+    // Screen x it's at now, past the `edge`, if the world keeps scrolling
+    // `scroll` pixels a step until it arrives
+    pub fn x_ahead(&self, edge: i32, scroll: f64) -> i32 {
+        edge + (self.steps.max(0) as f64 * scroll) as i32
+    }
+}
+
+// What the planner goes by when it rolls
+pub struct SpawnContext<'a> {
+    pub score: i32,
+    pub risk_zone: bool, // The edge is over a risk zone, spawns are packed tighter
+    pub density: i32,    // Spawn density multiplier from the loadout
+    pub on_screen: &'a [ObstacleType],
+    pub pickups: usize, // Coins and powers out now
+}
+
+pub struct SpawnPlanner {
+    planned: Vec<PlannedSpawn>, // In the order they were planned
+    next_roll: Timer,           // Can roll again once it runs out
+    gap: i32,                   // Minimum steps between spawns at the last roll
+}
+
+impl SpawnPlanner {
+    pub fn new(clock: &SimClock) -> SpawnPlanner {
+        SpawnPlanner {
+            planned: Vec::new(),
+            // A spawn gap of N leaves N whole steps without a spawn before
+            // the next roll
+            next_roll: clock.timer(FIRST_ROLL + 1),
+            gap: FIRST_ROLL as i32,
+        }
+    }
+
+    // Everything planned that hasn't arrived yet
+    pub fn planned(&self) -> &[PlannedSpawn] {
+        &self.planned
+    }
+
+    // Minimum steps between spawns as of the last roll
+    pub fn gap(&self) -> i32 {
+        self.gap
+    }
+
+    // Rolls for the step PLAN_STEPS ahead, moves everything planned a step
+    // closer and returns what's arrived at the edge, if anything has
+    pub fn step(&mut self, procgen: &mut ProceduralGen, clock: &SimClock, ctx: &SpawnContext) -> Option<PlannedSpawn> {
+        self.gap = min_gap(ctx.score, ctx.risk_zone, ctx.density);
+        let live = ctx.on_screen.len() + ctx.pickups + self.planned.len();
+        let trigger = procgen.spawn_rng().gen_range(0..MAX_NUM_OBJECTS);
+
+        if !self.next_roll.is_done(clock) {
+            // Still in the gap after the last spawn
+        } else if trigger >= live as i32 {
+            if let Some(pattern) = procgen.roll_pattern(ctx.score) {
+                crash::event(&format!("Planned a {} piece obstacle pattern", pattern.len()));
+                // Hold off other spawns until the whole pattern is out
+                let length = pattern.iter().map(|p| p.delay).max().unwrap_or(0);
+                self.next_roll = clock.timer((self.gap + length) as u32 + 1);
+                self.planned.extend(pattern.into_iter().map(|piece| PlannedSpawn {
+                    object: piece.object,
+                    height: piece.height,
+                    steps: piece.delay + PLAN_STEPS,
+                }));
+            } else {
+                let object = procgen.choose_static_object();
+                self.next_roll = clock.timer(self.gap as u32 + 1);
+                // Single obstacles are held to their type's cap, patterns
+                // are tuned in roll_pattern instead
+                let capped = spawned_obstacle(object).is_some_and(|obstacle_type| {
+                    let live = ctx.on_screen.iter().filter(|&&o| o == obstacle_type).count()
+                        + self
+                            .planned
+                            .iter()
+                            .filter(|p| spawned_obstacle(p.object) == Some(obstacle_type))
+                            .count();
+                    live >= obstacle_type.def().max_live_at(proceduralgen::difficulty(ctx.score))
+                });
+                if !capped {
+                    self.plan_single(object);
+                }
+            }
+        } else {
+            // Min spawn gap can be replaced with basically any value for this random
+            // range. Smaller values will spawn objects more often
+            self.next_roll = clock.timer(procgen.spawn_rng().gen_range(0..self.gap) as u32 + 1);
+        }
+
+        for planned in self.planned.iter_mut() {
+            planned.steps -= 1;
+        }
+        let i = self.planned.iter().position(|p| p.steps <= 0)?;
+        Some(self.planned.remove(i))
+    }
+
+    // Plans a single object, pushed back if it would arrive too close to
+    // another obstacle to be fair
+    fn plan_single(&mut self, object: StaticObject) {
+        let mut steps = PLAN_STEPS;
+        if spawned_obstacle(object).is_some() {
+            while let Some(other) = self
+                .planned
+                .iter()
+                .find(|p| spawned_obstacle(p.object).is_some() && (p.steps - steps).abs() < FAIR_STEPS)
+            {
+                steps = other.steps + FAIR_STEPS;
+            }
+        }
+        self.planned.push(PlannedSpawn {
+            object,
+            height: 0,
+            steps,
+        });
+    }
+}
+
+// Obstacle a spawned object turns into, None for pickups. Statues count as
+// plain ones, whether they turn out golden is rolled when they spawn.
+pub fn spawned_obstacle(object: StaticObject) -> Option<ObstacleType> {
+    match object {
+        StaticObject::Statue => Some(ObstacleType::Statue),
+        StaticObject::Balloon => Some(ObstacleType::Balloon),
+        StaticObject::Chest => Some(ObstacleType::Chest),
+        StaticObject::Coin | StaticObject::Power => None,
+    }
+}
+
+// Steps a roll that spawns something holds off the next one. Decreases to
+// increase spawn rates based on the score. Risk zones are packed twice as
+// densely, and so is everywhere with the double spawns modifier.
+pub fn min_gap(score: i32, risk_zone: bool, density: i32) -> i32 {
+    // These numbers could be terrible, we should mess around with it
+    let gap = if score > 100000 {
+        300 // Cap
+    } else if score > 90000 {
+        320
+    } else if score > 80000 {
+        340
+    } else if score > 70000 {
+        360
+    } else if score > 60000 {
+        380
+    } else if score > 50000 {
+        400
+    } else if score > 40000 {
+        420
+    } else if score > 30000 {
+        440
+    } else if score > 20000 {
+        460
+    } else if score > 10000 {
+        480
+    } else {
+        500 // Default
+    };
+    if risk_zone {
+        gap / 2 / density
+    } else {
+        gap / density
+    }
+}
@@ -0,0 +1,139 @@
+// Optional spoken announcements for players using a screen reader. Menus call
+// `announce` whenever keyboard focus moves, and the runner for run results.
+//
+// Off by default, turned on with `tts = true` in the config file. Speech goes
+// through the platform's command line synthesizer (espeak, say, or
+// System.Speech on Windows), which `tts_command` can replace, e.g.
+// `tts_command = espeak -s 160`.
+
+use crate::config::Config;
+
+use log::warn;
+
+use std::process::{Child, Command, Stdio};
+
+pub trait SpeechBackend {
+    // Speaks `text`, cutting off whatever was being said before
+    fn speak(&mut self, text: &str) -> Result<(), String>;
+}
+
+// Runs an external program once per announcement, with the text as its last
+// argument, or in the environment variable `text_env` when that's set
+pub struct CommandSpeech {
+    program: String,
+    args: Vec<String>,
+    text_env: Option<&'static str>,
+    child: Option<Child>,
+}
+
+impl CommandSpeech {
+    pub fn new(command: &str) -> Option<CommandSpeech> {
+        let mut parts = command.split_whitespace().map(|s| s.to_string());
+        let program = parts.next()?;
+        Some(CommandSpeech {
+            program,
+            args: parts.collect(),
+            text_env: None,
+            child: None,
+        })
+    }
+
+    // Synthesizer that ships with the current platform
+    pub fn platform_default() -> CommandSpeech {
+        if cfg!(target_os = "windows") {
+            // PowerShell joins arguments after -Command onto the script
+            // itself, so the text has to come in through the environment
+            CommandSpeech {
+                program: "powershell".to_string(),
+                args: vec![
+                    "-NoProfile".to_string(),
+                    "-Command".to_string(),
+                    "Add-Type -AssemblyName System.Speech; \
+                     (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak($env:IR_SPEAK)"
+                        .to_string(),
+                ],
+                text_env: Some("IR_SPEAK"),
+                child: None,
+            }
+        } else if cfg!(target_os = "macos") {
+            CommandSpeech::new("say").unwrap()
+        } else {
+            CommandSpeech::new("espeak").unwrap()
+        }
+    }
+}
+
+impl SpeechBackend for CommandSpeech {
+    fn speak(&mut self, text: &str) -> Result<(), String> {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        let mut command = Command::new(&self.program);
+        command.args(&self.args);
+        match self.text_env {
+            Some(var) => command.env(var, text),
+            None => command.arg(text),
+        };
+        let child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("{}: {}", self.program, e))?;
+        self.child = Some(child);
+        Ok(())
+    }
+}
+
+// Cuts off any announcement still playing so it doesn't hold up shutdown
+impl Drop for CommandSpeech {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+pub struct Announcer {
+    backend: Option<Box<dyn SpeechBackend>>,
+}
+
+impl Announcer {
+    pub fn from_config(config: &Config) -> Announcer {
+        if !config.get_or("tts", false) {
+            return Announcer::disabled();
+        }
+        let backend = match config.get("tts_command") {
+            Some(command) => CommandSpeech::new(command),
+            None => Some(CommandSpeech::platform_default()),
+        };
+        Announcer {
+            backend: backend.map(|b| Box::new(b) as Box<dyn SpeechBackend>),
+        }
+    }
+
+    pub fn disabled() -> Announcer {
+        Announcer { backend: None }
+    }
+
+    pub fn with_backend(backend: Box<dyn SpeechBackend>) -> Announcer {
+        Announcer { backend: Some(backend) }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.backend.is_some()
+    }
+
+    // Speaks `text` if announcements are on. A backend that fails is switched
+    // off so a missing synthesizer doesn't spam the log every frame.
+    pub fn announce(&mut self, text: &str) {
+        if let Some(backend) = self.backend.as_mut() {
+            if let Err(e) = backend.speak(text) {
+                warn!("Disabling speech, backend failed: {}", e);
+                self.backend = None;
+            }
+        }
+    }
+}
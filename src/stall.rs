@@ -0,0 +1,107 @@
+// Forward progress rule. Scoring leans on the player always moving forward, so
+// if anything ever lets them hang in place (balloon bouncing, updrafts) they
+// shouldn't keep piling up points there. A run has to cover `min_distance`
+// pixels in every `window` of simulation time; while it doesn't, the score
+// multiplier decays towards a floor and the coin combo drains, and both come
+// back once the player is moving again. Counted in steps, so replays agree.
+
+use crate::config::Config;
+use crate::framerate::SIM_FPS;
+
+use std::collections::VecDeque;
+
+// Steps between each coin the combo loses while stalled
+const COMBO_DRAIN_STEPS: u32 = 60;
+
+#[derive(Copy, Clone)]
+pub struct StallConfig {
+    // Steps the distance is measured over
+    pub window_steps: usize,
+    // Pixels the player has to cover in a window, 0 turns the rule off
+    pub min_distance: i32,
+    // Lowest the score multiplier decays to
+    pub floor: f64,
+    // Steps the multiplier takes to decay from 1 to the floor, and to recover
+    pub decay_steps: u32,
+}
+
+impl Default for StallConfig {
+    fn default() -> Self {
+        // The slowest the player can run covers 600 pixels in 10 seconds
+        StallConfig {
+            window_steps: (10.0 * SIM_FPS) as usize,
+            min_distance: 600,
+            floor: 0.25,
+            decay_steps: (5.0 * SIM_FPS) as u32,
+        }
+    }
+}
+
+impl StallConfig {
+    // Reads the `stall_*` keys of the config file
+    pub fn from_config(config: &Config) -> StallConfig {
+        let defaults = StallConfig::default();
+        let window_secs = config.get_or("stall_window_secs", 10.0_f64).max(1.0);
+        let decay_secs = config.get_or("stall_decay_secs", 5.0_f64).max(0.1);
+        StallConfig {
+            window_steps: (window_secs * SIM_FPS) as usize,
+            min_distance: config.get_or("stall_min_distance", defaults.min_distance).max(0),
+            floor: config.get_or("stall_floor", defaults.floor).clamp(0.0, 1.0),
+            decay_steps: (decay_secs * SIM_FPS) as u32,
+        }
+    }
+}
+
+pub struct StallRule {
+    config: StallConfig,
+    history: VecDeque<i32>, // Distance at each of the last window_steps steps, oldest first
+    multiplier: f64,
+    stalled_steps: u32, // Steps in a row the rule has been broken
+}
+
+impl StallRule {
+    pub fn new(config: StallConfig) -> StallRule {
+        StallRule {
+            config,
+            history: VecDeque::with_capacity(config.window_steps + 1),
+            multiplier: 1.0,
+            stalled_steps: 0,
+        }
+    }
+
+    // Checks the rule against the run's `distance` this step
+    pub fn step(&mut self, distance: i32) {
+        self.history.push_back(distance);
+        if self.history.len() > self.config.window_steps + 1 {
+            self.history.pop_front();
+        }
+        let rate = (1.0 - self.config.floor) / self.config.decay_steps.max(1) as f64;
+        if self.is_stalled() {
+            self.multiplier = (self.multiplier - rate).max(self.config.floor);
+            self.stalled_steps += 1;
+        } else {
+            self.multiplier = (self.multiplier + rate).min(1.0);
+            self.stalled_steps = 0;
+        }
+    }
+
+    // Whether the last window covered too little ground. Never true in the
+    // first window of a run.
+    pub fn is_stalled(&self) -> bool {
+        if self.config.min_distance == 0 || self.history.len() <= self.config.window_steps {
+            return false;
+        }
+        let covered = self.history.back().unwrap() - self.history.front().unwrap();
+        covered < self.config.min_distance
+    }
+
+    // Scales the score earned each step
+    pub fn multiplier(&self) -> f64 {
+        self.multiplier
+    }
+
+    // Whether the coin combo should lose a coin this step
+    pub fn drains_combo(&self) -> bool {
+        self.stalled_steps > 0 && self.stalled_steps.is_multiple_of(COMBO_DRAIN_STEPS)
+    }
+}
@@ -0,0 +1,215 @@
+// Dev tool for looking at procgen output without playing. Generates segments
+// from a seed, saves them in the terrain file format (or as JSON) and can
+// render them to a PNG strip to compare before and after a procgen change.
+//
+//   inf_runner dump-terrain <seed> <count> <out> [--json] [--png <path>]
+
+use inf_runner::proceduralgen::{ProceduralGen, TerrainSegment, GATE_W, MUD_COLOR};
+use inf_runner::rng::GameRng;
+use inf_runner::terrainfile;
+
+use rand::Rng;
+use sdl2::image::SaveSurface;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::{Point, Rect};
+use sdl2::surface::Surface;
+
+use std::fs::File;
+
+// The strip is drawn at 1 / STRIP_SCALE of the game's size
+const STRIP_SCALE: i32 = 4;
+const SKY: Color = Color::RGB(135, 206, 235);
+const RISK_TINT: Color = Color::RGBA(255, 0, 0, 90);
+const UPDRAFT_TINT: Color = Color::RGBA(255, 255, 255, 90);
+const HAZARD_MARK: Color = Color::RGB(230, 80, 20);
+const CEILING: Color = Color::RGB(84, 72, 64);
+const GATE_MARK: Color = Color::RGBA(255, 215, 0, 120);
+
+pub const USAGE: &str = "dump-terrain <seed> <count> <out> [--json] [--png <path>]";
+
+pub fn run(args: &[String], cam_w: u32, cam_h: u32) -> Result<(), String> {
+    let mut positional = Vec::new();
+    let mut json = false;
+    let mut png = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--png" => png = Some(args.next().ok_or("--png needs a path")?),
+            _ => positional.push(arg),
+        }
+    }
+    if positional.len() != 3 {
+        return Err(format!("Usage: {}", USAGE));
+    }
+    let seed: u64 = positional[0].parse().map_err(|e| format!("Bad seed: {}", e))?;
+    let count: usize = positional[1].parse().map_err(|e| format!("Bad count: {}", e))?;
+
+    let segments = generate(seed, count, cam_w as i32, cam_h as i32);
+
+    let out = positional[2];
+    if json {
+        std::fs::write(out, terrainfile::to_json(&segments)).map_err(|e| e.to_string())?;
+    } else {
+        let mut file = File::create(out).map_err(|e| e.to_string())?;
+        terrainfile::write_segments(&segments, &mut file)?;
+    }
+    println!("Wrote {} segments from seed {} to {}", segments.len(), seed, out);
+
+    if let Some(path) = png {
+        render_strip(&segments, cam_h, path)?;
+        println!("Rendered strip to {}", path);
+    }
+    Ok(())
+}
+
+// Chains segments the same way the game does, each starting at the end of the
+// last one
+fn generate(seed: u64, count: usize, cam_w: i32, cam_h: i32) -> Vec<TerrainSegment> {
+    let mut procgen = ProceduralGen::with_seed(seed);
+
+    let mut random = Box::new([[(0, 0); 256]; 256]);
+    let terrain_rng = procgen.terrain_rng();
+    for row in random.iter_mut() {
+        for cell in row.iter_mut() {
+            *cell = (terrain_rng.gen_range(0..256), terrain_rng.gen_range(0..256));
+        }
+    }
+
+    let mut segments = Vec::with_capacity(count);
+    let mut prev_point = (0.0, (cam_h * 2 / 3) as f64);
+    for _ in 0..count {
+        let mut segment = procgen.gen_terrain(
+            &random,
+            prev_point,
+            cam_w,
+            cam_h,
+            Vec::with_capacity(cam_w as usize),
+            false,
+            false,
+            false,
+        );
+        segment.set_gate(procgen.biome_transition());
+        let gated = segment.gate().is_some();
+        segment.set_risk_zone(procgen.roll_risk_zone());
+        segment.set_updraft(procgen.roll_updraft());
+        segment.set_mud(procgen.roll_mud(cam_w));
+        if segment.mud().is_none() && !gated {
+            segment.set_hazard(procgen.roll_hazard(cam_w));
+        }
+        if segment.mud().is_none() && segment.hazard().is_none() && !gated {
+            segment.set_gap(procgen.roll_gap(cam_w));
+        }
+        if segment.hazard().is_none() && segment.gap().is_none() && !gated {
+            let ceiling = procgen.roll_tunnel(segment.curve());
+            segment.set_ceiling(ceiling);
+        }
+        if let Some(&(x, y)) = segment.curve().last() {
+            prev_point = (x as f64, y as f64);
+        }
+        segments.push(segment);
+    }
+    segments
+}
+
+fn render_strip(segments: &[TerrainSegment], cam_h: u32, path: &str) -> Result<(), String> {
+    let (min_x, max_x) = segments
+        .iter()
+        .flat_map(|s| s.curve().iter())
+        .fold((i32::MAX, i32::MIN), |(lo, hi), &(x, _)| (lo.min(x), hi.max(x)));
+    if min_x > max_x {
+        return Err("No terrain to render".to_string());
+    }
+    let width = ((max_x - min_x) / STRIP_SCALE + 1) as u32;
+    let height = cam_h / STRIP_SCALE as u32;
+
+    let surface = Surface::new(width, height, PixelFormatEnum::RGB888)?;
+    let mut canvas = surface.into_canvas()?;
+    canvas.set_draw_color(SKY);
+    canvas.clear();
+    canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+
+    for segment in segments {
+        let curve = segment.curve();
+        let (Some(first), Some(last)) = (curve.first(), curve.last()) else {
+            continue;
+        };
+        let left = (first.0 - min_x) / STRIP_SCALE;
+        let right = (last.0 - min_x) / STRIP_SCALE;
+
+        // Ground, one column per pixel under the curve, left out over a gap
+        let gap = segment.gap().map(|g| (first.0 + g.start)..(first.0 + g.end));
+        canvas.set_draw_color(segment.color());
+        for pair in curve.windows(2) {
+            let (x0, y0) = pair[0];
+            let (x1, y1) = pair[1];
+            for x in x0..x1.max(x0 + 1) {
+                if gap.as_ref().is_some_and(|g| g.contains(&x)) {
+                    continue;
+                }
+                let t = if x1 == x0 {
+                    0.0
+                } else {
+                    (x - x0) as f64 / (x1 - x0) as f64
+                };
+                let y = (y0 as f64 + (y1 - y0) as f64 * t) as i32 / STRIP_SCALE;
+                let column = (x - min_x) / STRIP_SCALE;
+                canvas.draw_line(Point::new(column, y), Point::new(column, height as i32))?;
+            }
+        }
+
+        let span = Rect::new(left, 0, (right - left).max(1) as u32, height);
+        if segment.is_risk_zone() {
+            canvas.set_draw_color(RISK_TINT);
+            canvas.fill_rect(span)?;
+        }
+        if segment.has_updraft() {
+            canvas.set_draw_color(UPDRAFT_TINT);
+            canvas.fill_rect(span)?;
+        }
+        // Mud patches as a band along the bottom, under where they are
+        if let Some((start, end)) = segment.mud() {
+            let x = (first.0 + start - min_x) / STRIP_SCALE;
+            canvas.set_draw_color(MUD_COLOR);
+            canvas.fill_rect(Rect::new(
+                x,
+                height as i32 - 6,
+                ((end - start) / STRIP_SCALE).max(1) as u32,
+                6,
+            ))?;
+        }
+        // Hazard strips the same way
+        if let Some(strip) = segment.hazard() {
+            let x = (first.0 + strip.start - min_x) / STRIP_SCALE;
+            canvas.set_draw_color(HAZARD_MARK);
+            canvas.fill_rect(Rect::new(
+                x,
+                height as i32 - 6,
+                ((strip.end - strip.start) / STRIP_SCALE).max(1) as u32,
+                6,
+            ))?;
+        }
+
+        // Tunnel ceilings, from the top down to their underside
+        if let Some(ceiling) = segment.ceiling() {
+            canvas.set_draw_color(CEILING);
+            for &(x, y) in ceiling.iter().step_by(STRIP_SCALE as usize) {
+                let column = (x - min_x) / STRIP_SCALE;
+                canvas.draw_line(Point::new(column, 0), Point::new(column, y / STRIP_SCALE))?;
+            }
+        }
+
+        // Biome gates as a post from top to bottom
+        if let Some(gate) = segment.gate() {
+            let x = (first.0 + gate.at - min_x) / STRIP_SCALE;
+            canvas.set_draw_color(GATE_MARK);
+            canvas.fill_rect(Rect::new(x, 0, (GATE_W / STRIP_SCALE) as u32, height))?;
+        }
+
+        // Segment boundary
+        canvas.set_draw_color(Color::BLACK);
+        canvas.draw_line(Point::new(left, 0), Point::new(left, height as i32))?;
+    }
+
+    canvas.into_surface().save(path)
+}
@@ -0,0 +1,341 @@
+// Versioned file format for sequences of terrain segments, for saving procgen
+// output and inspecting it offline.
+//
+// Binary layout, all little endian:
+//   magic "IRTR", version u16, segment count u32, then per segment:
+//   x i32, y i32, w u32, h u32, angle_from_last f64, terrain type u8,
+//   color r g b a u8, flags u8 (1 = risk zone, 2 = updraft, 4 = mud,
+//   8 = hazard, 16 = gap, 32 = ceiling, 64 = gate), mud start i32 and end
+//   i32 if flagged (version 2 on), hazard type u8, start i32 and end i32 if
+//   flagged (version 3 on), gap start i32, end i32, platform width i32 and
+//   platform speed f64 if flagged (version 4 on), ceiling point count u32 and
+//   points (i32, i32) if flagged (version 5 on), gate position i32 if flagged
+//   (version 6 on), point count u32, first point (i32, i32), then the rest as
+//   (i16, i16) deltas from the previous point.
+//
+// Readers reject newer versions, so a format change only needs a version bump
+// and a new branch in `read_segment`.
+
+use crate::proceduralgen::{BiomeGate, Gap, HazardStrip, TerrainSegment};
+use crate::Hazard;
+use crate::TerrainType;
+
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+
+use std::convert::TryInto;
+use std::io::{Read, Write};
+
+pub const EXTENSION: &str = "irterrain";
+pub const FORMAT_VERSION: u16 = 6;
+const MAGIC: &[u8; 4] = b"IRTR";
+
+const FLAG_RISK_ZONE: u8 = 1;
+const FLAG_UPDRAFT: u8 = 2;
+const FLAG_MUD: u8 = 4;
+const FLAG_HAZARD: u8 = 8;
+const FLAG_GAP: u8 = 16;
+const FLAG_CEILING: u8 = 32;
+const FLAG_GATE: u8 = 64;
+
+pub fn write_segments<W: Write>(segments: &[TerrainSegment], out: &mut W) -> Result<(), String> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(segments.len() as u32).to_le_bytes());
+
+    for segment in segments {
+        let pos = segment.pos();
+        bytes.extend_from_slice(&pos.x().to_le_bytes());
+        bytes.extend_from_slice(&pos.y().to_le_bytes());
+        bytes.extend_from_slice(&pos.width().to_le_bytes());
+        bytes.extend_from_slice(&pos.height().to_le_bytes());
+        bytes.extend_from_slice(&segment.angle_from_last().to_le_bytes());
+        bytes.push(terrain_type_id(*segment.get_type()));
+        let color = segment.color();
+        bytes.extend_from_slice(&[color.r, color.g, color.b, color.a]);
+        let mut flags = 0;
+        if segment.is_risk_zone() {
+            flags |= FLAG_RISK_ZONE;
+        }
+        if segment.has_updraft() {
+            flags |= FLAG_UPDRAFT;
+        }
+        if segment.mud().is_some() {
+            flags |= FLAG_MUD;
+        }
+        if segment.hazard().is_some() {
+            flags |= FLAG_HAZARD;
+        }
+        if segment.gap().is_some() {
+            flags |= FLAG_GAP;
+        }
+        if segment.ceiling().is_some() {
+            flags |= FLAG_CEILING;
+        }
+        if segment.gate().is_some() {
+            flags |= FLAG_GATE;
+        }
+        bytes.push(flags);
+        if let Some((start, end)) = segment.mud() {
+            bytes.extend_from_slice(&start.to_le_bytes());
+            bytes.extend_from_slice(&end.to_le_bytes());
+        }
+        if let Some(strip) = segment.hazard() {
+            bytes.push(hazard_id(strip.hazard));
+            bytes.extend_from_slice(&strip.start.to_le_bytes());
+            bytes.extend_from_slice(&strip.end.to_le_bytes());
+        }
+        if let Some(gap) = segment.gap() {
+            bytes.extend_from_slice(&gap.start.to_le_bytes());
+            bytes.extend_from_slice(&gap.end.to_le_bytes());
+            bytes.extend_from_slice(&gap.platform_w.to_le_bytes());
+            bytes.extend_from_slice(&gap.speed.to_le_bytes());
+        }
+        if let Some(ceiling) = segment.ceiling() {
+            bytes.extend_from_slice(&(ceiling.len() as u32).to_le_bytes());
+            for &(x, y) in ceiling.iter() {
+                bytes.extend_from_slice(&x.to_le_bytes());
+                bytes.extend_from_slice(&y.to_le_bytes());
+            }
+        }
+        if let Some(gate) = segment.gate() {
+            bytes.extend_from_slice(&gate.at.to_le_bytes());
+        }
+
+        let curve = segment.curve();
+        bytes.extend_from_slice(&(curve.len() as u32).to_le_bytes());
+        let mut last = (0, 0);
+        for (i, &(x, y)) in curve.iter().enumerate() {
+            if i == 0 {
+                bytes.extend_from_slice(&x.to_le_bytes());
+                bytes.extend_from_slice(&y.to_le_bytes());
+            } else {
+                let dx: i16 = (x - last.0)
+                    .try_into()
+                    .map_err(|_| "Curve step too large".to_string())?;
+                let dy: i16 = (y - last.1)
+                    .try_into()
+                    .map_err(|_| "Curve step too large".to_string())?;
+                bytes.extend_from_slice(&dx.to_le_bytes());
+                bytes.extend_from_slice(&dy.to_le_bytes());
+            }
+            last = (x, y);
+        }
+    }
+
+    out.write_all(&bytes).map_err(|e| e.to_string())
+}
+
+pub fn read_segments<R: Read>(input: &mut R) -> Result<Vec<TerrainSegment>, String> {
+    let mut bytes = Vec::new();
+    input.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+    let mut reader = ByteReader { bytes: &bytes, at: 0 };
+
+    if reader.take(4)? != MAGIC {
+        return Err("Not a terrain file".to_string());
+    }
+    let version = reader.u16()?;
+    if version == 0 || version > FORMAT_VERSION {
+        return Err(format!("Unsupported terrain format version {}", version));
+    }
+    let count = reader.u32()?;
+    (0..count).map(|_| read_segment(&mut reader, version)).collect()
+}
+
+fn read_segment(reader: &mut ByteReader, version: u16) -> Result<TerrainSegment, String> {
+    let x = reader.i32()?;
+    let y = reader.i32()?;
+    let w = reader.u32()?;
+    let h = reader.u32()?;
+    let angle = f64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+    let terrain_type = terrain_type_from_id(reader.u8()?)?;
+    let c = reader.take(4)?;
+    let color = Color::RGBA(c[0], c[1], c[2], c[3]);
+    let flags = reader.u8()?;
+    let mud = if version >= 2 && flags & FLAG_MUD != 0 {
+        Some((reader.i32()?, reader.i32()?))
+    } else {
+        None
+    };
+    let hazard = if version >= 3 && flags & FLAG_HAZARD != 0 {
+        Some(HazardStrip {
+            hazard: hazard_from_id(reader.u8()?)?,
+            start: reader.i32()?,
+            end: reader.i32()?,
+        })
+    } else {
+        None
+    };
+    let gap = if version >= 4 && flags & FLAG_GAP != 0 {
+        Some(Gap::new(
+            reader.i32()?,
+            reader.i32()?,
+            reader.i32()?,
+            f64::from_le_bytes(reader.take(8)?.try_into().unwrap()),
+        ))
+    } else {
+        None
+    };
+    let ceiling = if version >= 5 && flags & FLAG_CEILING != 0 {
+        let len = reader.u32()? as usize;
+        let mut points = Vec::with_capacity(len.min(reader.remaining() / 8 + 1));
+        for _ in 0..len {
+            points.push((reader.i32()?, reader.i32()?));
+        }
+        Some(points)
+    } else {
+        None
+    };
+    let gate = if version >= 6 && flags & FLAG_GATE != 0 {
+        Some(BiomeGate::new(reader.i32()?))
+    } else {
+        None
+    };
+
+    let len = reader.u32()? as usize;
+    let mut curve = Vec::with_capacity(len.min(reader.remaining() / 4 + 1));
+    let mut last = (0, 0);
+    for i in 0..len {
+        last = if i == 0 {
+            (reader.i32()?, reader.i32()?)
+        } else {
+            (last.0 + reader.i16()? as i32, last.1 + reader.i16()? as i32)
+        };
+        curve.push(last);
+    }
+
+    let mut segment = TerrainSegment::new(Rect::new(x, y, w, h), curve, angle, terrain_type, color);
+    segment.set_risk_zone(flags & FLAG_RISK_ZONE != 0);
+    segment.set_updraft(flags & FLAG_UPDRAFT != 0);
+    segment.set_mud(mud);
+    segment.set_hazard(hazard);
+    segment.set_gap(gap);
+    segment.set_ceiling(ceiling);
+    segment.set_gate(gate);
+    Ok(segment)
+}
+
+// Human readable dump of the same data, points are written out in full
+pub fn to_json(segments: &[TerrainSegment]) -> String {
+    let mut json = format!("{{\n  \"version\": {},\n  \"segments\": [\n", FORMAT_VERSION);
+    for (i, segment) in segments.iter().enumerate() {
+        let pos = segment.pos();
+        let color = segment.color();
+        let points: Vec<String> = segment.curve().iter().map(|(x, y)| format!("[{},{}]", x, y)).collect();
+        json.push_str(&format!(
+            "    {{\"x\": {}, \"y\": {}, \"w\": {}, \"h\": {}, \"angle_from_last\": {}, \"type\": \"{:?}\", \
+             \"color\": [{},{},{},{}], \"risk_zone\": {}, \"updraft\": {}, \"mud\": {}, \
+             \"hazard\": {}, \"gap\": {}, \"ceiling\": {}, \"gate\": {}, \"curve\": [{}]}}{}\n",
+            pos.x(),
+            pos.y(),
+            pos.width(),
+            pos.height(),
+            segment.angle_from_last(),
+            segment.get_type(),
+            color.r,
+            color.g,
+            color.b,
+            color.a,
+            segment.is_risk_zone(),
+            segment.has_updraft(),
+            segment
+                .mud()
+                .map_or("null".to_string(), |(start, end)| format!("[{},{}]", start, end)),
+            segment.hazard().map_or("null".to_string(), |h| format!(
+                "{{\"type\": \"{:?}\", \"start\": {}, \"end\": {}}}",
+                h.hazard, h.start, h.end
+            )),
+            segment.gap().map_or("null".to_string(), |g| format!(
+                "{{\"start\": {}, \"end\": {}, \"platform_w\": {}, \"speed\": {}}}",
+                g.start, g.end, g.platform_w, g.speed
+            )),
+            segment.ceiling().map_or("null".to_string(), |c| format!(
+                "[{}]",
+                c.iter()
+                    .map(|(x, y)| format!("[{},{}]", x, y))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            )),
+            segment.gate().map_or("null".to_string(), |g| g.at.to_string()),
+            points.join(","),
+            if i + 1 < segments.len() { "," } else { "" }
+        ));
+    }
+    json.push_str("  ]\n}\n");
+    json
+}
+
+fn terrain_type_id(terrain_type: TerrainType) -> u8 {
+    match terrain_type {
+        TerrainType::Grass => 0,
+        TerrainType::Asphalt => 1,
+        TerrainType::Sand => 2,
+        TerrainType::Water => 3,
+        TerrainType::Mud => 4,
+    }
+}
+
+fn terrain_type_from_id(id: u8) -> Result<TerrainType, String> {
+    match id {
+        0 => Ok(TerrainType::Grass),
+        1 => Ok(TerrainType::Asphalt),
+        2 => Ok(TerrainType::Sand),
+        3 => Ok(TerrainType::Water),
+        4 => Ok(TerrainType::Mud),
+        _ => Err(format!("Unknown terrain type {}", id)),
+    }
+}
+
+fn hazard_id(hazard: Hazard) -> u8 {
+    match hazard {
+        Hazard::Lava => 0,
+        Hazard::Spikes => 1,
+    }
+}
+
+fn hazard_from_id(id: u8) -> Result<Hazard, String> {
+    match id {
+        0 => Ok(Hazard::Lava),
+        1 => Ok(Hazard::Spikes),
+        _ => Err(format!("Unknown hazard {}", id)),
+    }
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let end = self.at + n;
+        let slice = self.bytes.get(self.at..end).ok_or("Terrain file is truncated")?;
+        self.at = end;
+        Ok(slice)
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.at
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn i16(&mut self) -> Result<i16, String> {
+        Ok(i16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
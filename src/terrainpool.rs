@@ -0,0 +1,116 @@
+// Recycles the curve buffers of terrain segments that scrolled off screen, so
+// a long run reuses the same few allocations instead of allocating a fresh
+// screen-width curve for every new segment and freeing the old one.
+
+use crate::proceduralgen::TerrainSegment;
+
+use std::mem::size_of;
+
+// Resident segments past this are dropped oldest first, even if still on
+// screen. Normal play only ever has 2 or 3.
+pub const MAX_SEGMENTS: usize = 8;
+
+// Spare buffers kept around, any beyond this are freed
+const MAX_FREE_CURVES: usize = 4;
+
+// Snapshot of how much memory the terrain holds, for the debug overlay
+#[derive(Copy, Clone, Default)]
+pub struct TerrainMemory {
+    pub live_segments: usize,
+    pub live_bytes: usize,
+    pub pooled_curves: usize,
+    pub pooled_bytes: usize,
+}
+
+impl TerrainMemory {
+    pub fn total_bytes(&self) -> usize {
+        self.live_bytes + self.pooled_bytes
+    }
+}
+
+pub struct TerrainPool {
+    free_curves: Vec<Vec<(i32, i32)>>,
+    allocated: usize, // Buffers handed out that weren't recycled ones
+    reused: usize,    // Buffers handed out that were
+}
+
+impl TerrainPool {
+    pub fn new() -> TerrainPool {
+        TerrainPool {
+            free_curves: Vec::with_capacity(MAX_FREE_CURVES),
+            allocated: 0,
+            reused: 0,
+        }
+    }
+
+    // An empty curve buffer, recycled if one is available
+    pub fn take_curve(&mut self, capacity: usize) -> Vec<(i32, i32)> {
+        match self.free_curves.pop() {
+            Some(mut curve) => {
+                self.reused += 1;
+                curve.clear();
+                curve.reserve(capacity);
+                curve
+            }
+            None => {
+                self.allocated += 1;
+                Vec::with_capacity(capacity)
+            }
+        }
+    }
+
+    // Takes back a segment that's no longer needed and keeps its curve buffer
+    pub fn recycle(&mut self, segment: TerrainSegment) {
+        if self.free_curves.len() < MAX_FREE_CURVES {
+            self.free_curves.push(segment.into_curve());
+        }
+    }
+
+    // Recycles segments whose right edge is left of `min_x`. Segments are
+    // ordered left to right, so only the front of the list is checked.
+    pub fn recycle_offscreen(&mut self, segments: &mut Vec<TerrainSegment>, min_x: i32) {
+        let gone = segments.iter().take_while(|s| s.x() + s.w() <= min_x).count();
+        for segment in segments.drain(..gone) {
+            self.recycle(segment);
+        }
+    }
+
+    // Drops the oldest segments beyond MAX_SEGMENTS
+    pub fn enforce_cap(&mut self, segments: &mut Vec<TerrainSegment>) {
+        if segments.len() > MAX_SEGMENTS {
+            let excess = segments.len() - MAX_SEGMENTS;
+            for segment in segments.drain(..excess) {
+                self.recycle(segment);
+            }
+        }
+    }
+
+    pub fn memory(&self, segments: &[TerrainSegment]) -> TerrainMemory {
+        let point = size_of::<(i32, i32)>();
+        TerrainMemory {
+            live_segments: segments.len(),
+            live_bytes: segments
+                .iter()
+                .map(|s| size_of::<TerrainSegment>() + s.curve().capacity() * point)
+                .sum(),
+            pooled_curves: self.free_curves.len(),
+            pooled_bytes: self.free_curves.iter().map(|c| c.capacity() * point).sum(),
+        }
+    }
+
+    // Fraction of handed out buffers that were recycled
+    pub fn reuse_rate(&self) -> f64 {
+        let total = self.allocated + self.reused;
+        if total == 0 {
+            0.0
+        } else {
+            self.reused as f64 / total as f64
+        }
+    }
+}
+
+impl Default for TerrainPool {
+    fn default() -> Self {
+        TerrainPool::new()
+    }
+}
@@ -1,17 +1,17 @@
-// use crate::physics::Physics;
-// use crate::physics::Body;
-use crate::physics::Entity;
-use crate::physics::Player as PhysPlayer;
+// use inf_runner::physics::Physics;
+// use inf_runner::physics::Body;
+use inf_runner::physics::Entity;
+use inf_runner::physics::Player as PhysPlayer;
 
-use crate::proceduralgen;
-// use crate::proceduralgen::ProceduralGen;
-// use crate::proceduralgen::TerrainSegment;
+use inf_runner::proceduralgen;
+// use inf_runner::proceduralgen::ProceduralGen;
+// use inf_runner::proceduralgen::TerrainSegment;
 
-use crate::rect;
+use inf_runner::rect;
+use inf_runner::rng::GameRng;
 
 use inf_runner::Game;
 use inf_runner::GameState;
-use inf_runner::GameStatus;
 use inf_runner::SDLCore;
 //use proceduralgen::StaticObject;
 
@@ -34,6 +34,8 @@ use sdl2::rect::Point;
 use sdl2::render::BlendMode;
 use std::thread;
 
+use log::debug;
+
 use rand::Rng;
 
 const CAM_H: u32 = 720;
@@ -57,7 +59,7 @@ Modified from Farnan example code, intended for testing purposes only
 
 impl Game for TestBezier {
     fn init() -> Result<Self, String> {
-        //let core = SDLCore::init(TITLE, true, CAM_W, CAM_H)?;
+        //let core = SDLCoreBuilder::new(TITLE, CAM_W, CAM_H).build()?;
         Ok(TestBezier {})
     }
 
@@ -256,7 +258,7 @@ impl Game for TestBezier {
         */
 
         //core.wincan.set_draw_color(Color::RGBA(255, 0, 255, 255));
-        let mut rng = rand::thread_rng();
+        let mut rng = GameRng::from_entropy();
 
         //start at (0, 640)
         let mut p0: (f64, f64) = (0.0, 640.0);
@@ -278,7 +280,7 @@ impl Game for TestBezier {
             let rand_height = rng.gen_range(height_index + 160.0..height_index + 560.0);
             let p0 = (rand_width, rand_height);
 
-            println!("Width: {}\nHeight: {}\n", width_index, height_index);
+            debug!("Width: {} Height: {}", width_index, height_index);
 
             width_index = rand_width;
 
@@ -387,9 +389,6 @@ impl Game for TestBezier {
         core.wincan.present();
         thread::sleep(Duration::from_millis(TIMEOUT));
 
-        Ok(GameState {
-            status: Some(GameStatus::Main),
-            score: 0,
-        })
+        Ok(GameState::ToMenu)
     }
 }
@@ -1,24 +1,89 @@
-use crate::rect;
+use inf_runner::rect;
 
+use inf_runner::archive;
+use inf_runner::archive::SaveArchive;
+use inf_runner::assets;
+use inf_runner::audio::Audio;
+use inf_runner::biome::Biome;
+use inf_runner::config::Config;
+use inf_runner::cosmetics::Trail;
+use inf_runner::framerate::FrameCap;
+use inf_runner::highscores::HighScores;
+use inf_runner::loadout::Loadout;
+use inf_runner::loadout::MODIFIER_DEFS;
+use inf_runner::loadout::MUTATOR_DEFS;
+use inf_runner::power::PowerSaver;
+use inf_runner::profile::Profile;
+use inf_runner::profile::MAX_NAME_LEN;
+use inf_runner::progress::Progress;
+use inf_runner::quality::Quality;
+use inf_runner::race::ShareCode;
+use inf_runner::replay;
+use inf_runner::replay::InputLog;
+use inf_runner::rng::GameRng;
+use inf_runner::settings::Settings;
+use inf_runner::showcase::Showcase;
+use inf_runner::ui::{MenuInput, MenuState, Ui};
 use inf_runner::Game;
+use inf_runner::GameConfig;
 use inf_runner::GameState;
 use inf_runner::GameStatus;
+use inf_runner::RunMode;
+use inf_runner::RunStats;
 use inf_runner::SDLCore;
 
+use inf_runner::speech::Announcer;
+
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
+use sdl2::keyboard::Mod;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
+use sdl2::render::Texture;
 use sdl2::render::TextureQuery;
 
+use log::{info, warn};
+
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
 const CAM_W: u32 = 1280;
 const CAM_H: u32 = 720;
 
-pub struct Title;
+// Menu entries in focus order, with the name spoken when they gain focus
+const MENU_ITEMS: [(&str, GameStatus); 2] = [("Play", GameStatus::Game), ("Credits", GameStatus::Credits)];
+const MENU_Y: [i32; 3] = [200, 350, 500];
+
+// Seconds without input on the menu before the attract demo starts
+const IDLE_DEMO_SECS: u64 = 30;
+
+pub struct Title {
+    last_run: Option<RunStats>, // Shown on the menu until the next run ends
+}
+
+impl Title {
+    // Results of the run that just ended
+    pub fn set_last_run(&mut self, stats: RunStats) {
+        self.last_run = Some(stats);
+    }
+}
+
+// Whether the title screen is showing its menu, settings or the pre-run
+// screen, asking for a profile name or share code, or waiting for the player
+// to confirm an import
+enum TitleMode {
+    Menu,
+    Settings,
+    Loadout,
+    NewProfile,
+    ConfirmImport,
+    RaceCode,
+    HighScores,
+}
 
 impl Game for Title {
     fn init() -> Result<Self, String> {
-        Ok(Title {})
+        Ok(Title { last_run: None })
     }
 
     fn run(&mut self, core: &mut SDLCore) -> Result<GameState, String> {
@@ -26,12 +91,9 @@ impl Game for Title {
 
         let texture_creator = core.wincan.texture_creator();
 
-        core.wincan.set_draw_color(Color::RGBA(3, 120, 206, 255));
-        core.wincan.clear();
-
         let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
 
-        let mut font = ttf_context.load_font("./assets/DroidSansMono.ttf", 128)?;
+        let mut font = assets::load_font(&ttf_context, 128)?;
         font.set_style(sdl2::ttf::FontStyle::BOLD);
 
         let surface = font
@@ -87,57 +149,1126 @@ impl Game for Title {
             .create_texture_from_surface(&surface)
             .map_err(|e| e.to_string())?;
 
-        // Grey out screen
-        core.wincan.set_draw_color(Color::RGBA(0, 0, 0, 128));
-        core.wincan.fill_rect(rect!(0, 0, CAM_W, CAM_H))?;
-
-        // Draw text
-        core.wincan.copy(&title_texture, None, Some(rect!(cx, 50, w, h)))?;
-        core.wincan.copy(&play_texture, None, Some(rect!(125, 200, 600, 125)))?;
-        core.wincan
-            .copy(&credits_texture, None, Some(rect!(125, 350, 700, 125)))?;
-        core.wincan
-            .copy(&quit_texture, None, Some(rect!(125, 500, 1000, 125)))?;
+        // Profile handling. First launch goes straight to naming a profile.
+        let mut profile = Profile::current();
+        let mut mode = if profile.is_some() {
+            TitleMode::Menu
+        } else {
+            TitleMode::NewProfile
+        };
+        let mut name_entry = String::new();
+        let mut name_error: Option<String> = None;
+        let mut code_entry = String::new();
+        let mut code_error: Option<String> = None;
+        // Timestamp of the key that opened name entry, so its own text event
+        // doesn't end up in the name
+        let mut entry_opened_at: u32 = 0;
+        // Result of the last export/import, shown under the profile line
+        let mut menu_message: Option<String> = None;
+        let mut pending_import: Option<SaveArchive> = None;
+        let mut announced_message: Option<String> = None;
+        let mut redraw = true;
 
-        core.wincan.present();
+        // Keyboard focus in the menu, Up/Down to move and Enter to pick. The
+        // announcer speaks every focus change when accessibility speech is on.
+        let mut config = Config::for_profile(profile.as_ref());
+        let mut announcer = Announcer::from_config(&config);
+        let mut quality = Quality::from_config(&config);
+        let mut frame_cap = FrameCap::from_config(&config);
+        let mut settings = Settings::from_config(&config);
+        // For previewing jump sounds on the pre-run screen
+        let mut sound = Audio::open(core, settings.volume);
+        let mut settings_menu = MenuState::new();
+        let mut loadout_menu = MenuState::new();
+        let mut menu_input = MenuInput::default();
+        let mut focus: usize = 0;
+        match mode {
+            TitleMode::Menu => announcer.announce(&format!("Urban Odyssey. {}", menu_label(focus))),
+            _ => announcer.announce("Urban Odyssey. Name your profile, then press Enter"),
+        }
 
         let next_status: Option<GameStatus>;
+        // What the next run starts with if the player picks Play
+        let mut run_config = GameConfig::default();
+        // Mode Play starts in, preselected from the profile's last run and
+        // changed with M
+        let mut progress = Progress::load(profile.as_ref());
+        // Loaded again each time the table's opened, runs add to it
+        let mut high_scores = HighScores::load(profile.as_ref());
+        let mut progress_profile = profile.clone();
+        let mut run_mode = progress.last_mode();
+        // Modifiers picked on the pre-run screen, the last run's to start with
+        let mut loadout = self.last_run.as_ref().map(|s| s.config.loadout).unwrap_or_default();
+        let mut loadout_message: Option<String> = None;
+
+        let mut last_input = Instant::now();
+        let mut last_frame = Instant::now();
+        let power = PowerSaver::from_config(&config);
+        if power.is_active() {
+            info!("On battery, menus run at 30 FPS");
+        }
+
+        // A little run plays behind the menu, in the configured biome or a
+        // random one, unless the menu is drawing at low spec
+        let mut textures = assets::TextureCache::new(&texture_creator);
+        let tex_player = textures.get(&core.assets, "assets/player/player.png")?;
+        let showcase_biome = config
+            .get("biome")
+            .and_then(Biome::from_key)
+            .unwrap_or_else(|| Biome::pick(&mut GameRng::from_entropy()));
+        let mut showcase = Showcase::new(showcase_biome, (CAM_W as i32, CAM_H as i32), &tex_player);
 
         'gameloop: loop {
+            if matches!(mode, TitleMode::Menu) && last_input.elapsed() >= Duration::from_secs(IDLE_DEMO_SECS) {
+                info!("Title screen idle, starting attract demo");
+                run_config.demo = true;
+                next_status = Some(GameStatus::Game);
+                break 'gameloop;
+            }
+
             for event in core.event_pump.poll_iter() {
-                match event {
-                    Event::Quit { .. }
-                    | Event::KeyDown {
-                        keycode: Some(Keycode::Escape | Keycode::Q),
-                        ..
-                    } => {
-                        next_status = None;
-                        break 'gameloop;
-                    }
-                    Event::KeyDown { keycode: Some(k), .. } => match k {
-                        Keycode::P | Keycode::Space => {
-                            next_status = Some(GameStatus::Game);
+                if matches!(
+                    event,
+                    Event::KeyDown { .. } | Event::TextInput { .. } | Event::MouseButtonDown { .. }
+                ) {
+                    last_input = Instant::now();
+                }
+                match mode {
+                    TitleMode::NewProfile => match event {
+                        Event::Quit { .. } => {
+                            next_status = None;
+                            break 'gameloop;
+                        }
+                        Event::TextInput { text, timestamp, .. }
+                            if timestamp > entry_opened_at && name_entry.len() + text.len() <= MAX_NAME_LEN =>
+                        {
+                            name_entry.push_str(&text);
+                            redraw = true;
+                        }
+                        Event::KeyDown { keycode: Some(k), .. } => match k {
+                            Keycode::Backspace => {
+                                name_entry.pop();
+                                redraw = true;
+                            }
+                            Keycode::Return | Keycode::KpEnter => {
+                                match Profile::create(&name_entry).and_then(|p| p.select().map(|_| p)) {
+                                    Ok(p) => {
+                                        info!("Switched to new profile {}", p.name());
+                                        announcer.announce(&format!("Profile {}. {}", p.name(), menu_label(focus)));
+                                        profile = Some(p);
+                                        mode = TitleMode::Menu;
+                                        name_entry.clear();
+                                        name_error = None;
+                                    }
+                                    Err(e) => {
+                                        announcer.announce(&e);
+                                        name_error = Some(e);
+                                    }
+                                }
+                                redraw = true;
+                            }
+                            // Can only back out if there's a profile to go back to
+                            Keycode::Escape if profile.is_some() => {
+                                announcer.announce(menu_label(focus));
+                                mode = TitleMode::Menu;
+                                name_entry.clear();
+                                name_error = None;
+                                redraw = true;
+                            }
+                            _ => {}
+                        },
+                        _ => {}
+                    },
+                    TitleMode::Menu => match event {
+                        Event::Quit { .. }
+                        | Event::KeyDown {
+                            keycode: Some(Keycode::Escape | Keycode::Q),
+                            ..
+                        } => {
+                            next_status = None;
+                            break 'gameloop;
+                        }
+                        Event::KeyDown {
+                            keycode: Some(k),
+                            timestamp,
+                            ..
+                        } => match k {
+                            Keycode::Return | Keycode::KpEnter
+                                if MENU_ITEMS.get(focus).map(|(_, status)| *status) != Some(GameStatus::Game) =>
+                            {
+                                next_status = MENU_ITEMS.get(focus).map(|(_, status)| *status);
+                                break 'gameloop;
+                            }
+                            Keycode::P | Keycode::Space | Keycode::Return | Keycode::KpEnter => {
+                                match mode_config(run_mode, &progress) {
+                                    Ok(config) => {
+                                        run_config = config;
+                                        // Last run's modifiers may cost more than is left
+                                        if loadout.cost() > progress.coin_bank() {
+                                            loadout = Loadout::default();
+                                        }
+                                        mode = TitleMode::Loadout;
+                                        loadout_message = None;
+                                        announcer.announce("Before the run. Pick modifiers, then start");
+                                        redraw = true;
+                                    }
+                                    Err(e) => {
+                                        menu_message = Some(e);
+                                        redraw = true;
+                                    }
+                                }
+                            }
+                            Keycode::M => {
+                                run_mode = run_mode.next();
+                                announcer.announce(&format!("Mode {}", run_mode.label()));
+                                redraw = true;
+                            }
+                            Keycode::Up | Keycode::Down => {
+                                let count = MENU_ITEMS.len() + 1;
+                                focus = if k == Keycode::Up {
+                                    (focus + count - 1) % count
+                                } else {
+                                    (focus + 1) % count
+                                };
+                                announcer.announce(menu_label(focus));
+                                redraw = true;
+                            }
+                            Keycode::C => {
+                                next_status = Some(GameStatus::Credits);
+                                break 'gameloop;
+                            }
+                            Keycode::B => {
+                                next_status = Some(GameStatus::BezierSim);
+                                break 'gameloop;
+                            }
+                            Keycode::Tab => {
+                                // Cycle to the next profile alphabetically
+                                let all = Profile::all();
+                                let next = match all.iter().position(|p| Some(p) == profile.as_ref()) {
+                                    Some(i) => all.get((i + 1) % all.len()),
+                                    None => all.first(),
+                                };
+                                if let Some(p) = next {
+                                    p.select()?;
+                                    info!("Switched to profile {}", p.name());
+                                    announcer.announce(&format!("Profile {}", p.name()));
+                                    profile = Some(p.clone());
+                                    redraw = true;
+                                }
+                            }
+                            Keycode::S => {
+                                mode = TitleMode::Settings;
+                                menu_message = None;
+                                announcer.announce("Settings. Up and down to move, left and right to change");
+                                redraw = true;
+                            }
+                            Keycode::H => {
+                                high_scores = HighScores::load(profile.as_ref());
+                                mode = TitleMode::HighScores;
+                                announcer.announce(&match high_scores.entries().first() {
+                                    Some(best) => format!("High scores. Best {} points", best.score),
+                                    None => "High scores. None yet".to_string(),
+                                });
+                                redraw = true;
+                            }
+                            Keycode::R => {
+                                mode = TitleMode::RaceCode;
+                                entry_opened_at = timestamp;
+                                announcer.announce("Race a friend. Type or paste their share code, then press Enter");
+                                redraw = true;
+                            }
+                            Keycode::W | Keycode::G => match replay::latest().map(|path| (InputLog::load(&path), path))
+                            {
+                                // W watches the replay, G races its ghost
+                                Some((Ok(log), path)) => {
+                                    if k == Keycode::W {
+                                        info!("Watching replay {}", path.display());
+                                        run_config.replay = Some(log);
+                                    } else {
+                                        info!("Racing the ghost of replay {}", path.display());
+                                        run_config.ghost = Some(log);
+                                    }
+                                    next_status = Some(GameStatus::Game);
+                                    break 'gameloop;
+                                }
+                                Some((Err(e), path)) => {
+                                    warn!("Could not load replay {}: {}", path.display(), e);
+                                    menu_message = Some(format!("Could not load replay: {}", e));
+                                    redraw = true;
+                                }
+                                None => {
+                                    menu_message = Some(format!(
+                                        "No .{} file found in {}/",
+                                        replay::EXTENSION,
+                                        replay::REPLAY_DIR
+                                    ));
+                                    redraw = true;
+                                }
+                            },
+                            Keycode::N => {
+                                mode = TitleMode::NewProfile;
+                                entry_opened_at = timestamp;
+                                announcer.announce("New profile. Type a name, then press Enter");
+                                redraw = true;
+                            }
+                            Keycode::L => {
+                                quality = if quality.low_spec {
+                                    Quality::full()
+                                } else {
+                                    Quality::low_spec()
+                                };
+                                let state = if quality.low_spec { "on" } else { "off" };
+                                menu_message = Some(match quality.save(&mut config) {
+                                    Ok(()) => {
+                                        info!("Low-spec mode {}", state);
+                                        format!("Low-spec mode {}, display scaling applies after a restart", state)
+                                    }
+                                    Err(e) => format!("Could not save settings: {}", e),
+                                });
+                                redraw = true;
+                            }
+                            Keycode::F => {
+                                frame_cap = frame_cap.next();
+                                menu_message = Some(match frame_cap.save(&mut config) {
+                                    Ok(()) => {
+                                        info!("Frame rate cap set to {}", frame_cap.label());
+                                        format!("Frame rate {}, vsync changes apply after a restart", frame_cap.label())
+                                    }
+                                    Err(e) => format!("Could not save settings: {}", e),
+                                });
+                                redraw = true;
+                            }
+                            _ => {}
+                        },
+                        _ => {}
+                    },
+                    TitleMode::Settings | TitleMode::Loadout => match event {
+                        Event::Quit { .. } => {
+                            next_status = None;
                             break 'gameloop;
                         }
-                        Keycode::C => {
-                            next_status = Some(GameStatus::Credits);
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Escape),
+                            ..
+                        } => {
+                            announcer.announce(menu_label(focus));
+                            mode = TitleMode::Menu;
+                            redraw = true;
+                        }
+                        event => redraw |= menu_input.handle(&event),
+                    },
+                    TitleMode::RaceCode => match event {
+                        Event::Quit { .. } => {
+                            next_status = None;
                             break 'gameloop;
                         }
-                        Keycode::B => {
-                            next_status = Some(GameStatus::BezierSim);
+                        Event::TextInput { text, timestamp, .. } if timestamp > entry_opened_at => {
+                            code_entry.push_str(&text);
+                            redraw = true;
+                        }
+                        Event::KeyDown {
+                            keycode: Some(k),
+                            keymod,
+                            ..
+                        } => match k {
+                            Keycode::V
+                                if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD | Mod::LGUIMOD | Mod::RGUIMOD) =>
+                            {
+                                let clipboard = core.wincan.window().subsystem().clipboard();
+                                if let Ok(text) = clipboard.clipboard_text() {
+                                    code_entry = text.trim().to_string();
+                                    redraw = true;
+                                }
+                            }
+                            Keycode::Backspace => {
+                                code_entry.pop();
+                                redraw = true;
+                            }
+                            Keycode::Return | Keycode::KpEnter => match ShareCode::decode(&code_entry) {
+                                Ok(code) => {
+                                    info!("Racing share code with seed {}", code.seed());
+                                    run_config.race = Some(code);
+                                    next_status = Some(GameStatus::Game);
+                                    break 'gameloop;
+                                }
+                                Err(e) => {
+                                    announcer.announce(&e);
+                                    code_error = Some(e);
+                                    redraw = true;
+                                }
+                            },
+                            Keycode::Escape => {
+                                announcer.announce(menu_label(focus));
+                                mode = TitleMode::Menu;
+                                code_entry.clear();
+                                code_error = None;
+                                redraw = true;
+                            }
+                            _ => {}
+                        },
+                        _ => {}
+                    },
+                    TitleMode::HighScores => match event {
+                        Event::Quit { .. } => {
+                            next_status = None;
                             break 'gameloop;
                         }
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Escape | Keycode::H | Keycode::Return | Keycode::KpEnter),
+                            ..
+                        } => {
+                            announcer.announce(menu_label(focus));
+                            mode = TitleMode::Menu;
+                            redraw = true;
+                        }
                         _ => {}
                     },
-                    _ => {}
+                    TitleMode::ConfirmImport => match event {
+                        Event::Quit { .. } => {
+                            next_status = None;
+                            break 'gameloop;
+                        }
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Y),
+                            ..
+                        } => {
+                            if let Some(a) = pending_import.take() {
+                                menu_message = Some(match a.restore().and_then(|p| p.select().map(|_| p)) {
+                                    Ok(p) => {
+                                        info!("Imported profile {}", p.name());
+                                        let msg = format!("Imported profile {}", p.name());
+                                        profile = Some(p);
+                                        msg
+                                    }
+                                    Err(e) => {
+                                        warn!("Import failed: {}", e);
+                                        format!("Import failed: {}", e)
+                                    }
+                                });
+                            }
+                            mode = TitleMode::Settings;
+                            redraw = true;
+                        }
+                        Event::KeyDown {
+                            keycode: Some(Keycode::N | Keycode::Escape),
+                            ..
+                        } => {
+                            pending_import = None;
+                            announcer.announce("Import cancelled");
+                            mode = TitleMode::Settings;
+                            redraw = true;
+                        }
+                        _ => {}
+                    },
+                }
+            }
+
+            // Another profile has its own last mode and settings
+            if profile != progress_profile {
+                config = Config::for_profile(profile.as_ref());
+                announcer = Announcer::from_config(&config);
+                quality = Quality::from_config(&config);
+                frame_cap = FrameCap::from_config(&config);
+                settings = Settings::from_config(&config);
+                sound = Audio::open(core, settings.volume);
+                progress = Progress::load(profile.as_ref());
+                progress_profile = profile.clone();
+                run_mode = progress.last_mode();
+            }
+
+            if menu_message != announced_message {
+                if let Some(msg) = &menu_message {
+                    announcer.announce(msg);
                 }
+                announced_message = menu_message.clone();
+            }
+
+            let show_showcase = matches!(mode, TitleMode::Menu) && !power.menu_quality(quality).low_spec;
+            if show_showcase {
+                let cosmetics = progress.cosmetics();
+                showcase.dress(cosmetics.trail, progress.drone_unlocked());
+                showcase.update(Instant::now());
+                redraw = true;
+            }
+
+            if redraw {
+                // Leaving settings, or a change the widgets didn't draw,
+                // draws again on the next pass
+                let mut redraw_again = false;
+                core.wincan.set_draw_color(Color::RGBA(3, 120, 206, 255));
+                core.wincan.clear();
+                if show_showcase {
+                    showcase.draw(&mut core.wincan)?;
+                }
+
+                // Grey out screen
+                core.wincan.set_draw_color(Color::RGBA(0, 0, 0, 128));
+                core.wincan.fill_rect(rect!(0, 0, CAM_W, CAM_H))?;
+
+                // Draw text
+                core.wincan.copy(&title_texture, None, Some(rect!(cx, 50, w, h)))?;
+
+                match mode {
+                    TitleMode::Menu => {
+                        core.wincan
+                            .copy(&play_texture, None, Some(rect!(125, MENU_Y[0], 600, 125)))?;
+                        core.wincan
+                            .copy(&credits_texture, None, Some(rect!(125, MENU_Y[1], 700, 125)))?;
+                        core.wincan
+                            .copy(&quit_texture, None, Some(rect!(125, MENU_Y[2], 1000, 125)))?;
+
+                        let tex_quality = texture_creator
+                            .create_texture_from_surface(
+                                &font
+                                    .render(&format!(
+                                        "L - Low spec: {}",
+                                        if quality.low_spec { "On" } else { "Off" }
+                                    ))
+                                    .blended(Color::RGBA(255, 255, 255, 255))
+                                    .map_err(|e| e.to_string())?,
+                            )
+                            .map_err(|e| e.to_string())?;
+                        draw_text_line(core, &tex_quality, 820, 220, 30)?;
+
+                        let tex_mode = texture_creator
+                            .create_texture_from_surface(
+                                &font
+                                    .render(&format!("M - Mode: {}", run_mode.label()))
+                                    .blended(Color::RGBA(255, 255, 255, 255))
+                                    .map_err(|e| e.to_string())?,
+                            )
+                            .map_err(|e| e.to_string())?;
+                        draw_text_line(core, &tex_mode, 820, 180, 30)?;
+
+                        let tex_fps = texture_creator
+                            .create_texture_from_surface(
+                                &font
+                                    .render(&format!("F - Frame rate: {}", frame_cap.label()))
+                                    .blended(Color::RGBA(255, 255, 255, 255))
+                                    .map_err(|e| e.to_string())?,
+                            )
+                            .map_err(|e| e.to_string())?;
+                        draw_text_line(core, &tex_fps, 820, 260, 30)?;
+
+                        let tex_race = texture_creator
+                            .create_texture_from_surface(
+                                &font
+                                    .render("R - Race a share code")
+                                    .blended(Color::RGBA(255, 255, 255, 255))
+                                    .map_err(|e| e.to_string())?,
+                            )
+                            .map_err(|e| e.to_string())?;
+                        draw_text_line(core, &tex_race, 820, 300, 30)?;
+
+                        let tex_settings = texture_creator
+                            .create_texture_from_surface(
+                                &font
+                                    .render("S - Settings")
+                                    .blended(Color::RGBA(255, 255, 255, 255))
+                                    .map_err(|e| e.to_string())?,
+                            )
+                            .map_err(|e| e.to_string())?;
+                        draw_text_line(core, &tex_settings, 820, 340, 30)?;
+
+                        let tex_replay = texture_creator
+                            .create_texture_from_surface(
+                                &font
+                                    .render("W - Watch replay")
+                                    .blended(Color::RGBA(255, 255, 255, 255))
+                                    .map_err(|e| e.to_string())?,
+                            )
+                            .map_err(|e| e.to_string())?;
+                        draw_text_line(core, &tex_replay, 820, 380, 30)?;
+
+                        let tex_ghost = texture_creator
+                            .create_texture_from_surface(
+                                &font
+                                    .render("G - Race replay ghost")
+                                    .blended(Color::RGBA(255, 255, 255, 255))
+                                    .map_err(|e| e.to_string())?,
+                            )
+                            .map_err(|e| e.to_string())?;
+                        draw_text_line(core, &tex_ghost, 820, 420, 30)?;
+
+                        let tex_scores = texture_creator
+                            .create_texture_from_surface(
+                                &font
+                                    .render("H - High scores")
+                                    .blended(Color::RGBA(255, 255, 255, 255))
+                                    .map_err(|e| e.to_string())?,
+                            )
+                            .map_err(|e| e.to_string())?;
+                        draw_text_line(core, &tex_scores, 820, 460, 30)?;
+
+                        if let Some(stats) = &self.last_run {
+                            let tex_last = texture_creator
+                                .create_texture_from_surface(
+                                    &font
+                                        .render(&format!(
+                                            "Last run: {} points{}",
+                                            stats.total(),
+                                            if stats.ranked { "" } else { " (assisted)" }
+                                        ))
+                                        .blended(Color::RGBA(255, 255, 0, 255))
+                                        .map_err(|e| e.to_string())?,
+                                )
+                                .map_err(|e| e.to_string())?;
+                            draw_text_line(core, &tex_last, 820, 510, 30)?;
+                        }
+
+                        // Focus marker
+                        core.wincan.set_draw_color(Color::RGBA(255, 255, 255, 255));
+                        core.wincan.fill_rect(rect!(85, MENU_Y[focus] + 45, 25, 35))?;
+
+                        let name = profile.as_ref().map(|p| p.name()).unwrap_or("");
+                        let tex_profile = texture_creator
+                            .create_texture_from_surface(
+                                &font
+                                    .render(&format!(
+                                        "Profile: {}  (Tab - Switch, N - New)",
+                                        name
+                                    ))
+                                    .blended(Color::RGBA(255, 255, 255, 255))
+                                    .map_err(|e| e.to_string())?,
+                            )
+                            .map_err(|e| e.to_string())?;
+                        draw_text_line(core, &tex_profile, 125, 640, 36)?;
+
+                        if let Some(msg) = &menu_message {
+                            let tex_msg = texture_creator
+                                .create_texture_from_surface(
+                                    &font
+                                        .render(msg)
+                                        .blended(Color::RGBA(255, 255, 0, 255))
+                                        .map_err(|e| e.to_string())?,
+                                )
+                                .map_err(|e| e.to_string())?;
+                            draw_text_line(core, &tex_msg, 125, 680, 30)?;
+                        }
+                    }
+                    TitleMode::Settings => {
+                        let mut ui = Ui::begin(
+                            &mut settings_menu,
+                            std::mem::take(&mut menu_input),
+                            &font,
+                            settings.ui_scale(),
+                        );
+                        let row = ui.row_height();
+                        let x = 160;
+                        let mut y = 220;
+
+                        let mut low_spec = quality.low_spec;
+                        if ui.toggle(&mut core.wincan, &texture_creator, "Low spec", &mut low_spec, (x, y))? {
+                            quality = if low_spec { Quality::low_spec() } else { Quality::full() };
+                            if let Err(e) = quality.save(&mut config) {
+                                warn!("Could not save settings: {}", e);
+                            }
+                        }
+                        y += row;
+                        let fps_label = format!("Frame rate: {}", frame_cap.label());
+                        if ui.button(&mut core.wincan, &texture_creator, &fps_label, (x, y))? {
+                            frame_cap = frame_cap.next();
+                            if let Err(e) = frame_cap.save(&mut config) {
+                                warn!("Could not save settings: {}", e);
+                            }
+                            announcer.announce(&format!("Frame rate {}", frame_cap.label()));
+                        }
+                        y += row;
+                        let mut changed = false;
+                        if ui.toggle(
+                            &mut core.wincan,
+                            &texture_creator,
+                            "Fullscreen",
+                            &mut settings.fullscreen,
+                            (x, y),
+                        )? {
+                            core.set_fullscreen(settings.fullscreen)?;
+                            changed = true;
+                        }
+                        y += row;
+                        changed |= ui.toggle(
+                            &mut core.wincan,
+                            &texture_creator,
+                            "Show hitboxes",
+                            &mut settings.show_hitboxes,
+                            (x, y),
+                        )?;
+                        y += row;
+                        changed |= ui.toggle(
+                            &mut core.wincan,
+                            &texture_creator,
+                            "Show inputs (F4 in a run)",
+                            &mut settings.show_inputs,
+                            (x, y),
+                        )?;
+                        y += row;
+                        let (min, max) = Settings::VOLUME_RANGE;
+                        changed |= ui.slider(
+                            &mut core.wincan,
+                            &texture_creator,
+                            "Volume",
+                            &mut settings.volume,
+                            (min, max, 10),
+                            (x, y),
+                        )?;
+                        y += row;
+                        let (min, max) = Settings::UI_SCALE_RANGE;
+                        changed |= ui.slider(
+                            &mut core.wincan,
+                            &texture_creator,
+                            "UI scale",
+                            &mut settings.ui_scale,
+                            (min, max, 25),
+                            (x, y),
+                        )?;
+                        y += row;
+                        if ui.toggle(
+                            &mut core.wincan,
+                            &texture_creator,
+                            "Pixel perfect",
+                            &mut settings.pixel_perfect,
+                            (x, y),
+                        )? {
+                            core.set_pixel_perfect(settings.pixel_perfect)?;
+                            changed = true;
+                        }
+                        y += row;
+                        let filter_label = format!("Texture filter: {}", settings.texture_filter.label());
+                        if ui.button(&mut core.wincan, &texture_creator, &filter_label, (x, y))? {
+                            settings.texture_filter = settings.texture_filter.next();
+                            core.set_texture_filter(settings.texture_filter);
+                            announcer.announce(&format!("Texture filter {}", settings.texture_filter.label()));
+                            changed = true;
+                        }
+                        y += row;
+                        changed |= ui.toggle(
+                            &mut core.wincan,
+                            &texture_creator,
+                            "Supersample runs",
+                            &mut settings.supersample,
+                            (x, y),
+                        )?;
+                        // Gameplay settings get a column of their own
+                        let col_x = x + 620;
+                        let mut col_y = 220;
+                        changed |= ui.toggle(
+                            &mut core.wincan,
+                            &texture_creator,
+                            "Auto-jump assist",
+                            &mut settings.auto_jump,
+                            (col_x, col_y),
+                        )?;
+                        col_y += row;
+                        changed |= ui.toggle(
+                            &mut core.wincan,
+                            &texture_creator,
+                            "Reduce motion",
+                            &mut settings.reduce_motion,
+                            (col_x, col_y),
+                        )?;
+                        col_y += row;
+                        changed |= ui.toggle(
+                            &mut core.wincan,
+                            &texture_creator,
+                            "Battery saver",
+                            &mut settings.battery_saver,
+                            (col_x, col_y),
+                        )?;
+                        col_y += row;
+                        changed |= ui.toggle(
+                            &mut core.wincan,
+                            &texture_creator,
+                            "Hold R restarts same seed",
+                            &mut settings.restart_same_seed,
+                            (col_x, col_y),
+                        )?;
+                        // Saves go to saves/, and are read back from import/
+                        col_y += row * 2;
+                        if ui.button(&mut core.wincan, &texture_creator, "Export saves", (col_x, col_y))? {
+                            if let Some(p) = &profile {
+                                let path = archive::export_path(p);
+                                let result = SaveArchive::collect(p).and_then(|a| a.write(&path));
+                                menu_message = Some(match result {
+                                    Ok(()) => {
+                                        info!("Exported profile {} to {}", p.name(), path.display());
+                                        format!("Exported to {}", path.display())
+                                    }
+                                    Err(e) => {
+                                        warn!("Export failed: {}", e);
+                                        format!("Export failed: {}", e)
+                                    }
+                                });
+                            }
+                        }
+                        col_y += row;
+                        if ui.button(&mut core.wincan, &texture_creator, "Import saves", (col_x, col_y))? {
+                            match archive::find_import().map(|path| (SaveArchive::read(&path), path)) {
+                                Some((Ok(a), path)) => {
+                                    info!("Read archive {}", path.display());
+                                    announcer.announce(&format!(
+                                        "Import profile {}? This overwrites its saves. Y to import, N to cancel",
+                                        a.profile_name()
+                                    ));
+                                    pending_import = Some(a);
+                                    mode = TitleMode::ConfirmImport;
+                                    redraw_again = true;
+                                }
+                                Some((Err(e), path)) => {
+                                    warn!("Could not import {}: {}", path.display(), e);
+                                    menu_message = Some(format!("Import failed: {}", e));
+                                }
+                                None => {
+                                    menu_message = Some(format!(
+                                        "No .{} file found in {}/",
+                                        archive::EXTENSION,
+                                        archive::IMPORT_DIR
+                                    ));
+                                }
+                            }
+                        }
+                        // Result of the last export or import
+                        if let Some(msg) = &menu_message {
+                            let tex_msg = texture_creator
+                                .create_texture_from_surface(
+                                    &font
+                                        .render(msg)
+                                        .blended(Color::RGBA(255, 255, 0, 255))
+                                        .map_err(|e| e.to_string())?,
+                                )
+                                .map_err(|e| e.to_string())?;
+                            draw_text_line(core, &tex_msg, col_x, col_y + row, 30)?;
+                        }
+                        y += row;
+                        let back = ui.button(&mut core.wincan, &texture_creator, "Back", (x, y))?;
+
+                        if let Some(label) = ui.end() {
+                            announcer.announce(&label);
+                        }
+                        if changed {
+                            if let Err(e) = settings.save(&mut config) {
+                                warn!("Could not save settings: {}", e);
+                            }
+                        }
+                        if back {
+                            announcer.announce(menu_label(focus));
+                            mode = TitleMode::Menu;
+                            redraw_again = true;
+                        }
+                    }
+                    TitleMode::Loadout => {
+                        let bank = progress.coin_bank();
+                        let tex_heading = texture_creator
+                            .create_texture_from_surface(
+                                &font
+                                    .render(&format!("Before the run - {} coins banked", bank))
+                                    .blended(Color::RGBA(255, 255, 255, 255))
+                                    .map_err(|e| e.to_string())?,
+                            )
+                            .map_err(|e| e.to_string())?;
+                        draw_text_line(core, &tex_heading, 125, 100, 60)?;
+
+                        let mut ui = Ui::begin(
+                            &mut loadout_menu,
+                            std::mem::take(&mut menu_input),
+                            &font,
+                            settings.ui_scale(),
+                        );
+                        let row = ui.row_height();
+                        let x = 160;
+                        let mut y = 220;
+                        for def in MODIFIER_DEFS.iter() {
+                            let label = format!("{} ({} coins, score x{})", def.label, def.cost, def.score_scale);
+                            let mut picked = loadout.has(def.modifier);
+                            if ui.toggle(&mut core.wincan, &texture_creator, &label, &mut picked, (x, y))? {
+                                let mut wanted = loadout;
+                                wanted.set(def.modifier, picked);
+                                // Can only pick what the bank covers
+                                if wanted.cost() <= bank {
+                                    loadout = wanted;
+                                    loadout_message = None;
+                                } else {
+                                    loadout_message = Some(format!("Not enough coins for {}", def.label));
+                                    redraw_again = true;
+                                }
+                            }
+                            y += row;
+                        }
+                        // Mutators are free, they only move the run to another board
+                        for def in MUTATOR_DEFS.iter() {
+                            let label = format!("{} (own board)", def.label);
+                            let mut picked = loadout.has_mutator(def.mutator);
+                            if ui.toggle(&mut core.wincan, &texture_creator, &label, &mut picked, (x, y))? {
+                                loadout.set_mutator(def.mutator, picked);
+                            }
+                            y += row;
+                        }
+                        // Cosmetics are free too, each pick is previewed
+                        let col_x = x + 620;
+                        let mut col_y = 220;
+                        let mut cosmetics = progress.cosmetics();
+                        let trail_label = format!("Trail: {}", cosmetics.trail.def().label);
+                        let next_trail = ui.button(&mut core.wincan, &texture_creator, &trail_label, (col_x, col_y))?;
+                        col_y += row;
+                        let sound_label = format!("Jump sound: {}", cosmetics.jump_sound.def().label);
+                        let next_sound = ui.button(&mut core.wincan, &texture_creator, &sound_label, (col_x, col_y))?;
+                        col_y += row;
+                        let start = ui.button(&mut core.wincan, &texture_creator, "Start run", (x, y))?;
+                        y += row;
+                        let back = ui.button(&mut core.wincan, &texture_creator, "Back", (x, y))?;
+
+                        if next_trail || next_sound {
+                            if next_trail {
+                                cosmetics.trail = cosmetics.trail.next();
+                            }
+                            if next_sound {
+                                cosmetics.jump_sound = cosmetics.jump_sound.next();
+                                sound.play(cosmetics.jump_sound.def().notes);
+                            }
+                            progress.set_cosmetics(cosmetics);
+                            if let Err(e) = progress.save() {
+                                warn!("Could not save progress: {}", e);
+                            }
+                            redraw_again = true;
+                        }
+                        Trail::preview(
+                            cosmetics.trail,
+                            (col_x, col_y + row / 2),
+                            (col_x + 300, col_y + row / 2),
+                        )
+                        .draw(&mut core.wincan)?;
+
+                        if let Some(label) = ui.end() {
+                            announcer.announce(&label);
+                        }
+
+                        let summary = loadout_message.clone().unwrap_or_else(|| {
+                            format!(
+                                "Costs {} coins, score {}, {} best {}",
+                                loadout.cost(),
+                                loadout.score_label(),
+                                loadout.board_label(),
+                                progress.best_score(loadout.board())
+                            )
+                        });
+                        let tex_summary = texture_creator
+                            .create_texture_from_surface(
+                                &font
+                                    .render(&summary)
+                                    .blended(Color::RGBA(255, 255, 0, 255))
+                                    .map_err(|e| e.to_string())?,
+                            )
+                            .map_err(|e| e.to_string())?;
+                        draw_text_line(core, &tex_summary, 160, 620, 36)?;
+
+                        if start {
+                            run_config.loadout = loadout;
+                            next_status = Some(GameStatus::Game);
+                            break 'gameloop;
+                        }
+                        if back {
+                            announcer.announce(menu_label(focus));
+                            mode = TitleMode::Menu;
+                            redraw_again = true;
+                        }
+                    }
+                    TitleMode::ConfirmImport => {
+                        let (name, count) = pending_import
+                            .as_ref()
+                            .map(|a| (a.profile_name(), a.file_count()))
+                            .unwrap_or(("", 0));
+                        let lines = [
+                            format!("Import profile {} ({} files)?", name, count),
+                            "This overwrites its saves and settings.".to_string(),
+                            "Y - Import, N - Cancel".to_string(),
+                        ];
+                        for (i, line) in lines.iter().enumerate() {
+                            let tex_line = texture_creator
+                                .create_texture_from_surface(
+                                    &font
+                                        .render(line)
+                                        .blended(Color::RGBA(255, 255, 255, 255))
+                                        .map_err(|e| e.to_string())?,
+                                )
+                                .map_err(|e| e.to_string())?;
+                            draw_text_line(core, &tex_line, 125, 250 + i as i32 * 100, 60)?;
+                        }
+                    }
+                    TitleMode::HighScores => {
+                        let tex_heading = texture_creator
+                            .create_texture_from_surface(
+                                &font
+                                    .render("High scores")
+                                    .blended(Color::RGBA(119, 3, 252, 255))
+                                    .map_err(|e| e.to_string())?,
+                            )
+                            .map_err(|e| e.to_string())?;
+                        draw_text_line(core, &tex_heading, 125, 200, 70)?;
+
+                        let mut lines: Vec<String> = high_scores
+                            .entries()
+                            .iter()
+                            .enumerate()
+                            .map(|(i, e)| format!("{:>2}.  {:>7}   {}   Seed {}", i + 1, e.score, e.date(), e.seed))
+                            .collect();
+                        if lines.is_empty() {
+                            lines.push("No high scores yet, they're kept once a run ends".to_string());
+                        }
+                        for (i, line) in lines.iter().enumerate() {
+                            let tex_line = texture_creator
+                                .create_texture_from_surface(
+                                    &font
+                                        .render(line)
+                                        .blended(Color::RGBA(255, 255, 255, 255))
+                                        .map_err(|e| e.to_string())?,
+                                )
+                                .map_err(|e| e.to_string())?;
+                            draw_text_line(core, &tex_line, 160, 290 + i as i32 * 34, 30)?;
+                        }
+
+                        let tex_hint = texture_creator
+                            .create_texture_from_surface(
+                                &font
+                                    .render("Escape - Back")
+                                    .blended(Color::RGBA(255, 255, 255, 255))
+                                    .map_err(|e| e.to_string())?,
+                            )
+                            .map_err(|e| e.to_string())?;
+                        draw_text_line(core, &tex_hint, 125, 650, 36)?;
+                    }
+                    TitleMode::RaceCode => {
+                        let tex_prompt = texture_creator
+                            .create_texture_from_surface(
+                                &font
+                                    .render("Friend's share code:")
+                                    .blended(Color::RGBA(119, 3, 252, 255))
+                                    .map_err(|e| e.to_string())?,
+                            )
+                            .map_err(|e| e.to_string())?;
+                        draw_text_line(core, &tex_prompt, 125, 220, 90)?;
+
+                        let tex_code = texture_creator
+                            .create_texture_from_surface(
+                                &font
+                                    .render(&format!("{}_", code_entry))
+                                    .blended(Color::RGBA(255, 255, 255, 255))
+                                    .map_err(|e| e.to_string())?,
+                            )
+                            .map_err(|e| e.to_string())?;
+                        draw_text_line(core, &tex_code, 60, 350, 50)?;
+
+                        let hint = code_error
+                            .clone()
+                            .unwrap_or_else(|| "Enter - Race, Ctrl+V - Paste, Escape - Cancel".to_string());
+                        let tex_hint = texture_creator
+                            .create_texture_from_surface(
+                                &font
+                                    .render(&hint)
+                                    .blended(Color::RGBA(255, 255, 255, 255))
+                                    .map_err(|e| e.to_string())?,
+                            )
+                            .map_err(|e| e.to_string())?;
+                        draw_text_line(core, &tex_hint, 125, 520, 40)?;
+                    }
+                    TitleMode::NewProfile => {
+                        let tex_prompt = texture_creator
+                            .create_texture_from_surface(
+                                &font
+                                    .render("Name your profile:")
+                                    .blended(Color::RGBA(119, 3, 252, 255))
+                                    .map_err(|e| e.to_string())?,
+                            )
+                            .map_err(|e| e.to_string())?;
+                        draw_text_line(core, &tex_prompt, 125, 220, 90)?;
+
+                        let tex_name = texture_creator
+                            .create_texture_from_surface(
+                                &font
+                                    .render(&format!("{}_", name_entry))
+                                    .blended(Color::RGBA(255, 255, 255, 255))
+                                    .map_err(|e| e.to_string())?,
+                            )
+                            .map_err(|e| e.to_string())?;
+                        draw_text_line(core, &tex_name, 125, 350, 110)?;
+
+                        let hint = match &name_error {
+                            Some(e) => e.clone(),
+                            None if profile.is_some() => "Enter - Confirm, Escape - Cancel".to_string(),
+                            None => "Enter - Confirm".to_string(),
+                        };
+                        let tex_hint = texture_creator
+                            .create_texture_from_surface(
+                                &font
+                                    .render(&hint)
+                                    .blended(Color::RGBA(255, 255, 255, 255))
+                                    .map_err(|e| e.to_string())?,
+                            )
+                            .map_err(|e| e.to_string())?;
+                        draw_text_line(core, &tex_hint, 125, 520, 40)?;
+                    }
+                }
+
+                core.wincan.present();
+                redraw = redraw_again;
+            }
+
+            // Nothing here needs more than the frame cap, or 30 FPS on battery
+            let frame_cap = power.menu_frame_cap(frame_cap);
+            let delay = frame_cap.frame_time().unwrap_or(0.0) - last_frame.elapsed().as_secs_f64();
+            if delay > 0.0 {
+                sleep(Duration::from_secs_f64(delay));
+            }
+            last_frame = Instant::now();
+        }
+
+        // Whatever the player starts is what Play starts next time
+        if next_status == Some(GameStatus::Game) && !run_config.demo && run_config.replay.is_none() {
+            progress.remember_mode(&run_config);
+            if let Err(e) = progress.save() {
+                warn!("Could not save progress: {}", e);
             }
         }
 
         // Out of game loop, return Ok
-        Ok(GameState {
-            status: next_status,
-            score: 0,
+        Ok(match next_status {
+            Some(GameStatus::Game) => GameState::ToGame(run_config),
+            Some(status) => GameState::to(status),
+            None => GameState::Quit,
         })
     }
 }
+
+// Draws a line of text at the given height, keeping the text's aspect ratio
+fn draw_text_line(core: &mut SDLCore, texture: &Texture, x: i32, y: i32, h: u32) -> Result<(), String> {
+    let TextureQuery { width, height, .. } = texture.query();
+    let w = (width as f64 * h as f64 / height as f64) as u32;
+    core.wincan
+        .copy(texture, None, Some(rect!(x, y, w.min(CAM_W - x as u32), h)))
+}
+
+// What gets spoken when the given menu entry gains focus
+// Config for a run in `mode`. Races reuse the last share code and ghosts the
+// newest replay, the same ones R and G would pick.
+fn mode_config(mode: RunMode, progress: &Progress) -> Result<GameConfig, String> {
+    let mut config = GameConfig::default();
+    match mode {
+        RunMode::Solo => {}
+        RunMode::Race => {
+            let code = progress
+                .last_race_code()
+                .ok_or("No share code raced yet, press R to enter one")?;
+            config.race = Some(ShareCode::decode(code)?);
+        }
+        RunMode::Ghost => {
+            let path = replay::latest().ok_or("No replay to race, save one from the results screen")?;
+            config.ghost = Some(InputLog::load(path)?);
+        }
+    }
+    Ok(config)
+}
+
+fn menu_label(focus: usize) -> &'static str {
+    match MENU_ITEMS.get(focus) {
+        Some((label, _)) => label,
+        None => "Quit",
+    }
+}
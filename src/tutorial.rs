@@ -0,0 +1,60 @@
+// One-time introductions to each obstacle. The first time one spawns in a
+// profile's runs, time slows and a card names it and says how to get past it.
+// Which ones a profile has seen is kept in its progress, so they're only ever
+// shown once. Slowing time only changes how fast steps play out, not what
+// happens in them, so replays of the run are unaffected.
+
+use crate::ObstacleType;
+
+// Steps a card stays up, and how fast time runs meanwhile
+pub const CALLOUT_STEPS: u32 = 150;
+pub const SLOW_MOTION: f64 = 0.3;
+// Steps at the end of a card over which time speeds back up
+const EASE_STEPS: u32 = 30;
+
+pub struct Callout {
+    pub obstacle: ObstacleType,
+    pub key: &'static str, // Name in progress files
+    pub title: &'static str,
+    pub hint: &'static str,
+}
+
+pub const CALLOUTS: [Callout; 4] = [
+    Callout {
+        obstacle: ObstacleType::Statue,
+        key: "statue",
+        title: "Statue",
+        hint: "Jump over it, or flip or shield into it to bounce off",
+    },
+    Callout {
+        obstacle: ObstacleType::GoldenStatue,
+        key: "golden_statue",
+        title: "Golden statue",
+        hint: "Any touch is fatal, but clearing it cleanly pays big",
+    },
+    Callout {
+        obstacle: ObstacleType::Balloon,
+        key: "balloon",
+        title: "Balloon",
+        hint: "Only the balloon hurts, you can pass under its string",
+    },
+    Callout {
+        obstacle: ObstacleType::Chest,
+        key: "chest",
+        title: "Chest",
+        hint: "Jump it or knock it out of the way",
+    },
+];
+
+impl Callout {
+    pub fn of(obstacle: ObstacleType) -> &'static Callout {
+        CALLOUTS.iter().find(|c| c.obstacle == obstacle).unwrap()
+    }
+}
+
+// How fast time runs with `steps_left` of a card to go, easing back to full
+// speed at the end so the run doesn't lurch
+pub fn time_scale(steps_left: u32) -> f64 {
+    let t = (steps_left as f64 / EASE_STEPS as f64).clamp(0.0, 1.0);
+    1.0 + (SLOW_MOTION - 1.0) * t
+}
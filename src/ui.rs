@@ -0,0 +1,231 @@
+// Immediate-mode menu widgets. A screen calls `button`, `toggle` and `slider`
+// in order every time it draws, and each call both draws the widget and
+// reports whether it was used. Only the focus position lives between frames,
+// in a MenuState, so screens don't keep rects and text textures of their own.
+//
+// Keyboard only: Up/Down move focus, Left/Right change sliders and Enter or
+// Space presses buttons and flips toggles.
+
+use crate::rect;
+
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{TextureCreator, TextureQuery, WindowCanvas};
+use sdl2::ttf::Font;
+use sdl2::video::WindowContext;
+
+const TEXT: Color = Color::RGBA(255, 255, 255, 255);
+const FOCUSED: Color = Color::RGBA(255, 215, 0, 255);
+const TRACK: Color = Color::RGBA(255, 255, 255, 90);
+// Height of a widget's text at UI scale 1, widgets are a bit taller
+pub const TEXT_HEIGHT: u32 = 40;
+
+// Menu keys pressed since the last frame
+#[derive(Copy, Clone, Default)]
+pub struct MenuInput {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub activate: bool,
+}
+
+impl MenuInput {
+    // Picks out the keys menus use, returns true if the event was one of them
+    pub fn handle(&mut self, event: &Event) -> bool {
+        let key = match event {
+            Event::KeyDown { keycode: Some(k), .. } => *k,
+            _ => return false,
+        };
+        match key {
+            Keycode::Up | Keycode::W => self.up = true,
+            Keycode::Down | Keycode::S => self.down = true,
+            Keycode::Left | Keycode::A => self.left = true,
+            Keycode::Right | Keycode::D => self.right = true,
+            Keycode::Return | Keycode::KpEnter | Keycode::Space => self.activate = true,
+            _ => return false,
+        }
+        true
+    }
+}
+
+// What a menu remembers between frames
+#[derive(Default)]
+pub struct MenuState {
+    focus: usize,
+    count: usize, // Widgets drawn last frame
+}
+
+impl MenuState {
+    pub fn new() -> MenuState {
+        MenuState::default()
+    }
+
+    pub fn focus(&self) -> usize {
+        self.focus
+    }
+}
+
+// One frame of a menu, see the module comment
+pub struct Ui<'s, 'f, 'r> {
+    state: &'s mut MenuState,
+    input: MenuInput,
+    font: &'f Font<'r, 'static>,
+    scale: f64,
+    index: usize,
+    focused_label: Option<String>,
+}
+
+impl<'s, 'f, 'r> Ui<'s, 'f, 'r> {
+    pub fn begin(state: &'s mut MenuState, input: MenuInput, font: &'f Font<'r, 'static>, scale: f64) -> Self {
+        if state.count > 0 {
+            if input.up {
+                state.focus = (state.focus + state.count - 1) % state.count;
+            }
+            if input.down {
+                state.focus = (state.focus + 1) % state.count;
+            }
+        }
+        Ui {
+            state,
+            input,
+            font,
+            scale,
+            index: 0,
+            focused_label: None,
+        }
+    }
+
+    // Height widgets are laid out with at the current UI scale
+    pub fn row_height(&self) -> i32 {
+        (TEXT_HEIGHT as f64 * self.scale * 1.5) as i32
+    }
+
+    // Returns true on the frame the button is pressed
+    pub fn button(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        texture_creator: &TextureCreator<WindowContext>,
+        label: &str,
+        pos: (i32, i32),
+    ) -> Result<bool, String> {
+        let focused = self.next_widget(label);
+        self.draw_text(canvas, texture_creator, label, pos, focused)?;
+        Ok(focused && self.input.activate)
+    }
+
+    // Returns true on the frame the value changes
+    pub fn toggle(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        texture_creator: &TextureCreator<WindowContext>,
+        label: &str,
+        value: &mut bool,
+        pos: (i32, i32),
+    ) -> Result<bool, String> {
+        let focused = self.next_widget(label);
+        let changed = focused && (self.input.activate || self.input.left || self.input.right);
+        if changed {
+            *value = !*value;
+        }
+        let text = format!("{}: {}", label, if *value { "On" } else { "Off" });
+        self.draw_text(canvas, texture_creator, &text, pos, focused)?;
+        Ok(changed)
+    }
+
+    // Left/Right move the value by `step`, clamped to `min..=max`. Returns
+    // true on the frame the value changes.
+    #[allow(clippy::too_many_arguments)]
+    pub fn slider(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        texture_creator: &TextureCreator<WindowContext>,
+        label: &str,
+        value: &mut i32,
+        (min, max, step): (i32, i32, i32),
+        pos: (i32, i32),
+    ) -> Result<bool, String> {
+        let focused = self.next_widget(label);
+        let old = *value;
+        if focused {
+            if self.input.left {
+                *value -= step;
+            }
+            if self.input.right {
+                *value += step;
+            }
+        }
+        *value = (*value).clamp(min, max);
+
+        let text_w = self.draw_text(canvas, texture_creator, &format!("{}: {}", label, value), pos, focused)?;
+
+        // Track with a handle, to the right of the text
+        let h = (TEXT_HEIGHT as f64 * self.scale) as i32;
+        let track_x = pos.0 + text_w as i32 + h / 2;
+        let track_w = (h * 6) as u32;
+        canvas.set_draw_color(TRACK);
+        canvas.fill_rect(rect!(track_x, pos.1 + h / 2 - 3, track_w, 6))?;
+        let t = if max > min {
+            (*value - min) as f64 / (max - min) as f64
+        } else {
+            0.0
+        };
+        canvas.set_draw_color(if focused { FOCUSED } else { TEXT });
+        canvas.fill_rect(rect!(
+            track_x + (t * track_w as f64) as i32 - h / 6,
+            pos.1 + h / 6,
+            h / 3,
+            h * 2 / 3
+        ))?;
+        Ok(*value != old)
+    }
+
+    // Finishes the frame. Returns the label of the focused widget if focus
+    // moved this frame, for announcing.
+    pub fn end(self) -> Option<String> {
+        self.state.count = self.index;
+        if self.state.focus >= self.index {
+            self.state.focus = 0;
+        }
+        if self.input.up || self.input.down {
+            self.focused_label
+        } else {
+            None
+        }
+    }
+
+    fn next_widget(&mut self, label: &str) -> bool {
+        let focused = self.index == self.state.focus;
+        if focused {
+            self.focused_label = Some(label.to_string());
+        }
+        self.index += 1;
+        focused
+    }
+
+    // Draws a line of text with a focus marker, returns its width
+    fn draw_text(
+        &self,
+        canvas: &mut WindowCanvas,
+        texture_creator: &TextureCreator<WindowContext>,
+        text: &str,
+        pos: (i32, i32),
+        focused: bool,
+    ) -> Result<u32, String> {
+        let color = if focused { FOCUSED } else { TEXT };
+        let texture = texture_creator
+            .create_texture_from_surface(&self.font.render(text).blended(color).map_err(|e| e.to_string())?)
+            .map_err(|e| e.to_string())?;
+        let TextureQuery { width, height, .. } = texture.query();
+        let h = (TEXT_HEIGHT as f64 * self.scale) as u32;
+        let w = (width as f64 * h as f64 / height as f64) as u32;
+        canvas.copy(&texture, None, rect!(pos.0, pos.1, w, h))?;
+        if focused {
+            canvas.set_draw_color(FOCUSED);
+            canvas.fill_rect(rect!(pos.0 - h as i32 / 2 - 10, pos.1 + h as i32 / 4, h / 2, h / 2))?;
+        }
+        Ok(w)
+    }
+}
@@ -0,0 +1,127 @@
+// Splits per-step simulation work (obstacle integration, particles) across a
+// few scoped threads. SDL stays on the main thread: jobs only get `Send` data,
+// which rules out anything holding a texture, and every job has finished
+// before the call returns.
+//
+// Obstacles are double-buffered. The world reads the front buffer while the
+// workers write the next step's state into the back one, then the two are
+// swapped, so nothing ever sees an obstacle half updated.
+
+use std::thread;
+
+// Upper bound on worker threads, the per-step workload doesn't scale further
+const MAX_WORKERS: usize = 4;
+
+pub struct Workers {
+    threads: usize,
+}
+
+impl Workers {
+    pub fn new() -> Workers {
+        let threads = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(MAX_WORKERS);
+        Workers::with_threads(threads)
+    }
+
+    // A thread count of 1 runs everything on the calling thread
+    pub fn with_threads(threads: usize) -> Workers {
+        Workers {
+            threads: threads.max(1),
+        }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    // Runs `f` on every item, split into one chunk per thread
+    pub fn for_each<T, F>(&self, items: &mut [T], f: F)
+    where
+        T: Send,
+        F: Fn(&mut T) + Sync,
+    {
+        if self.threads == 1 || items.len() < 2 {
+            items.iter_mut().for_each(f);
+            return;
+        }
+
+        let chunk_size = items.len().div_ceil(self.threads);
+        let f = &f;
+        thread::scope(|scope| {
+            let mut chunks = items.chunks_mut(chunk_size);
+            // The calling thread takes the first chunk itself
+            let first = chunks.next();
+            for chunk in chunks {
+                scope.spawn(move || chunk.iter_mut().for_each(f));
+            }
+            if let Some(chunk) = first {
+                chunk.iter_mut().for_each(f);
+            }
+        });
+    }
+
+    // Copies `front` into `back` and has `f` update each copy from its
+    // original, split into one chunk per thread. The front buffer is only
+    // read, swap the two afterwards to make the update current.
+    pub fn update_into<T, F>(&self, front: &[T], back: &mut Vec<T>, f: F)
+    where
+        T: Clone + Send + Sync,
+        F: Fn(&T, &mut T) + Sync,
+    {
+        // Reuses the back buffer's allocation from step to step
+        back.truncate(front.len());
+        let kept = back.len();
+        back.clone_from_slice(&front[..kept]);
+        back.extend_from_slice(&front[kept..]);
+        if self.threads == 1 || front.len() < 2 {
+            front.iter().zip(back.iter_mut()).for_each(|(a, b)| f(a, b));
+            return;
+        }
+
+        let chunk_size = front.len().div_ceil(self.threads);
+        let f = &f;
+        thread::scope(|scope| {
+            let mut chunks = front.chunks(chunk_size).zip(back.chunks_mut(chunk_size));
+            let first = chunks.next();
+            for (front, back) in chunks {
+                scope.spawn(move || front.iter().zip(back.iter_mut()).for_each(|(a, b)| f(a, b)));
+            }
+            if let Some((front, back)) = first {
+                front.iter().zip(back.iter_mut()).for_each(|(a, b)| f(a, b));
+            }
+        });
+    }
+}
+
+impl Default for Workers {
+    fn default() -> Self {
+        Workers::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_into_writes_every_item_to_the_back_buffer() {
+        let workers = Workers::with_threads(4);
+        let front: Vec<u32> = (0..10).collect();
+        let mut back = vec![99; 20];
+        workers.update_into(&front, &mut back, |a, b| *b = a * 2);
+        assert_eq!(back, (0..10).map(|i| i * 2).collect::<Vec<_>>());
+        assert_eq!(front, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn jobs_run_off_the_calling_thread() {
+        let workers = Workers::with_threads(2);
+        let caller = thread::current().id();
+        let mut ids = vec![caller; 2];
+        workers.for_each(&mut ids, |id| *id = thread::current().id());
+        assert_eq!(ids[0], caller);
+        assert_ne!(ids[1], caller);
+    }
+}
@@ -0,0 +1,750 @@
+// The world a run plays out in and the part of each simulation step that
+// moves it along: the terrain, everything spawned on it, the player's physics,
+// scrolling, the camera and culling. The runner senses the ground, handles
+// input, then steps the world and reacts to what the step reports with score,
+// lives, sound and the rest. The soak test drives the same two calls with the
+// autopilot, so what it checks is what a real run does.
+
+use crate::camera::Camera;
+use crate::clock::SimClock;
+use crate::crash;
+use crate::modifiers::Surroundings;
+use crate::p_rect;
+use crate::physics::{Body, Coin, Entity, Impact, LandingGrade, Obstacle, PhysRect, Physics, Player, Power, Pump};
+use crate::proceduralgen::{self, ProceduralGen, TerrainSegment};
+use crate::profiler::{Profiler, Scope};
+use crate::rect;
+use crate::spawner::{spawned_obstacle, SpawnContext, SpawnPlanner};
+use crate::terrainpool::TerrainPool;
+use crate::workers::Workers;
+use crate::{Hazard, ObstacleType, PowerType, StaticObject, TerrainType, TILE_SIZE};
+
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+use sdl2::render::Texture;
+
+use log::{debug, trace};
+use rand::Rng;
+use std::time::Duration;
+
+const CAM_W: u32 = 1280;
+const CAM_H: u32 = 720;
+
+// Screen x of the player's left edge, the world scrolls past them
+pub const PLAYER_X: i32 = 2 * TILE_SIZE as i32;
+
+// How far below the terrain the bottom of a gap is, far enough that nothing
+// falling in ever reaches it
+const GAP_DEPTH: i32 = 10000;
+// Chance that a power spawned in a dark biome is a lantern instead
+const LANTERN_CHANCE: f64 = 0.5;
+
+// The ground under the player at the start of a step, before input
+pub struct Underfoot {
+    pub terrain: Point, // Terrain under the player's left edge
+    pub ground: Point,  // What they stand on there, a gap's platform or nothing
+    pub angle: f64,     // Slope to the terrain a tile ahead
+    pub risk_zone: bool,
+    pub was_jumping: bool,
+}
+
+// What a step goes by that the world doesn't keep itself
+pub struct StepContext<'c> {
+    pub clock: &'c SimClock,
+    pub game_over: bool,
+    pub score: i32,     // Picks the difficulty of spawns
+    pub density: i32,   // Spawn density multiplier from the loadout
+    pub lanterns: bool, // Dark biome, some powers spawn as lanterns
+    pub view_w: i32,    // Everything spawns and is generated past this
+    pub shake: i32,     // Extra camera offset this step, from an earthquake
+}
+
+// What happened during a step, for the runner to score and sound out
+#[derive(Default)]
+pub struct StepReport {
+    pub jumped: bool, // Took off this step, however the jump started
+    pub power_expired: Option<PowerType>,
+    pub toppled: bool, // Not on their feet
+    pub hits: Vec<(Impact, ObstacleType)>,
+    pub launched: bool, // Sent an obstacle flying
+    pub hazard: Option<Hazard>,
+    pub vaults: Vec<Point>,       // Centers of golden statues jumped clean over
+    pub dodges: Vec<Point>,       // And of obstacles got past untouched
+    pub coins: Vec<(i32, Point)>, // Value and center of each coin collected
+    pub power: Option<PowerType>, // Picked up
+    pub pump: Option<Pump>,
+    pub bonked: bool, // Jumped into a tunnel ceiling
+    pub fell: bool,   // Below the terrain, into a gap
+    pub landing: Option<LandingGrade>,
+    pub spawned: Vec<ObstacleType>,
+    pub pickups_spawned: usize,
+    pub travel: f64,       // Pixels scrolled
+    pub camera_adj: i32,   // Pixels everything was moved down
+    pub new_segment: bool, // The last terrain segment was just generated
+}
+
+pub struct World<'a> {
+    pub terrain: Vec<TerrainSegment>,
+    pub pool: TerrainPool,        // Reuses curves of offscreen segments
+    pub obstacles: Vec<Obstacle>, // Front buffer, see move_obstacles
+    obstacles_back: Vec<Obstacle>,
+    pub coins: Vec<Coin<'a>>,
+    pub powers: Vec<Power<'a>>, // Spawned on the ground, not active powers
+    pub procgen: ProceduralGen,
+    pub planner: SpawnPlanner, // Decides what spawns at the edge, PLAN_STEPS ahead
+    pub camera: Camera,
+    noise: Box<[[(i32, i32); 256]; 256]>, // Gradients gen_terrain's noise comes from
+    workers: Workers,
+    tex_coin: &'a Texture<'a>,
+    tex_powers: Vec<&'a Texture<'a>>, // Indexed by PowerType::index
+}
+
+impl<'a> World<'a> {
+    // Two flat segments to start on, everything after them from the seed
+    pub fn new(
+        seed: u64,
+        clock: &SimClock,
+        camera: Camera,
+        tex_coin: &'a Texture<'a>,
+        tex_powers: Vec<&'a Texture<'a>>,
+    ) -> World<'a> {
+        let mut procgen = ProceduralGen::with_seed(seed);
+
+        // Perlin Noise init
+        let mut noise = Box::new([[(0, 0); 256]; 256]);
+        let terrain_rng = procgen.terrain_rng();
+        for i in 0..noise.len() - 1 {
+            for j in 0..noise.len() - 1 {
+                noise[i][j] = (terrain_rng.gen_range(0..256), terrain_rng.gen_range(0..256));
+            }
+        }
+
+        let mut pool = TerrainPool::new();
+        let ground_y = CAM_H as i32 * 2 / 3;
+        let mut terrain = Vec::new();
+        for (start, color) in [(0, Color::GREEN), (CAM_W as i32, Color::BLUE)].iter() {
+            let mut curve = pool.take_curve(CAM_W as usize);
+            curve.extend((*start..*start + CAM_W as i32).map(|x| (x, ground_y)));
+            terrain.push(TerrainSegment::new(
+                rect!(*start, ground_y, CAM_W, ground_y),
+                curve,
+                0.0,
+                TerrainType::Grass,
+                *color,
+            ));
+        }
+
+        World {
+            terrain,
+            pool,
+            obstacles: Vec::new(),
+            obstacles_back: Vec::new(),
+            coins: Vec::new(),
+            powers: Vec::new(),
+            procgen,
+            planner: SpawnPlanner::new(clock),
+            camera,
+            noise,
+            workers: Workers::new(),
+            tex_coin,
+            tex_powers,
+        }
+    }
+
+    // Finds the ground under the player and lets them feel it. Over a gap
+    // they stand on its platform or nothing, going by what's under their
+    // middle.
+    pub fn sense(&self, player: &mut Player) -> Underfoot {
+        let terrain = get_ground_coord(&self.terrain, PLAYER_X);
+        let next_ground_point = get_ground_coord(&self.terrain, PLAYER_X + TILE_SIZE as i32);
+        let angle = ((next_ground_point.y() as f64 - terrain.y() as f64) / (TILE_SIZE as f64)).atan();
+        let (floor_y, platform_vel) = get_floor(&self.terrain, PLAYER_X + TILE_SIZE as i32 / 2);
+        let ground = Point::new(terrain.x(), floor_y);
+        player.sense_ground(ground, angle);
+        player.ride(platform_vel);
+        player.set_surroundings(get_surroundings(&self.terrain, PLAYER_X));
+        player.step_footing();
+        Underfoot {
+            terrain,
+            ground,
+            angle,
+            risk_zone: is_risk_zone(&self.terrain, PLAYER_X),
+            was_jumping: player.is_jumping(),
+        }
+    }
+
+    // Threads the world's per-step work runs on, for the runner's particles
+    pub fn workers(&self) -> &Workers {
+        &self.workers
+    }
+
+    // Starts and ends of hazard strips and gaps on screen, what the autopilot
+    // jumps over. Jumping at a gap beats running off into it.
+    pub fn hazards(&self) -> Vec<(i32, i32)> {
+        self.terrain
+            .iter()
+            .filter_map(|g| g.hazard().map(|h| (g.x() + h.start, g.x() + h.end)))
+            .chain(
+                self.terrain
+                    .iter()
+                    .filter_map(|g| g.gap().map(|h| (g.x() + h.start, g.x() + h.end))),
+            )
+            .collect()
+    }
+
+    // One simulation step after input, see StepReport for what comes back.
+    // Time spent is split between the profiler's scopes as it goes.
+    pub fn step(
+        &mut self,
+        player: &mut Player<'a>,
+        under: &Underfoot,
+        ctx: &StepContext,
+        profiler: &mut Profiler,
+    ) -> StepReport {
+        let mut report = StepReport::default();
+        let game_over = ctx.game_over;
+
+        report.power_expired = player.tick_power();
+        // Bouncy shoes are effectively just repeated jumps, independent of
+        // player input
+        if let Some(effect) = player.power_effect() {
+            if effect.power == PowerType::BouncyShoes && !player.is_jumping() {
+                player.jump_scaled(under.ground, Duration::new(1111, 0), effect.strength);
+            }
+        }
+        report.jumped = player.is_jumping() && !under.was_jumping;
+
+        profiler.begin(Scope::Collision);
+        self.collide(player, under, game_over, &mut report);
+
+        /* ~~~~~~ Handle Forces from Physics and move sprites ~~~~~~ */
+        profiler.begin(Scope::Physics);
+        // Crouching at the bottom of a dip
+        report.pump = player.track_pump(under.angle);
+        if let Some(pump) = report.pump {
+            debug!("Pump, accuracy {:.2}, +{:.2} speed", pump.accuracy, pump.boost);
+        }
+
+        // Gravity, normal, and friction, then propel forward
+        let current_power = player.power_effect();
+        let curr_terrain_type = get_ground_type(&self.terrain, PLAYER_X);
+        Physics::apply_terrain_forces(player, under.angle, under.ground, curr_terrain_type, current_power);
+        Physics::apply_skate_force(player, under.angle);
+
+        player.update_vel(game_over);
+        player.update_pos(under.ground, under.angle, game_over);
+        player.flip();
+
+        // Tunnel ceilings stop jumps with a bonk
+        let ceiling = [PLAYER_X, PLAYER_X + TILE_SIZE as i32 - 1]
+            .iter()
+            .filter_map(|&x| get_ceiling(&self.terrain, x))
+            .min();
+        if let Some(ceiling_y) = ceiling {
+            if player.hit_ceiling(ceiling_y) {
+                report.bonked = true;
+                crash::event("Bonked a ceiling");
+            }
+        }
+
+        // Missing the platform over a gap ends the run once the player has
+        // dropped below the terrain
+        report.fell = player.y() > under.terrain.y();
+        report.landing = player.take_landing();
+
+        trace!(
+            "player before reset: vx:{} ax:{} vy:{} ay:{}",
+            player.vel_x(),
+            player.accel_x(),
+            player.vel_y(),
+            player.accel_y()
+        );
+        player.reset_accel();
+
+        self.move_obstacles(game_over);
+
+        profiler.begin(Scope::Procgen);
+        if !game_over {
+            self.spawn(player, ctx, &mut report);
+        }
+        profiler.begin(Scope::Physics);
+
+        /* Update ground / object positions to move player forward
+         * by the distance they should move this single iteration of the game loop
+         */
+        report.travel = player.travel();
+        let travel = report.travel as i32;
+        for ground in self.terrain.iter_mut() {
+            ground.travel_update(travel);
+            ground.step_gap();
+        }
+        for obs in self.obstacles.iter_mut() {
+            obs.travel_update(travel);
+        }
+        for coin in self.coins.iter_mut() {
+            coin.travel_update(travel);
+        }
+        for power_up in self.powers.iter_mut() {
+            power_up.travel_update(travel);
+        }
+
+        // Dropped coins fall, bounce and lie on the ground for a moment, or
+        // fall out the bottom of a gap. The rest are culled when they scroll
+        // off the left.
+        for coin in self.coins.iter_mut().filter(|c| c.drop_state().is_some()) {
+            let (floor_y, _) = get_floor(&self.terrain, coin.x() + TILE_SIZE as i32 / 2);
+            coin.fall(floor_y);
+        }
+        self.coins
+            .retain(|c| !c.is_expired() && (c.drop_state().is_none() || c.y() < CAM_H as i32));
+
+        profiler.begin(Scope::Procgen);
+        report.new_segment = self.extend_terrain(ctx.view_w);
+        profiler.begin(Scope::Physics);
+
+        /* ~~~~~~ Begin Camera Section ~~~~~~ */
+        /* This should be the very last section of calcultions, as the camera
+         * position relies upon updated math for EVERYTHING ELSE.
+         */
+        // Adjust camera vertically based on y/height of the ground under the
+        // player and the terrain coming up
+        let lookahead_end = (PLAYER_X + self.camera.config().lookahead).min(ctx.view_w - 1);
+        let ahead: Vec<i32> = (PLAYER_X + TILE_SIZE as i32..=lookahead_end)
+            .step_by(TILE_SIZE as usize)
+            .map(|x| get_ground_coord(&self.terrain, x))
+            .filter(|p| p.x() >= 0)
+            .map(|p| p.y())
+            .collect();
+        report.camera_adj = self.camera.adjust(under.terrain.y(), &ahead) + ctx.shake;
+        self.camera_adj(player, report.camera_adj);
+
+        /* ~~~~~~ Remove stuff which is now offscreen ~~~~~~ */
+        // Terrain, handing the curves back to the pool
+        self.pool.recycle_offscreen(&mut self.terrain, -(TILE_SIZE as i32));
+        Physics::remove_offscreen(&mut self.obstacles, -(TILE_SIZE as i32));
+        Physics::remove_offscreen(&mut self.coins, -(TILE_SIZE as i32));
+        Physics::remove_offscreen(&mut self.powers, -(TILE_SIZE as i32));
+
+        report
+    }
+
+    // Collisions with everything on screen and whatever's underfoot. Inside a
+    // risk zone shields don't help against obstacles.
+    fn collide(&mut self, player: &mut Player<'a>, under: &Underfoot, game_over: bool, report: &mut StepReport) {
+        report.toppled = !Physics::check_player_upright(player, under.angle);
+
+        for o in self.obstacles.iter_mut() {
+            if Physics::check_collision(player, o) {
+                let was_launched = o.launched();
+                let impact = if under.risk_zone {
+                    player.collide_obstacle_unshielded(o)
+                } else {
+                    player.collide_obstacle(o)
+                };
+                if o.launched() && !was_launched {
+                    report.launched = true;
+                }
+                if impact != Impact::None {
+                    debug!("{:?} impact with {:?}", impact, o.obstacle_type());
+                    crash::event(&format!("{:?} impact with {:?}", impact, o.obstacle_type()));
+                    report.hits.push((impact, o.obstacle_type()));
+                }
+            }
+        }
+
+        // Hazard strips throw the player back up off them
+        if player.is_grounded() && !game_over {
+            if let Some(hazard) = get_hazard(&self.terrain, player.center().x()) {
+                player.jump(under.ground, Duration::from_millis(0));
+                crash::event(&format!("Touched {:?}", hazard));
+                report.hazard = Some(hazard);
+            }
+        }
+
+        if !game_over {
+            // Golden statues pay out for a jump that passes over the whole
+            // statue without touching it
+            for o in self.obstacles.iter_mut() {
+                if o.obstacle_type() == ObstacleType::GoldenStatue
+                    && o.track_vault(player.hitbox(), player.is_jumping())
+                {
+                    crash::event("Vaulted a golden statue");
+                    report.vaults.push(o.center());
+                }
+            }
+            // Getting past a hazard untouched is a near miss, springs
+            // aren't hazards
+            for o in self.obstacles.iter_mut() {
+                if o.obstacle_type() != ObstacleType::Balloon && o.check_dodged(player.x()) {
+                    report.dodges.push(o.center());
+                }
+            }
+        }
+
+        self.coins.retain_mut(|c| {
+            if Physics::check_collision(player, c) && player.collide_coin(c) {
+                report.coins.push((c.value(), c.center()));
+                return false;
+            }
+            true
+        });
+        self.powers.retain_mut(|p| {
+            if Physics::check_collision(player, p) && player.collide_power(p) {
+                crash::event(&format!("Picked up {:?}", player.power_up()));
+                report.power = player.power_up();
+                return false;
+            }
+            true
+        });
+    }
+
+    // Only obstacles that have been hit move, rolling down the slope under
+    // them and piling up against the rest. The workers integrate them from
+    // the front buffer into the back one, which then becomes the front.
+    fn move_obstacles(&mut self, game_over: bool) {
+        let terrain = &self.terrain;
+        self.workers
+            .update_into(&self.obstacles, &mut self.obstacles_back, |_, o| {
+                if !o.collided() {
+                    return;
+                }
+                let object_ground = get_ground_coord(terrain, o.x());
+                let object_ground_right = get_ground_coord(terrain, o.x() + TILE_SIZE as i32 - 1);
+                let object_angle =
+                    ((object_ground_right.y() - object_ground.y()) as f64 / (TILE_SIZE as f64 - 1.0)).atan();
+                let object_terrain_type = get_ground_type(terrain, o.x());
+                // Very small friction coefficient because there's no "skate
+                // force" to counteract friction
+                Physics::apply_terrain_forces(o, object_angle, object_ground, object_terrain_type, None);
+                o.update_vel(false);
+                o.update_pos(object_ground, object_angle, game_over);
+                o.reset_accel();
+                // Off the left end of the terrain there's nothing to land on
+                if object_ground.x() >= 0 && object_ground_right.x() >= 0 {
+                    Physics::settle_on_terrain(o, object_ground, object_ground_right);
+                }
+            });
+        std::mem::swap(&mut self.obstacles, &mut self.obstacles_back);
+        Physics::resolve_obstacles(&mut self.obstacles);
+    }
+
+    // Rolls for the step PLAN_STEPS ahead and puts whatever's arrived at the
+    // edge on the terrain there
+    fn spawn(&mut self, player: &Player, ctx: &StepContext, report: &mut StepReport) {
+        let edge_x = ctx.view_w - 1;
+        let on_screen: Vec<ObstacleType> = self.obstacles.iter().map(|o| o.obstacle_type()).collect();
+        let arrived = self.planner.step(
+            &mut self.procgen,
+            ctx.clock,
+            &SpawnContext {
+                score: ctx.score,
+                risk_zone: is_risk_zone(&self.terrain, edge_x),
+                density: ctx.density,
+                on_screen: &on_screen,
+                pickups: self.coins.len() + self.powers.len(),
+            },
+        );
+        let mut new_object = arrived.map(|p| p.object);
+        // Height above the ground of the spawned obstacle, only pattern
+        // pieces float
+        let spawn_height = arrived.map_or(0, |p| p.height);
+
+        // Obstacles need ground to stand on, so none spawn over a gap
+        let over_gap = in_gap(&self.terrain, edge_x) || in_gap(&self.terrain, edge_x + TILE_SIZE as i32);
+        if over_gap && new_object.and_then(spawned_obstacle).is_some() {
+            new_object = None;
+        }
+        // Or too high up to fit under a tunnel's ceiling
+        if get_ceiling(&self.terrain, edge_x).is_some()
+            && spawn_height + TILE_SIZE as i32 > proceduralgen::TUNNEL_CLEARANCE
+        {
+            new_object = None;
+        }
+
+        let spawn_coord = get_ground_coord(&self.terrain, edge_x);
+        let hitbox = p_rect!(
+            spawn_coord.x,
+            spawn_coord.y - TILE_SIZE as i32 - spawn_height,
+            TILE_SIZE,
+            TILE_SIZE
+        );
+        let difficulty = proceduralgen::difficulty(ctx.score);
+        let obstacle = match new_object {
+            // Only statues on the ground can be golden, as many as the golden
+            // statue cap allows
+            Some(StaticObject::Statue) => {
+                let golden_cap = ObstacleType::GoldenStatue.def().max_live_at(difficulty);
+                let goldens = self
+                    .obstacles
+                    .iter()
+                    .filter(|o| o.obstacle_type() == ObstacleType::GoldenStatue)
+                    .count();
+                if spawn_height == 0 && self.procgen.roll_golden_statue() && goldens < golden_cap {
+                    Some((ObstacleType::GoldenStatue, 50.0))
+                } else {
+                    Some((ObstacleType::Statue, 50.0))
+                }
+            }
+            Some(StaticObject::Balloon) => Some((ObstacleType::Balloon, 1.0)),
+            Some(StaticObject::Chest) => Some((ObstacleType::Chest, 1.0)),
+            _ => None,
+        };
+        if let Some((obstacle_type, mass)) = obstacle {
+            // Chests always sit on the ground
+            let hitbox = if obstacle_type == ObstacleType::Chest {
+                p_rect!(spawn_coord.x, spawn_coord.y - TILE_SIZE as i32, TILE_SIZE, TILE_SIZE)
+            } else {
+                hitbox
+            };
+            let mut obstacle = Obstacle::new(hitbox, mass, obstacle_type);
+            obstacle.set_drift(obstacle_type.def().speed_at(difficulty));
+            self.obstacles.push(obstacle);
+            report.spawned.push(obstacle_type);
+        }
+
+        if !matches!(new_object, Some(StaticObject::Coin | StaticObject::Power)) {
+            return;
+        }
+        // Coins and powers go where nothing else is, or is about to be before
+        // they've scrolled clear of the edge, lifted over it if they can be
+        let clear_steps = (TILE_SIZE as f64 / player.vel_x().max(1.0)).ceil() as i32;
+        let occupied: Vec<_> = self
+            .obstacles
+            .iter()
+            .map(|o| o.hitbox())
+            .chain(self.coins.iter().map(|c| c.hitbox()))
+            .chain(self.powers.iter().map(|p| p.hitbox()))
+            .chain(
+                self.planner
+                    .planned()
+                    .iter()
+                    .filter(|p| p.steps <= clear_steps && spawned_obstacle(p.object).is_some())
+                    .map(|p| {
+                        p_rect!(
+                            spawn_coord.x,
+                            spawn_coord.y - TILE_SIZE as i32 - p.height,
+                            TILE_SIZE,
+                            TILE_SIZE
+                        )
+                    }),
+            )
+            .collect();
+        let candidate = p_rect!(spawn_coord.x, spawn_coord.y - TILE_SIZE as i32, TILE_SIZE, TILE_SIZE);
+        if new_object == Some(StaticObject::Coin) {
+            match Physics::place_spawn(candidate, &occupied) {
+                Some(hitbox) => {
+                    // Worth more the riskier it is to grab
+                    let center = hitbox.center();
+                    let nearest_obstacle = self
+                        .obstacles
+                        .iter()
+                        .map(|o| {
+                            let other = o.center();
+                            let (dx, dy) = (other.x() - center.x(), other.y() - center.y());
+                            ((dx * dx + dy * dy) as f64).sqrt()
+                        })
+                        .min_by(|a, b| a.total_cmp(b));
+                    let value = proceduralgen::coin_value(
+                        candidate.y() - hitbox.y(),
+                        nearest_obstacle,
+                        is_risk_zone(&self.terrain, edge_x),
+                    );
+                    self.coins.push(Coin::new(hitbox, self.tex_coin, value));
+                    report.pickups_spawned += 1;
+                }
+                None => debug!("No room for a coin, dropped it"),
+            }
+        } else {
+            let mut power_type = self.procgen.choose_power_up();
+            if ctx.lanterns && self.procgen.spawn_rng().gen_bool(LANTERN_CHANCE) {
+                power_type = PowerType::Lantern;
+            }
+            match Physics::place_spawn(candidate, &occupied) {
+                Some(hitbox) => {
+                    self.powers
+                        .push(Power::new(hitbox, self.tex_powers[power_type.index()], power_type));
+                    report.pickups_spawned += 1;
+                }
+                None => debug!("No room for a {:?} power, dropped it", power_type),
+            }
+        }
+    }
+
+    // Generates a new segment once the last one becomes visible. Each one
+    // starts out level with where the last one ended and gets its features,
+    // one with none rolls up and down. Returns whether it made one.
+    fn extend_terrain(&mut self, view_w: i32) -> bool {
+        let last_seg = self.terrain.last().unwrap();
+        if last_seg.x() >= view_w {
+            return false;
+        }
+        let &(last_x, last_y) = last_seg.curve().last().unwrap();
+        let mut new_curve = self.pool.take_curve(CAM_W as usize);
+        new_curve.extend((last_x + 1..last_x + CAM_W as i32 + 1).map(|x| (x, last_y)));
+        let mut new_terrain = TerrainSegment::new(
+            rect!(last_x + 1, last_y, CAM_W, CAM_H * 2 / 3),
+            new_curve,
+            0.0,
+            TerrainType::Grass,
+            Color::GREEN,
+        );
+        let procgen = &mut self.procgen;
+        // Nothing gets in the way of a biome gate
+        new_terrain.set_gate(procgen.biome_transition());
+        let gated = new_terrain.gate().is_some();
+        if !gated {
+            new_terrain.set_risk_zone(procgen.roll_risk_zone());
+            new_terrain.set_updraft(procgen.roll_updraft());
+        }
+        new_terrain.set_mud(procgen.roll_mud(CAM_W as i32));
+        if new_terrain.mud().is_none() && !gated {
+            new_terrain.set_hazard(procgen.roll_hazard(CAM_W as i32));
+        }
+        if new_terrain.mud().is_none() && new_terrain.hazard().is_none() && !gated {
+            new_terrain.set_gap(procgen.roll_gap(CAM_W as i32));
+        }
+        if new_terrain.hazard().is_none() && new_terrain.gap().is_none() && !gated {
+            let ceiling = procgen.roll_tunnel(new_terrain.curve());
+            new_terrain.set_ceiling(ceiling);
+        }
+        if new_terrain.is_plain() {
+            new_terrain = procgen.gen_terrain(
+                &self.noise,
+                (last_x as f64, last_y as f64),
+                CAM_W as i32,
+                CAM_H as i32,
+                new_terrain.into_curve(),
+                false,
+                false,
+                false,
+            );
+        }
+        self.terrain.push(new_terrain);
+        self.pool.enforce_cap(&mut self.terrain);
+        true
+    }
+
+    // Moves everything in the world and the player down by `dy`
+    fn camera_adj(&mut self, player: &mut Player, dy: i32) {
+        for ground in self.terrain.iter_mut() {
+            ground.camera_adj(0, dy);
+        }
+        for obs in self.obstacles.iter_mut() {
+            obs.camera_adj(0, dy);
+        }
+        for coin in self.coins.iter_mut() {
+            coin.camera_adj(0, dy);
+        }
+        for power_up in self.powers.iter_mut() {
+            power_up.camera_adj(0, dy);
+        }
+        player.camera_adj(0, dy);
+    }
+}
+
+// Given the current terrain and an x coordinate of the screen, returns the
+// (x, y) of the ground at that x
+pub fn get_ground_coord(all_terrain: &[TerrainSegment], screen_x: i32) -> Point {
+    // Loop backwards
+    for ground in all_terrain.iter().rev() {
+        // The first segment starting at or behind
+        // the given x, which it must be above
+        if ground.x() <= screen_x {
+            let point_ind: usize = (screen_x - ground.x()) as usize;
+            return Point::new(
+                ground.curve().get(point_ind).unwrap().0,
+                ground.curve().get(point_ind).unwrap().1,
+            );
+        }
+    }
+    Point::new(-1, -1)
+}
+
+// Ground y at the given x of the screen, which can be past its right edge,
+// None past the end of the terrain generated so far
+pub fn ground_ahead(all_terrain: &[TerrainSegment], screen_x: i32) -> Option<i32> {
+    let ground = all_terrain.iter().rev().find(|g| g.x() <= screen_x)?;
+    ground.curve().get((screen_x - ground.x()) as usize).map(|p| p.1)
+}
+
+// What's under the given x of the screen, for power modifiers
+pub fn get_surroundings(all_terrain: &[TerrainSegment], screen_x: i32) -> Surroundings {
+    match all_terrain.iter().rev().find(|ground| ground.x() <= screen_x) {
+        Some(ground) => Surroundings {
+            terrain: *ground.terrain_at(screen_x),
+            updraft: ground.has_updraft(),
+        },
+        None => Surroundings::default(),
+    }
+}
+
+// Whether the terrain at the given x of the screen is a risk zone
+pub fn is_risk_zone(all_terrain: &[TerrainSegment], screen_x: i32) -> bool {
+    all_terrain
+        .iter()
+        .rev()
+        .find(|ground| ground.x() <= screen_x)
+        .is_some_and(|ground| ground.is_risk_zone())
+}
+
+// Height of what the player would stand on at the given x of the screen, the
+// terrain except over a gap where it's the gap's platform or nothing, and the
+// platform's speed if it's one
+pub fn get_floor(all_terrain: &[TerrainSegment], screen_x: i32) -> (i32, Option<f64>) {
+    let Some(ground) = all_terrain.iter().rev().find(|ground| ground.x() <= screen_x) else {
+        return (-1, None);
+    };
+    let ground_y = ground.curve()[(screen_x - ground.x()) as usize].1;
+    match ground.gap() {
+        Some(gap) if ground.in_gap(screen_x) => {
+            let (left, right) = gap.platform();
+            if (left..right).contains(&(screen_x - ground.x())) {
+                (ground_y, Some(gap.platform_vel()))
+            } else {
+                (ground_y + GAP_DEPTH, None)
+            }
+        }
+        _ => (ground_y, None),
+    }
+}
+
+// Underside of a tunnel ceiling at the given x of the screen, if there's one
+// there
+pub fn get_ceiling(all_terrain: &[TerrainSegment], screen_x: i32) -> Option<i32> {
+    all_terrain
+        .iter()
+        .rev()
+        .find(|ground| ground.x() <= screen_x)
+        .and_then(|ground| ground.ceiling_at(screen_x))
+}
+
+// Whether the given x of the screen is over a gap
+pub fn in_gap(all_terrain: &[TerrainSegment], screen_x: i32) -> bool {
+    all_terrain
+        .iter()
+        .rev()
+        .find(|ground| ground.x() <= screen_x)
+        .is_some_and(|ground| ground.in_gap(screen_x))
+}
+
+// Hazard on the ground at the given x of the screen, if any
+pub fn get_hazard(all_terrain: &[TerrainSegment], screen_x: i32) -> Option<Hazard> {
+    all_terrain
+        .iter()
+        .rev()
+        .find(|ground| ground.x() <= screen_x)
+        .and_then(|ground| ground.hazard_at(screen_x))
+}
+
+// Type of the ground at the given x of the screen, grass past the end
+pub fn get_ground_type(all_terrain: &[TerrainSegment], screen_x: i32) -> &TerrainType {
+    // Loop backwards
+    for ground in all_terrain.iter().rev() {
+        // The first segment starting at or behind
+        // the given x, which it must be above
+        if ground.x() <= screen_x {
+            return ground.terrain_at(screen_x);
+        }
+    }
+    &TerrainType::Grass // default to grass
+}
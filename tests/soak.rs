@@ -0,0 +1,236 @@
+// Soak test: the autopilot plays run after run without a window for a long
+// stretch of simulated time, an hour unless SOAK_MINUTES says otherwise. Each
+// step is the runner's own, through World::sense and World::step. Fails if
+// entity counts, Vec capacities or terrain memory keep growing, or if anything
+// panics, then prints a performance summary.
+//
+//     SOAK_MINUTES=60 cargo test --release --test soak -- --ignored --nocapture
+
+use inf_runner::autopilot::Autopilot;
+use inf_runner::camera::{Camera, CameraConfig};
+use inf_runner::clock::SimClock;
+use inf_runner::framerate::SIM_FPS;
+use inf_runner::p_rect;
+use inf_runner::physics::Body;
+use inf_runner::physics::Impact;
+use inf_runner::physics::PhysRect;
+use inf_runner::physics::Player;
+use inf_runner::profiler::Profiler;
+use inf_runner::rect;
+use inf_runner::rng::GameRng;
+use inf_runner::terrainpool::MAX_SEGMENTS;
+use inf_runner::world::{get_ground_coord, StepContext, World, PLAYER_X};
+use inf_runner::TILE_SIZE;
+
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::rect::Rect;
+use sdl2::render::Texture;
+use sdl2::surface::Surface;
+
+use rand::Rng;
+use std::time::{Duration, Instant};
+
+const CAM_W: u32 = 1280;
+const CAM_H: u32 = 720;
+
+// Limits the run is checked against. The planner keeps at most 10 objects out
+// at once, anything near these means something isn't being removed.
+const MAX_ENTITIES: usize = 64;
+const MAX_CAPACITY: usize = 256;
+const MAX_TERRAIN_BYTES: usize = (MAX_SEGMENTS + 4) * (CAM_W as usize + 64) * 8;
+// Runs longer than this many steps are ended, the autopilot can go forever
+const MAX_RUN_STEPS: u32 = 5 * 60 * SIM_FPS as u32;
+
+struct Run<'a> {
+    clock: SimClock,
+    world: World<'a>,
+    player: Player<'a>,
+    score: i32, // Distance and coins, before any multipliers
+    distance: f64,
+}
+
+#[derive(Default)]
+struct Stats {
+    runs: u32,
+    steps: u64,
+    distance: f64,
+    max_obstacles: usize,
+    max_coins: usize,
+    max_powers: usize,
+    max_segments: usize,
+    max_capacity: usize,
+    max_terrain_bytes: usize,
+    curve_reuse: f64, // Summed over finished runs
+    step_times: Vec<Duration>,
+}
+
+#[test]
+#[ignore]
+fn soak() {
+    let minutes: u64 = std::env::var("SOAK_MINUTES")
+        .ok()
+        .and_then(|m| m.parse().ok())
+        .unwrap_or(60);
+    let total_steps = minutes * 60 * SIM_FPS as u64;
+
+    // Software renderer, so entities can hold a texture without opening a window
+    let surface = Surface::new(TILE_SIZE, TILE_SIZE, PixelFormatEnum::RGBA8888).unwrap();
+    let canvas = surface.into_canvas().unwrap();
+    let texture_creator = canvas.texture_creator();
+    let texture = texture_creator
+        .create_texture_target(None, TILE_SIZE, TILE_SIZE)
+        .unwrap();
+
+    let mut seeds = GameRng::new(1666);
+    let autopilot = Autopilot::new();
+    let mut profiler = Profiler::new();
+    let mut stats = Stats::default();
+    let mut failures: Vec<String> = Vec::new();
+
+    println!("Soaking for {} simulated minutes ({} steps)", minutes, total_steps);
+    let started = Instant::now();
+    let mut run = new_run(seeds.gen(), &texture);
+
+    while stats.steps < total_steps {
+        let step_start = Instant::now();
+        let over = step(&mut run, &autopilot, &mut profiler);
+        stats.step_times.push(step_start.elapsed());
+        stats.steps += 1;
+
+        let world = &run.world;
+        stats.max_obstacles = stats.max_obstacles.max(world.obstacles.len());
+        stats.max_coins = stats.max_coins.max(world.coins.len());
+        stats.max_powers = stats.max_powers.max(world.powers.len());
+        stats.max_segments = stats.max_segments.max(world.terrain.len());
+        stats.max_capacity = stats
+            .max_capacity
+            .max(world.obstacles.capacity())
+            .max(world.coins.capacity())
+            .max(world.powers.capacity());
+        stats.max_terrain_bytes = stats
+            .max_terrain_bytes
+            .max(world.pool.memory(&world.terrain).total_bytes());
+
+        if over || run.clock.step() >= MAX_RUN_STEPS {
+            stats.runs += 1;
+            stats.distance += run.distance;
+            stats.curve_reuse += run.world.pool.reuse_rate();
+            run = new_run(seeds.gen(), &texture);
+        }
+    }
+    let elapsed = started.elapsed();
+
+    if stats.max_obstacles + stats.max_coins + stats.max_powers > MAX_ENTITIES {
+        failures.push(format!(
+            "entity count reached {} obstacles, {} coins, {} powers",
+            stats.max_obstacles, stats.max_coins, stats.max_powers
+        ));
+    }
+    if stats.max_segments > MAX_SEGMENTS {
+        failures.push(format!("{} terrain segments resident", stats.max_segments));
+    }
+    if stats.max_capacity > MAX_CAPACITY {
+        failures.push(format!("an entity Vec grew to a capacity of {}", stats.max_capacity));
+    }
+    if stats.max_terrain_bytes > MAX_TERRAIN_BYTES {
+        failures.push(format!("terrain held {} bytes", stats.max_terrain_bytes));
+    }
+
+    print_summary(&stats, elapsed);
+    assert!(failures.is_empty(), "Soak failed: {}", failures.join(", "));
+}
+
+// A run as the runner starts one, on a fresh world from the seed
+fn new_run<'a>(seed: u64, texture: &'a Texture<'a>) -> Run<'a> {
+    let clock = SimClock::new();
+    let camera = Camera::new(CameraConfig::new(CAM_H as i32));
+    let world = World::new(
+        seed,
+        &clock,
+        camera,
+        texture,
+        vec![texture; inf_runner::POWER_DEFS.len()],
+    );
+    // Where the runner starts the player
+    let start_y = 3 * TILE_SIZE as i32;
+    Run {
+        clock,
+        world,
+        player: Player::new(
+            p_rect!(PLAYER_X, start_y, TILE_SIZE, TILE_SIZE),
+            rect!(PLAYER_X, start_y, TILE_SIZE, TILE_SIZE),
+            3.0,
+            texture,
+        ),
+        score: 0,
+        distance: 0.0,
+    }
+}
+
+// One simulation step, the runner's with the autopilot at the controls and
+// without lives. Returns true on game over.
+fn step(run: &mut Run, autopilot: &Autopilot, profiler: &mut Profiler) -> bool {
+    run.clock.queue_steps(1);
+    run.clock.tick();
+    run.player.remember_pos();
+
+    let under = run.world.sense(&mut run.player);
+    let world = &run.world;
+    let hazards = world.hazards();
+    if let Some(hold) = autopilot.decide(&run.player, &world.obstacles, &hazards, |x| {
+        get_ground_coord(&world.terrain, x).y()
+    }) {
+        run.player.jump(under.ground, hold);
+    }
+
+    let report = run.world.step(
+        &mut run.player,
+        &under,
+        &StepContext {
+            clock: &run.clock,
+            game_over: false,
+            score: run.score,
+            density: 1,
+            lanterns: false,
+            view_w: CAM_W as i32,
+            shake: 0,
+        },
+        profiler,
+    );
+    run.score += 1 + report.coins.iter().map(|&(value, _)| value).sum::<i32>();
+    run.distance += report.travel.max(0.0);
+
+    report.toppled || report.fell || report.hits.iter().any(|&(impact, _)| impact == Impact::Severe)
+}
+
+fn print_summary(stats: &Stats, elapsed: Duration) {
+    let mut times = stats.step_times.clone();
+    times.sort();
+    let percentile = |p: f64| times[((times.len() - 1) as f64 * p) as usize];
+
+    println!("---------------- Soak summary ----------------");
+    println!(
+        "{} steps in {:.1}s ({:.0} steps/s, {:.0}x real time)",
+        stats.steps,
+        elapsed.as_secs_f64(),
+        stats.steps as f64 / elapsed.as_secs_f64(),
+        stats.steps as f64 / SIM_FPS / elapsed.as_secs_f64()
+    );
+    println!("{} runs finished, {:.0} px travelled", stats.runs, stats.distance);
+    println!(
+        "step time p50 {:?}, p99 {:?}, max {:?}",
+        percentile(0.5),
+        percentile(0.99),
+        percentile(1.0)
+    );
+    println!(
+        "max entities: {} obstacles, {} coins, {} powers, {} segments",
+        stats.max_obstacles, stats.max_coins, stats.max_powers, stats.max_segments
+    );
+    println!(
+        "max Vec capacity {}, max terrain memory {} KiB, curve reuse {:.0}%",
+        stats.max_capacity,
+        stats.max_terrain_bytes / 1024,
+        stats.curve_reuse / stats.runs.max(1) as f64 * 100.0
+    );
+}